@@ -0,0 +1,602 @@
+//  DOWNLOAD.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 16:10:41
+//  Last edited:
+//    18 Oct 2026, 16:10:41
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Entrypoint to the `download` command-line tool, which exposes the
+//!   library's functions to shell scripts and Makefiles.
+//!
+//!   Only built when the `cli`-feature is enabled.
+//
+
+use std::error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use console::Style;
+use download::{download_file, ensure_file, tar, verify_file, DownloadSecurity, Ensured, Error};
+
+
+/***** CONSTANTS *****/
+/// Exit code for when everything went alright.
+const EXIT_OK: u8 = 0;
+// Note: `2` is used by `clap` for invalid usage.
+/// Exit code for when the network or the remote server failed.
+const EXIT_NETWORK: u8 = 3;
+/// Exit code for when a security measure refused a download or an archive.
+const EXIT_SECURITY: u8 = 4;
+/// Exit code for when the given input (URLs, certificates, ...) was invalid.
+const EXIT_INPUT: u8 = 5;
+/// Exit code for when reading from or writing to the local filesystem failed.
+const EXIT_FILESYSTEM: u8 = 6;
+/// Exit code for when an archive was malformed.
+const EXIT_ARCHIVE: u8 = 7;
+
+/// The text explaining the exit codes in the `--help`.
+const EXIT_CODES: &str = "Exit codes:
+  0  Success
+  2  Invalid usage
+  3  Network or HTTP failure
+  4  Security check failed (checksum, HTTPS, certificate pin, redirect, size, content, escaping archive entry or link, archive limit)
+  5  Invalid input (URL, certificate, ...)
+  6  Filesystem failure
+  7  Malformed archive";
+
+
+
+
+
+/***** ARGUMENTS *****/
+/// Defines the toplevel arguments of the binary.
+#[derive(Debug, Parser)]
+#[clap(name = "download", author, version, about, after_help = EXIT_CODES)]
+struct Arguments {
+    /// If given, does not show progress bars and other status messages.
+    #[clap(short, long, global = true)]
+    quiet: bool,
+
+    /// The subcommand to run.
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Defines the toplevel subcommands.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Downloads a file over HTTP(S).
+    #[clap(name = "get", after_help = EXIT_CODES)]
+    Get {
+        /// The URL of the file to download.
+        url:    String,
+        /// The path to download the file to.
+        target: PathBuf,
+
+        /// If given, the downloaded file must have this (hex-encoded) SHA-256 checksum.
+        #[clap(long)]
+        sha256:    Option<String>,
+        /// If given, only downloads over HTTPS (including any redirects).
+        #[clap(long)]
+        https:     bool,
+        /// If given, aborts the download when it becomes larger than this many bytes.
+        #[clap(long)]
+        max_bytes: Option<u64>,
+        /// If given, skips the download if the target already exists with the checksum given by `--sha256`.
+        #[clap(long, requires = "sha256")]
+        ensure:    bool,
+    },
+    /// Verifies the checksum of a local file.
+    #[clap(name = "verify", after_help = EXIT_CODES)]
+    Verify {
+        /// The path to the file to verify.
+        file:   PathBuf,
+        /// The (hex-encoded) SHA-256 checksum that the file should have.
+        #[clap(long)]
+        sha256: String,
+    },
+    /// Creates, extracts or lists (compressed) tarballs.
+    #[clap(name = "tar", subcommand)]
+    Tar(TarCommand),
+}
+
+/// Defines the subcommands of the `tar`-subcommand.
+#[derive(Debug, Subcommand)]
+enum TarCommand {
+    /// Archives a file or directory as a (compressed) tarball.
+    #[clap(name = "create", after_help = EXIT_CODES)]
+    Create {
+        /// The file or directory to archive.
+        source:  PathBuf,
+        /// The path of the tarball to create.
+        tarball: PathBuf,
+
+        /// If given, and `source` is a directory, archives its contents instead of the directory itself.
+        #[clap(long)]
+        skip_root_dir: bool,
+        /// How to compress the tarball (e.g., `none`, `gzip`, `xz`, `zstd` or `bzip2`, depending on the enabled features).
+        #[clap(short, long, default_value_t = tar::Compression::Gzip)]
+        compression:   tar::Compression,
+        /// The level to compress with, where higher is smaller but slower (e.g., 0 to 9 for gzip). Defaults to the best for gzip and bzip2, and
+        /// to the default of their CLI tools for xz and zstd.
+        #[clap(short, long)]
+        level:         Option<u32>,
+        /// If given, compresses gzip tarballs on all cores (like `pigz`). They remain readable by any gzip decoder.
+        #[clap(short, long)]
+        parallel:      bool,
+        /// What to do with symlinks: `preserve` them as links, `follow` them to archive what they point to, or `error` on them.
+        #[clap(long, default_value_t = tar::SymlinkPolicy::Follow)]
+        symlinks:      tar::SymlinkPolicy,
+    },
+    /// Extracts a (compressed) tarball to a directory.
+    #[clap(name = "extract", after_help = EXIT_CODES)]
+    Extract {
+        /// The tarball to extract.
+        tarball: PathBuf,
+        /// The directory to extract to. Must not exist yet.
+        target:  PathBuf,
+
+        /// How the tarball is compressed (e.g., `none`, `gzip`, `xz`, `zstd` or `bzip2`, depending on the enabled features). If omitted, it
+        /// is detected from the tarball's first bytes.
+        #[clap(short, long)]
+        compression:      Option<tar::Compression>,
+        /// Which symlinks to extract: `allow` any, only those that resolve `inside` the target directory, or `reject` them all.
+        #[clap(long, default_value_t = tar::LinkPolicy::Inside)]
+        symlinks:         tar::LinkPolicy,
+        /// Which hardlinks to extract: `allow` any, only those that resolve `inside` the target directory, or `reject` them all.
+        #[clap(long, default_value_t = tar::LinkPolicy::Inside)]
+        hardlinks:        tar::LinkPolicy,
+        /// The number of leading components to strip from the paths of entries (e.g., `1` to drop a `foo-1.2.3/`-directory). Entries with no
+        /// more components than this are skipped.
+        #[clap(long, default_value_t = 0)]
+        strip_components: usize,
+        /// The entries to extract, as globs on their paths in the tarball (e.g., `*/bin/*`). May be given multiple times. If omitted, all
+        /// entries are extracted.
+        #[clap(long)]
+        include:          Vec<tar::Glob>,
+        /// The entries to skip, as globs on their paths in the tarball (e.g., `**/*.md`). May be given multiple times.
+        #[clap(long)]
+        exclude:          Vec<tar::Glob>,
+        /// The limits the tarball must stay within.
+        #[clap(flatten)]
+        limits:           LimitArgs,
+    },
+    /// Lists the entries in a (compressed) tarball.
+    #[clap(name = "list", after_help = EXIT_CODES)]
+    List {
+        /// The tarball to list.
+        tarball: PathBuf,
+
+        /// How the tarball is compressed (e.g., `none`, `gzip`, `xz`, `zstd` or `bzip2`, depending on the enabled features). If omitted, it
+        /// is detected from the tarball's first bytes.
+        #[clap(short, long)]
+        compression: Option<tar::Compression>,
+        /// The entries to list, as globs on their paths in the tarball (e.g., `*/bin/*`). May be given multiple times. If omitted, all
+        /// entries are listed.
+        #[clap(long)]
+        include:     Vec<tar::Glob>,
+        /// The entries to leave out, as globs on their paths in the tarball (e.g., `**/*.md`). May be given multiple times.
+        #[clap(long)]
+        exclude:     Vec<tar::Glob>,
+        /// The limits the tarball must stay within.
+        #[clap(flatten)]
+        limits:      LimitArgs,
+    },
+}
+
+/// Defines the arguments for the limits that a tarball must stay within when reading it.
+#[derive(Args, Debug)]
+struct LimitArgs {
+    /// If given, aborts when the entries together become larger than this many bytes.
+    #[clap(long)]
+    max_bytes:       Option<u64>,
+    /// If given, aborts when any entry is larger than this many bytes.
+    #[clap(long)]
+    max_entry_bytes: Option<u64>,
+    /// If given, aborts when there are more than this many entries.
+    #[clap(long)]
+    max_entries:     Option<u64>,
+    /// If given, aborts when the path of any entry has more than this many components.
+    #[clap(long)]
+    max_depth:       Option<u64>,
+    /// If given, aborts when the path of any entry is longer than this many bytes.
+    #[clap(long)]
+    max_path_len:    Option<u64>,
+    /// If given, aborts when more than this many bytes are decompressed per compressed byte (checked after the first MiB).
+    #[clap(long)]
+    max_ratio:       Option<u64>,
+}
+impl From<LimitArgs> for tar::UnarchiveLimits {
+    #[inline]
+    fn from(value: LimitArgs) -> Self {
+        let LimitArgs { max_bytes, max_entry_bytes, max_entries, max_depth, max_path_len, max_ratio } = value;
+        Self { max_bytes, max_entry_bytes, max_entries, max_depth, max_path_len, max_ratio }
+    }
+}
+
+
+
+
+
+/***** HELPERS *****/
+/// Prints an error, together with its sources, to stderr.
+///
+/// The error itself is printed with `{:#}`, such that it includes any details (e.g., the body of a non-OK response).
+///
+/// # Arguments
+/// - `err`: The error to print.
+fn print_error(err: &dyn error::Error) {
+    eprintln!("{}: {:#}", Style::new().red().bold().apply_to("error"), err);
+    let mut source: Option<&dyn error::Error> = err.source();
+    while let Some(err) = source {
+        eprintln!("  caused by: {err}");
+        source = err.source();
+    }
+}
+
+/// Parses a hex-encoded checksum given on the command-line.
+///
+/// # Arguments
+/// - `raw`: The hex-string to parse.
+///
+/// # Returns
+/// The parsed checksum, or [`None`] if it wasn't a valid SHA-256 checksum (after the error has been printed).
+fn parse_checksum(raw: &str) -> Option<Vec<u8>> {
+    match hex::decode(raw) {
+        Ok(checksum) if checksum.len() == 32 => Some(checksum),
+        Ok(checksum) => {
+            eprintln!(
+                "{}: Checksum '{}' is {} bytes long, but a SHA-256 checksum is 32 bytes",
+                Style::new().red().bold().apply_to("error"),
+                raw,
+                checksum.len()
+            );
+            None
+        },
+        Err(err) => {
+            eprintln!("{}: Checksum '{}' is not valid hexadecimal: {}", Style::new().red().bold().apply_to("error"), raw, err);
+            None
+        },
+    }
+}
+
+/// Maps a download error to the exit code of its category.
+///
+/// # Arguments
+/// - `err`: The [`Error`] to categorize.
+///
+/// # Returns
+/// One of the `EXIT_*`-constants.
+fn download_exit_code(err: &Error) -> u8 {
+    match err {
+        Error::RequestCreate { .. }
+        | Error::RequestExecute { .. }
+        | Error::ResponseDownload { .. }
+        | Error::ResponseNotOk { .. }
+        | Error::RedirectLocation { .. }
+        | Error::Transport { .. } => EXIT_NETWORK,
+        #[cfg(feature = "async-tokio")]
+        Error::ResponseDownloadAsync { .. } => EXIT_NETWORK,
+
+        Error::RedirectNotAllowed { .. }
+        | Error::ResponseTooLarge { .. }
+        | Error::SecurityChecksum { .. }
+        | Error::SecurityNoHttps { .. }
+        | Error::UnexpectedContent { .. } => EXIT_SECURITY,
+        #[cfg(feature = "__tls")]
+        Error::SecurityCertificatePin { .. } => EXIT_SECURITY,
+
+        Error::ClientCreate { .. } | Error::SourceParse { .. } => EXIT_INPUT,
+        #[cfg(feature = "__tls")]
+        Error::TlsRootCertificate { .. } => EXIT_INPUT,
+
+        Error::TargetCreate { .. }
+        | Error::TargetMetadata { .. }
+        | Error::TargetNoSpace { .. }
+        | Error::TargetParentNotFound { .. }
+        | Error::TargetRead { .. }
+        | Error::TargetRename { .. }
+        | Error::TargetWrite { .. } => EXIT_FILESYSTEM,
+    }
+}
+
+/// Maps a tar error to the exit code of its category.
+///
+/// # Arguments
+/// - `err`: The [`tar::Error`] to categorize.
+///
+/// # Returns
+/// One of the `EXIT_*`-constants.
+fn tar_exit_code(err: &tar::Error) -> u8 {
+    use tar::Error::*;
+    match err {
+        // (note: decoders report corrupt streams as I/O errors, so we have to tell those apart from actual filesystem failures)
+        SourceTarEntryUnpack { err, .. } | SourceTarOpen { err, .. }
+            if matches!(err.kind(), std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof) =>
+        {
+            EXIT_ARCHIVE
+        },
+
+        LimitExceeded { .. }
+        | SourceTarEntryEscaped { .. }
+        | SourceTarEntryHardlink { .. }
+        | SourceTarEntryLinkEscaped { .. }
+        | SourceTarEntrySymlink { .. } => EXIT_SECURITY,
+
+        InvalidCompressionLevel { .. } | SourceTarEntryNotFound { .. } => EXIT_INPUT,
+
+        SourceTarEntries { .. } | SourceTarEntry { .. } | SourceTarEntryPath { .. } | UnknownArchiveFormat { .. } => EXIT_ARCHIVE,
+
+        SourceDirEntryRead { .. }
+        | SourceDirRead { .. }
+        | SourceMetadata { .. }
+        | SourceNotAFileOrDir { .. }
+        | SourceNotFound { .. }
+        | SourceSymlink { .. }
+        | SourceSymlinkBroken { .. }
+        | SourceSymlinkCycle { .. }
+        | TargetTarAppend { .. }
+        | TargetTarCreate { .. }
+        | TargetTarFinish { .. }
+        | TargetTarFlush { .. }
+        | SourceTarEntryUnpack { .. }
+        | SourceTarOpen { .. }
+        | TargetDirCreate { .. }
+        | TargetExists { .. } => EXIT_FILESYSTEM,
+    }
+}
+
+
+
+
+
+/***** ENTRYPOINT *****/
+/// Runs the binary with the given (parsed) arguments.
+///
+/// # Arguments
+/// - `args`: The [`Arguments`] to run with.
+///
+/// # Returns
+/// The exit code to quit with (one of the `EXIT_*`-constants). Any error has already been printed.
+fn run(args: Arguments) -> u8 {
+    let verbose: Option<Style> = if args.quiet { None } else { Some(Style::new().bold()) };
+
+    // Match on the subcommand
+    match args.command {
+        Command::Get { url, target, sha256, https, max_bytes, ensure } => {
+            let checksum: Option<Vec<u8>> = match sha256.as_deref().map(parse_checksum) {
+                Some(Some(checksum)) => Some(checksum),
+                Some(None) => return EXIT_INPUT,
+                None => None,
+            };
+            let mut security: DownloadSecurity = DownloadSecurity { checksum: checksum.as_deref(), https, ..DownloadSecurity::none() };
+            if let Some(max_bytes) = max_bytes {
+                security = security.with_max_bytes(max_bytes);
+            }
+
+            // Run either the download or the ensure
+            let res: Result<(), Error> = if ensure {
+                ensure_file(&url, &target, security, verbose.clone()).map(|ensured| {
+                    if verbose.is_some() && ensured == Ensured::AlreadyPresent {
+                        println!("'{}' is already present", target.display());
+                    }
+                })
+            } else {
+                download_file(&url, &target, security, verbose)
+            };
+            if let Err(err) = res {
+                print_error(&err);
+                return download_exit_code(&err);
+            }
+        },
+
+        Command::Verify { file, sha256 } => {
+            let checksum: Vec<u8> = match parse_checksum(&sha256) {
+                Some(checksum) => checksum,
+                None => return EXIT_INPUT,
+            };
+            if let Err(err) = verify_file(&file, &checksum, verbose) {
+                print_error(&err);
+                return download_exit_code(&err);
+            }
+        },
+
+        Command::Tar(TarCommand::Create { source, tarball, skip_root_dir, compression, level, parallel, symlinks }) => {
+            let options: tar::ArchiveOptions = tar::ArchiveOptions { level, ..tar::ArchiveOptions::new() }
+                .with_skip_root_dir(skip_root_dir)
+                .with_compression(compression)
+                .with_parallel(parallel)
+                .with_symlinks(symlinks);
+            if let Err(err) = tar::archive_with(&source, &tarball, options) {
+                print_error(&err);
+                return tar_exit_code(&err);
+            }
+        },
+        Command::Tar(TarCommand::Extract { tarball, target, compression, symlinks, hardlinks, strip_components, include, exclude, limits }) => {
+            let options: tar::UnarchiveOptions = tar::UnarchiveOptions {
+                compression,
+                symlinks,
+                hardlinks,
+                limits: limits.into(),
+                strip_components,
+                include,
+                exclude,
+                filter: None,
+            };
+            if let Err(err) = tar::unarchive_with(&tarball, &target, options) {
+                print_error(&err);
+                return tar_exit_code(&err);
+            }
+        },
+        Command::Tar(TarCommand::List { tarball, compression, include, exclude, limits }) => {
+            let options: tar::UnarchiveOptions =
+                tar::UnarchiveOptions { compression, include, exclude, limits: limits.into(), ..tar::UnarchiveOptions::new() };
+            match tar::list_with(&tarball, options) {
+                Ok(entries) => {
+                    for entry in entries {
+                        println!("{}", entry.display());
+                    }
+                },
+                Err(err) => {
+                    print_error(&err);
+                    return tar_exit_code(&err);
+                },
+            }
+        },
+    }
+
+    // Done
+    EXIT_OK
+}
+
+fn main() -> ExitCode { ExitCode::from(run(Arguments::parse())) }
+
+
+
+
+
+/***** TESTS *****/
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use rand::RngCore as _;
+    use tempfile::TempDir;
+
+    use super::*;
+    #[cfg(feature = "testing")]
+    use download::testing::{Route, TestServer};
+
+
+    /***** HELPERS *****/
+    /// Runs the binary quietly with the given arguments.
+    ///
+    /// # Arguments
+    /// - `args`: The arguments to give, excluding the name of the binary.
+    ///
+    /// # Returns
+    /// The exit code that the binary quit with.
+    fn run_with<'a>(args: impl IntoIterator<Item = &'a str>) -> u8 {
+        let args: Arguments = Arguments::try_parse_from(["download", "--quiet"].into_iter().chain(args)).unwrap_or_else(|err| panic!("{err}"));
+        run(args)
+    }
+
+    /// Creates a gzipped tarball with a single, incompressible file in it.
+    ///
+    /// # Arguments
+    /// - `dir`: The (temporary) directory to create the tarball in.
+    ///
+    /// # Returns
+    /// The path to the tarball (`dir/archive.tar.gz`).
+    fn create_tarball(dir: &Path) -> PathBuf {
+        let source: PathBuf = dir.join("payload");
+        fs::create_dir(&source).unwrap_or_else(|err| panic!("Failed to create directory '{}': {}", source.display(), err));
+        let mut random: Vec<u8> = vec![0; 64 * 1024];
+        rand::rng().fill_bytes(&mut random);
+        fs::write(source.join("random.bin"), random).unwrap_or_else(|err| panic!("Failed to write payload: {err}"));
+        let tarball: PathBuf = dir.join("archive.tar.gz");
+        tar::archive(&source, &tarball, true).unwrap_or_else(|err| panic!("Failed to archive payload: {err}"));
+        tarball
+    }
+
+
+
+    /***** TESTS *****/
+    /// Test whether successful commands quit with [`EXIT_OK`].
+    #[test]
+    fn test_exit_ok() {
+        let tempdir: TempDir = TempDir::new().unwrap();
+        let tarball: PathBuf = create_tarball(tempdir.path());
+        let target: PathBuf = tempdir.path().join("out");
+        assert_eq!(run_with(["tar", "list", tarball.to_str().unwrap()]), EXIT_OK);
+        assert_eq!(run_with(["tar", "extract", tarball.to_str().unwrap(), target.to_str().unwrap()]), EXIT_OK);
+        assert_eq!(fs::read(target.join("random.bin")).unwrap(), fs::read(tempdir.path().join("payload/random.bin")).unwrap());
+    }
+
+    /// Test whether network and HTTP failures quit with [`EXIT_NETWORK`].
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_exit_network() {
+        let server: TestServer = TestServer::spawn().unwrap_or_else(|err| panic!("Failed to spawn test server: {err}"));
+        let tempdir: TempDir = TempDir::new().unwrap();
+        let target: PathBuf = tempdir.path().join("file");
+        assert_eq!(run_with(["get", &server.url("/missing"), target.to_str().unwrap()]), EXIT_NETWORK);
+        assert!(!target.exists());
+    }
+
+    /// Test whether refused downloads and archives quit with [`EXIT_SECURITY`].
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_exit_security() {
+        let server: TestServer =
+            TestServer::spawn().unwrap_or_else(|err| panic!("Failed to spawn test server: {err}")).with_route("/file", Route::bytes("Hello there!\n"));
+        let tempdir: TempDir = TempDir::new().unwrap();
+        let target: PathBuf = tempdir.path().join("file");
+        let zeroes: String = "00".repeat(32);
+        assert_eq!(run_with(["get", &server.url("/file"), target.to_str().unwrap(), "--sha256", &zeroes]), EXIT_SECURITY);
+        assert_eq!(run_with(["get", &server.url("/file"), target.to_str().unwrap(), "--https"]), EXIT_SECURITY);
+        assert_eq!(run_with(["get", &server.url("/file"), target.to_str().unwrap(), "--max-bytes", "4"]), EXIT_SECURITY);
+        assert!(!target.exists());
+
+        // Archives too
+        let tarball: PathBuf = create_tarball(tempdir.path());
+        assert_eq!(run_with(["tar", "list", tarball.to_str().unwrap(), "--max-entries", "0"]), EXIT_SECURITY);
+    }
+
+    /// Test whether invalid input quits with [`EXIT_INPUT`].
+    #[test]
+    fn test_exit_input() {
+        let tempdir: TempDir = TempDir::new().unwrap();
+        let target: PathBuf = tempdir.path().join("file");
+        fs::write(&target, "Hello there!\n").unwrap();
+        assert_eq!(run_with(["get", "not a url", tempdir.path().join("other").to_str().unwrap()]), EXIT_INPUT);
+        assert_eq!(run_with(["verify", target.to_str().unwrap(), "--sha256", "xyz"]), EXIT_INPUT);
+        assert_eq!(run_with(["verify", target.to_str().unwrap(), "--sha256", "00"]), EXIT_INPUT);
+
+        // Invalid options for archives too
+        let tarball: PathBuf = tempdir.path().join("archive.tar.gz");
+        assert_eq!(run_with(["tar", "create", target.to_str().unwrap(), tarball.to_str().unwrap(), "--level", "42"]), EXIT_INPUT);
+        assert!(!tarball.exists());
+    }
+
+    /// Test whether failures of the local filesystem quit with [`EXIT_FILESYSTEM`].
+    #[test]
+    fn test_exit_filesystem() {
+        let tempdir: TempDir = TempDir::new().unwrap();
+        let tarball: PathBuf = create_tarball(tempdir.path());
+        let missing: PathBuf = tempdir.path().join("missing");
+        assert_eq!(run_with(["verify", missing.to_str().unwrap(), "--sha256", &"00".repeat(32)]), EXIT_FILESYSTEM);
+        assert_eq!(run_with(["tar", "list", missing.to_str().unwrap()]), EXIT_FILESYSTEM);
+        assert_eq!(run_with(["tar", "extract", tarball.to_str().unwrap(), tempdir.path().to_str().unwrap()]), EXIT_FILESYSTEM);
+    }
+
+    /// Test whether malformed archives quit with [`EXIT_ARCHIVE`], even when the decoder reports them as I/O errors.
+    #[test]
+    fn test_exit_archive() {
+        let tempdir: TempDir = TempDir::new().unwrap();
+        let tarball: PathBuf = create_tarball(tempdir.path());
+        let target: PathBuf = tempdir.path().join("out");
+
+        // Cut the tarball off halfway through its only file
+        let raw: Vec<u8> = fs::read(&tarball).unwrap();
+        let truncated: PathBuf = tempdir.path().join("truncated.tar.gz");
+        fs::write(&truncated, &raw[..raw.len() / 2]).unwrap();
+        assert_eq!(run_with(["tar", "extract", truncated.to_str().unwrap(), target.to_str().unwrap()]), EXIT_ARCHIVE);
+
+        // Garbage after a gzip-header
+        let corrupt: PathBuf = tempdir.path().join("corrupt.tar.gz");
+        fs::write(&corrupt, [&[0x1F, 0x8B, 0x08][..], &[42; 1024]].concat()).unwrap();
+        assert_eq!(run_with(["tar", "list", corrupt.to_str().unwrap()]), EXIT_ARCHIVE);
+
+        // Something that isn't compressed as we know it
+        let unknown: PathBuf = tempdir.path().join("unknown.tar.gz");
+        fs::write(&unknown, [0xFF; 1024]).unwrap();
+        assert_eq!(run_with(["tar", "list", unknown.to_str().unwrap()]), EXIT_ARCHIVE);
+    }
+}
@@ -12,18 +12,33 @@
 //!   Defines functions that download files from the internet.
 //
 
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::ffi::{OsStr, OsString};
 use std::fmt::{Display, Formatter, Result as FResult};
+use std::hash::{BuildHasher as _, Hasher as _};
 use std::io::{Read as _, Write as _};
 use std::path::{Path, PathBuf};
 use std::str::FromStr as _;
+use std::time::SystemTime;
 use std::{error, fs};
 
 pub use console::Style;
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::blocking::{Client, Request, Response};
-use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, LAST_MODIFIED, LOCATION, RANGE};
+use reqwest::{Method, StatusCode};
 use sha2::{Digest as _, Sha256};
 use url::Url;
+
+use crate::transport::{ReqwestTransport, Transport, TransportResponse};
+#[cfg(feature = "async-tokio")]
+use crate::transport::build_client;
+#[cfg(unix)]
+use ::{std::os::unix::fs::PermissionsExt as _, std::os::unix::io::AsFd};
+
+#[cfg(feature = "__tls")]
+use ::{reqwest::tls::Certificate, x509_parser::certificate::X509Certificate};
+#[cfg(all(feature = "__tls", feature = "async-tokio"))]
+use reqwest::tls::TlsInfo;
 #[cfg(feature = "async-tokio")]
 use ::{
     reqwest::{Client as AsyncClient, Request as AsyncRequest, Response as AsyncResponse},
@@ -31,6 +46,13 @@ use ::{
     tokio::io::AsyncWriteExt as _,
     tokio_stream::StreamExt as _,
 };
+#[cfg(feature = "async-smol")]
+use ::{
+    async_fs as sfs,
+    blocking::{unblock, Unblock},
+    futures_lite::io::{AsyncReadExt as _, AsyncWriteExt as _},
+    std::sync::Arc,
+};
 
 
 /***** MACROS *****/
@@ -54,6 +76,12 @@ macro_rules! debug {
 /// Defines the errors tha may occur when dealing with the filesystem operations.
 #[derive(Debug)]
 pub enum Error {
+    /// Failed to build the HTTP client used to send requests.
+    ClientCreate { err: reqwest::Error },
+    /// A server redirected us to a `Location` that we could not parse as a URL.
+    RedirectLocation { url: String, location: String, chain: Vec<String> },
+    /// A server redirected us somewhere that the [`RedirectPolicy`] does not allow.
+    RedirectNotAllowed { url: String, chain: Vec<String>, reason: RedirectViolation },
     /// Failed to build a new request to the given URL.
     RequestCreate { url: String, err: reqwest::Error },
     /// Failed to execute a request to the given URL.
@@ -64,49 +92,120 @@ pub enum Error {
     #[cfg(feature = "async-tokio")]
     ResponseDownloadAsync { url: String, err: reqwest::Error },
     /// The given response was not an OK-response.
-    ResponseNotOk { url: String, code: StatusCode, response: Option<String> },
+    ///
+    /// The `response` is the start of its body (see [`DownloadSecurity::with_max_error_body()`]), decoded leniently and with control characters
+    /// removed. Since it may still be long, it's only shown when formatting the error with `{:#}`.
+    ResponseNotOk { url: String, code: StatusCode, headers: Box<HeaderMap>, response: Option<String> },
+    /// The response was larger than the maximum size allowed by the [`DownloadSecurity`].
+    ///
+    /// If `content_length` is given, then the server announced the size up front; otherwise, the limit was passed while streaming.
+    ResponseTooLarge { url: String, max: u64, content_length: Option<u64> },
+    /// The server's certificate did not match any of the pinned public keys.
+    ///
+    /// `got` is [`None`] if the server did not present a (parseable) certificate at all, e.g., because the download didn't use TLS.
+    #[cfg(feature = "__tls")]
+    SecurityCertificatePin { url: String, got: Option<String>, expected: Vec<String> },
     /// The downloaded target did not match the given checksum.
     SecurityChecksum { path: PathBuf, got: String, expected: String },
     /// HTTPS security was enabled, but the target address isn't HTTPS (or couldn't be parsed).
-    SecurityNoHttps { url: String },
+    ///
+    /// The `chain` lists the addresses that redirected us to `url`, and is empty if `url` is the one given by the user.
+    SecurityNoHttps { url: String, chain: Vec<String> },
     /// Failed to parse the source URL as a... well... URL.
     SourceParse { raw: String, err: url::ParseError },
     /// Failed to create the target for writing.
     TargetCreate { path: PathBuf, err: std::io::Error },
+    /// Failed to set the permissions or modification time of the (temporary) target.
+    TargetMetadata { path: PathBuf, err: std::io::Error },
+    /// There is not enough free space on the target's filesystem to download the response.
+    ///
+    /// `available` is [`None`] if we found out because preallocating the file failed.
+    TargetNoSpace { path: PathBuf, needed: u64, available: Option<u64> },
     /// The target's directory is not found.
     TargetParentNotFound { path: PathBuf },
+    /// Failed to read an already existing target (to compute its checksum).
+    TargetRead { path: PathBuf, err: std::io::Error },
+    /// Failed to move the temporary file that we downloaded to onto the target.
+    TargetRename { from: PathBuf, to: PathBuf, err: std::io::Error },
     /// Failed to write to the given target.
     TargetWrite { path: PathBuf, err: std::io::Error },
+    /// Failed to load one of the extra root certificates given in the [`TlsConfig`].
+    ///
+    /// Note that some TLS backends only detect invalid certificates when building the client, in which case [`Error::ClientCreate`] is returned instead.
+    #[cfg(feature = "__tls")]
+    TlsRootCertificate { index: usize, err: reqwest::Error },
+    /// A custom [`Transport`] failed to send a request to the given URL.
+    Transport { url: String, err: Box<dyn error::Error + Send + Sync> },
+    /// The response is not what the [`ContentCheck`] expected (e.g., an HTML error page instead of a tarball).
+    ///
+    /// The `snippet` shows the start of the body, with control characters removed.
+    UnexpectedContent { url: String, reason: ContentViolation, snippet: String },
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
         use Error::*;
         match self {
-            RequestCreate { url, .. } => write!(f, "Failed to create GET-request to '{url}'"),
-            RequestExecute { url, .. } => write!(f, "Failed to execute GET-request to '{url}'"),
+            ClientCreate { .. } => write!(f, "Failed to create HTTP client"),
+            RedirectLocation { url, location, chain } => {
+                write!(f, "Failed to parse redirect location '{location}' returned by '{url}' as a URL{}", RedirectChain(chain))
+            },
+            RedirectNotAllowed { url, chain, reason } => write!(f, "Redirect to '{url}' is not allowed: {reason}{}", RedirectChain(chain)),
+            RequestCreate { url, .. } => write!(f, "Failed to create request to '{url}'"),
+            RequestExecute { url, .. } => write!(f, "Failed to execute request to '{url}'"),
             ResponseDownload { url, .. } => write!(f, "Failed to download response body from '{url}'"),
             #[cfg(feature = "async-tokio")]
             ResponseDownloadAsync { url, .. } => write!(f, "Failed to download response body from '{url}'"),
-            ResponseNotOk { url, code, response } => write!(
-                f,
-                "GET-request to '{}' failed with {} ({}){}",
-                url,
-                code.as_u16(),
-                code.canonical_reason().unwrap_or("???"),
-                if let Some(res) = response {
-                    format!("\n\nResponse:\n{}\n{}\n{}\n", (0..80).map(|_| '-').collect::<String>(), res, (0..80).map(|_| '-').collect::<String>())
+            ResponseNotOk { url, code, response, .. } => {
+                write!(f, "GET-request to '{}' failed with {} ({})", url, code.as_u16(), code.canonical_reason().unwrap_or("???"))?;
+                match response {
+                    Some(res) if f.alternate() => {
+                        let line: String = (0..80).map(|_| '-').collect();
+                        write!(f, "\n\nResponse:\n{line}\n{res}\n{line}\n")
+                    },
+                    _ => Ok(()),
+                }
+            },
+            ResponseTooLarge { url, max, content_length } => {
+                if let Some(len) = content_length {
+                    write!(f, "Response from '{url}' is {len} bytes, which is larger than the maximum of {max} bytes")
                 } else {
-                    String::new()
+                    write!(f, "Response from '{url}' exceeded the maximum of {max} bytes")
                 }
+            },
+            #[cfg(feature = "__tls")]
+            SecurityCertificatePin { url, got, expected } => write!(
+                f,
+                "Certificate of '{}' does not match any pinned public key (got {}, expected one of {})",
+                url,
+                if let Some(got) = got { format!("'{got}'") } else { "no certificate".into() },
+                expected.iter().map(|pin| format!("'{pin}'")).collect::<Vec<String>>().join(", ")
             ),
             SecurityChecksum { path, got, expected } => {
                 write!(f, "Checksum of downloaded file '{}' does not match (got '{}', expected '{}')", path.display(), got, expected)
             },
-            SecurityNoHttps { url } => write!(f, "HTTPS check enabled, but given url '{url}' does not have an HTTPS request"),
+            SecurityNoHttps { url, chain } => {
+                write!(f, "HTTPS check enabled, but given url '{url}' does not have an HTTPS request{}", RedirectChain(chain))
+            },
             SourceParse { raw, .. } => write!(f, "Failed to parse source '{raw}' as a URL"),
             TargetCreate { path, .. } => write!(f, "Failed to create target file '{}'", path.display()),
+            TargetMetadata { path, .. } => write!(f, "Failed to set permissions or modification time of target file '{}'", path.display()),
+            TargetNoSpace { path, needed, available } => write!(
+                f,
+                "Not enough free space to download target file '{}' (need {} bytes{})",
+                path.display(),
+                needed,
+                if let Some(available) = available { format!(", {available} bytes available") } else { String::new() }
+            ),
             TargetParentNotFound { path } => write!(f, "Target's parent directory '{}' not found", path.display()),
+            TargetRead { path, .. } => write!(f, "Failed to read existing target file '{}'", path.display()),
+            TargetRename { from, to, .. } => write!(f, "Failed to move downloaded file '{}' to target '{}'", from.display(), to.display()),
             TargetWrite { path, .. } => write!(f, "Failed to write to target file '{}'", path.display()),
+            #[cfg(feature = "__tls")]
+            TlsRootCertificate { index, .. } => write!(f, "Failed to load extra root certificate {index}"),
+            Transport { url, .. } => write!(f, "Failed to send request to '{url}'"),
+            UnexpectedContent { url, reason, snippet } => {
+                write!(f, "Response from '{url}' has unexpected content: {reason} (body starts with {snippet:?})")
+            },
         }
     }
 }
@@ -114,18 +213,32 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         use Error::*;
         match self {
+            ClientCreate { err } => Some(err),
+            RedirectLocation { .. } => None,
+            RedirectNotAllowed { .. } => None,
             RequestCreate { err, .. } => Some(err),
             RequestExecute { err, .. } => Some(err),
             ResponseDownload { err, .. } => Some(err),
             #[cfg(feature = "async-tokio")]
             ResponseDownloadAsync { err, .. } => Some(err),
             ResponseNotOk { .. } => None,
+            ResponseTooLarge { .. } => None,
+            #[cfg(feature = "__tls")]
+            SecurityCertificatePin { .. } => None,
             SecurityChecksum { .. } => None,
             SecurityNoHttps { .. } => None,
             SourceParse { err, .. } => Some(err),
             TargetCreate { err, .. } => Some(err),
+            TargetMetadata { err, .. } => Some(err),
+            TargetNoSpace { .. } => None,
             TargetParentNotFound { .. } => None,
+            TargetRead { err, .. } => Some(err),
+            TargetRename { err, .. } => Some(err),
             TargetWrite { err, .. } => Some(err),
+            #[cfg(feature = "__tls")]
+            TlsRootCertificate { err, .. } => Some(err),
+            Transport { err, .. } => Some(&**err),
+            UnexpectedContent { .. } => None,
         }
     }
 }
@@ -134,197 +247,22 @@ impl error::Error for Error {
 
 
 
-/***** AUXILLARY *****/
-/// Defines things to do to assert a downloaded file is secure and what we expect.
-#[derive(Clone, Debug)]
-pub struct DownloadSecurity<'c> {
-    /// If not `None`, then it defined the checksum that the file should have.
-    pub checksum: Option<&'c [u8]>,
-    /// If true, then the file can only be downloaded over HTTPS.
-    pub https:    bool,
-}
-impl<'c> DownloadSecurity<'c> {
-    /// Constructor for the DownloadSecurity that enables with all security measures enabled.
-    ///
-    /// This will provide you with the most security, but is also the slowest method (since it does both encryption and checksum computation).
-    ///
-    /// Usually, it sufficies to only use a checksum (`DownloadSecurity::checksum()`) if you know what the file looks like a-priori.
-    ///
-    /// # Arguments
-    /// - `checksum`: The checksum that we want the file to have. If you are unsure, give a garbage checksum, then run the function once and check what the file had (after making sure the download went correctly, of course).
-    ///
-    /// # Returns
-    /// A new DownloadSecurity instance that will make your downloaded file so secure you can use it to store a country's deficit (not legal advice).
-    ///
-    /// # Example
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity};
-    /// use hex_literal::hex;
-    ///
-    /// // Download some file
-    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// download_file(
-    ///     &url,
-    ///     &file,
-    ///     DownloadSecurity::all(&hex!(
-    ///         "c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4"
-    ///     )),
-    ///     None,
-    /// )
-    /// .unwrap();
-    ///
-    /// // It exists now!
-    /// assert!(file.is_file());
-    /// assert!(std::fs::read_to_string(&file).is_ok());
-    /// ```
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity, Error};
-    /// use hex_literal::hex;
-    ///
-    /// // Using a non-HTTPS URL
-    /// let url = "http://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// match download_file(&url, &file, DownloadSecurity::all(&hex!("deadbeef")), None) {
-    ///     Err(Error::SecurityNoHttps { .. }) => println!("Yeah that failed"),
-    /// #   got => panic!("Did not crash as expected, got {got:?}"),
-    /// }
-    /// ```
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity, Error};
-    /// use hex_literal::hex;
-    ///
-    /// // Using the wrong checksum!
-    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// match download_file(&url, &file, DownloadSecurity::all(&hex!("deadbeef")), None) {
-    ///     Err(Error::SecurityChecksum { .. }) => println!("Yeah that failed"),
-    /// #   got => panic!("Did not crash as expected, got {got:?}"),
-    /// }
-    /// ```
-    #[inline]
-    pub fn all(checkum: &'c [u8]) -> Self { Self { checksum: Some(checkum), https: true } }
-
-    /// Constructor for the DownloadSecurity that enables checksum verification only.
-    ///
-    /// Using this method is considered secure, since it guarantees that the downloaded file is what we expect. It is thus safe to use if you don't trust either the network or the remote praty.
-    ///
-    /// Note, however, that this method only works if you know a-priori what the downloaded file should look like. If not, you must use another security method (e.g., `DownloadSecurity::https()`).
-    ///
-    /// # Arguments
-    /// - `checksum`: The checksum that we want the file to have. If you are unsure, give a garbage checksum, then run the function once and check what the file had (after making sure the download went correctly, of course).
-    ///
-    /// # Returns
-    /// A new DownloadSecurity instance that will make sure your file has the given checksum before returning.
-    ///
-    /// # Example
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity};
-    /// use hex_literal::hex;
-    ///
-    /// // Download some file
-    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// download_file(
-    ///     &url,
-    ///     &file,
-    ///     DownloadSecurity::checksum(&hex!(
-    ///         "c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4"
-    ///     )),
-    ///     None,
-    /// )
-    /// .unwrap();
-    ///
-    /// // It exists now!
-    /// assert!(file.is_file());
-    /// assert!(std::fs::read_to_string(&file).is_ok());
-    /// ```
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity, Error};
-    /// use hex_literal::hex;
-    ///
-    /// // Using the wrong checksum!
-    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// match download_file(&url, &file, DownloadSecurity::checksum(&hex!("deadbeef")), None) {
-    ///     Err(Error::SecurityChecksum { .. }) => println!("Yeah that failed"),
-    /// #   got => panic!("Did not crash as expected, got {got:?}"),
-    /// }
-    /// ```
-    #[inline]
-    pub fn checksum(checkum: &'c [u8]) -> Self { Self { checksum: Some(checkum), https: false } }
-
-    /// Constructor for the DownloadSecurity that forces downloads to go over HTTPS.
-    ///
-    /// You should only use this method if you trust the remote party. However, if you do, then it guarantees that there was no man-in-the-middle changing the downloaded file.
-    ///
-    /// # Returns
-    /// A new DownloadSecurity instance that will make sure your file if downloaded over HTTPS only.
-    ///
-    /// # Example
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity};
-    ///
-    /// // Download some file
-    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// download_file(&url, &file, DownloadSecurity::https(), None).unwrap();
-    ///
-    /// // It exists now!
-    /// assert!(file.is_file());
-    /// assert!(std::fs::read_to_string(&file).is_ok());
-    /// ```
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity, Error};
-    ///
-    /// // Using a non-HTTPS URL
-    /// let url = "http://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// match download_file(&url, &file, DownloadSecurity::https(), None) {
-    ///     Err(Error::SecurityNoHttps { .. }) => println!("Yeah that failed"),
-    /// #   got => panic!("Did not crash as expected, got {got:?}"),
-    /// }
-    /// ```
-    #[inline]
-    pub fn https() -> Self { Self { checksum: None, https: true } }
-
-    /// Constructor for the DownloadSecurity that disabled all security measures.
-    ///
-    /// For obvious reasons, this security is not recommended unless you trust both the network _and_ the remote party.
-    ///
-    /// # Returns
-    /// A new DownloadSecurity instance that will require no additional security measures on the downloaded file.
-    ///
-    /// # Example
-    /// ```rust
-    /// use download::{download_file, DownloadSecurity};
-    ///
-    /// // Download some file
-    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
-    /// let file = std::env::temp_dir().join("index.html");
-    /// download_file(&url, &file, DownloadSecurity::none(), None).unwrap();
-    ///
-    /// // It exists now!
-    /// assert!(file.is_file());
-    /// assert!(std::fs::read_to_string(&file).is_ok());
-    /// ```
-    #[inline]
-    pub fn none() -> Self { Self { checksum: None, https: false } }
-}
-impl<'c> Display for DownloadSecurity<'c> {
+/***** FORMATTERS *****/
+/// Formats a chain of redirects as a suffix for error messages.
+struct RedirectChain<'c>(&'c [String]);
+impl<'c> Display for RedirectChain<'c> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
-        // Write what is enabled
-        if let Some(checksum) = &self.checksum {
-            write!(f, "Checksum ({})", hex::encode(checksum))?;
-            if self.https {
-                write!(f, ", HTTPS")?;
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        write!(f, " (redirected from ")?;
+        for (i, url) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
             }
-            Ok(())
-        } else if self.https {
-            write!(f, "HTTPS")
-        } else {
-            write!(f, "None")
+            write!(f, "'{url}'")?;
         }
+        write!(f, ")")
     }
 }
 
@@ -332,152 +270,2281 @@ impl<'c> Display for DownloadSecurity<'c> {
 
 
 
-/***** LIBRARY *****/
-/// Downloads some file from the interwebs to the given location.
-///
-/// If you enabled the `async-tokio` feature, also check the [`download_file_async()`]-function for async contexts.
+/***** HELPER FUNCTIONS *****/
+/// Runs a blocking closure on [`tokio`]'s blocking thread pool, like `blocking::unblock()` does for the `async-smol` feature.
 ///
 /// # Arguments
-/// - `source`: The URL to download the file from.
-/// - `target`: The location to download the file to.
-/// - `verification`: Some method to verify the file is what we think it is. See the `VerifyMethod`-enum for more information.
-/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+/// - `f`: The closure to run.
 ///
 /// # Returns
-/// Nothing, except that when it does you can assume a file exists at the given location.
-///
-/// # Errors
-/// This function may error if we failed to download the file or write it (which may happen if the parent directory of `local` does not exist, among other things).
-///
-/// # Example
-/// ```rust
-/// use download::{download_file, DownloadSecurity};
+/// Whatever the closure returned. If it panicked, the panic is resumed on the calling task.
+#[cfg(feature = "async-tokio")]
+async fn unblock_tokio<T: 'static + Send>(f: impl 'static + Send + FnOnce() -> T) -> T {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(res) => res,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Removes a partially downloaded target file, e.g., after aborting a download.
 ///
-/// // Download some file
-/// let url = "http://theuselessweb.com/index.html";
-/// let file = std::env::temp_dir().join("index.html");
-/// download_file(&url, &file, DownloadSecurity::none(), None).unwrap();
+/// Failures are ignored (only logged), since we're already on an error path anyway. The async drivers run this on their blocking thread pool.
 ///
-/// // It exists now!
-/// assert!(file.is_file());
-/// assert!(std::fs::read_to_string(&file).is_ok());
-/// ```
-pub fn download_file(source: impl AsRef<str>, target: impl AsRef<Path>, security: DownloadSecurity<'_>, verbose: Option<Style>) -> Result<(), Error> {
-    let source: &str = source.as_ref();
-    let target: &Path = target.as_ref();
-    debug!("Downloading '{}' to '{}' (Security: {})...", source, target.display(), security);
-    if let Some(style) = &verbose {
-        println!("Downloading {}...", style.apply_to(source));
+/// # Arguments
+/// - `target`: The path of the partial file to remove.
+fn remove_partial(target: &Path) {
+    debug!("Removing partially downloaded file '{}'...", target.display());
+    if let Err(_err) = fs::remove_file(target) {
+        debug!("Failed to remove partially downloaded file '{}': {}", target.display(), _err);
     }
+}
 
-    // Parse as a URL
-    let url: Url = match Url::from_str(source) {
-        Ok(url) => url,
-        Err(err) => return Err(Error::SourceParse { raw: source.into(), err }),
-    };
-
-    // Assert the download directory exists
-    if let Some(parent) = target.parent() {
-        if !parent.exists() {
-            return Err(Error::TargetParentNotFound { path: parent.into() });
-        }
-    }
 
-    // Open the target file for writing
-    let mut handle: fs::File = match fs::File::create(target) {
-        // Ok(handle) => {
-        //     // Prepare the permissions to set by reading the file's metadata
-        //     let mut permissions: Permissions = match handle.metadata() {
-        //         Ok(metadata) => metadata.permissions(),
-        //         Err(err)     => { return Err(Error::FileMetadataError{ what: "temporary binary", path: local.into(), err }); },
-        //     };
-        //     permissions.set_mode(permissions.mode() | 0o100);
 
-        //     // Set them
-        //     if let Err(err) = handle.set_permissions(permissions) { return Err(Error::FilePermissionsError{ what: "temporary binary", path: local.into(), err }); }
 
-        //     // Return the handle
-        //     handle
-        // },
-        Ok(handle) => handle,
-        Err(err) => {
-            return Err(Error::TargetCreate { path: target.into(), err });
-        },
-    };
 
-    // Send a request
-    let mut res: Response = if security.https {
-        debug!("Sending download request to '{}' (HTTPS enabled)...", url);
+/// Generates a unique, hidden name next to the given target for temporary files or directories.
+///
+/// # Arguments
+/// - `target`: The path of the target.
+/// - `attempt`: The number of earlier names that were already taken, to make sure we generate a different one.
+/// - `suffix`: The extension to give the temporary path (e.g., `part`).
+///
+/// # Returns
+/// A path of the form `.<name>.<random>.<suffix>` in the same directory as `target`.
+fn temp_path(target: &Path, attempt: u32, suffix: &str) -> PathBuf {
+    let name: &OsStr = target.file_name().unwrap_or(OsStr::new("download"));
+    let mut hasher: DefaultHasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u128(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default());
+    hasher.write_u32(attempt);
+    let mut file: OsString = OsString::from(".");
+    file.push(name);
+    file.push(format!(".{:016x}.{}", hasher.finish(), suffix));
+    target.with_file_name(file)
+}
 
-        // Assert the address starts with HTTPS first
-        if url.scheme() != "https" {
-            return Err(Error::SecurityNoHttps { url: url.into() });
+/// Creates something (e.g., a temporary file or directory) under a unique, hidden name next to the given target.
+///
+/// Names are generated by [`temp_path()`] until one is found that doesn't exist yet, so things that are there already are never touched.
+///
+/// # Arguments
+/// - `target`: The path of the target.
+/// - `suffix`: The extension to give the temporary path (e.g., `part`).
+/// - `create`: Creates the thing at the given path. Must fail with [`std::io::ErrorKind::AlreadyExists`] if something exists there already.
+///
+/// # Returns
+/// The path of the created thing, together with what `create` returned for it.
+///
+/// # Errors
+/// This function errors with the path and the error of the last attempt if `create` failed, or if we ran out of names to try.
+pub(crate) fn create_temp<T>(
+    target: &Path,
+    suffix: &str,
+    mut create: impl FnMut(&Path) -> std::io::Result<T>,
+) -> Result<(PathBuf, T), (PathBuf, std::io::Error)> {
+    const ATTEMPTS: u32 = 16;
+    let mut attempt: u32 = 0;
+    loop {
+        let temp: PathBuf = temp_path(target, attempt, suffix);
+        debug!("Creating temporary '{}'...", temp.display());
+        match create(&temp) {
+            Ok(res) => return Ok((temp, res)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists && attempt + 1 < ATTEMPTS => attempt += 1,
+            Err(err) => return Err((temp, err)),
+        }
+    }
+}
+
+/// Creates the temporary file that a target is downloaded to before it is moved into place.
+///
+/// The file is created next to the target (so that moving it is atomic) with a unique, hidden name (`.<name>.<random>.part`). It is always
+/// created anew, so existing files (or symlinks) are never truncated or followed, and concurrent downloads of the same target don't share it.
+///
+/// # Arguments
+/// - `target`: The path of the target.
+///
+/// # Returns
+/// The path of the temporary file, together with the handle to write to it.
+///
+/// # Errors
+/// This function errors if we failed to create the file.
+fn create_partial(target: &Path) -> Result<(PathBuf, fs::File), Error> {
+    match create_temp(target, "part", |partial| fs::OpenOptions::new().write(true).create_new(true).open(partial)) {
+        Ok((partial, handle)) => Ok((partial, handle)),
+        Err((path, err)) => Err(Error::TargetCreate { path, err }),
+    }
+}
+
+/// Gives a downloaded file the metadata dictated by the [`DownloadSecurity`].
+///
+/// # Arguments
+/// - `handle`: The (still opened) downloaded file.
+/// - `path`: The path of the downloaded file, for debugging purposes.
+/// - `persist`: The [`Persist`] describing what to set.
+///
+/// # Errors
+/// This function errors if we failed to set the permissions or the modification time.
+fn set_metadata(handle: &fs::File, path: &Path, persist: &Persist) -> Result<(), Error> {
+    #[cfg(unix)]
+    if let Some(mode) = persist.mode {
+        debug!("Setting permissions of '{}' to {:o}...", path.display(), mode);
+        if let Err(err) = handle.set_permissions(fs::Permissions::from_mode(mode)) {
+            return Err(Error::TargetMetadata { path: path.into(), err });
+        }
+    }
+    if let Some(modified) = persist.modified {
+        debug!("Setting modification time of '{}' to {}...", path.display(), httpdate::fmt_http_date(modified));
+        if let Err(err) = handle.set_modified(modified) {
+            return Err(Error::TargetMetadata { path: path.into(), err });
+        }
+    }
+    Ok(())
+}
+
+/// Makes sure a downloaded file fits on the target's filesystem before downloading it, and reserves the space if asked to.
+///
+/// # Arguments
+/// - `handle`: The (just created) file to download to.
+/// - `path`: The path of the file, for debugging purposes.
+/// - `core`: The [`DownloadCore`] that knows the response's length and what to check.
+///
+/// # Errors
+/// This function errors with [`Error::TargetNoSpace`] if the file doesn't fit. Other failures (e.g., filesystems not supporting the checks) are
+/// ignored, since the download may succeed anyway.
+#[cfg(unix)]
+fn preflight(handle: &impl AsFd, path: &Path, core: &DownloadCore) -> Result<(), Error> {
+    let Some(len) = core.content_length() else {
+        return Ok(());
+    };
+
+    // Compare the length with what's free
+    if let Some(margin) = core.security().free_space_margin {
+        match rustix::fs::fstatvfs(handle) {
+            Ok(stat) => {
+                let available: u64 = stat.f_bavail.saturating_mul(stat.f_frsize);
+                let needed: u64 = len.saturating_add(margin);
+                debug!("Target '{}' needs {} bytes, filesystem has {} bytes available", path.display(), needed, available);
+                if available < needed {
+                    return Err(Error::TargetNoSpace { path: path.into(), needed, available: Some(available) });
+                }
+            },
+            Err(_err) => {
+                debug!("Failed to get free space of filesystem of '{}': {}", path.display(), _err);
+            },
+        }
+    }
+
+    // Reserve the space (without changing the file's size, so short bodies don't leave garbage at the end)
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if core.security().preallocate && len > 0 {
+        debug!("Preallocating {} bytes for '{}'...", len, path.display());
+        match rustix::fs::fallocate(handle, rustix::fs::FallocateFlags::KEEP_SIZE, 0, len) {
+            Ok(_) => {},
+            Err(rustix::io::Errno::NOSPC) => return Err(Error::TargetNoSpace { path: path.into(), needed: len, available: None }),
+            Err(_err) => {
+                debug!("Failed to preallocate '{}': {}", path.display(), _err);
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Makes sure a downloaded file fits on the target's filesystem before downloading it.
+///
+/// This is a no-op on non-Unix platforms.
+///
+/// # Arguments
+/// - `handle`: The (just created) file to download to.
+/// - `path`: The path of the file, for debugging purposes.
+/// - `core`: The [`DownloadCore`] that knows the response's length and what to check.
+#[cfg(not(unix))]
+#[inline]
+fn preflight<F>(_handle: &F, _path: &Path, _core: &DownloadCore) -> Result<(), Error> { Ok(()) }
+
+/// Turns (the start of) a response body into something that can be safely shown to the user.
+///
+/// # Arguments
+/// - `body`: The raw bytes of the body.
+/// - `max`: The maximum number of bytes to show.
+///
+/// # Returns
+/// The leniently decoded body, without control characters (except newlines and tabs) and with `...` appended if it was cut off.
+fn sanitize(body: &[u8], max: usize) -> String {
+    let mut text: String = String::from_utf8_lossy(&body[..body.len().min(max)]).chars().filter(|c| !c.is_control() || *c == '\n' || *c == '\t').collect();
+    if body.len() > max {
+        text.push_str("...");
+    }
+    text
+}
+
+/// Creates the progress bar shown while downloading or hashing something.
+///
+/// # Arguments
+/// - `len`: The total number of bytes to process, if known.
+///
+/// # Returns
+/// A new [`ProgressBar`] that shows a bar if the length is known, or a spinner otherwise.
+fn progress_bar(len: Option<u64>) -> ProgressBar {
+    if let Some(len) = len {
+        ProgressBar::new(len).with_style(ProgressStyle::with_template("    {bar:60} {bytes}/{total_bytes} {bytes_per_sec} ETA {eta_precise}").unwrap())
+    } else {
+        ProgressBar::new_spinner().with_style(ProgressStyle::with_template("    {elapsed_precise} {bar:60} {bytes} {binary_bytes_per_sec}").unwrap())
+    }
+}
+
+/// Computes the SHA-256 checksum of an existing file.
+///
+/// The async drivers run this on their blocking thread pool.
+///
+/// # Arguments
+/// - `path`: The path of the file to hash.
+/// - `verbose`: Whether to show a progress bar while hashing.
+///
+/// # Returns
+/// The checksum of the file.
+///
+/// # Errors
+/// This function errors if we failed to read the file.
+fn hash_file(path: &Path, verbose: bool) -> Result<[u8; 32], Error> {
+    let mut handle: fs::File = match fs::File::open(path) {
+        Ok(handle) => handle,
+        Err(err) => return Err(Error::TargetRead { path: path.into(), err }),
+    };
+    let mut hashing: Hashing = Hashing::new(handle.metadata().ok().map(|md| md.len()), verbose);
+
+    // Hash the file chunk-by-chunk
+    let mut chunk: [u8; 65535] = [0; 65535];
+    loop {
+        match handle.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(len) => hashing.update(&chunk[..len]),
+            Err(err) => return Err(Error::TargetRead { path: path.into(), err }),
+        }
+    }
+    Ok(hashing.finish())
+}
+
+/// Asserts the certificate a server presented matches one of the pinned public keys, if any.
+///
+/// # Arguments
+/// - `url`: The URL of the server (used for debugging purposes only).
+/// - `tls`: The [`TlsConfig`] that lists the pins.
+/// - `certificate`: The DER-encoded certificate that the server presented, if any.
+///
+/// # Errors
+/// This function errors if there are pins but the presented certificate doesn't match any of them (or there is no certificate at all).
+#[cfg(feature = "__tls")]
+fn check_pins(url: &Url, tls: &TlsConfig, certificate: Option<&[u8]>) -> Result<(), Error> {
+    if tls.spki_pins.is_empty() {
+        return Ok(());
+    }
+    debug!("Verifying certificate of '{}' against {} pin(s)...", url, tls.spki_pins.len());
+
+    // Hash the public key of the peer's certificate
+    let got: Option<[u8; 32]> = certificate.and_then(|der| match x509_parser::parse_x509_certificate(der) {
+        Ok((_, cert)) => Some(spki_sha256(&cert)),
+        Err(_) => None,
+    });
+    if let Some(got) = got {
+        if tls.spki_pins.contains(&got) {
+            return Ok(());
+        }
+    }
+    Err(Error::SecurityCertificatePin {
+        url:      url.as_str().into(),
+        got:      got.map(hex::encode),
+        expected: tls.spki_pins.iter().map(hex::encode).collect(),
+    })
+}
+
+/// Computes the SHA-256 hash of a certificate's DER-encoded SubjectPublicKeyInfo, i.e., its SPKI pin.
+///
+/// # Arguments
+/// - `cert`: The [`X509Certificate`] to compute the pin of.
+///
+/// # Returns
+/// The raw pin.
+#[cfg(feature = "__tls")]
+#[inline]
+fn spki_sha256(cert: &X509Certificate) -> [u8; 32] { Sha256::digest(cert.tbs_certificate.subject_pki.raw).into() }
+
+/// Finds out where a response redirects us to, if anywhere.
+///
+/// # Arguments
+/// - `url`: The URL that returned the response (used to resolve relative locations).
+/// - `status`: The status code of the response.
+/// - `headers`: The headers of the response.
+/// - `chain`: The addresses we were redirected from so far, to embed in errors.
+///
+/// # Returns
+/// The URL to redirect to, or [`None`] if this response is not a redirect (or has no `Location` to redirect to).
+///
+/// # Errors
+/// This function errors if the response's `Location`-header is not a valid (relative) URL.
+fn redirect_target(url: &Url, status: StatusCode, headers: &HeaderMap, chain: &[String]) -> Result<Option<Url>, Error> {
+    if !matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return Ok(None);
+    }
+    let location: &str = match headers.get(LOCATION).map(|loc| loc.to_str()) {
+        Some(Ok(location)) => location,
+        Some(Err(_)) => {
+            return Err(Error::RedirectLocation {
+                url:      url.as_str().into(),
+                location: String::from_utf8_lossy(headers[LOCATION].as_bytes()).into(),
+                chain:    chain.into(),
+            });
+        },
+        None => return Ok(None),
+    };
+    match url.join(location) {
+        Ok(next) => Ok(Some(next)),
+        Err(_) => Err(Error::RedirectLocation { url: url.as_str().into(), location: location.into(), chain: chain.into() }),
+    }
+}
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Explains why a [`RedirectPolicy`] refused to follow a redirect.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RedirectViolation {
+    /// We were redirected more often than the policy allows.
+    TooManyHops { max: usize },
+    /// The policy only allows redirects to the same origin (scheme, host and port) as the original URL.
+    CrossOrigin { origin: String },
+    /// The policy only allows redirects to a specific set of hosts, and this isn't one of them.
+    HostNotAllowed { host: Option<String> },
+}
+impl Display for RedirectViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use RedirectViolation::*;
+        match self {
+            TooManyHops { max } => write!(f, "more than {max} redirects"),
+            CrossOrigin { origin } => write!(f, "only redirects within origin '{origin}' are allowed"),
+            HostNotAllowed { host: Some(host) } => write!(f, "host '{host}' is not in the list of allowed hosts"),
+            HostNotAllowed { host: None } => write!(f, "URL has no host to check against the list of allowed hosts"),
+        }
+    }
+}
+
+/// Defines which redirects we're willing to follow when downloading a file.
+///
+/// Note that, regardless of this policy, [`DownloadSecurity::https`] is enforced on every hop.
+#[derive(Clone, Debug)]
+pub struct RedirectPolicy {
+    /// The maximum number of redirects to follow. Use `0` to disable redirects altogether.
+    pub max_hops: usize,
+    /// If true, only redirects to the same origin (scheme, host and port) as the original URL are followed.
+    pub same_origin: bool,
+    /// If not `None`, then only redirects to one of these hosts are followed.
+    pub allowed_hosts: Option<Vec<String>>,
+}
+impl Default for RedirectPolicy {
+    /// Follows up to 10 redirects to anywhere (same as [`reqwest`]'s default).
+    #[inline]
+    fn default() -> Self { Self::limited(10) }
+}
+impl RedirectPolicy {
+    /// Constructor for a RedirectPolicy that doesn't follow any redirects.
+    ///
+    /// # Returns
+    /// A new RedirectPolicy that will refuse the first redirect it sees.
+    #[inline]
+    pub fn none() -> Self { Self::limited(0) }
+
+    /// Constructor for a RedirectPolicy that follows a limited number of redirects to anywhere.
+    ///
+    /// # Arguments
+    /// - `max_hops`: The maximum number of redirects to follow.
+    ///
+    /// # Returns
+    /// A new RedirectPolicy that will follow up to `max_hops` redirects.
+    #[inline]
+    pub fn limited(max_hops: usize) -> Self { Self { max_hops, same_origin: false, allowed_hosts: None } }
+
+    /// Constructor for a RedirectPolicy that only follows redirects to the same origin as the original URL.
+    ///
+    /// # Arguments
+    /// - `max_hops`: The maximum number of redirects to follow.
+    ///
+    /// # Returns
+    /// A new RedirectPolicy that will follow up to `max_hops` redirects, as long as they stay on the same scheme, host and port.
+    #[inline]
+    pub fn same_origin(max_hops: usize) -> Self { Self { max_hops, same_origin: true, allowed_hosts: None } }
+
+    /// Constructor for a RedirectPolicy that only follows redirects to the given hosts.
+    ///
+    /// # Arguments
+    /// - `max_hops`: The maximum number of redirects to follow.
+    /// - `hosts`: The hosts (e.g., `objects.githubusercontent.com`) that we may be redirected to.
+    ///
+    /// # Returns
+    /// A new RedirectPolicy that will follow up to `max_hops` redirects, as long as they go to one of the given `hosts`.
+    #[inline]
+    pub fn allow_hosts(max_hops: usize, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self { max_hops, same_origin: false, allowed_hosts: Some(hosts.into_iter().map(Into::into).collect()) }
+    }
+
+    /// Checks whether a redirect is allowed by this policy.
+    ///
+    /// # Arguments
+    /// - `origin`: The URL that the user originally asked for.
+    /// - `hops`: The number of redirects followed so far, including this one.
+    /// - `next`: The URL we are being redirected to.
+    ///
+    /// # Errors
+    /// This function errors with the reason why the redirect is not allowed, if it isn't.
+    pub fn check(&self, origin: &Url, hops: usize, next: &Url) -> Result<(), RedirectViolation> {
+        if hops > self.max_hops {
+            return Err(RedirectViolation::TooManyHops { max: self.max_hops });
+        }
+        if self.same_origin && origin.origin() != next.origin() {
+            return Err(RedirectViolation::CrossOrigin { origin: origin.origin().ascii_serialization() });
+        }
+        if let Some(hosts) = &self.allowed_hosts {
+            if !next.host_str().map(|host| hosts.iter().any(|h| h.eq_ignore_ascii_case(host))).unwrap_or(false) {
+                return Err(RedirectViolation::HostNotAllowed { host: next.host_str().map(String::from) });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Defines a root certificate to trust when downloading files.
+#[cfg(feature = "__tls")]
+#[derive(Clone, Debug)]
+pub enum RootCertificate {
+    /// A PEM-encoded certificate (i.e., `-----BEGIN CERTIFICATE-----`...).
+    Pem(Vec<u8>),
+    /// A DER-encoded certificate.
+    Der(Vec<u8>),
+}
+#[cfg(feature = "__tls")]
+impl RootCertificate {
+    /// Parses this RootCertificate as something [`reqwest`] understands.
+    ///
+    /// # Returns
+    /// A [`Certificate`] that can be given to a client.
+    ///
+    /// # Errors
+    /// This function errors if the certificate was not valid PEM or DER.
+    #[inline]
+    pub(crate) fn load(&self) -> Result<Certificate, reqwest::Error> {
+        match self {
+            Self::Pem(pem) => Certificate::from_pem(pem),
+            Self::Der(der) => Certificate::from_der(der),
+        }
+    }
+}
+
+/// Defines how the TLS connection of a download is verified beyond the default checks.
+///
+/// Only available if one of the TLS features (e.g., `native-tls` or `rustls-tls`) is enabled.
+#[cfg(feature = "__tls")]
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// Root certificates to trust in addition to the built-in ones (e.g., the CA of an internal mirror).
+    pub extra_roots:   Vec<RootCertificate>,
+    /// Whether to trust the built-in root certificates (i.e., those of the system or bundled ones, depending on the TLS backend).
+    pub builtin_roots: bool,
+    /// If not empty, then the server we download from must present a certificate whose public key hashes to one of these.
+    ///
+    /// Every pin is the SHA-256 hash of the DER-encoded SubjectPublicKeyInfo of the certificate (i.e., the same as HPKP pins, but not base64-encoded).
+    pub spki_pins:     Vec<[u8; 32]>,
+}
+#[cfg(feature = "__tls")]
+impl Default for TlsConfig {
+    /// Trusts the built-in roots only and doesn't pin anything.
+    #[inline]
+    fn default() -> Self { Self { extra_roots: Vec::new(), builtin_roots: true, spki_pins: Vec::new() } }
+}
+#[cfg(feature = "__tls")]
+impl TlsConfig {
+    /// Adds a root certificate to trust.
+    ///
+    /// # Arguments
+    /// - `root`: The [`RootCertificate`] to trust in addition to the others.
+    ///
+    /// # Returns
+    /// The same TlsConfig but with the extra root.
+    #[inline]
+    pub fn with_root(mut self, root: RootCertificate) -> Self {
+        self.extra_roots.push(root);
+        self
+    }
+
+    /// Changes whether the built-in root certificates are trusted.
+    ///
+    /// # Arguments
+    /// - `builtin_roots`: If false, only the roots given with [`TlsConfig::with_root()`] are trusted.
+    ///
+    /// # Returns
+    /// The same TlsConfig but with the built-in roots enabled or disabled.
+    #[inline]
+    pub fn with_builtin_roots(mut self, builtin_roots: bool) -> Self {
+        self.builtin_roots = builtin_roots;
+        self
+    }
+
+    /// Adds a public key pin.
+    ///
+    /// If at least one pin is given, the server we download from must present a certificate matching one of them.
+    ///
+    /// # Arguments
+    /// - `pin`: The SHA-256 hash of the DER-encoded SubjectPublicKeyInfo of an acceptable certificate.
+    ///
+    /// # Returns
+    /// The same TlsConfig but with the extra pin.
+    #[inline]
+    pub fn with_spki_pin(mut self, pin: [u8; 32]) -> Self {
+        self.spki_pins.push(pin);
+        self
+    }
+}
+
+/// Explains why a [`ContentCheck`] refused a response.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ContentViolation {
+    /// The response's `Content-Type` is not one of the expected ones.
+    ContentType { got: String, expected: Vec<String> },
+    /// The response is an HTML page, judging by its `Content-Type` or its first bytes.
+    Html,
+    /// The body does not start with the expected magic bytes.
+    Magic { expected: Vec<u8>, got: Vec<u8> },
+}
+impl Display for ContentViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use ContentViolation::*;
+        match self {
+            ContentType { got, expected } => write!(f, "content type '{}' is not one of '{}'", got, expected.join("', '")),
+            Html => write!(f, "got an HTML page"),
+            Magic { expected, got } => write!(f, "expected body to start with {}, got {}", hex::encode(expected), hex::encode(got)),
+        }
+    }
+}
+
+/// Defines what the body of a download is expected to look like, to catch servers returning something else with a success status.
+///
+/// Misconfigured mirrors often return an HTML login- or error-page with a `200 OK`, which would otherwise only be caught by a checksum (if any) or
+/// when the file is used (e.g., with a confusing gzip error when unarchiving it). By default, nothing is checked.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ContentCheck {
+    /// If not empty, the response's `Content-Type` must be one of these (e.g., `application/gzip`). Parameters (e.g., `; charset=utf-8`) and case
+    /// are ignored. Responses without a `Content-Type` are accepted.
+    pub content_types: Vec<String>,
+    /// If not `None`, the body must start with these bytes.
+    pub magic: Option<Vec<u8>>,
+    /// If true, HTML pages are refused, judging by their `Content-Type` or by their first bytes (e.g., `<!DOCTYPE html>`).
+    pub reject_html: bool,
+}
+impl ContentCheck {
+    /// Constructor for a ContentCheck that doesn't check anything.
+    ///
+    /// # Returns
+    /// A new ContentCheck that accepts everything.
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Constructor for a ContentCheck that expects a gzip-compressed file (e.g., a `.tar.gz`-file).
+    ///
+    /// # Returns
+    /// A new ContentCheck that refuses HTML pages and bodies not starting with the gzip magic bytes (`1f 8b`).
+    #[inline]
+    pub fn gzip() -> Self { Self::new().with_reject_html().with_magic([0x1F, 0x8B]) }
+
+    /// Adds a `Content-Type` that the response may have.
+    ///
+    /// # Arguments
+    /// - `content_type`: The content type to allow, without parameters (e.g., `application/gzip`).
+    ///
+    /// # Returns
+    /// The same ContentCheck but with the extra content type allowed.
+    #[inline]
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_types.push(content_type.into());
+        self
+    }
+
+    /// Requires the body to start with the given bytes.
+    ///
+    /// # Arguments
+    /// - `magic`: The bytes that the body should start with.
+    ///
+    /// # Returns
+    /// The same ContentCheck but with the magic bytes set.
+    #[inline]
+    pub fn with_magic(mut self, magic: impl Into<Vec<u8>>) -> Self {
+        self.magic = Some(magic.into());
+        self
+    }
+
+    /// Refuses HTML pages.
+    ///
+    /// # Returns
+    /// The same ContentCheck but refusing HTML.
+    #[inline]
+    pub fn with_reject_html(mut self) -> Self {
+        self.reject_html = true;
+        self
+    }
+
+    /// Returns whether this check checks anything at all.
+    ///
+    /// # Returns
+    /// True if every response is accepted, or false otherwise.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.content_types.is_empty() && self.magic.is_none() && !self.reject_html }
+
+    /// Checks the `Content-Type` of a response.
+    ///
+    /// # Arguments
+    /// - `content_type`: The value of the `Content-Type`-header, if any.
+    ///
+    /// # Errors
+    /// This function errors with the reason if the content type is not acceptable.
+    fn check_type(&self, content_type: Option<&str>) -> Result<(), ContentViolation> {
+        let essence: String = match content_type {
+            Some(content_type) => content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase(),
+            None => return Ok(()),
+        };
+        if self.reject_html && (essence == "text/html" || essence == "application/xhtml+xml") {
+            return Err(ContentViolation::Html);
+        }
+        if !self.content_types.is_empty() && !self.content_types.iter().any(|expected| expected.eq_ignore_ascii_case(&essence)) {
+            return Err(ContentViolation::ContentType { got: essence, expected: self.content_types.clone() });
+        }
+        Ok(())
+    }
+
+    /// Checks the first bytes of a body.
+    ///
+    /// # Arguments
+    /// - `head`: The first (up to [`SNIFF_LEN`]) bytes of the body, or the whole body if it's shorter.
+    ///
+    /// # Errors
+    /// This function errors with the reason if the body is not acceptable.
+    fn check_body(&self, head: &[u8]) -> Result<(), ContentViolation> {
+        if self.reject_html {
+            // Skip any byte-order mark and whitespace before looking for tags
+            let start: &[u8] = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+            let start: &[u8] = &start[start.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(start.len())..];
+            if [&b"<!doctype html"[..], b"<html", b"<head", b"<body", b"<title"]
+                .iter()
+                .any(|tag| start.len() >= tag.len() && start[..tag.len()].eq_ignore_ascii_case(tag))
+            {
+                return Err(ContentViolation::Html);
+            }
+        }
+        if let Some(magic) = &self.magic {
+            if !head.starts_with(magic) {
+                return Err(ContentViolation::Magic { expected: magic.clone(), got: head[..head.len().min(magic.len())].into() });
+            }
+        }
+        Ok(())
+    }
+}
+
+
+
+/// Describes a successful download (see [`download_file_report()`]).
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "manifest"), allow(dead_code))]
+pub(crate) struct DownloadReport {
+    /// The URL that the file was eventually downloaded from (i.e., after redirects).
+    pub url:    Url,
+    /// The SHA-256 checksum of the downloaded file.
+    pub sha256: [u8; 32],
+}
+
+
+
+/// Describes what [`ensure_file()`] (or [`ensure_file_async()`]) had to do to make sure the target exists.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ensured {
+    /// The target already existed with the expected checksum, so nothing was downloaded.
+    AlreadyPresent,
+    /// The target was (re-)downloaded.
+    Downloaded,
+}
+
+
+
+/// Describes a remote file without downloading it (see [`probe()`]).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Probe {
+    /// The status code of the final response (i.e., after redirects).
+    ///
+    /// Note that a non-success status is not an error; in that case, the other fields describe nothing and are left empty.
+    pub status: StatusCode,
+    /// The URL that the file would eventually be downloaded from (i.e., after redirects).
+    pub url: Url,
+    /// The length of the file in bytes, if the server announced it.
+    pub content_length: Option<u64>,
+    /// The `Content-Type` of the file, if the server announced it.
+    pub content_type: Option<String>,
+    /// The (quoted) `ETag` of the file, if the server announced it.
+    pub etag: Option<String>,
+    /// When the file was last modified, if the server announced it (in a valid format).
+    pub last_modified: Option<SystemTime>,
+    /// Whether the server supports `Range`-requests for the file (i.e., whether downloads of it can be resumed).
+    pub ranges: bool,
+}
+impl Probe {
+    /// Returns whether the file exists, i.e., whether the server answered with a success status.
+    ///
+    /// # Returns
+    /// True if [`Probe::status`] is a `2xx`-code, or false otherwise.
+    #[inline]
+    pub fn exists(&self) -> bool { self.status.is_success() }
+}
+
+
+
+/// Defines things to do to assert a downloaded file is secure and what we expect.
+#[derive(Clone, Debug)]
+pub struct DownloadSecurity<'c> {
+    /// If not `None`, then it defined the checksum that the file should have.
+    pub checksum: Option<&'c [u8]>,
+    /// If true, then the file can only be downloaded over HTTPS.
+    pub https:    bool,
+    /// If not `None`, then the download is aborted (and the target removed) as soon as it becomes larger than this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Determines which redirects will be followed. Note that `https` is checked for every hop as well.
+    pub redirects: RedirectPolicy,
+    /// Determines what the body should look like (e.g., not an HTML error page). Checks nothing by default.
+    pub content: ContentCheck,
+    /// The maximum number of bytes of the body of a non-OK response to read and embed in [`Error::ResponseNotOk`]. If 0, it is not read at all.
+    pub max_error_body: usize,
+    /// Determines which certificates are trusted for HTTPS-connections.
+    #[cfg(feature = "__tls")]
+    pub tls: TlsConfig,
+    /// If not `None`, then the target is given these Unix permissions (e.g., `0o755` for executables) before it appears.
+    #[cfg(unix)]
+    pub mode: Option<u32>,
+    /// If true, then the target's modification time is set to the response's `Last-Modified`-header (if it has a valid one) before it appears.
+    pub last_modified: bool,
+    /// If not `None`, then the download is refused up front if the target's filesystem has less than the response's `Content-Length` plus this
+    /// many bytes free. Only checked on Unix platforms, and only if the server announces the length.
+    pub free_space_margin: Option<u64>,
+    /// If true, then the space for the response's `Content-Length` is reserved before downloading it (using `fallocate`), which guarantees the
+    /// space and reduces fragmentation. Only done on Linux, and only if the server announces the length and the filesystem supports it.
+    pub preallocate: bool,
+}
+impl<'c> DownloadSecurity<'c> {
+    /// Constructor for the DownloadSecurity that enables with all security measures enabled.
+    ///
+    /// This will provide you with the most security, but is also the slowest method (since it does both encryption and checksum computation).
+    ///
+    /// Usually, it sufficies to only use a checksum (`DownloadSecurity::checksum()`) if you know what the file looks like a-priori.
+    ///
+    /// # Arguments
+    /// - `checksum`: The checksum that we want the file to have. If you are unsure, give a garbage checksum, then run the function once and check what the file had (after making sure the download went correctly, of course).
+    ///
+    /// # Returns
+    /// A new DownloadSecurity instance that will make your downloaded file so secure you can use it to store a country's deficit (not legal advice).
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity};
+    /// use hex_literal::hex;
+    ///
+    /// // Download some file
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// download_file(
+    ///     &url,
+    ///     &file,
+    ///     DownloadSecurity::all(&hex!(
+    ///         "c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4"
+    ///     )),
+    ///     None,
+    /// )
+    /// .unwrap();
+    ///
+    /// // It exists now!
+    /// assert!(file.is_file());
+    /// assert!(std::fs::read_to_string(&file).is_ok());
+    /// ```
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error};
+    /// use hex_literal::hex;
+    ///
+    /// // Using a non-HTTPS URL
+    /// let url = "http://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// match download_file(&url, &file, DownloadSecurity::all(&hex!("deadbeef")), None) {
+    ///     Err(Error::SecurityNoHttps { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error};
+    /// use hex_literal::hex;
+    ///
+    /// // Using the wrong checksum!
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// match download_file(&url, &file, DownloadSecurity::all(&hex!("deadbeef")), None) {
+    ///     Err(Error::SecurityChecksum { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn all(checkum: &'c [u8]) -> Self { Self { checksum: Some(checkum), https: true, ..Self::none() } }
+
+    /// Constructor for the DownloadSecurity that enables checksum verification only.
+    ///
+    /// Using this method is considered secure, since it guarantees that the downloaded file is what we expect. It is thus safe to use if you don't trust either the network or the remote praty.
+    ///
+    /// Note, however, that this method only works if you know a-priori what the downloaded file should look like. If not, you must use another security method (e.g., `DownloadSecurity::https()`).
+    ///
+    /// # Arguments
+    /// - `checksum`: The checksum that we want the file to have. If you are unsure, give a garbage checksum, then run the function once and check what the file had (after making sure the download went correctly, of course).
+    ///
+    /// # Returns
+    /// A new DownloadSecurity instance that will make sure your file has the given checksum before returning.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity};
+    /// use hex_literal::hex;
+    ///
+    /// // Download some file
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// download_file(
+    ///     &url,
+    ///     &file,
+    ///     DownloadSecurity::checksum(&hex!(
+    ///         "c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4"
+    ///     )),
+    ///     None,
+    /// )
+    /// .unwrap();
+    ///
+    /// // It exists now!
+    /// assert!(file.is_file());
+    /// assert!(std::fs::read_to_string(&file).is_ok());
+    /// ```
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error};
+    /// use hex_literal::hex;
+    ///
+    /// // Using the wrong checksum!
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// match download_file(&url, &file, DownloadSecurity::checksum(&hex!("deadbeef")), None) {
+    ///     Err(Error::SecurityChecksum { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn checksum(checkum: &'c [u8]) -> Self { Self { checksum: Some(checkum), ..Self::none() } }
+
+    /// Constructor for the DownloadSecurity that forces downloads to go over HTTPS.
+    ///
+    /// You should only use this method if you trust the remote party. However, if you do, then it guarantees that there was no man-in-the-middle changing the downloaded file.
+    ///
+    /// # Returns
+    /// A new DownloadSecurity instance that will make sure your file if downloaded over HTTPS only.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity};
+    ///
+    /// // Download some file
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// download_file(&url, &file, DownloadSecurity::https(), None).unwrap();
+    ///
+    /// // It exists now!
+    /// assert!(file.is_file());
+    /// assert!(std::fs::read_to_string(&file).is_ok());
+    /// ```
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error};
+    ///
+    /// // Using a non-HTTPS URL
+    /// let url = "http://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// match download_file(&url, &file, DownloadSecurity::https(), None) {
+    ///     Err(Error::SecurityNoHttps { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn https() -> Self { Self { https: true, ..Self::none() } }
+
+    /// Constructor for the DownloadSecurity that disabled all security measures.
+    ///
+    /// For obvious reasons, this security is not recommended unless you trust both the network _and_ the remote party.
+    ///
+    /// # Returns
+    /// A new DownloadSecurity instance that will require no additional security measures on the downloaded file.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity};
+    ///
+    /// // Download some file
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("index.html");
+    /// download_file(&url, &file, DownloadSecurity::none(), None).unwrap();
+    ///
+    /// // It exists now!
+    /// assert!(file.is_file());
+    /// assert!(std::fs::read_to_string(&file).is_ok());
+    /// ```
+    #[inline]
+    pub fn none() -> Self {
+        Self {
+            checksum: None,
+            https: false,
+            max_bytes: None,
+            redirects: RedirectPolicy::default(),
+            content: ContentCheck::new(),
+            max_error_body: 4096,
+            #[cfg(feature = "__tls")]
+            tls: TlsConfig::default(),
+            #[cfg(unix)]
+            mode: None,
+            last_modified: false,
+            free_space_margin: Some(0),
+            preallocate: false,
+        }
+    }
+
+    /// Limits the size of the downloaded file to the given number of bytes.
+    ///
+    /// Responses announcing a larger `Content-Length` are rejected before anything is downloaded, and responses that turn out to be larger
+    /// while streaming are aborted as soon as the limit is passed. In both cases, the target is left untouched (and the temporary file that was
+    /// being downloaded to is removed).
+    ///
+    /// # Arguments
+    /// - `max_bytes`: The maximum number of bytes the downloaded file may have.
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the limit set.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error};
+    ///
+    /// // The licence is definitely larger than 16 bytes
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("max_bytes_LICENSE");
+    /// # if file.exists() { std::fs::remove_file(&file).unwrap(); }
+    /// match download_file(&url, &file, DownloadSecurity::https().with_max_bytes(16), None) {
+    ///     Err(Error::ResponseTooLarge { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// assert!(!file.exists());
+    /// ```
+    #[inline]
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Changes which redirects are followed when downloading.
+    ///
+    /// # Arguments
+    /// - `redirects`: The [`RedirectPolicy`] to use instead of the default (which follows up to 10 redirects to anywhere).
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the new redirect policy.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error, RedirectPolicy};
+    ///
+    /// // GitHub redirects this to a different host, which we don't allow
+    /// let url = "https://github.com/Lut99/download-rs/raw/main/LICENSE";
+    /// let file = std::env::temp_dir().join("LICENSE");
+    /// match download_file(&url, &file, DownloadSecurity::https().with_redirects(RedirectPolicy::same_origin(10)), None) {
+    ///     Err(Error::RedirectNotAllowed { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_redirects(mut self, redirects: RedirectPolicy) -> Self {
+        self.redirects = redirects;
+        self
+    }
+
+    /// Checks that the body looks like what we expect, e.g., to refuse HTML error pages served instead of a tarball.
+    ///
+    /// The check is done on the `Content-Type`-header and the first bytes of the body, so it fails early and with an [`Error::UnexpectedContent`]
+    /// that shows what the server sent instead.
+    ///
+    /// # Arguments
+    /// - `content`: The [`ContentCheck`] to apply.
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the content check set.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, ContentCheck, DownloadSecurity, Error};
+    ///
+    /// // The licence is certainly not gzipped
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("LICENSE.tar.gz");
+    /// match download_file(&url, &file, DownloadSecurity::https().with_content(ContentCheck::gzip()), None) {
+    ///     Err(Error::UnexpectedContent { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_content(mut self, content: ContentCheck) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Changes how much of the body of a non-OK response is captured in an [`Error::ResponseNotOk`].
+    ///
+    /// By default, up to 4 KiB is read, which is usually enough to see what an error page says without risking large (or binary) bodies
+    /// flooding your logs or memory.
+    ///
+    /// # Arguments
+    /// - `max_error_body`: The maximum number of bytes to capture, or 0 to not read the body at all.
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the new limit.
+    #[inline]
+    pub fn with_max_error_body(mut self, max_error_body: usize) -> Self {
+        self.max_error_body = max_error_body;
+        self
+    }
+
+    /// Changes which certificates are trusted when downloading over HTTPS.
+    ///
+    /// Only available if one of the TLS features (e.g., `native-tls` or `rustls-tls`) is enabled.
+    ///
+    /// # Arguments
+    /// - `tls`: The [`TlsConfig`] to use instead of the default (which trusts the built-in roots only).
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the new TLS configuration.
+    #[cfg(feature = "__tls")]
+    #[inline]
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Sets the Unix permissions of the downloaded file.
+    ///
+    /// They are applied to the temporary file that is downloaded to, before it is moved to the target; so the target never exists without them.
+    ///
+    /// Only available on Unix platforms.
+    ///
+    /// # Arguments
+    /// - `mode`: The permissions to give the target (e.g., `0o755` for executables).
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the permissions set.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::os::unix::fs::PermissionsExt as _;
+    ///
+    /// use download::{download_file, DownloadSecurity};
+    ///
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("mode_LICENSE");
+    /// download_file(&url, &file, DownloadSecurity::https().with_mode(0o755), None).unwrap();
+    /// assert_eq!(std::fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o755);
+    /// ```
+    #[cfg(unix)]
+    #[inline]
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the modification time of the downloaded file to the `Last-Modified`-header of the response.
+    ///
+    /// Like the [mode](DownloadSecurity::with_mode()), it is applied before the file is moved to the target. Responses without a (valid) header
+    /// leave the modification time at the time of downloading.
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the modification time being copied.
+    #[inline]
+    pub fn with_last_modified(mut self) -> Self {
+        self.last_modified = true;
+        self
+    }
+
+    /// Changes how much space should be left on the target's filesystem after downloading.
+    ///
+    /// By default, downloads are refused up front if the response's `Content-Length` doesn't fit on the target's filesystem (i.e., the margin is
+    /// 0). This saves downloading (most of) a file, only to fail with an [`Error::TargetWrite`] at the end.
+    ///
+    /// # Arguments
+    /// - `margin`: The number of bytes that should still be free after downloading, or [`None`] to disable the check altogether.
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with the new margin.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::{download_file, DownloadSecurity, Error};
+    ///
+    /// // Nobody has this much space to spare
+    /// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+    /// let file = std::env::temp_dir().join("LICENSE");
+    /// match download_file(&url, &file, DownloadSecurity::https().with_free_space_margin(Some(u64::MAX / 2)), None) {
+    ///     Err(Error::TargetNoSpace { .. }) => println!("Yeah that failed"),
+    /// #   got => panic!("Did not crash as expected, got {got:?}"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_free_space_margin(mut self, margin: Option<u64>) -> Self {
+        self.free_space_margin = margin;
+        self
+    }
+
+    /// Reserves the space for the downloaded file before downloading it.
+    ///
+    /// This guarantees that the download doesn't run out of space halfway (failing with an [`Error::TargetNoSpace`] up front instead), and reduces
+    /// fragmentation of large files. Only done on Linux, and only if the server announces the `Content-Length`.
+    ///
+    /// # Returns
+    /// The same DownloadSecurity but with preallocation enabled.
+    #[inline]
+    pub fn with_preallocate(mut self) -> Self {
+        self.preallocate = true;
+        self
+    }
+}
+impl<'c> Display for DownloadSecurity<'c> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        // Collect what is enabled
+        let mut measures: Vec<String> = Vec::with_capacity(3);
+        if let Some(checksum) = &self.checksum {
+            measures.push(format!("Checksum ({})", hex::encode(checksum)));
+        }
+        if self.https {
+            measures.push("HTTPS".into());
+        }
+        if let Some(max) = self.max_bytes {
+            measures.push(format!("Max {max} bytes"));
+        }
+        if !self.content.is_empty() {
+            measures.push("Content check".into());
+        }
+
+        // Write it
+        if measures.is_empty() { write!(f, "None") } else { write!(f, "{}", measures.join(", ")) }
+    }
+}
+
+
+
+
+
+/***** CORE *****/
+/// The number of bytes at the start of a body that are given to [`ContentCheck::check_body()`] (unless a longer magic is expected).
+const SNIFF_LEN: usize = 512;
+/// The maximum number of bytes of a body shown in an [`Error::UnexpectedContent`].
+const SNIPPET_LEN: usize = 256;
+
+/// Prints that a checksum matched, if asked.
+///
+/// # Arguments
+/// - `checksum`: The checksum that matched.
+/// - `note`: Something to add after the "OK" (e.g., `" (already present)"`).
+/// - `verbose`: If not `None`, prints with accents given in the given `Style`.
+fn print_checksum_ok(checksum: &[u8], note: &str, verbose: Option<Style>) {
+    if let Some(style) = verbose {
+        // Create the dim styles
+        let dim: Style = Style::new().dim();
+        let accent: Style = style.dim();
+
+        // Write it with those styles
+        println!("{}{}{}", dim.apply_to(" > Checksum "), accent.apply_to(hex::encode(checksum)), dim.apply_to(format!(" OK{note}")));
+    }
+}
+
+
+
+
+/// Computes a SHA-256 checksum chunk-by-chunk, optionally showing progress.
+struct Hashing {
+    /// The hasher that is being fed.
+    hasher: Sha256,
+    /// The progress bar to update, if any.
+    prgs:   Option<ProgressBar>,
+}
+impl Hashing {
+    /// Constructor for the Hashing.
+    ///
+    /// # Arguments
+    /// - `len`: The total number of bytes to hash, if known.
+    /// - `verbose`: Whether to show a progress bar.
+    ///
+    /// # Returns
+    /// A new Hashing that hasn't hashed anything yet.
+    #[inline]
+    fn new(len: Option<u64>, verbose: bool) -> Self { Self { hasher: Sha256::new(), prgs: if verbose { Some(progress_bar(len)) } else { None } } }
+
+    /// Hashes the next chunk.
+    ///
+    /// # Arguments
+    /// - `chunk`: The next bytes to hash.
+    #[inline]
+    fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        if let Some(prgs) = &self.prgs {
+            prgs.inc(chunk.len() as u64);
+        }
+    }
+
+    /// Finishes hashing.
+    ///
+    /// # Returns
+    /// The checksum of everything given to [`Hashing::update()`].
+    #[inline]
+    fn finish(self) -> [u8; 32] {
+        if let Some(prgs) = &self.prgs {
+            prgs.finish_and_clear();
+        }
+        self.hasher.finalize().into()
+    }
+}
+
+
+
+/// Follows the redirects of a request as far as the [`DownloadSecurity`] allows, without doing any IO itself.
+///
+/// Shared by the [`DownloadCore`] and the [`ProbeCore`], which call [`Hops::request()`] before every request and [`Hops::on_response()`] after
+/// every response.
+struct Hops {
+    /// The URL that the user asked for.
+    origin: Url,
+    /// The URL that we're currently requesting.
+    url:    Url,
+    /// The URLs that redirected us to `url`.
+    chain:  Vec<String>,
+}
+impl Hops {
+    /// Constructor for Hops that haven't been redirected yet.
+    ///
+    /// # Arguments
+    /// - `url`: The URL that the user asked for.
+    ///
+    /// # Returns
+    /// New Hops that will request the given URL first.
+    #[inline]
+    fn new(url: Url) -> Self { Self { origin: url.clone(), url, chain: Vec::new() } }
+
+    /// Returns the URL that the next request should be sent to.
+    ///
+    /// # Arguments
+    /// - `security`: The [`DownloadSecurity`] to enforce.
+    ///
+    /// # Errors
+    /// This function errors if HTTPS is enforced but the URL is not an HTTPS-URL.
+    fn request(&self, security: &DownloadSecurity) -> Result<&Url, Error> {
+        // Assert the address starts with HTTPS first
+        if security.https && self.url.scheme() != "https" {
+            return Err(Error::SecurityNoHttps { url: self.url.as_str().into(), chain: self.chain.clone() });
+        }
+        debug!("Sending request to '{}'{}...", self.url, if security.https { " (HTTPS enabled)" } else { "" });
+        Ok(&self.url)
+    }
+
+    /// Processes the head of the response to the last request.
+    ///
+    /// # Arguments
+    /// - `security`: The [`DownloadSecurity`] to enforce.
+    /// - `status`: The status code of the response.
+    /// - `headers`: The headers of the response.
+    /// - `peer_certificate`: The DER-encoded certificate that the server presented, if any.
+    ///
+    /// # Returns
+    /// True if we've been redirected (and should thus send a new request), or false if this response is the final one.
+    ///
+    /// # Errors
+    /// This function errors if the response redirects somewhere not allowed, or if it came from a server whose certificate isn't pinned.
+    #[cfg_attr(not(feature = "__tls"), allow(unused_variables))]
+    fn on_response(&mut self, security: &DownloadSecurity, status: StatusCode, headers: &HeaderMap, peer_certificate: Option<&[u8]>) -> Result<bool, Error> {
+        if let Some(next) = redirect_target(&self.url, status, headers, &self.chain)? {
+            debug!("'{}' redirects to '{}'", self.url, next);
+            self.chain.push(self.url.as_str().into());
+            if let Err(reason) = security.redirects.check(&self.origin, self.chain.len(), &next) {
+                return Err(Error::RedirectNotAllowed { url: next.into(), chain: self.chain.clone(), reason });
+            }
+            self.url = next;
+            return Ok(true);
+        }
+        #[cfg(feature = "__tls")]
+        check_pins(&self.url, &security.tls, peer_certificate)?;
+        Ok(false)
+    }
+}
+
+
+
+/// Describes what a driver should do after giving a response to the [`DownloadCore`].
+enum Step {
+    /// Send a new request to [`DownloadCore::request()`], since we've been redirected.
+    Redirect,
+    /// Read (at most [`DownloadCore::max_error_body()`] bytes plus one of) the body of the (non-OK) response and give it to
+    /// [`DownloadCore::not_ok()`].
+    NotOk,
+    /// Read the body of the response and give it to [`DownloadCore::on_chunk()`].
+    Body,
+}
+
+/// Describes the metadata that should be given to a downloaded file before it is moved to the target (see [`DownloadCore::finish()`]).
+struct Persist {
+    /// The Unix permissions to set, if any.
+    #[cfg(unix)]
+    mode:     Option<u32>,
+    /// The modification time to set, if any.
+    modified: Option<SystemTime>,
+}
+
+/// Implements the protocol-, security- and checksum-logic of downloading a file, without doing any IO itself.
+///
+/// A driver (i.e., [`download_file()`] or [`download_file_async()`]) uses it as follows:
+/// 1. Send a GET-request to [`DownloadCore::request()`] and give the response's head to [`DownloadCore::on_response()`]. Repeat as long as
+///    [`Step::Redirect`] is returned.
+/// 2. Give every chunk of the body to [`DownloadCore::on_chunk()`] before writing it to the target.
+/// 3. Call [`DownloadCore::finish()`] at the end of the body, apply the returned [`Persist`] to the file and move it to the target.
+///
+/// Any error returned means the download should be aborted. Note that drivers write to a temporary file next to the target (see
+/// [`create_partial()`]), so that the target never exists with partial contents or without its metadata.
+struct DownloadCore<'c, 'p> {
+    /// The security measures to enforce.
+    security: DownloadSecurity<'c>,
+    /// Whether (and how) to print progress.
+    verbose:  Option<Style>,
+    /// The path to download to.
+    target:   &'p Path,
+    /// Follows redirects for us.
+    hops:     Hops,
+    /// The number of bytes of the body received so far.
+    total:    u64,
+    /// Hashes the body as it comes in.
+    hashing:  Option<Hashing>,
+    /// The modification time to give the target, if any.
+    modified: Option<SystemTime>,
+    /// The length of the body as announced by the server, if any.
+    content_length: Option<u64>,
+    /// The start of the body, collected until the [`ContentCheck`] has been done (or `None` if it has).
+    sniffed:  Option<Vec<u8>>,
+    /// Why the [`ContentCheck`] refused the response's headers, if it did. Reported once we have a snippet of the body.
+    violation: Option<ContentViolation>,
+}
+impl<'c, 'p> DownloadCore<'c, 'p> {
+    /// Constructor for the DownloadCore.
+    ///
+    /// # Arguments
+    /// - `source`: The URL to download the file from.
+    /// - `target`: The location to download the file to.
+    /// - `security`: The [`DownloadSecurity`] to enforce.
+    /// - `verbose`: If not `None`, will print to the output with accents given in the given `Style`.
+    ///
+    /// # Returns
+    /// A new DownloadCore that is ready to send its first request.
+    ///
+    /// # Errors
+    /// This function errors if the `source` is not a valid URL or the `target`'s parent directory does not exist.
+    fn new(source: &str, target: &'p Path, security: DownloadSecurity<'c>, verbose: Option<Style>) -> Result<Self, Error> {
+        debug!("Downloading '{}' to '{}' (Security: {})...", source, target.display(), security);
+        if let Some(style) = &verbose {
+            println!("Downloading {}...", style.apply_to(source));
+        }
+
+        // Parse as a URL
+        let url: Url = match Url::from_str(source) {
+            Ok(url) => url,
+            Err(err) => return Err(Error::SourceParse { raw: source.into(), err }),
+        };
+
+        // Assert the download directory exists
+        // (note: an empty parent means the current directory, e.g., for 'file.txt')
+        if let Some(parent) = target.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                return Err(Error::TargetParentNotFound { path: parent.into() });
+            }
+        }
+
+        // Done
+        Ok(Self { security, verbose, target, hops: Hops::new(url), total: 0, hashing: None, modified: None, content_length: None, sniffed: None, violation: None })
+    }
+
+    /// Returns the URL that the next request should be sent to.
+    ///
+    /// # Errors
+    /// This function errors if HTTPS is enforced but the URL is not an HTTPS-URL.
+    #[inline]
+    fn request(&self) -> Result<&Url, Error> { self.hops.request(&self.security) }
+
+    /// Processes the head of the response to the last request.
+    ///
+    /// # Arguments
+    /// - `status`: The status code of the response.
+    /// - `headers`: The headers of the response.
+    /// - `peer_certificate`: The DER-encoded certificate that the server presented, if any.
+    ///
+    /// # Returns
+    /// The [`Step`] that the driver should take next.
+    ///
+    /// # Errors
+    /// This function errors if the response violates the security policy (e.g., redirects somewhere not allowed, or is too large).
+    fn on_response(&mut self, status: StatusCode, headers: &HeaderMap, peer_certificate: Option<&[u8]>) -> Result<Step, Error> {
+        // See if we have to go somewhere else
+        if self.hops.on_response(&self.security, status, headers, peer_certificate)? {
+            return Ok(Step::Redirect);
+        }
+
+        // Assert it succeeded
+        if !status.is_success() {
+            return Ok(Step::NotOk);
+        }
+
+        // Check the length, if any
+        debug!("Downloading response to file '{}'...", self.target.display());
+        let len: Option<u64> = headers.get("Content-Length").and_then(|len| len.to_str().ok()).and_then(|len| u64::from_str(len).ok());
+        if let (Some(max), Some(len)) = (self.security.max_bytes, len) {
+            if len > max {
+                return Err(Error::ResponseTooLarge { url: self.hops.url.as_str().into(), max, content_length: Some(len) });
+            }
+        }
+
+        // Remember when it was last modified, if we care
+        if self.security.last_modified {
+            self.modified = headers.get(LAST_MODIFIED).and_then(|date| date.to_str().ok()).and_then(|date| httpdate::parse_http_date(date).ok());
+            if self.modified.is_none() {
+                debug!("Response from '{}' has no valid Last-Modified-header; not setting modification time", self.hops.url);
+            }
+        }
+
+        // Check the content type now, but only report it once we can show what we got instead
+        if !self.security.content.is_empty() {
+            self.violation = self.security.content.check_type(headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok())).err();
+            self.sniffed = Some(Vec::new());
+        }
+
+        // Prepare getting a checksum (always, since we report it) and showing progress
+        self.content_length = len;
+        self.hashing = Some(Hashing::new(len, self.verbose.is_some()));
+        Ok(Step::Body)
+    }
+
+    /// Builds the error for a non-OK response.
+    ///
+    /// # Arguments
+    /// - `status`: The status code of the response.
+    /// - `headers`: The headers of the response.
+    /// - `body`: The start of the body of the response, if we read it. If it's longer than [`DownloadCore::max_error_body()`], it is cut off.
+    ///
+    /// # Returns
+    /// An [`Error::ResponseNotOk`].
+    fn not_ok(&self, status: StatusCode, headers: &HeaderMap, body: Option<&[u8]>) -> Error {
+        Error::ResponseNotOk {
+            url: self.hops.url.as_str().into(),
+            code: status,
+            headers: Box::new(headers.clone()),
+            response: body.map(|body| sanitize(body, self.security.max_error_body)),
+        }
+    }
+
+    /// Returns how many bytes of the body of a non-OK response should be given to [`DownloadCore::not_ok()`].
+    ///
+    /// Drivers should read one byte more than this, so that the core can tell whether the body was cut off. If this is 0, they shouldn't read the
+    /// body at all.
+    #[inline]
+    fn max_error_body(&self) -> usize { self.security.max_error_body }
+
+    /// Processes the next chunk of the body.
+    ///
+    /// Should be called before writing the chunk to the target.
+    ///
+    /// # Arguments
+    /// - `chunk`: The next bytes of the body.
+    ///
+    /// # Errors
+    /// This function errors if the body has become larger than allowed.
+    fn on_chunk(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        // Stop if the server sends us more than we bargained for
+        self.total += chunk.len() as u64;
+        if let Some(max) = self.security.max_bytes {
+            if self.total > max {
+                return Err(Error::ResponseTooLarge { url: self.hops.url.as_str().into(), max, content_length: None });
+            }
+        }
+
+        // Check the content as soon as we've seen enough of it
+        if let Some(sniffed) = &mut self.sniffed {
+            let sniff_len: usize = SNIFF_LEN.max(self.security.content.magic.as_ref().map(Vec::len).unwrap_or(0));
+            sniffed.extend_from_slice(&chunk[..chunk.len().min(sniff_len - sniffed.len())]);
+            if sniffed.len() >= sniff_len {
+                self.check_content()?;
+            }
+        }
+
+        // Update the hash (and what we've written if needed)
+        if let Some(hashing) = &mut self.hashing {
+            hashing.update(chunk);
+        }
+        Ok(())
+    }
+
+    /// Applies the [`ContentCheck`] to the start of the body collected so far, if it hasn't been yet.
+    ///
+    /// # Errors
+    /// This function errors if either the response's headers or its body are refused by the check.
+    fn check_content(&mut self) -> Result<(), Error> {
+        let sniffed: Vec<u8> = match self.sniffed.take() {
+            Some(sniffed) => sniffed,
+            None => return Ok(()),
+        };
+        debug!("Checking content of response from '{}'...", self.hops.url);
+        match self.violation.take().map(Err).unwrap_or_else(|| self.security.content.check_body(&sniffed)) {
+            Ok(()) => Ok(()),
+            Err(reason) => Err(Error::UnexpectedContent { url: self.hops.url.as_str().into(), reason, snippet: sanitize(&sniffed, SNIPPET_LEN) }),
+        }
+    }
+
+    /// Finishes the download.
+    ///
+    /// # Returns
+    /// A [`DownloadReport`] describing the download, and the [`Persist`]-metadata to give the downloaded file before moving it to the target.
+    ///
+    /// # Errors
+    /// This function errors if the downloaded file does not have the expected checksum.
+    fn finish(&mut self) -> Result<(DownloadReport, Persist), Error> {
+        // Bodies shorter than what we sniff haven't been checked yet
+        self.check_content()?;
+        let result: [u8; 32] = self.hashing.take().unwrap_or_else(|| Hashing::new(None, false)).finish();
+
+        // Assert the checksums are the same if we're doing that
+        if let Some(checksum) = self.security.checksum {
+            debug!("Verifying checksum...");
+            if &result[..] != checksum {
+                return Err(Error::SecurityChecksum { path: self.target.into(), expected: hex::encode(checksum), got: hex::encode(&result[..]) });
+            }
+            print_checksum_ok(&result, "", self.verbose.clone());
+        }
+
+        // Done
+        let persist: Persist = Persist {
+            #[cfg(unix)]
+            mode: self.security.mode,
+            modified: self.modified,
+        };
+        Ok((DownloadReport { url: self.hops.url.clone(), sha256: result }, persist))
+    }
+
+    /// Returns the URL that we're currently requesting (i.e., the one that the body comes from).
+    #[inline]
+    fn url(&self) -> &Url { &self.hops.url }
+
+    /// Returns the length of the body as announced by the server, if any.
+    #[inline]
+    fn content_length(&self) -> Option<u64> { self.content_length }
+
+    /// Returns the security measures enforced by this download.
+    #[inline]
+    fn security(&self) -> &DownloadSecurity<'c> { &self.security }
+}
+
+
+/// Decides whether [`ensure_file()`] (and friends) have to download their target, without doing any IO itself.
+///
+/// A driver asks [`EnsureCore::existing()`] whether there is an existing target worth checking. If so, it hashes the target and gives the checksum to
+/// [`EnsureCore::on_hashed()`], which says whether it can be kept. Otherwise, the driver downloads the target as usual.
+struct EnsureCore<'c, 'p> {
+    /// The target to ensure.
+    target:   &'p Path,
+    /// The checksum that the target should have, if any.
+    checksum: Option<&'c [u8]>,
+    /// Whether (and how) to print progress.
+    verbose:  Option<Style>,
+}
+impl<'c, 'p> EnsureCore<'c, 'p> {
+    /// Constructor for the EnsureCore.
+    ///
+    /// # Arguments
+    /// - `source`: The URL to download the target from (used for debugging purposes only).
+    /// - `target`: The target to ensure.
+    /// - `security`: The [`DownloadSecurity`] that holds the checksum the target should have.
+    /// - `verbose`: If not `None`, prints progress with accents given in the given `Style`.
+    ///
+    /// # Returns
+    /// A new EnsureCore for the given target.
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    fn new(source: &str, target: &'p Path, security: &DownloadSecurity<'c>, verbose: Option<Style>) -> Self {
+        debug!("Ensuring '{}' exists (from '{}')...", target.display(), source);
+        Self { target, checksum: security.checksum, verbose }
+    }
+
+    /// Decides whether the existing target should be hashed.
+    ///
+    /// # Returns
+    /// Whether to show progress while hashing if it should, or [`None`] if the target has to be downloaded regardless (because it doesn't exist or
+    /// there is no checksum to compare it with).
+    fn existing(&self) -> Option<bool> {
+        if self.checksum.is_none() || !self.target.is_file() {
+            return None;
+        }
+        if let Some(style) = &self.verbose {
+            println!("Verifying existing {}...", style.apply_to(self.target.display()));
+        }
+        Some(self.verbose.is_some())
+    }
+
+    /// Decides whether an existing target can be kept, given its checksum.
+    ///
+    /// # Arguments
+    /// - `got`: The checksum of the existing target.
+    ///
+    /// # Returns
+    /// True if the target is already present, or false if it has to be downloaded again.
+    fn on_hashed(&self, got: &[u8; 32]) -> bool {
+        let expected: &[u8] = self.checksum.unwrap_or_default();
+        if &got[..] == expected {
+            debug!("Target '{}' already present with checksum {}", self.target.display(), hex::encode(got));
+            print_checksum_ok(got, " (already present)", self.verbose.clone());
+            true
+        } else {
+            debug!(
+                "Target '{}' exists but has checksum {} instead of {}; downloading again",
+                self.target.display(),
+                hex::encode(got),
+                hex::encode(expected)
+            );
+            false
+        }
+    }
+}
+
+
+
+/// Implements the protocol- and security-logic of probing a file, without doing any IO itself.
+///
+/// A driver (i.e., [`probe()`] or [`probe_async()`]) sends the request returned by [`ProbeCore::request()`] and gives the response's head to
+/// [`ProbeCore::on_response()`], until the latter returns a [`Probe`]. Any error returned means the probe should be aborted.
+///
+/// Note that a HEAD-request is sent first. If the server refuses it (with anything other than `404 Not Found` or `410 Gone`), the core falls back to
+/// a GET-request for the first byte only, since some servers (e.g., pre-signed cloud storage URLs) only accept GET-requests.
+struct ProbeCore<'c> {
+    /// The security measures to enforce.
+    security: DownloadSecurity<'c>,
+    /// Follows redirects for us.
+    hops:     Hops,
+    /// Whether we're still sending HEAD-requests (instead of ranged GET-requests).
+    head:     bool,
+}
+impl<'c> ProbeCore<'c> {
+    /// Constructor for the ProbeCore.
+    ///
+    /// # Arguments
+    /// - `source`: The URL of the file to probe.
+    /// - `security`: The [`DownloadSecurity`] to enforce.
+    ///
+    /// # Returns
+    /// A new ProbeCore that is ready to send its first request.
+    ///
+    /// # Errors
+    /// This function errors if the `source` is not a valid URL.
+    fn new(source: &str, security: DownloadSecurity<'c>) -> Result<Self, Error> {
+        debug!("Probing '{}' (Security: {})...", source, security);
+        let url: Url = match Url::from_str(source) {
+            Ok(url) => url,
+            Err(err) => return Err(Error::SourceParse { raw: source.into(), err }),
+        };
+        Ok(Self { security, hops: Hops::new(url), head: true })
+    }
+
+    /// Returns the request that should be sent next.
+    ///
+    /// # Returns
+    /// A tuple with the [`Method`] of the request, the URL to send it to and any extra headers to send along.
+    ///
+    /// # Errors
+    /// This function errors if HTTPS is enforced but the URL is not an HTTPS-URL.
+    fn request(&self) -> Result<(Method, &Url, HeaderMap), Error> {
+        let url: &Url = self.hops.request(&self.security)?;
+        if self.head {
+            return Ok((Method::HEAD, url, HeaderMap::new()));
+        }
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert(RANGE, HeaderValue::from_static("bytes=0-0"));
+        Ok((Method::GET, url, headers))
+    }
+
+    /// Processes the head of the response to the last request.
+    ///
+    /// # Arguments
+    /// - `status`: The status code of the response.
+    /// - `headers`: The headers of the response.
+    /// - `peer_certificate`: The DER-encoded certificate that the server presented, if any.
+    ///
+    /// # Returns
+    /// The [`Probe`] describing the file, or [`None`] if the driver should send another request.
+    ///
+    /// # Errors
+    /// This function errors if the response violates the security policy (e.g., redirects somewhere not allowed).
+    fn on_response(&mut self, status: StatusCode, headers: &HeaderMap, peer_certificate: Option<&[u8]>) -> Result<Option<Probe>, Error> {
+        // See if we have to go somewhere else
+        if self.hops.on_response(&self.security, status, headers, peer_certificate)? {
+            return Ok(None);
+        }
+        if self.head && !status.is_success() && status != StatusCode::NOT_FOUND && status != StatusCode::GONE {
+            debug!("HEAD-request to '{}' failed with {}; falling back to a ranged GET-request", self.hops.url, status);
+            self.head = false;
+            return Ok(None);
         }
 
-        // Send the request with a user-agent header (to make GitHub happy)
-        let client: Client = Client::new();
-        let req: Request = match client.get(url.clone()).header("User-Agent", "reqwest").build() {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(Error::RequestCreate { url: url.into(), err });
+        // Collect what the server told us about the file
+        let mut probe: Probe =
+            Probe { status, url: self.hops.url.clone(), content_length: None, content_type: None, etag: None, last_modified: None, ranges: false };
+        if !status.is_success() {
+            return Ok(Some(probe));
+        }
+        let header = |name| headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok());
+        if status == StatusCode::PARTIAL_CONTENT {
+            // The length is the total after the slash (e.g., `bytes 0-0/1234`)
+            probe.content_length = header(CONTENT_RANGE).and_then(|range| range.rsplit_once('/')).and_then(|(_, total)| u64::from_str(total.trim()).ok());
+            probe.ranges = true;
+        } else {
+            // Note that a server answering our ranged GET-request in full doesn't support ranges, regardless of what it says
+            probe.content_length = header(CONTENT_LENGTH).and_then(|len| u64::from_str(len.trim()).ok());
+            probe.ranges = self.head && header(ACCEPT_RANGES).is_some_and(|units| units.split(',').any(|unit| unit.trim().eq_ignore_ascii_case("bytes")));
+        }
+        probe.content_type = header(CONTENT_TYPE).map(String::from);
+        probe.etag = header(ETAG).map(String::from);
+        probe.last_modified = header(LAST_MODIFIED).and_then(|date| httpdate::parse_http_date(date).ok());
+        Ok(Some(probe))
+    }
+
+    /// Returns the security measures enforced by this probe.
+    #[inline]
+    fn security(&self) -> &DownloadSecurity<'c> { &self.security }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Downloads some file from the interwebs to the given location.
+///
+/// The file is first downloaded to a temporary file next to the target (`.<name>.<random>.part`), and only moved to the target once it is complete, has
+/// passed the checks of the [`DownloadSecurity`] and has been given its [mode](DownloadSecurity::with_mode()) and
+/// [modification time](DownloadSecurity::with_last_modified()). As such, an existing target is left alone if the download fails.
+///
+/// If you enabled the `async-tokio` feature, also check the [`download_file_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `verification`: Some method to verify the file is what we think it is. See the `VerifyMethod`-enum for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Nothing, except that when it does you can assume a file exists at the given location.
+///
+/// # Errors
+/// This function may error if we failed to download the file or write it (which may happen if the parent directory of `local` does not exist, among other things).
+///
+/// # Example
+/// ```rust
+/// use download::{download_file, DownloadSecurity};
+///
+/// // Download some file
+/// let url = "http://theuselessweb.com/index.html";
+/// let file = std::env::temp_dir().join("index.html");
+/// download_file(&url, &file, DownloadSecurity::none(), None).unwrap();
+///
+/// // It exists now!
+/// assert!(file.is_file());
+/// assert!(std::fs::read_to_string(&file).is_ok());
+/// ```
+pub fn download_file(source: impl AsRef<str>, target: impl AsRef<Path>, security: DownloadSecurity<'_>, verbose: Option<Style>) -> Result<(), Error> {
+    download_file_report(None, source.as_ref(), target.as_ref(), security, verbose).map(|_| ())
+}
+
+/// Downloads some file from the interwebs to the given location, using a custom [`Transport`] to send requests.
+///
+/// This behaves exactly like [`download_file()`] (which uses a [`ReqwestTransport`]), except that you control the HTTP stack. This is mostly useful for
+/// testing (see `testing::MockTransport` if the `testing`-feature is enabled) or to use another HTTP client altogether.
+///
+/// Note that the [`TlsConfig`]'s roots only apply to the [`ReqwestTransport`] built by [`download_file()`]; its pins are checked against the
+/// [`TransportResponse::peer_certificate`] of any transport.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with.
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Nothing, except that when it does you can assume a file exists at the given location.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file()`], or if the `transport` fails to send a request.
+pub fn download_file_with_transport(
+    transport: &dyn Transport,
+    source: impl AsRef<str>,
+    target: impl AsRef<Path>,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<(), Error> {
+    download_file_report(Some(transport), source.as_ref(), target.as_ref(), security, verbose).map(|_| ())
+}
+
+/// Implements [`download_file()`], but also reports where the file was eventually downloaded from and what its checksum is.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with. If omitted, builds a [`ReqwestTransport`] based on the `security`.
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// A [`DownloadReport`] describing the download.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file()`].
+pub(crate) fn download_file_report(
+    transport: Option<&dyn Transport>,
+    source: &str,
+    target: &Path,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<DownloadReport, Error> {
+    let mut core: DownloadCore = DownloadCore::new(source, target, security, verbose)?;
+
+    // Open the temporary file for writing
+    let (partial, handle): (PathBuf, fs::File) = create_partial(target)?;
+
+    // Download to it, removing it again if that fails
+    let res: Result<DownloadReport, Error> = download_to(transport, &mut core, handle, &partial);
+    let report: DownloadReport = match res {
+        Ok(report) => report,
+        Err(err) => {
+            remove_partial(&partial);
+            return Err(err);
+        },
+    };
+
+    // Move it into place
+    debug!("Moving '{}' to '{}'...", partial.display(), target.display());
+    if let Err(err) = fs::rename(&partial, target) {
+        remove_partial(&partial);
+        return Err(Error::TargetRename { from: partial, to: target.into(), err });
+    }
+    Ok(report)
+}
+
+/// Implements the part of [`download_file_report()`] that downloads the file to a temporary file.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with. If omitted, builds a [`ReqwestTransport`] based on the `core`'s security.
+/// - `core`: The [`DownloadCore`] that decides what to do.
+/// - `handle`: The temporary file to download to.
+/// - `partial`: The path of the temporary file, for debugging purposes.
+///
+/// # Returns
+/// A [`DownloadReport`] describing the download. The temporary file has been given its metadata by then.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file()`].
+fn download_to(transport: Option<&dyn Transport>, core: &mut DownloadCore, mut handle: fs::File, partial: &Path) -> Result<DownloadReport, Error> {
+    // Send requests until we're no longer redirected (as far as the security policy allows)
+    let default: ReqwestTransport;
+    let transport: &dyn Transport = match transport {
+        Some(transport) => transport,
+        None => {
+            default = ReqwestTransport::new(core.security())?;
+            &default
+        },
+    };
+    let mut res: TransportResponse = loop {
+        let mut res: TransportResponse = transport.get(core.request()?)?;
+        match core.on_response(res.status, &res.headers, res.peer_certificate.as_deref())? {
+            Step::Redirect => continue,
+            Step::NotOk => {
+                let mut body: Vec<u8> = Vec::new();
+                let max: usize = core.max_error_body();
+                let read: bool = max > 0 && (&mut res.body).take(max as u64 + 1).read_to_end(&mut body).is_ok();
+                return Err(core.not_ok(res.status, &res.headers, if read { Some(&body) } else { None }));
             },
-        };
-        match client.execute(req) {
-            Ok(req) => req,
+            Step::Body => break res,
+        }
+    };
+    preflight(&handle, partial, core)?;
+
+    // Download the response to the opened output file
+    let mut chunk: [u8; 65535] = [0; 65535];
+    loop {
+        // Read the next chunk
+        let chunk_len: usize = match res.body.read(&mut chunk) {
+            Ok(len) => len,
             Err(err) => {
-                return Err(Error::RequestExecute { url: url.into(), err });
+                return Err(Error::ResponseDownload { url: core.url().as_str().into(), err });
             },
+        };
+        if chunk_len == 0 {
+            break;
         }
-    } else {
-        debug!("Sending download request to '{}'...", url);
+        let next: &[u8] = &chunk[..chunk_len];
+
+        // Let the core check & hash it, then write it to the file
+        core.on_chunk(next)?;
+        if let Err(err) = handle.write_all(next) {
+            return Err(Error::TargetWrite { path: partial.into(), err });
+        }
+    }
+
+    // Assert the checksums are the same if we're doing that, then set the metadata
+    let (report, persist): (DownloadReport, Persist) = core.finish()?;
+    set_metadata(&handle, partial, &persist)?;
+    Ok(report)
+}
+
+/// Downloads some file from the interwebs to the given location.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `verification`: Some method to verify the file is what we think it is. See the `VerifyMethod`-enum for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Nothing, except that when it does you can assume a file exists at the given location.
+///
+/// # Errors
+/// This function may error if we failed to download the file or write it (which may happen if the parent directory of `local` does not exist, among other things).
+///
+/// # Example
+/// ```rust
+/// # tokio_test::block_on(async {
+/// use download::{download_file_async, DownloadSecurity};
+///
+/// // Download some file
+/// let url = "https://theuselessweb.com/index.html";
+/// let file = std::env::temp_dir().join("index.html");
+/// download_file_async(&url, &file, DownloadSecurity::none(), None).await.unwrap();
+///
+/// // It exists now!
+/// assert!(file.is_file());
+/// assert!(tokio::fs::read_to_string(&file).await.is_ok());
+/// # });
+/// ```
+#[cfg(feature = "async-tokio")]
+pub async fn download_file_async(
+    source: impl AsRef<str>,
+    target: impl AsRef<Path>,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<(), Error> {
+    let target: &Path = target.as_ref();
+    let mut core: DownloadCore = DownloadCore::new(source.as_ref(), target, security, verbose)?;
+
+    // Open the temporary file for writing
+    let (partial, handle): (PathBuf, fs::File) = create_partial(target)?;
+    let handle: tfs::File = tfs::File::from_std(handle);
+
+    // Download to it, removing it again if that fails
+    let res: Result<DownloadReport, Error> = download_to_async(&mut core, handle, &partial).await;
+    if let Err(err) = res {
+        let thread_partial: PathBuf = partial.clone();
+        unblock_tokio(move || remove_partial(&thread_partial)).await;
+        return Err(err);
+    }
+
+    // Move it into place
+    debug!("Moving '{}' to '{}'...", partial.display(), target.display());
+    if let Err(err) = tfs::rename(&partial, target).await {
+        let thread_partial: PathBuf = partial.clone();
+        unblock_tokio(move || remove_partial(&thread_partial)).await;
+        return Err(Error::TargetRename { from: partial, to: target.into(), err });
+    }
+    Ok(())
+}
 
+/// Implements the part of [`download_file_async()`] that downloads the file to a temporary file.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `core`: The [`DownloadCore`] that decides what to do.
+/// - `handle`: The temporary file to download to.
+/// - `partial`: The path of the temporary file, for debugging purposes.
+///
+/// # Returns
+/// A [`DownloadReport`] describing the download. The temporary file has been given its metadata by then.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file_async()`].
+#[cfg(feature = "async-tokio")]
+async fn download_to_async(core: &mut DownloadCore<'_, '_>, mut handle: tfs::File, partial: &Path) -> Result<DownloadReport, Error> {
+    // Send requests until we're no longer redirected (as far as the security policy allows)
+    let client: AsyncClient = build_client(AsyncClient::builder(), core.security())?;
+    let res: AsyncResponse = loop {
         // Send the request with a user-agent header (to make GitHub happy)
-        let client: Client = Client::new();
-        let req: Request = match client.get(url.clone()).header("User-Agent", "reqwest").build() {
+        let url: &Url = core.request()?;
+        let req: AsyncRequest = match client.get(url.clone()).header("User-Agent", "reqwest").build() {
             Ok(req) => req,
             Err(err) => {
-                return Err(Error::RequestCreate { url: url.into(), err });
+                return Err(Error::RequestCreate { url: url.as_str().into(), err });
             },
         };
-        match client.execute(req) {
-            Ok(req) => req,
+        let mut res: AsyncResponse = match client.execute(req).await {
+            Ok(res) => res,
+            Err(err) => {
+                return Err(Error::RequestExecute { url: url.as_str().into(), err });
+            },
+        };
+
+        // Let the core decide what to do with it
+        #[cfg(feature = "__tls")]
+        let peer_certificate: Option<&[u8]> = res.extensions().get::<TlsInfo>().and_then(TlsInfo::peer_certificate);
+        #[cfg(not(feature = "__tls"))]
+        let peer_certificate: Option<&[u8]> = None;
+        match core.on_response(res.status(), res.headers(), peer_certificate)? {
+            Step::Redirect => continue,
+            Step::NotOk => {
+                let (status, headers): (StatusCode, HeaderMap) = (res.status(), res.headers().clone());
+                let mut body: Vec<u8> = Vec::new();
+                let mut read: bool = core.max_error_body() > 0;
+                while read && body.len() <= core.max_error_body() {
+                    match res.chunk().await {
+                        Ok(Some(chunk)) => body.extend_from_slice(&chunk[..chunk.len().min(core.max_error_body() + 1 - body.len())]),
+                        Ok(None) => break,
+                        Err(_) => read = false,
+                    }
+                }
+                return Err(core.not_ok(status, &headers, if read { Some(&body) } else { None }));
+            },
+            Step::Body => break res,
+        }
+    };
+    preflight(&handle, partial, core)?;
+
+    // Download the response to the opened output file
+    let mut stream = res.bytes_stream();
+    while let Some(next) = stream.next().await {
+        // Unwrap the result
+        let next = match next {
+            Ok(next) => next,
             Err(err) => {
-                return Err(Error::RequestExecute { url: url.into(), err });
+                return Err(Error::ResponseDownloadAsync { url: core.url().as_str().into(), err });
             },
+        };
+
+        // Let the core check & hash it, then write it to the file
+        core.on_chunk(&next)?;
+        if let Err(err) = handle.write_all(&next).await {
+            return Err(Error::TargetWrite { path: partial.into(), err });
         }
+    }
+
+    // Assert the checksums are the same if we're doing that, then set the metadata
+    // (note: converting to a std file waits until all writes are done)
+    let (report, persist): (DownloadReport, Persist) = core.finish()?;
+    set_metadata(&handle.into_std().await, partial, &persist)?;
+    Ok(report)
+}
+
+
+/// Downloads some file from the interwebs to the given location.
+///
+/// This variation is built using runtime-agnostic versions of the normal operations, and is as such only available on the `async-smol` feature. It
+/// works on any executor (e.g., [`smol`](https://docs.rs/smol), `async-std` or `futures::executor`), since requests are sent by a (blocking)
+/// [`ReqwestTransport`] on the [`blocking`] thread pool, and the response is streamed to the file as a [`futures_lite::AsyncRead`].
+///
+/// # Arguments
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Nothing, except that when it does you can assume a file exists at the given location.
+///
+/// # Errors
+/// This function may error if we failed to download the file or write it (which may happen if the parent directory of `local` does not exist, among other things).
+///
+/// # Example
+/// ```rust
+/// # futures_lite::future::block_on(async {
+/// use download::{download_file_smol, DownloadSecurity};
+///
+/// // Download some file
+/// let url = "https://theuselessweb.com/index.html";
+/// let file = std::env::temp_dir().join("index.html");
+/// download_file_smol(&url, &file, DownloadSecurity::none(), None).await.unwrap();
+///
+/// // It exists now!
+/// assert!(file.is_file());
+/// assert!(async_fs::read_to_string(&file).await.is_ok());
+/// # });
+/// ```
+#[cfg(feature = "async-smol")]
+pub async fn download_file_smol(
+    source: impl AsRef<str>,
+    target: impl AsRef<Path>,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<(), Error> {
+    download_file_report_smol(None, source.as_ref(), target.as_ref(), security, verbose).await.map(|_| ())
+}
+
+/// Downloads some file from the interwebs to the given location, using a custom [`Transport`] to send requests.
+///
+/// This behaves exactly like [`download_file_smol()`], except that you control the HTTP stack (see [`download_file_with_transport()`]). Since the
+/// transport is called on the [`blocking`] thread pool, it has to be shareable across threads.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with.
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Nothing, except that when it does you can assume a file exists at the given location.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file_smol()`], or if the `transport` fails to send a request.
+#[cfg(feature = "async-smol")]
+pub async fn download_file_with_transport_smol(
+    transport: Arc<dyn Transport + Send + Sync>,
+    source: impl AsRef<str>,
+    target: impl AsRef<Path>,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<(), Error> {
+    download_file_report_smol(Some(transport), source.as_ref(), target.as_ref(), security, verbose).await.map(|_| ())
+}
+
+/// Implements [`download_file_smol()`], but also reports where the file was eventually downloaded from and what its checksum is.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with. If omitted, builds a [`ReqwestTransport`] based on the `security`.
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// A [`DownloadReport`] describing the download.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file_smol()`].
+#[cfg(feature = "async-smol")]
+async fn download_file_report_smol(
+    transport: Option<Arc<dyn Transport + Send + Sync>>,
+    source: &str,
+    target: &Path,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<DownloadReport, Error> {
+    let mut core: DownloadCore = DownloadCore::new(source, target, security, verbose)?;
+
+    // Open the temporary file for writing
+    let thread_target: PathBuf = target.into();
+    let (partial, handle): (PathBuf, fs::File) = unblock(move || create_partial(&thread_target)).await?;
+
+    // Download to it, removing it again if that fails
+    let res: Result<DownloadReport, Error> = download_to_smol(transport, &mut core, handle, &partial).await;
+    let report: DownloadReport = match res {
+        Ok(report) => report,
+        Err(err) => {
+            let thread_partial: PathBuf = partial.clone();
+            unblock(move || remove_partial(&thread_partial)).await;
+            return Err(err);
+        },
     };
 
-    // Assert it succeeded
-    if !res.status().is_success() {
-        return Err(Error::ResponseNotOk { url: url.into(), code: res.status(), response: res.text().ok() });
+    // Move it into place
+    debug!("Moving '{}' to '{}'...", partial.display(), target.display());
+    if let Err(err) = sfs::rename(&partial, target).await {
+        let thread_partial: PathBuf = partial.clone();
+        unblock(move || remove_partial(&thread_partial)).await;
+        return Err(Error::TargetRename { from: partial, to: target.into(), err });
     }
+    Ok(report)
+}
 
-    // Create the progress bar based on whether if there is a length
-    debug!("Downloading response to file '{}'...", target.display());
-    let len: Option<u64> = res.headers().get("Content-Length").and_then(|len| len.to_str().ok()).and_then(|len| u64::from_str(len).ok());
-    let prgs: Option<ProgressBar> = if verbose.is_some() {
-        Some(if let Some(len) = len {
-            ProgressBar::new(len)
-                .with_style(ProgressStyle::with_template("    {bar:60} {bytes}/{total_bytes} {bytes_per_sec} ETA {eta_precise}").unwrap())
-        } else {
-            ProgressBar::new_spinner()
-                .with_style(ProgressStyle::with_template("    {elapsed_precise} {bar:60} {bytes} {binary_bytes_per_sec}").unwrap())
-        })
-    } else {
-        None
+/// Implements the part of [`download_file_report_smol()`] that downloads the file to a temporary file.
+///
+/// This variation is built using runtime-agnostic versions of the normal operations, and is as such only available on the `async-smol` feature.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with. If omitted, builds a [`ReqwestTransport`] based on the `core`'s security.
+/// - `core`: The [`DownloadCore`] that decides what to do.
+/// - `handle`: The temporary file to download to. It is written to on the [`blocking`] thread pool.
+/// - `partial`: The path of the temporary file, for debugging purposes.
+///
+/// # Returns
+/// A [`DownloadReport`] describing the download. The temporary file has been given its metadata by then.
+///
+/// # Errors
+/// This function errors for the same reasons as [`download_file_smol()`].
+#[cfg(feature = "async-smol")]
+async fn download_to_smol(
+    transport: Option<Arc<dyn Transport + Send + Sync>>,
+    core: &mut DownloadCore<'_, '_>,
+    handle: fs::File,
+    partial: &Path,
+) -> Result<DownloadReport, Error> {
+    // Send requests until we're no longer redirected (as far as the security policy allows)
+    let transport: Arc<dyn Transport + Send + Sync> = match transport {
+        Some(transport) => transport,
+        None => Arc::new(ReqwestTransport::new(core.security())?),
     };
-
-    // Prepare getting a checksum if that is our method of choice
-    let mut hasher: Option<Sha256> = if security.checksum.is_some() { Some(Sha256::new()) } else { None };
+    let res: TransportResponse = loop {
+        // The transport blocks, so send the request on a separate thread
+        let url: Url = core.request()?.clone();
+        let thread_transport: Arc<dyn Transport + Send + Sync> = transport.clone();
+        let res: TransportResponse = unblock(move || thread_transport.get(&url)).await?;
+        match core.on_response(res.status, &res.headers, res.peer_certificate.as_deref())? {
+            Step::Redirect => continue,
+            Step::NotOk => {
+                let mut body: Vec<u8> = Vec::new();
+                let max: usize = core.max_error_body();
+                let read: bool = max > 0 && Unblock::new(res.body).take(max as u64 + 1).read_to_end(&mut body).await.is_ok();
+                return Err(core.not_ok(res.status, &res.headers, if read { Some(&body) } else { None }));
+            },
+            Step::Body => break res,
+        }
+    };
+    preflight(&handle, partial, core)?;
 
     // Download the response to the opened output file
+    let mut handle: Unblock<fs::File> = Unblock::new(handle);
+    let mut body: Unblock<Box<dyn std::io::Read + Send>> = Unblock::new(res.body);
     let mut chunk: [u8; 65535] = [0; 65535];
     loop {
         // Read the next chunk
-        let chunk_len: usize = match res.read(&mut chunk) {
+        let chunk_len: usize = match body.read(&mut chunk).await {
             Ok(len) => len,
             Err(err) => {
-                return Err(Error::ResponseDownload { url: url.into(), err });
+                return Err(Error::ResponseDownload { url: core.url().as_str().into(), err });
             },
         };
         if chunk_len == 0 {
@@ -485,242 +2552,507 @@ pub fn download_file(source: impl AsRef<str>, target: impl AsRef<Path>, security
         }
         let next: &[u8] = &chunk[..chunk_len];
 
-        // Write it to the file
-        if let Err(err) = handle.write(&next) {
-            return Err(Error::TargetWrite { path: target.into(), err });
-        }
-
-        // If desired, update the hash
-        if let Some(hasher) = &mut hasher {
-            hasher.update(&*next);
-        }
-
-        // Update what we've written if needed
-        if let Some(prgs) = &prgs {
-            prgs.update(|state| state.set_pos(state.pos() + next.len() as u64));
+        // Let the core check & hash it, then write it to the file
+        core.on_chunk(next)?;
+        if let Err(err) = handle.write_all(next).await {
+            return Err(Error::TargetWrite { path: partial.into(), err });
         }
     }
-    if let Some(prgs) = &prgs {
-        prgs.finish_and_clear();
+    // Writes are buffered, so make sure they end up in the file before we continue
+    if let Err(err) = handle.flush().await {
+        return Err(Error::TargetWrite { path: partial.into(), err });
     }
 
-    // Assert the checksums are the same if we're doing that
-    if let Some(checksum) = security.checksum {
-        // Finalize the hasher first
-        let result = hasher.unwrap().finalize();
-        debug!("Verifying checksum...");
+    // Assert the checksums are the same if we're doing that, then set the metadata on the same file
+    let (report, persist): (DownloadReport, Persist) = core.finish()?;
+    let handle: fs::File = handle.into_inner().await;
+    let partial: PathBuf = partial.into();
+    unblock(move || set_metadata(&handle, &partial, &persist)).await?;
+    Ok(report)
+}
 
-        // Assert the checksums check out (wheezes)
-        if &result[..] != checksum {
-            return Err(Error::SecurityChecksum { path: target.into(), expected: hex::encode(checksum), got: hex::encode(&result[..]) });
-        }
 
-        // Print that the checksums are equal if asked
-        if let Some(style) = verbose {
-            // Create the dim styles
-            let dim: Style = Style::new().dim();
-            let accent: Style = style.dim();
+/// Makes sure the given target exists with the expected checksum, downloading it only if it doesn't.
+///
+/// Concretely, if the `target` already exists and its checksum matches [`DownloadSecurity::checksum`], this function returns immediately.
+/// Otherwise, it behaves like [`download_file()`]. Note that without a checksum to compare against, the file is always downloaded.
+///
+/// If you enabled the `async-tokio` feature, also check the [`ensure_file_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Whether the file was [already present](Ensured::AlreadyPresent) or [downloaded](Ensured::Downloaded).
+///
+/// # Errors
+/// This function may error if we failed to read an existing target, or for any of the reasons [`download_file()`] may error.
+///
+/// # Example
+/// ```rust
+/// use download::{ensure_file, DownloadSecurity, Ensured};
+/// use hex_literal::hex;
+///
+/// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+/// let file = std::env::temp_dir().join("ensure_LICENSE");
+/// # if file.exists() { std::fs::remove_file(&file).unwrap(); }
+/// let checksum = hex!("c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4");
+///
+/// // The first time, it is downloaded...
+/// assert_eq!(ensure_file(&url, &file, DownloadSecurity::checksum(&checksum), None).unwrap(), Ensured::Downloaded);
+/// // ...but not the second time!
+/// assert_eq!(ensure_file(&url, &file, DownloadSecurity::checksum(&checksum), None).unwrap(), Ensured::AlreadyPresent);
+/// ```
+pub fn ensure_file(source: impl AsRef<str>, target: impl AsRef<Path>, security: DownloadSecurity<'_>, verbose: Option<Style>) -> Result<Ensured, Error> {
+    match ensure_file_report(None, source.as_ref(), target.as_ref(), security, verbose)? {
+        Some(_) => Ok(Ensured::Downloaded),
+        None => Ok(Ensured::AlreadyPresent),
+    }
+}
+
+/// Makes sure the given target exists with the expected checksum, downloading it using a custom [`Transport`] only if it doesn't.
+///
+/// This behaves exactly like [`ensure_file()`], except that downloads are done like [`download_file_with_transport()`].
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with.
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Whether the file was [already present](Ensured::AlreadyPresent) or [downloaded](Ensured::Downloaded).
+///
+/// # Errors
+/// This function errors for the same reasons as [`ensure_file()`], or if the `transport` fails to send a request.
+pub fn ensure_file_with_transport(
+    transport: &dyn Transport,
+    source: impl AsRef<str>,
+    target: impl AsRef<Path>,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<Ensured, Error> {
+    match ensure_file_report(Some(transport), source.as_ref(), target.as_ref(), security, verbose)? {
+        Some(_) => Ok(Ensured::Downloaded),
+        None => Ok(Ensured::AlreadyPresent),
+    }
+}
 
-            // Write it with those styles
-            println!("{}{}{}", dim.apply_to(" > Checksum "), accent.apply_to(hex::encode(&result[..])), dim.apply_to(" OK"));
+/// Implements [`ensure_file()`], but also reports where the file was downloaded from and what its checksum is (if it was downloaded).
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with. If omitted, builds a [`ReqwestTransport`] based on the `security`.
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// A [`DownloadReport`] if the file was downloaded, or [`None`] if it was already present.
+///
+/// # Errors
+/// This function errors for the same reasons as [`ensure_file()`].
+pub(crate) fn ensure_file_report(
+    transport: Option<&dyn Transport>,
+    source: &str,
+    target: &Path,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<Option<DownloadReport>, Error> {
+    let core: EnsureCore = EnsureCore::new(source, target, &security, verbose.clone());
+
+    // See if the target is already there
+    if let Some(progress) = core.existing() {
+        let got: [u8; 32] = hash_file(target, progress)?;
+        if core.on_hashed(&got) {
+            return Ok(None);
         }
     }
 
-    // Done
-    Ok(())
+    // Otherwise, download it
+    download_file_report(transport, source, target, security, verbose).map(Some)
 }
 
-/// Downloads some file from the interwebs to the given location.
+/// Makes sure the given target exists with the expected checksum, downloading it only if it doesn't.
+///
+/// Concretely, if the `target` already exists and its checksum matches [`DownloadSecurity::checksum`], this function returns immediately.
+/// Otherwise, it behaves like [`download_file_async()`]. Note that without a checksum to compare against, the file is always downloaded.
 ///
 /// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
 ///
 /// # Arguments
 /// - `source`: The URL to download the file from.
 /// - `target`: The location to download the file to.
-/// - `verification`: Some method to verify the file is what we think it is. See the `VerifyMethod`-enum for more information.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
 /// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
 ///
 /// # Returns
-/// Nothing, except that when it does you can assume a file exists at the given location.
+/// Whether the file was [already present](Ensured::AlreadyPresent) or [downloaded](Ensured::Downloaded).
 ///
 /// # Errors
-/// This function may error if we failed to download the file or write it (which may happen if the parent directory of `local` does not exist, among other things).
+/// This function may error if we failed to read an existing target, or for any of the reasons [`download_file_async()`] may error.
 ///
 /// # Example
 /// ```rust
 /// # tokio_test::block_on(async {
-/// use download::{download_file_async, DownloadSecurity};
+/// use download::{ensure_file_async, DownloadSecurity, Ensured};
+/// use hex_literal::hex;
 ///
-/// // Download some file
-/// let url = "https://theuselessweb.com/index.html";
-/// let file = std::env::temp_dir().join("index.html");
-/// download_file_async(&url, &file, DownloadSecurity::none(), None).await.unwrap();
+/// let url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE";
+/// let file = std::env::temp_dir().join("ensure_async_LICENSE");
+/// # if file.exists() { tokio::fs::remove_file(&file).await.unwrap(); }
+/// let checksum = hex!("c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4");
 ///
-/// // It exists now!
-/// assert!(file.is_file());
-/// assert!(tokio::fs::read_to_string(&file).await.is_ok());
+/// // The first time, it is downloaded...
+/// assert_eq!(ensure_file_async(&url, &file, DownloadSecurity::checksum(&checksum), None).await.unwrap(), Ensured::Downloaded);
+/// // ...but not the second time!
+/// assert_eq!(ensure_file_async(&url, &file, DownloadSecurity::checksum(&checksum), None).await.unwrap(), Ensured::AlreadyPresent);
 /// # });
 /// ```
 #[cfg(feature = "async-tokio")]
-pub async fn download_file_async(
+pub async fn ensure_file_async(
     source: impl AsRef<str>,
     target: impl AsRef<Path>,
     security: DownloadSecurity<'_>,
     verbose: Option<Style>,
-) -> Result<(), Error> {
+) -> Result<Ensured, Error> {
     let source: &str = source.as_ref();
     let target: &Path = target.as_ref();
-    debug!("Downloading '{}' to '{}' (Security: {})...", source, target.display(), security);
-    if let Some(style) = &verbose {
-        println!("Downloading {}...", style.apply_to(source));
+    let core: EnsureCore = EnsureCore::new(source, target, &security, verbose.clone());
+
+    // See if the target is already there
+    if let Some(progress) = core.existing() {
+        let thread_target: PathBuf = target.into();
+        let got: [u8; 32] = unblock_tokio(move || hash_file(&thread_target, progress)).await?;
+        if core.on_hashed(&got) {
+            return Ok(Ensured::AlreadyPresent);
+        }
     }
 
-    // Assert the download directory exists
-    if let Some(parent) = target.parent() {
-        if !parent.exists() {
-            return Err(Error::TargetParentNotFound { path: parent.into() });
+    // Otherwise, download it
+    download_file_async(source, target, security, verbose).await?;
+    Ok(Ensured::Downloaded)
+}
+
+
+/// Makes sure the given target exists with the expected checksum, downloading it only if it doesn't.
+///
+/// Concretely, if the `target` already exists and its checksum matches [`DownloadSecurity::checksum`], this function returns immediately.
+/// Otherwise, it behaves like [`download_file_smol()`]. Note that without a checksum to compare against, the file is always downloaded.
+///
+/// This variation is built using runtime-agnostic versions of the normal operations, and is as such only available on the `async-smol` feature.
+///
+/// # Arguments
+/// - `source`: The URL to download the file from.
+/// - `target`: The location to download the file to.
+/// - `security`: Some method to verify the file is what we think it is. See the [`DownloadSecurity`]-struct for more information.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// Whether the file was [already present](Ensured::AlreadyPresent) or [downloaded](Ensured::Downloaded).
+///
+/// # Errors
+/// This function may error if we failed to read an existing target, or for any of the reasons [`download_file_smol()`] may error.
+#[cfg(feature = "async-smol")]
+pub async fn ensure_file_smol(
+    source: impl AsRef<str>,
+    target: impl AsRef<Path>,
+    security: DownloadSecurity<'_>,
+    verbose: Option<Style>,
+) -> Result<Ensured, Error> {
+    let source: &str = source.as_ref();
+    let target: &Path = target.as_ref();
+    let core: EnsureCore = EnsureCore::new(source, target, &security, verbose.clone());
+
+    // See if the target is already there
+    if let Some(progress) = core.existing() {
+        let thread_target: PathBuf = target.into();
+        let got: [u8; 32] = unblock(move || hash_file(&thread_target, progress)).await?;
+        if core.on_hashed(&got) {
+            return Ok(Ensured::AlreadyPresent);
         }
     }
 
-    // Open the target file for writing
-    let mut handle: tfs::File = match tfs::File::create(target).await {
-        // Ok(handle) => {
-        //     // Prepare the permissions to set by reading the file's metadata
-        //     let mut permissions: Permissions = match handle.metadata() {
-        //         Ok(metadata) => metadata.permissions(),
-        //         Err(err)     => { return Err(Error::FileMetadataError{ what: "temporary binary", path: local.into(), err }); },
-        //     };
-        //     permissions.set_mode(permissions.mode() | 0o100);
+    // Otherwise, download it
+    download_file_smol(source, target, security, verbose).await?;
+    Ok(Ensured::Downloaded)
+}
 
-        //     // Set them
-        //     if let Err(err) = handle.set_permissions(permissions) { return Err(Error::FilePermissionsError{ what: "temporary binary", path: local.into(), err }); }
 
-        //     // Return the handle
-        //     handle
-        // },
-        Ok(handle) => handle,
-        Err(err) => {
-            return Err(Error::TargetCreate { path: target.into(), err });
+/// Verifies that an existing file has the given checksum.
+///
+/// If you enabled the `async-tokio` feature, also check the [`verify_file_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `path`: The file to verify.
+/// - `checksum`: The SHA-256 checksum that we want the file to have.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Errors
+/// This function errors if we failed to read the file, or if its checksum does not match (in which case [`Error::SecurityChecksum`] is returned).
+///
+/// # Example
+/// ```rust
+/// use download::{verify_file, Error};
+/// use hex_literal::hex;
+///
+/// let file = std::env::temp_dir().join("hello.txt");
+/// std::fs::write(&file, "Hello there!\n").unwrap();
+/// verify_file(&file, &hex!("1c5b885943f571434f0396da0cb5c2aa16cc546d16e679db2a6eea65bfe2b326"), None).unwrap();
+/// match verify_file(&file, &hex!("deadbeef"), None) {
+///     Err(Error::SecurityChecksum { .. }) => println!("Yeah that failed"),
+/// #   got => panic!("Did not crash as expected, got {got:?}"),
+/// }
+/// ```
+pub fn verify_file(path: impl AsRef<Path>, checksum: &[u8], verbose: Option<Style>) -> Result<(), Error> {
+    let path: &Path = path.as_ref();
+    debug!("Verifying checksum of '{}'...", path.display());
+    if let Some(style) = &verbose {
+        println!("Verifying {}...", style.apply_to(path.display()));
+    }
+
+    // Compare the checksums
+    let got: [u8; 32] = hash_file(path, verbose.is_some())?;
+    if &got[..] != checksum {
+        return Err(Error::SecurityChecksum { path: path.into(), expected: hex::encode(checksum), got: hex::encode(got) });
+    }
+    print_checksum_ok(&got, "", verbose);
+    Ok(())
+}
+
+/// Verifies that an existing file has the given checksum.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `path`: The file to verify.
+/// - `checksum`: The SHA-256 checksum that we want the file to have.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Errors
+/// This function errors if we failed to read the file, or if its checksum does not match (in which case [`Error::SecurityChecksum`] is returned).
+///
+/// # Example
+/// ```rust
+/// # tokio_test::block_on(async {
+/// use download::{verify_file_async, Error};
+/// use hex_literal::hex;
+///
+/// let file = std::env::temp_dir().join("hello.txt");
+/// tokio::fs::write(&file, "Hello there!\n").await.unwrap();
+/// verify_file_async(&file, &hex!("1c5b885943f571434f0396da0cb5c2aa16cc546d16e679db2a6eea65bfe2b326"), None).await.unwrap();
+/// match verify_file_async(&file, &hex!("deadbeef"), None).await {
+///     Err(Error::SecurityChecksum { .. }) => println!("Yeah that failed"),
+/// #   got => panic!("Did not crash as expected, got {got:?}"),
+/// }
+/// # });
+/// ```
+#[cfg(feature = "async-tokio")]
+pub async fn verify_file_async(path: impl AsRef<Path>, checksum: &[u8], verbose: Option<Style>) -> Result<(), Error> {
+    let path: &Path = path.as_ref();
+    debug!("Verifying checksum of '{}'...", path.display());
+    if let Some(style) = &verbose {
+        println!("Verifying {}...", style.apply_to(path.display()));
+    }
+
+    // Compare the checksums
+    let (thread_path, progress): (PathBuf, bool) = (path.into(), verbose.is_some());
+    let got: [u8; 32] = unblock_tokio(move || hash_file(&thread_path, progress)).await?;
+    if &got[..] != checksum {
+        return Err(Error::SecurityChecksum { path: path.into(), expected: hex::encode(checksum), got: hex::encode(got) });
+    }
+    print_checksum_ok(&got, "", verbose);
+    Ok(())
+}
+
+/// Verifies that an existing file has the given checksum.
+///
+/// This variation is built using runtime-agnostic versions of the normal operations, and is as such only available on the `async-smol` feature.
+///
+/// # Arguments
+/// - `path`: The file to verify.
+/// - `checksum`: The SHA-256 checksum that we want the file to have.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Errors
+/// This function errors if we failed to read the file, or if its checksum does not match (in which case [`Error::SecurityChecksum`] is returned).
+#[cfg(feature = "async-smol")]
+pub async fn verify_file_smol(path: impl AsRef<Path>, checksum: &[u8], verbose: Option<Style>) -> Result<(), Error> {
+    let path: &Path = path.as_ref();
+    debug!("Verifying checksum of '{}'...", path.display());
+    if let Some(style) = &verbose {
+        println!("Verifying {}...", style.apply_to(path.display()));
+    }
+
+    // Compare the checksums
+    let (thread_path, progress): (PathBuf, bool) = (path.into(), verbose.is_some());
+    let got: [u8; 32] = unblock(move || hash_file(&thread_path, progress)).await?;
+    if &got[..] != checksum {
+        return Err(Error::SecurityChecksum { path: path.into(), expected: hex::encode(checksum), got: hex::encode(got) });
+    }
+    print_checksum_ok(&got, "", verbose);
+    Ok(())
+}
+
+
+/// Finds out what a remote file looks like without downloading it.
+///
+/// Concretely, sends a HEAD-request to the `source`, falling back to a GET-request for only its first byte (`Range: bytes=0-0`) if the server
+/// refuses that. The HTTPS-, redirect- and TLS-policies of the given [`DownloadSecurity`] are enforced like they are for [`download_file()`]; the
+/// other measures (e.g., the checksum) are about the downloaded file, and as such ignored.
+///
+/// If you enabled the `async-tokio` feature, also check the [`probe_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `source`: The URL of the file to probe.
+/// - `security`: The [`DownloadSecurity`] to enforce.
+///
+/// # Returns
+/// A [`Probe`] describing the file. Note that a non-success status (e.g., `404 Not Found`) is not an error, but reported in [`Probe::status`].
+///
+/// # Errors
+/// This function errors if the `source` is not a valid URL, if we failed to send a request or if a response violated the security policy.
+///
+/// # Example
+/// ```rust
+/// use download::{probe, DownloadSecurity};
+///
+/// let probe = probe("https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE", DownloadSecurity::none()).unwrap();
+/// assert!(probe.exists());
+/// println!("LICENSE is {:?} bytes", probe.content_length);
+/// ```
+pub fn probe(source: impl AsRef<str>, security: DownloadSecurity<'_>) -> Result<Probe, Error> { probe_transport(None, source.as_ref(), security) }
+
+/// Finds out what a remote file looks like without downloading it, using a custom [`Transport`] to send requests.
+///
+/// This behaves exactly like [`probe()`], except that you control the HTTP stack. Note that transports that don't override [`Transport::send()`]
+/// emulate HEAD-requests by sending GET-requests, and don't send the `Range`-header of the fallback.
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with.
+/// - `source`: The URL of the file to probe.
+/// - `security`: The [`DownloadSecurity`] to enforce.
+///
+/// # Returns
+/// A [`Probe`] describing the file.
+///
+/// # Errors
+/// This function errors for the same reasons as [`probe()`].
+pub fn probe_with_transport(transport: &dyn Transport, source: impl AsRef<str>, security: DownloadSecurity<'_>) -> Result<Probe, Error> {
+    probe_transport(Some(transport), source.as_ref(), security)
+}
+
+/// Implements [`probe()`] and [`probe_with_transport()`].
+///
+/// # Arguments
+/// - `transport`: The [`Transport`] to send the request(s) with. If omitted, builds a [`ReqwestTransport`] based on the `security`.
+/// - `source`: The URL of the file to probe.
+/// - `security`: The [`DownloadSecurity`] to enforce.
+///
+/// # Returns
+/// A [`Probe`] describing the file.
+///
+/// # Errors
+/// This function errors for the same reasons as [`probe()`].
+fn probe_transport(transport: Option<&dyn Transport>, source: &str, security: DownloadSecurity<'_>) -> Result<Probe, Error> {
+    let mut core: ProbeCore = ProbeCore::new(source, security)?;
+    let default: ReqwestTransport;
+    let transport: &dyn Transport = match transport {
+        Some(transport) => transport,
+        None => {
+            default = ReqwestTransport::new(core.security())?;
+            &default
         },
     };
 
-    // Send a request
-    let res: AsyncResponse = if security.https {
-        debug!("Sending download request to '{}' (HTTPS enabled)...", source);
-
-        // Assert the address starts with HTTPS first
-        if Url::parse(source).ok().map(|u| u.scheme() != "https").unwrap_or(true) {
-            return Err(Error::SecurityNoHttps { url: source.into() });
+    // Send requests until the core is satisfied (as far as the security policy allows)
+    loop {
+        let (method, url, headers): (Method, &Url, HeaderMap) = core.request()?;
+        let res: TransportResponse = transport.send(method, url, headers)?;
+        if let Some(probe) = core.on_response(res.status, &res.headers, res.peer_certificate.as_deref())? {
+            return Ok(probe);
         }
+    }
+}
 
-        // Send the request with a user-agent header (to make GitHub happy)
-        let client: AsyncClient = AsyncClient::new();
-        let req: AsyncRequest = match client.get(source).header("User-Agent", "reqwest").build() {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(Error::RequestCreate { url: source.into(), err });
-            },
-        };
-        match client.execute(req).await {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(Error::RequestExecute { url: source.into(), err });
-            },
-        }
-    } else {
-        debug!("Sending download request to '{}'...", source);
+/// Finds out what a remote file looks like without downloading it.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `source`: The URL of the file to probe.
+/// - `security`: The [`DownloadSecurity`] to enforce.
+///
+/// # Returns
+/// A [`Probe`] describing the file. Note that a non-success status (e.g., `404 Not Found`) is not an error, but reported in [`Probe::status`].
+///
+/// # Errors
+/// This function errors for the same reasons as [`probe()`].
+#[cfg(feature = "async-tokio")]
+pub async fn probe_async(source: impl AsRef<str>, security: DownloadSecurity<'_>) -> Result<Probe, Error> {
+    let mut core: ProbeCore = ProbeCore::new(source.as_ref(), security)?;
+    let client: AsyncClient = build_client(AsyncClient::builder(), core.security())?;
 
+    // Send requests until the core is satisfied (as far as the security policy allows)
+    loop {
         // Send the request with a user-agent header (to make GitHub happy)
-        let client: AsyncClient = AsyncClient::new();
-        let req: AsyncRequest = match client.get(source).header("User-Agent", "reqwest").build() {
+        let (method, url, headers): (Method, &Url, HeaderMap) = core.request()?;
+        let req: AsyncRequest = match client.request(method, url.clone()).header("User-Agent", "reqwest").headers(headers).build() {
             Ok(req) => req,
             Err(err) => {
-                return Err(Error::RequestCreate { url: source.into(), err });
+                return Err(Error::RequestCreate { url: url.as_str().into(), err });
             },
         };
-        match client.execute(req).await {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(Error::RequestExecute { url: source.into(), err });
-            },
-        }
-    };
-
-    // Assert it succeeded
-    if !res.status().is_success() {
-        return Err(Error::ResponseNotOk { url: source.into(), code: res.status(), response: res.text().await.ok() });
-    }
-
-    // Create the progress bar based on whether if there is a length
-    debug!("Downloading response to file '{}'...", target.display());
-    let len: Option<u64> = res.headers().get("Content-Length").and_then(|len| len.to_str().ok()).and_then(|len| u64::from_str(len).ok());
-    let prgs: Option<ProgressBar> = if verbose.is_some() {
-        Some(if let Some(len) = len {
-            ProgressBar::new(len)
-                .with_style(ProgressStyle::with_template("    {bar:60} {bytes}/{total_bytes} {bytes_per_sec} ETA {eta_precise}").unwrap())
-        } else {
-            ProgressBar::new_spinner()
-                .with_style(ProgressStyle::with_template("    {elapsed_precise} {bar:60} {bytes} {binary_bytes_per_sec}").unwrap())
-        })
-    } else {
-        None
-    };
-
-    // Prepare getting a checksum if that is our method of choice
-    let mut hasher: Option<Sha256> = if security.checksum.is_some() { Some(Sha256::new()) } else { None };
-
-    // Download the response to the opened output file
-    let mut stream = res.bytes_stream();
-    while let Some(next) = stream.next().await {
-        // Unwrap the result
-        let next = match next {
-            Ok(next) => next,
+        let res: AsyncResponse = match client.execute(req).await {
+            Ok(res) => res,
             Err(err) => {
-                return Err(Error::ResponseDownloadAsync { url: source.into(), err });
+                return Err(Error::RequestExecute { url: url.as_str().into(), err });
             },
         };
 
-        // Write it to the file
-        if let Err(err) = handle.write(&next).await {
-            return Err(Error::TargetWrite { path: target.into(), err });
-        }
-
-        // If desired, update the hash
-        if let Some(hasher) = &mut hasher {
-            hasher.update(&*next);
-        }
-
-        // Update what we've written if needed
-        if let Some(prgs) = &prgs {
-            prgs.update(|state| state.set_pos(state.pos() + next.len() as u64));
+        // Let the core decide what to do with it
+        #[cfg(feature = "__tls")]
+        let peer_certificate: Option<&[u8]> = res.extensions().get::<TlsInfo>().and_then(TlsInfo::peer_certificate);
+        #[cfg(not(feature = "__tls"))]
+        let peer_certificate: Option<&[u8]> = None;
+        if let Some(probe) = core.on_response(res.status(), res.headers(), peer_certificate)? {
+            return Ok(probe);
         }
     }
-    if let Some(prgs) = &prgs {
-        prgs.finish_and_clear();
-    }
-
-    // Assert the checksums are the same if we're doing that
-    if let Some(checksum) = security.checksum {
-        // Finalize the hasher first
-        let result = hasher.unwrap().finalize();
-        debug!("Verifying checksum...");
-
-        // Assert the checksums check out (wheezes)
-        if &result[..] != checksum {
-            return Err(Error::SecurityChecksum { path: target.into(), expected: hex::encode(checksum), got: hex::encode(&result[..]) });
-        }
+}
 
-        // Print that the checksums are equal if asked
-        if let Some(style) = verbose {
-            // Create the dim styles
-            let dim: Style = Style::new().dim();
-            let accent: Style = style.dim();
+/// Finds out what a remote file looks like without downloading it.
+///
+/// This variation is built using runtime-agnostic versions of the normal operations, and is as such only available on the `async-smol` feature. Like
+/// [`download_file_smol()`], the requests are sent by a (blocking) [`ReqwestTransport`] on the [`blocking`] thread pool.
+///
+/// # Arguments
+/// - `source`: The URL of the file to probe.
+/// - `security`: The [`DownloadSecurity`] to enforce.
+///
+/// # Returns
+/// A [`Probe`] describing the file. Note that a non-success status (e.g., `404 Not Found`) is not an error, but reported in [`Probe::status`].
+///
+/// # Errors
+/// This function errors for the same reasons as [`probe()`].
+#[cfg(feature = "async-smol")]
+pub async fn probe_smol(source: impl AsRef<str>, security: DownloadSecurity<'_>) -> Result<Probe, Error> {
+    let mut core: ProbeCore = ProbeCore::new(source.as_ref(), security)?;
+    let transport: Arc<ReqwestTransport> = Arc::new(ReqwestTransport::new(core.security())?);
 
-            // Write it with those styles
-            println!("{}{}{}", dim.apply_to(" > Checksum "), accent.apply_to(hex::encode(&result[..])), dim.apply_to(" OK"));
+    // Send requests until the core is satisfied (as far as the security policy allows)
+    loop {
+        // The transport blocks, so send the request on a separate thread
+        let (method, url, headers): (Method, &Url, HeaderMap) = core.request()?;
+        let url: Url = url.clone();
+        let thread_transport: Arc<ReqwestTransport> = transport.clone();
+        let res: TransportResponse = unblock(move || thread_transport.send(method, &url, headers)).await?;
+        if let Some(probe) = core.on_response(res.status, &res.headers, res.peer_certificate.as_deref())? {
+            return Ok(probe);
         }
     }
-
-    // Done
-    Ok(())
 }
@@ -13,7 +13,14 @@
 //!   
 //!   Concretely, offers the following functionality:
 //!   - Download files using standard HTTP GET-requests (`download_file()` and `download_file_async()`).
-//!   - (Un)archive `.tar.gz`-files (`tar::archive()`, `tar::archive_async()`, `tar::unarchive()` and `tar::unarchive_async()`).
+//!   - Plug in your own HTTP stack by implementing the `Transport`-trait (`download_file_with_transport()` and `ensure_file_with_transport()`).
+//!   - Only download files if they don't already exist with the expected checksum (`ensure_file()` and `ensure_file_async()`).
+//!   - Find out the size, type, ETag, modification time and range support of remote files without downloading them (`probe()` and `probe_async()`).
+//!   - Refuse HTML error pages and other unexpected content that misconfigured servers return instead of the file you asked for (`DownloadSecurity::with_content()`).
+//!   - (Un)archive `.tar.gz`-files (`tar::archive()`, `tar::archive_async()`, `tar::unarchive()` and `tar::unarchive_async()`), or tarballs compressed with xz, zstd or bzip2 (`tar::archive_with()`, `tar::unarchive_with()` and a `tar::Compression`), at a configurable level and optionally on all cores for gzip, detecting the compression automatically and enforcing limits against decompression bombs when unarchiving. Entries can be selected with globs or a callback, or extracted one file at a time (`tar::unarchive_file()`).
+//!   - (Un)archive `.zip`-files (`zip::archive()`, `zip::archive_async()`, `zip::unarchive()` and `zip::unarchive_async()`).
+//!   - Download (and unpack) all artifacts listed in a TOML- or JSON-manifest, pinned by a lockfile (`manifest::ensure_all()` and `manifest::lock()`).
+//!   - Download, verify and (un)archive files from the command-line with the `download` binary.
 //!   
 //!   
 //!   # Installation
@@ -46,11 +53,17 @@
 //!   - _Functionality_
 //!       - `download` _(default)_: Enables the toplevel download functions (`download_file()`, `download_file_async()` and associated structures)
 //!       - `tar`: Enables the tarball-related archive/unarchival functions in the `tar`-module (`tar::archive()`, `tar::archive_async()`, `tar::unarchive()`, `tar::unarchive_async()` and associated structures).
+//!       - `xz`, `zstd` and `bzip2`: Enable (un)archiving tarballs compressed with the codec of the same name (`tar::Compression::Xz`, `tar::Compression::Zstd` and `tar::Compression::Bzip2`, respectively). Implies `tar`.
+//!       - `zip`: Enables the zip-related archive/unarchival functions in the `zip`-module (`zip::archive()`, `zip::archive_async()`, `zip::unarchive()`, `zip::unarchive_async()` and associated structures).
+//!       - `manifest`: Enables declarative manifests of artifacts to download and unpack, together with lockfiles, in the `manifest`-module (`manifest::ensure_all()`, `manifest::lock()` and associated structures). Implies `download` and `tar`.
+//!       - `cli`: Builds the `download` binary, which exposes downloading (`download get`), verifying (`download verify`) and (un)archiving (`download tar create|extract|list`) to shell scripts. Run `download --help` for its exit codes. Implies `download` and `tar`.
+//!       - `testing`: Enables the `testing`-module with utilities for testing code that downloads files without a network (`testing::MockTransport` and the local HTTP-server `testing::TestServer`). Implies `download`.
 //!       - `log`: Enables printing [`log`](https://docs.rs/log/latest/log/)-statements in functions in the library.
 //!       - `async-tokio`: Enables async versions of functions in the library that use [`tokio`](https://tokio.rs/) as a backend.
+//!       - `async-smol`: Enables runtime-agnostic async versions of functions in the library (`download_file_smol()`, `tar::archive_smol()`, etc.) that work on any executor, such as [`smol`](https://docs.rs/smol) or `async-std`. Requests are sent by a blocking `Transport` on the [`blocking`](https://docs.rs/blocking) thread pool, and responses are streamed to disk using `futures-io`.
 //!   - _Aliases_
 //!       - `async`: Enables the "default" backend (`async-tokio`).
-//!       - `archives`: Enables the `tar`-feature.
+//!       - `archives`: Enables the `tar`- and `zip`-features.
 //!   
 //!   
 //!   # Changelog
@@ -69,11 +82,21 @@
 // Declare the modules
 #[cfg(feature = "download")]
 mod download;
+#[cfg(feature = "manifest")]
+pub mod manifest;
 #[cfg(feature = "tar")]
 pub mod tar;
+#[cfg(feature = "testing")]
+pub mod testing;
 #[cfg(test)]
 pub mod tests;
+#[cfg(feature = "download")]
+mod transport;
+#[cfg(feature = "zip")]
+pub mod zip;
 
 // Bring some of it into this namespace
 #[cfg(feature = "download")]
 pub use download::*;
+#[cfg(feature = "download")]
+pub use transport::*;
@@ -0,0 +1,687 @@
+//  MANIFEST.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 14:10:37
+//  Last edited:
+//    18 Oct 2026, 14:10:37
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines declarative manifests of artifacts to download (and unpack),
+//!   together with a lockfile that records what was actually downloaded.
+//!
+//!   A manifest is a TOML- or JSON-file that looks like:
+//!   ```toml
+//!   [artifacts.license]
+//!   url = "https://raw.githubusercontent.com/Lut99/download-rs/main/LICENSE"
+//!   sha256 = "c71d239df91726fc519c6eb72d318ec65820627232b2f796219e87dcf35d0ab4"
+//!   target = "third_party/LICENSE"
+//!
+//!   [artifacts.sdk]
+//!   url = "https://example.com/sdk-1.2.3.tar.gz"
+//!   target = "third_party/sdk.tar.gz"
+//!   extract = "third_party/sdk"
+//!   https = true
+//!   ```
+//!   Relative paths are resolved relative to the directory containing the manifest.
+//!
+//!   Artifacts without a `sha256` are _unpinned_. [`lock()`] downloads them once and records their checksum in the lockfile (next to the
+//!   manifest, with the `.lock`-extension), after which [`ensure_all()`] will only accept that exact file.
+//
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FResult};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::{error, fs};
+
+use console::Style;
+use serde::{Deserialize, Serialize};
+
+use crate::download::{create_temp, download_file_report, ensure_file_report, ContentCheck, DownloadReport, DownloadSecurity, Ensured};
+
+
+/***** MACROS *****/
+/// Mirrors [`log`]'s [`debug!`]-macro, but only when the `log`-feature it given.
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($t:tt)*) => {
+        ::log::debug!($($t)*)
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($t:tt)*) => {};
+}
+
+
+
+
+
+/***** CONSTANTS *****/
+/// The header written at the top of every lockfile.
+const LOCKFILE_HEADER: &str = "# This file is generated by `download-rs`. It is not intended for manual editing.\n\n";
+
+
+
+
+
+/***** ERRORS *****/
+/// Defines the errors tha may occur when dealing with manifests and lockfiles.
+#[derive(Debug)]
+pub enum Error {
+    /// The checksum given for an artifact is not a hex-encoded SHA-256 hash.
+    ArtifactChecksumParse { name: String, raw: String },
+    /// Failed to download an artifact.
+    ///
+    /// The error is boxed to keep this enum small, since download errors can be quite large.
+    ArtifactDownload { name: String, err: Box<crate::download::Error> },
+    /// Failed to unpack a downloaded artifact.
+    ArtifactExtract { name: String, err: crate::tar::Error },
+    /// Failed to move an unpacked artifact from its temporary directory into place.
+    ArtifactExtractMove { name: String, from: PathBuf, to: PathBuf, err: std::io::Error },
+    /// Failed to remove a previous unpacked version of a re-downloaded artifact.
+    ArtifactExtractRemove { name: String, path: PathBuf, err: std::io::Error },
+    /// Failed to create the directory to download an artifact to.
+    ArtifactTargetDirCreate { name: String, path: PathBuf, err: std::io::Error },
+    /// An artifact has no checksum in the manifest nor in the lockfile.
+    ArtifactUnpinned { name: String },
+    /// Failed to parse the lockfile.
+    LockfileParse { path: PathBuf, err: toml::de::Error },
+    /// Failed to read the lockfile.
+    LockfileRead { path: PathBuf, err: std::io::Error },
+    /// Failed to serialize the lockfile.
+    LockfileSerialize { path: PathBuf, err: toml::ser::Error },
+    /// Failed to write the lockfile.
+    LockfileWrite { path: PathBuf, err: std::io::Error },
+    /// Failed to parse a manifest as JSON.
+    ManifestParseJson { path: PathBuf, err: serde_json::Error },
+    /// Failed to parse a manifest as TOML.
+    ManifestParseToml { path: PathBuf, err: toml::de::Error },
+    /// Failed to read a manifest.
+    ManifestRead { path: PathBuf, err: std::io::Error },
+    /// The manifest does not have a `.toml`- or `.json`-extension.
+    ManifestUnknownFormat { path: PathBuf },
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            ArtifactChecksumParse { name, raw } => write!(f, "Checksum '{raw}' of artifact '{name}' is not a hex-encoded SHA-256 hash"),
+            ArtifactDownload { name, .. } => write!(f, "Failed to download artifact '{name}'"),
+            ArtifactExtract { name, .. } => write!(f, "Failed to extract artifact '{name}'"),
+            ArtifactExtractMove { name, from, to, .. } => {
+                write!(f, "Failed to move extracted artifact '{}' from '{}' to '{}'", name, from.display(), to.display())
+            },
+            ArtifactExtractRemove { name, path, .. } => {
+                write!(f, "Failed to remove previously extracted directory '{}' of artifact '{}'", path.display(), name)
+            },
+            ArtifactTargetDirCreate { name, path, .. } => write!(f, "Failed to create directory '{}' for artifact '{}'", path.display(), name),
+            ArtifactUnpinned { name } => write!(f, "Artifact '{name}' has no checksum in the manifest or lockfile (lock it first)"),
+            LockfileParse { path, .. } => write!(f, "Failed to parse lockfile '{}'", path.display()),
+            LockfileRead { path, .. } => write!(f, "Failed to read lockfile '{}'", path.display()),
+            LockfileSerialize { path, .. } => write!(f, "Failed to serialize lockfile '{}'", path.display()),
+            LockfileWrite { path, .. } => write!(f, "Failed to write lockfile '{}'", path.display()),
+            ManifestParseJson { path, .. } => write!(f, "Failed to parse manifest '{}' as JSON", path.display()),
+            ManifestParseToml { path, .. } => write!(f, "Failed to parse manifest '{}' as TOML", path.display()),
+            ManifestRead { path, .. } => write!(f, "Failed to read manifest '{}'", path.display()),
+            ManifestUnknownFormat { path } => write!(f, "Manifest '{}' is neither a '.toml'- nor a '.json'-file", path.display()),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            ArtifactChecksumParse { .. } => None,
+            ArtifactDownload { err, .. } => Some(&**err),
+            ArtifactExtract { err, .. } => Some(err),
+            ArtifactExtractMove { err, .. } => Some(err),
+            ArtifactExtractRemove { err, .. } => Some(err),
+            ArtifactTargetDirCreate { err, .. } => Some(err),
+            ArtifactUnpinned { .. } => None,
+            LockfileParse { err, .. } => Some(err),
+            LockfileRead { err, .. } => Some(err),
+            LockfileSerialize { err, .. } => Some(err),
+            LockfileWrite { err, .. } => Some(err),
+            ManifestParseJson { err, .. } => Some(err),
+            ManifestParseToml { err, .. } => Some(err),
+            ManifestRead { err, .. } => Some(err),
+            ManifestUnknownFormat { .. } => None,
+        }
+    }
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Parses a hex-encoded SHA-256 checksum.
+///
+/// # Arguments
+/// - `name`: The name of the artifact the checksum belongs to (used for debugging purposes only).
+/// - `raw`: The hex-encoded checksum.
+///
+/// # Returns
+/// The raw checksum.
+///
+/// # Errors
+/// This function errors if `raw` is not valid hex or does not encode exactly 32 bytes.
+fn parse_checksum(name: &str, raw: &str) -> Result<[u8; 32], Error> {
+    let mut checksum: [u8; 32] = [0; 32];
+    match hex::decode_to_slice(raw, &mut checksum) {
+        Ok(_) => Ok(checksum),
+        Err(_) => Err(Error::ArtifactChecksumParse { name: name.into(), raw: raw.into() }),
+    }
+}
+
+/// Makes sure the parent directory of an artifact's target exists.
+///
+/// # Arguments
+/// - `name`: The name of the artifact (used for debugging purposes only).
+/// - `target`: The path the artifact will be downloaded to.
+///
+/// # Errors
+/// This function errors if we failed to create the directory.
+fn create_parent(name: &str, target: &Path) -> Result<(), Error> {
+    if let Some(parent) = target.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            debug!("Creating directory '{}' for artifact '{}'...", parent.display(), name);
+            if let Err(err) = fs::create_dir_all(parent) {
+                return Err(Error::ArtifactTargetDirCreate { name: name.into(), path: parent.into(), err });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks an artifact such that the extraction directory only ever appears complete.
+///
+/// The tarball is first unpacked in a unique, hidden directory next to `extract` (`.<name>.<random>.extract`), which is only moved into
+/// place once unpacking succeeded. The temporary directory is always removed afterwards, so failures leave nothing behind that later runs
+/// would mistake for a finished extraction.
+///
+/// # Arguments
+/// - `name`: The name of the artifact (used for debugging purposes only).
+/// - `tarball`: The path of the downloaded artifact.
+/// - `extract`: The path to unpack the artifact to. Must not exist yet.
+///
+/// # Errors
+/// This function errors if we failed to create the temporary directory, to unpack the artifact or to move it into place.
+fn extract_artifact(name: &str, tarball: &Path, extract: &Path) -> Result<(), Error> {
+    create_parent(name, extract)?;
+
+    // Claim a fresh temporary directory
+    let temp: PathBuf = match create_temp(extract, "extract", |temp| fs::create_dir(temp)) {
+        Ok((temp, _)) => temp,
+        Err((path, err)) => return Err(Error::ArtifactTargetDirCreate { name: name.into(), path, err }),
+    };
+
+    // Unpack in there, and only then move it into place
+    let unpacked: PathBuf = temp.join("out");
+    let res: Result<(), Error> = match crate::tar::unarchive(tarball, &unpacked) {
+        Ok(_) => {
+            debug!("Moving extracted artifact '{}' to '{}'...", name, extract.display());
+            fs::rename(&unpacked, extract).map_err(|err| Error::ArtifactExtractMove { name: name.into(), from: unpacked, to: extract.into(), err })
+        },
+        Err(err) => Err(Error::ArtifactExtract { name: name.into(), err }),
+    };
+
+    // Clean up whatever is left of the temporary directory (failures are only logged, since the extraction itself is done or failed already)
+    debug!("Removing temporary directory '{}'...", temp.display());
+    if let Err(_err) = fs::remove_dir_all(&temp) {
+        debug!("Failed to remove temporary directory '{}': {}", temp.display(), _err);
+    }
+    res
+}
+
+/// Makes sure a single artifact is downloaded (and unpacked, if requested), recording it in the lockfile.
+///
+/// # Arguments
+/// - `manifest`: The [`Manifest`] that the artifact is in (used to resolve its paths).
+/// - `name`: The name of the artifact.
+/// - `artifact`: The [`Artifact`] to ensure.
+/// - `lockfile`: The [`Lockfile`] to pin the artifact with and to record it in.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style`.
+///
+/// # Returns
+/// What had to be done for the artifact.
+///
+/// # Errors
+/// This function errors if the artifact is unpinned, or failed to download, verify or unpack.
+fn ensure_artifact(
+    manifest: &Manifest,
+    name: &str,
+    artifact: &Artifact,
+    lockfile: &mut Lockfile,
+    verbose: Option<Style>,
+) -> Result<Ensured, Error> {
+    let checksum: [u8; 32] = match Manifest::pin(name, artifact, lockfile)? {
+        Some(checksum) => checksum,
+        None => return Err(Error::ArtifactUnpinned { name: name.into() }),
+    };
+    // (note: artifacts that we extract should be tarballs, not, say, an HTML error page; their compression is detected when extracting them)
+    let content: ContentCheck = if artifact.extract.is_some() { ContentCheck::new().with_reject_html() } else { ContentCheck::new() };
+    let security: DownloadSecurity = DownloadSecurity { https: artifact.https, content, ..DownloadSecurity::checksum(&checksum) };
+
+    // Download it if necessary
+    let target: PathBuf = manifest.resolve(&artifact.target);
+    create_parent(name, &target)?;
+    let ensured: Ensured = match ensure_file_report(None, &artifact.url, &target, security, verbose.clone()) {
+        Ok(Some(report)) => {
+            lockfile.record(name, artifact, &report);
+            Ensured::Downloaded
+        },
+        Ok(None) => {
+            // Make sure the lockfile knows about it even if it was downloaded by other means (but keep where it resolved to if we knew)
+            let sha256: String = hex::encode(checksum);
+            if !lockfile
+                .artifacts
+                .get(name)
+                .map(|locked| locked.url == artifact.url && locked.sha256.eq_ignore_ascii_case(&sha256))
+                .unwrap_or(false)
+            {
+                let locked: LockedArtifact = LockedArtifact { url: artifact.url.clone(), resolved: None, sha256 };
+                lockfile.artifacts.insert(name.into(), locked);
+            }
+            Ensured::AlreadyPresent
+        },
+        Err(err) => return Err(Error::ArtifactDownload { name: name.into(), err: Box::new(err) }),
+    };
+
+    // Unpack it if necessary
+    if let Some(extract) = &artifact.extract {
+        let extract: PathBuf = manifest.resolve(extract);
+        if ensured == Ensured::Downloaded && extract.exists() {
+            debug!("Removing stale extraction '{}' of artifact '{}'...", extract.display(), name);
+            if let Err(err) = fs::remove_dir_all(&extract) {
+                return Err(Error::ArtifactExtractRemove { name: name.into(), path: extract, err });
+            }
+        }
+        if !extract.exists() {
+            if let Some(style) = &verbose {
+                println!("Extracting {}...", style.apply_to(extract.display()));
+            }
+            extract_artifact(name, &target, &extract)?;
+        }
+    }
+    Ok(ensured)
+}
+
+/// Pins a single artifact by downloading it and recording its checksum in the lockfile, if it isn't pinned already.
+///
+/// # Arguments
+/// - `manifest`: The [`Manifest`] that the artifact is in (used to resolve its paths).
+/// - `name`: The name of the artifact.
+/// - `artifact`: The [`Artifact`] to lock.
+/// - `lockfile`: The [`Lockfile`] to record the artifact in.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style`.
+///
+/// # Returns
+/// Whether the artifact was newly locked.
+///
+/// # Errors
+/// This function errors if the artifact's checksum is invalid or if we failed to download it.
+fn lock_artifact(
+    manifest: &Manifest,
+    name: &str,
+    artifact: &Artifact,
+    lockfile: &mut Lockfile,
+    verbose: Option<Style>,
+) -> Result<bool, Error> {
+    if Manifest::pin(name, artifact, lockfile)?.is_some() {
+        return Ok(false);
+    }
+    debug!("Locking artifact '{}'...", name);
+    let target: PathBuf = manifest.resolve(&artifact.target);
+    create_parent(name, &target)?;
+    let content: ContentCheck = if artifact.extract.is_some() { ContentCheck::new().with_reject_html() } else { ContentCheck::new() };
+    let security: DownloadSecurity = DownloadSecurity { https: artifact.https, content, ..DownloadSecurity::none() };
+    match download_file_report(None, &artifact.url, &target, security, verbose) {
+        Ok(report) => {
+            lockfile.record(name, artifact, &report);
+            Ok(true)
+        },
+        Err(err) => Err(Error::ArtifactDownload { name: name.into(), err: Box::new(err) }),
+    }
+}
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Describes a single artifact in a [`Manifest`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Artifact {
+    /// The URL to download the artifact from.
+    pub url:     String,
+    /// The hex-encoded SHA-256 checksum of the artifact. If omitted, the artifact is unpinned and has to be [locked](lock()) first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256:  Option<String>,
+    /// Where to download the artifact to.
+    pub target:  PathBuf,
+    /// If given, the artifact is a (compressed) tarball that is unpacked to this directory. Its compression is detected from its first bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<PathBuf>,
+    /// Whether the artifact (and all redirects) must be downloaded over HTTPS.
+    #[serde(default)]
+    pub https:   bool,
+}
+
+
+
+/// Describes a set of artifacts to download.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The artifacts in this manifest, by name.
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, Artifact>,
+    /// The directory relative to which the paths in the artifacts are resolved.
+    #[serde(skip)]
+    pub root:      PathBuf,
+    /// The path of the lockfile that belongs to this manifest.
+    #[serde(skip)]
+    pub lockfile:  PathBuf,
+}
+impl Manifest {
+    /// Loads a manifest from the given path.
+    ///
+    /// Files with the `.json`-extension are parsed as JSON; files with the `.toml`-extension as TOML.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the manifest to load.
+    ///
+    /// # Returns
+    /// A new Manifest with its paths resolved relative to the directory of `path`, and its lockfile next to it (with the `.lock`-extension).
+    ///
+    /// # Errors
+    /// This function errors if we failed to read or parse the file, or if it has an unknown extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        debug!("Loading manifest '{}'...", path.display());
+        let raw: String = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) => return Err(Error::ManifestRead { path: path.into(), err }),
+        };
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&raw, path),
+            Some("toml") => Self::from_toml_str(&raw, path),
+            _ => Err(Error::ManifestUnknownFormat { path: path.into() }),
+        }
+    }
+
+    /// Parses a manifest from TOML.
+    ///
+    /// # Arguments
+    /// - `raw`: The TOML to parse.
+    /// - `path`: The path where the manifest (virtually) lives, which determines the root and lockfile (see [`Manifest::from_path()`]).
+    ///
+    /// # Returns
+    /// A new Manifest.
+    ///
+    /// # Errors
+    /// This function errors if `raw` is not a valid manifest.
+    pub fn from_toml_str(raw: &str, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        match toml::from_str::<Self>(raw) {
+            Ok(manifest) => Ok(manifest.located_at(path)),
+            Err(err) => Err(Error::ManifestParseToml { path: path.into(), err }),
+        }
+    }
+
+    /// Parses a manifest from JSON.
+    ///
+    /// # Arguments
+    /// - `raw`: The JSON to parse.
+    /// - `path`: The path where the manifest (virtually) lives, which determines the root and lockfile (see [`Manifest::from_path()`]).
+    ///
+    /// # Returns
+    /// A new Manifest.
+    ///
+    /// # Errors
+    /// This function errors if `raw` is not a valid manifest.
+    pub fn from_json_str(raw: &str, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        match serde_json::from_str::<Self>(raw) {
+            Ok(manifest) => Ok(manifest.located_at(path)),
+            Err(err) => Err(Error::ManifestParseJson { path: path.into(), err }),
+        }
+    }
+
+    /// Sets the root and lockfile of this manifest as if it were loaded from the given path.
+    ///
+    /// # Arguments
+    /// - `path`: The path where the manifest lives.
+    ///
+    /// # Returns
+    /// The same Manifest, but with `root` and `lockfile` set.
+    #[inline]
+    fn located_at(mut self, path: &Path) -> Self {
+        self.root = path.parent().map(PathBuf::from).unwrap_or_default();
+        self.lockfile = path.with_extension("lock");
+        self
+    }
+
+    /// Resolves a path in an artifact relative to the root of this manifest.
+    ///
+    /// # Arguments
+    /// - `path`: The path to resolve.
+    ///
+    /// # Returns
+    /// The resolved path (which is `path` itself if it was absolute).
+    #[inline]
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathBuf { self.root.join(path) }
+
+    /// Finds the checksum an artifact is pinned to, if any.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the artifact.
+    /// - `artifact`: The artifact itself.
+    /// - `lockfile`: The [`Lockfile`] to fall back to if the manifest doesn't pin the artifact.
+    ///
+    /// # Returns
+    /// The checksum in the manifest if given, or else the one in the lockfile if it was recorded for the same URL, or else [`None`].
+    ///
+    /// # Errors
+    /// This function errors if the checksum is not a valid hex-encoded SHA-256 hash.
+    fn pin(name: &str, artifact: &Artifact, lockfile: &Lockfile) -> Result<Option<[u8; 32]>, Error> {
+        if let Some(raw) = &artifact.sha256 {
+            return parse_checksum(name, raw).map(Some);
+        }
+        match lockfile.artifacts.get(name) {
+            Some(locked) if locked.url == artifact.url => parse_checksum(name, &locked.sha256).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+
+
+/// Describes an artifact as it was downloaded, as recorded in a [`Lockfile`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedArtifact {
+    /// The URL in the manifest at the time of downloading.
+    pub url:      String,
+    /// The URL that the artifact was eventually downloaded from (i.e., after redirects).
+    ///
+    /// This is `None` if the artifact was already present when it was recorded, in which case we don't know where it came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    /// The hex-encoded SHA-256 checksum of the artifact.
+    pub sha256:   String,
+}
+
+/// Records what the artifacts in a [`Manifest`] resolved to.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    /// The locked artifacts, by name.
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, LockedArtifact>,
+}
+impl Lockfile {
+    /// Loads a lockfile from the given path.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the lockfile to load.
+    ///
+    /// # Returns
+    /// The parsed Lockfile, or an empty one if there is no file at `path`.
+    ///
+    /// # Errors
+    /// This function errors if we failed to read or parse the file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path: &Path = path.as_ref();
+        if !path.exists() {
+            debug!("Lockfile '{}' does not exist; using empty lockfile", path.display());
+            return Ok(Self::default());
+        }
+        debug!("Loading lockfile '{}'...", path.display());
+        let raw: String = match fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(err) => return Err(Error::LockfileRead { path: path.into(), err }),
+        };
+        match toml::from_str(&raw) {
+            Ok(lockfile) => Ok(lockfile),
+            Err(err) => Err(Error::LockfileParse { path: path.into(), err }),
+        }
+    }
+
+    /// Writes this lockfile to the given path.
+    ///
+    /// It is written to a temporary file next to `path` first, which is then moved into place; so an interrupted write never leaves half a
+    /// lockfile.
+    ///
+    /// # Arguments
+    /// - `path`: The path to write the lockfile to.
+    ///
+    /// # Errors
+    /// This function errors if we failed to serialize or write the lockfile.
+    pub fn to_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path: &Path = path.as_ref();
+        debug!("Writing lockfile '{}'...", path.display());
+        let raw: String = match toml::to_string_pretty(self) {
+            Ok(raw) => raw,
+            Err(err) => return Err(Error::LockfileSerialize { path: path.into(), err }),
+        };
+        let (temp, mut handle): (PathBuf, fs::File) =
+            match create_temp(path, "tmp", |temp| fs::OpenOptions::new().write(true).create_new(true).open(temp)) {
+                Ok(temp) => temp,
+                Err((temp, err)) => return Err(Error::LockfileWrite { path: temp, err }),
+            };
+        if let Err(err) = handle.write_all(format!("{LOCKFILE_HEADER}{raw}").as_bytes()).and_then(|_| handle.sync_all()) {
+            let _ = fs::remove_file(&temp);
+            return Err(Error::LockfileWrite { path: temp, err });
+        }
+        if let Err(err) = fs::rename(&temp, path) {
+            let _ = fs::remove_file(&temp);
+            return Err(Error::LockfileWrite { path: path.into(), err });
+        }
+        Ok(())
+    }
+
+    /// Records that an artifact has been downloaded.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the artifact.
+    /// - `artifact`: The artifact in the manifest.
+    /// - `report`: The [`DownloadReport`] of downloading it.
+    fn record(&mut self, name: &str, artifact: &Artifact, report: &DownloadReport) {
+        let locked: LockedArtifact =
+            LockedArtifact { url: artifact.url.clone(), resolved: Some(report.url.to_string()), sha256: hex::encode(report.sha256) };
+        self.artifacts.insert(name.into(), locked);
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Makes sure all artifacts in the given manifest are downloaded (and unpacked, if requested).
+///
+/// Artifacts that are already present with the pinned checksum are not downloaded again; others are (re-)downloaded, verified and unpacked.
+/// Every artifact must be pinned, either by a `sha256` in the manifest or by an entry in the lockfile (see [`lock()`]). The lockfile is
+/// updated with the URLs and checksums of everything that was downloaded.
+///
+/// # Arguments
+/// - `manifest`: The [`Manifest`] listing the artifacts.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// What had to be done for every artifact, by name.
+///
+/// # Errors
+/// This function errors if any of the artifacts is unpinned, or failed to download, verify or unpack.
+pub fn ensure_all(manifest: &Manifest, verbose: Option<Style>) -> Result<BTreeMap<String, Ensured>, Error> {
+    debug!("Ensuring {} artifact(s) in manifest (lockfile '{}')...", manifest.artifacts.len(), manifest.lockfile.display());
+    let loaded: Lockfile = Lockfile::from_path(&manifest.lockfile)?;
+    let mut lockfile: Lockfile = loaded.clone();
+    lockfile.artifacts.retain(|name, _| manifest.artifacts.contains_key(name));
+
+    // Go through the artifacts one-by-one
+    let mut result: BTreeMap<String, Ensured> = BTreeMap::new();
+    let mut res: Result<(), Error> = Ok(());
+    for (name, artifact) in &manifest.artifacts {
+        match ensure_artifact(manifest, name, artifact, &mut lockfile, verbose.clone()) {
+            Ok(ensured) => {
+                result.insert(name.clone(), ensured);
+            },
+            Err(err) => {
+                res = Err(err);
+                break;
+            },
+        }
+    }
+
+    // Write the lockfile if we learned something new (even if an artifact failed, so the ones downloaded before it are not forgotten)
+    let written: Result<(), Error> = if lockfile != loaded { lockfile.to_path(&manifest.lockfile) } else { Ok(()) };
+    res.and(written).map(|_| result)
+}
+
+/// Pins all unpinned artifacts in the given manifest by downloading them and recording their checksums in the lockfile.
+///
+/// Artifacts that are already pinned (by the manifest, or by the lockfile for the same URL) are left alone, and entries for artifacts that
+/// are no longer in the manifest are removed from the lockfile. Note that this trusts whatever is downloaded, so only lock artifacts from
+/// sources you trust (e.g., by setting `https` on them).
+///
+/// # Arguments
+/// - `manifest`: The [`Manifest`] listing the artifacts.
+/// - `verbose`: If not `None`, will print to the output with accents given in the given `Style` (use a non-exciting Style to print without styles).
+///
+/// # Returns
+/// The names of the artifacts that were newly locked.
+///
+/// # Errors
+/// This function errors if we failed to download any of the unpinned artifacts, or to read or write the lockfile.
+pub fn lock(manifest: &Manifest, verbose: Option<Style>) -> Result<Vec<String>, Error> {
+    debug!("Locking artifacts in manifest (lockfile '{}')...", manifest.lockfile.display());
+    let loaded: Lockfile = Lockfile::from_path(&manifest.lockfile)?;
+    let mut lockfile: Lockfile = loaded.clone();
+    lockfile.artifacts.retain(|name, _| manifest.artifacts.contains_key(name));
+
+    // Download the unpinned ones
+    let mut locked: Vec<String> = Vec::new();
+    let mut res: Result<(), Error> = Ok(());
+    for (name, artifact) in &manifest.artifacts {
+        match lock_artifact(manifest, name, artifact, &mut lockfile, verbose.clone()) {
+            Ok(true) => locked.push(name.clone()),
+            Ok(false) => {},
+            Err(err) => {
+                res = Err(err);
+                break;
+            },
+        }
+    }
+
+    // Write the lockfile if we learned something new (even if an artifact failed, so the ones downloaded before it are not forgotten)
+    let written: Result<(), Error> = if lockfile != loaded { lockfile.to_path(&manifest.lockfile) } else { Ok(()) };
+    res.and(written).map(|_| locked)
+}
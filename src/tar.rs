@@ -12,24 +12,52 @@
 //!   Defines functions for archiving/unarchiving tarballs.
 //
 
+use std::borrow::Cow;
+use std::convert::Infallible;
 use std::ffi::{OsStr, OsString};
-use std::fmt::{Display, Formatter, Result as FResult};
-use std::path::{Path, PathBuf};
+use std::fmt::{Debug, Display, Formatter, Result as FResult};
+use std::io::{BufRead, Read, Seek as _, Write};
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::{error, fs, io};
 
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
-use flate2::Compression;
-use tar::{Archive, Builder, Entries, Entry};
+use flate2::{Compress, Compression as GzLevel, Crc, FlushCompress};
+use tar::{Archive, Builder, Entries, Entry, EntryType};
 #[cfg(feature = "async-tokio")]
 use ::{
     async_compression::tokio::bufread::GzipDecoder as AsyncGzipDecoder,
     async_compression::tokio::write::GzipEncoder as AsyncGzipEncoder,
-    tokio::io::AsyncWriteExt as _,
+    async_compression::Level as AsyncLevel,
+    std::future::Future as _,
+    std::pin::Pin,
+    std::task::{ready, Context, Poll},
+    tokio::io::{AsyncRead, AsyncReadExt as _, AsyncSeekExt as _, AsyncWrite, AsyncWriteExt as _},
     tokio::{fs as tfs, io as tio},
     tokio_stream::StreamExt as _,
-    tokio_tar::{Archive as AsyncArchive, Builder as AsyncBuilder, Entries as AsyncEntries, Entry as AsyncEntry},
+    tokio_tar::{Archive as AsyncArchive, Builder as AsyncBuilder, Entries as AsyncEntries, Entry as AsyncEntry, EntryType as AsyncEntryType, Header as AsyncHeader},
 };
+#[cfg(feature = "async-smol")]
+use blocking::unblock;
+#[cfg(all(feature = "async-tokio", feature = "bzip2"))]
+use async_compression::tokio::{bufread::BzDecoder as AsyncBzDecoder, write::BzEncoder as AsyncBzEncoder};
+#[cfg(all(feature = "async-tokio", feature = "xz"))]
+use async_compression::tokio::{bufread::XzDecoder as AsyncXzDecoder, write::XzEncoder as AsyncXzEncoder};
+#[cfg(all(feature = "async-tokio", feature = "zstd"))]
+use async_compression::tokio::{bufread::ZstdDecoder as AsyncZstdDecoder, write::ZstdEncoder as AsyncZstdEncoder};
+#[cfg(feature = "bzip2")]
+use bzip2::{bufread::MultiBzDecoder, write::BzEncoder, Compression as BzLevel};
+#[cfg(feature = "xz")]
+use liblzma::{bufread::XzDecoder, write::XzEncoder};
+#[cfg(feature = "zstd")]
+use zstd::stream::{read::Decoder as ZstdDecoder, write::Encoder as ZstdEncoder};
+
+pub use tar::Header;
 
 
 /***** MACROS *****/
@@ -54,14 +82,24 @@ macro_rules! debug {
 #[derive(Debug)]
 pub enum Error {
     // Archive errors
+    /// The compression level given in the [`ArchiveOptions`] is not supported by the chosen [`Compression`].
+    InvalidCompressionLevel { compression: Compression, level: u32 },
     /// Failed to read an entry in the to-be-archived directory.
     SourceDirEntryRead { path: PathBuf, entry: usize, err: std::io::Error },
     /// Failed to read the to-be-archived directory.
     SourceDirRead { path: PathBuf, err: std::io::Error },
+    /// Failed to read the metadata of a to-be-archived file or directory.
+    SourceMetadata { path: PathBuf, err: std::io::Error },
     /// The given source entry is neither a file nor a directory.
     SourceNotAFileOrDir { path: PathBuf },
     /// The given source file did not exist.
     SourceNotFound { path: PathBuf },
+    /// Found a symlink while the [`SymlinkPolicy`] is to error on them.
+    SourceSymlink { path: PathBuf },
+    /// Failed to follow a symlink, e.g., because it's dangling.
+    SourceSymlinkBroken { path: PathBuf, err: std::io::Error },
+    /// Following a symlink would have led to one of its own parent directories, and thus to an endless loop.
+    SourceSymlinkCycle { path: PathBuf, target: PathBuf },
     /// Failed to append a file to the output tar file.
     TargetTarAppend { source: PathBuf, tarball: PathBuf, err: std::io::Error },
     /// Failed to create the output tar file.
@@ -72,14 +110,24 @@ pub enum Error {
     TargetTarFlush { tarball: PathBuf, err: std::io::Error },
 
     // Unarchive errors
+    /// Stopped reading the tarball because it exceeded one of the [`UnarchiveLimits`]. `entry` is the entry that exceeded it, if we got that far.
+    LimitExceeded { tarball: PathBuf, entry: Option<PathBuf>, kind: LimitKind, limit: u64, value: u64 },
     /// Failed to read the available entries in the given source tarball.
     SourceTarEntries { tarball: PathBuf, err: std::io::Error },
     /// Failed to read the one of the availablke entries in the given source tarball.
     SourceTarEntry { tarball: PathBuf, entry: usize, err: std::io::Error },
     /// Did not extract an entry because its path would have escaped the target directory.
     SourceTarEntryEscaped { tarball: PathBuf, entry: PathBuf },
+    /// Did not extract a hardlink because the [`LinkPolicy`] rejects them.
+    SourceTarEntryHardlink { tarball: PathBuf, entry: PathBuf, link: PathBuf },
+    /// Did not keep a symlink or hardlink because it resolves to outside of the target directory.
+    SourceTarEntryLinkEscaped { tarball: PathBuf, entry: PathBuf, link: PathBuf },
+    /// The file given to [`unarchive_file_with()`] (or friends) is not in the source tarball.
+    SourceTarEntryNotFound { tarball: PathBuf, entry: PathBuf },
     /// Failed to read the relative path of an entry in the given source tarball.
     SourceTarEntryPath { tarball: PathBuf, entry: usize, err: std::io::Error },
+    /// Did not extract a symlink because the [`LinkPolicy`] rejects them.
+    SourceTarEntrySymlink { tarball: PathBuf, entry: PathBuf, link: PathBuf },
     /// Failed to unpack an entry from the given source tarball to the given location.
     SourceTarEntryUnpack { tarball: PathBuf, entry: PathBuf, target: PathBuf, err: std::io::Error },
     /// Failed to open the source tarball.
@@ -88,32 +136,71 @@ pub enum Error {
     TargetDirCreate { path: PathBuf, err: std::io::Error },
     /// The target path already exists.
     TargetExists { path: PathBuf },
+    /// The source tarball is not compressed with any of the enabled [`Compression`]s (or it isn't a tarball at all).
+    UnknownArchiveFormat { tarball: PathBuf, magic: Vec<u8> },
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
         use Error::*;
         match self {
+            InvalidCompressionLevel { compression, level } => {
+                let levels: RangeInclusive<u32> = compression.levels();
+                write!(f, "Compression level {} is not supported by {} (expected {} to {})", level, compression, levels.start(), levels.end())
+            },
             SourceDirEntryRead { path, entry, .. } => write!(f, "Failed to read entry {} in source directory '{}'", entry, path.display()),
             SourceDirRead { path, .. } => write!(f, "Failed to read source directory '{}'", path.display()),
+            SourceMetadata { path, .. } => write!(f, "Failed to read metadata of source '{}'", path.display()),
             SourceNotAFileOrDir { path } => write!(f, "Source '{}' is not a file or a directory", path.display()),
             SourceNotFound { path } => write!(f, "Source '{}' not found", path.display()),
+            SourceSymlink { path } => write!(f, "Source '{}' is a symlink, which are not allowed", path.display()),
+            SourceSymlinkBroken { path, .. } => write!(f, "Failed to follow symlink '{}'", path.display()),
+            SourceSymlinkCycle { path, target } => {
+                write!(f, "Following symlink '{}' would loop back to its parent directory '{}'", path.display(), target.display())
+            },
             TargetTarAppend { source, tarball, .. } => write!(f, "Failed to append file '{}' to tarball '{}'", source.display(), tarball.display()),
             TargetTarCreate { tarball, .. } => write!(f, "Failed to create tarball '{}'", tarball.display()),
             TargetTarFinish { tarball, .. } => write!(f, "Failed to finish up tarball '{}'", tarball.display()),
             TargetTarFlush { tarball, .. } => write!(f, "Failed to finish tarball '{}'", tarball.display()),
 
+            LimitExceeded { tarball, entry: Some(entry), kind, limit, value } => {
+                write!(f, "Entry '{}' in tarball '{}' exceeds the limit on the {} ({} > {})", entry.display(), tarball.display(), kind, value, limit)
+            },
+            LimitExceeded { tarball, entry: None, kind, limit, value } => {
+                write!(f, "Tarball '{}' exceeds the limit on the {} ({} > {})", tarball.display(), kind, value, limit)
+            },
             SourceTarEntries { tarball, .. } => write!(f, "Failed to read entries in tarball '{}'", tarball.display()),
             SourceTarEntry { tarball, entry, .. } => write!(f, "Failed to read entry {} in tarball '{}'", entry, tarball.display()),
             SourceTarEntryEscaped { tarball, entry } => {
                 write!(f, "Entry '{}' in tarball '{}' would have escaped target directory", entry.display(), tarball.display())
             },
+            SourceTarEntryHardlink { tarball, entry, link } => {
+                write!(f, "Entry '{}' in tarball '{}' is a hardlink to '{}', which are not allowed", entry.display(), tarball.display(), link.display())
+            },
+            SourceTarEntryLinkEscaped { tarball, entry, link } => write!(
+                f,
+                "Entry '{}' in tarball '{}' links to '{}', which resolves to outside of the target directory",
+                entry.display(),
+                tarball.display(),
+                link.display()
+            ),
+            SourceTarEntryNotFound { tarball, entry } => write!(f, "Tarball '{}' has no file '{}'", tarball.display(), entry.display()),
             SourceTarEntryPath { tarball, entry, .. } => write!(f, "Failed to get path of entry {} in tarball '{}'", entry, tarball.display()),
+            SourceTarEntrySymlink { tarball, entry, link } => {
+                write!(f, "Entry '{}' in tarball '{}' is a symlink to '{}', which are not allowed", entry.display(), tarball.display(), link.display())
+            },
             SourceTarEntryUnpack { tarball, entry, target, .. } => {
                 write!(f, "Failed to unpack entry '{}' in tarball '{}' to '{}'", entry.display(), tarball.display(), target.display())
             },
             SourceTarOpen { tarball, .. } => write!(f, "Failed to open source tarball '{}'", tarball.display()),
             TargetDirCreate { path, .. } => write!(f, "Failed to create target directory '{}'", path.display()),
             TargetExists { path } => write!(f, "Target path '{}' already exists", path.display()),
+            UnknownArchiveFormat { tarball, magic } => {
+                write!(f, "Unknown archive format of tarball '{}' (starts with {})", tarball.display(), hex_bytes(magic))?;
+                if let Some((_, name)) = MAGICS.iter().find(|(m, _)| magic.starts_with(m)) {
+                    write!(f, "; it looks {name}-compressed, but the '{name}'-feature is not enabled")?;
+                }
+                Ok(())
+            },
         }
     }
 }
@@ -121,199 +208,2875 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         use Error::*;
         match self {
+            InvalidCompressionLevel { .. } => None,
             SourceDirEntryRead { err, .. } => Some(err),
             SourceDirRead { err, .. } => Some(err),
+            SourceMetadata { err, .. } => Some(err),
             SourceNotAFileOrDir { .. } => None,
             SourceNotFound { .. } => None,
+            SourceSymlink { .. } => None,
+            SourceSymlinkBroken { err, .. } => Some(err),
+            SourceSymlinkCycle { .. } => None,
             TargetTarAppend { err, .. } => Some(err),
             TargetTarCreate { err, .. } => Some(err),
             TargetTarFinish { err, .. } => Some(err),
             TargetTarFlush { err, .. } => Some(err),
 
+            LimitExceeded { .. } => None,
             SourceTarEntries { err, .. } => Some(err),
             SourceTarEntry { err, .. } => Some(err),
             SourceTarEntryEscaped { .. } => None,
+            SourceTarEntryHardlink { .. } => None,
+            SourceTarEntryLinkEscaped { .. } => None,
+            SourceTarEntryNotFound { .. } => None,
             SourceTarEntryPath { err, .. } => Some(err),
+            SourceTarEntrySymlink { .. } => None,
             SourceTarEntryUnpack { err, .. } => Some(err),
             SourceTarOpen { err, .. } => Some(err),
             TargetDirCreate { err, .. } => Some(err),
             TargetExists { .. } => None,
+            UnknownArchiveFormat { .. } => None,
         }
     }
 }
 
 
 
+/// Defines the error returned when parsing an unknown [`Compression`].
+#[derive(Debug)]
+pub struct UnknownCompressionError {
+    /// The string that we failed to parse.
+    pub raw: String,
+}
+impl Display for UnknownCompressionError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        write!(f, "Unknown compression '{}' (expected one of: {})", self.raw, Compression::ALL.iter().map(Compression::to_string).collect::<Vec<String>>().join(", "))
+    }
+}
+impl error::Error for UnknownCompressionError {}
+
+/// Defines the error returned when parsing an unknown [`SymlinkPolicy`].
+#[derive(Debug)]
+pub struct UnknownSymlinkPolicyError {
+    /// The string that we failed to parse.
+    pub raw: String,
+}
+impl Display for UnknownSymlinkPolicyError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        write!(f, "Unknown symlink policy '{}' (expected one of: {})", self.raw, SymlinkPolicy::ALL.map(|p| p.to_string()).join(", "))
+    }
+}
+impl error::Error for UnknownSymlinkPolicyError {}
+
+/// Defines the error returned when parsing an unknown [`LinkPolicy`].
+#[derive(Debug)]
+pub struct UnknownLinkPolicyError {
+    /// The string that we failed to parse.
+    pub raw: String,
+}
+impl Display for UnknownLinkPolicyError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        write!(f, "Unknown link policy '{}' (expected one of: {})", self.raw, LinkPolicy::ALL.map(|p| p.to_string()).join(", "))
+    }
+}
+impl error::Error for UnknownLinkPolicyError {}
+
+
 
 
-/***** LIBRARY *****/
-/// Archives the given file or directory as a `.tar.gz` file.
+
+/***** HELPER FUNCTIONS *****/
+/// Formats bytes as space-separated hexadecimal pairs (e.g., `1f 8b`).
 ///
-/// If you enabled the `async-tokio` feature, also check the [`archive_async()`]-function for async contexts.
+/// # Arguments
+/// - `bytes`: The bytes to format.
+///
+/// # Returns
+/// A string with the formatted bytes, or `nothing` if there are none.
+fn hex_bytes(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "nothing".into();
+    }
+    bytes.iter().map(|b| format!("{b:02x}")).collect::<Vec<String>>().join(" ")
+}
+
+/// Decides how a tarball is compressed based on its first bytes.
 ///
 /// # Arguments
-/// - `source`: The source file or directory to archive.
-/// - `tarball`: The target tarball file to archive to.
-/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting tarfile
-///   (i.e., the files in the root dir will be in the tar's root instead of the directory). Ignore otherwise.
+/// - `tarball`: The path of the tarball (for errors).
+/// - `header`: The first [`Compression::DETECT_LEN`] bytes of the tarball (or less if it's shorter).
+///
+/// # Returns
+/// The detected [`Compression`].
 ///
 /// # Errors
-/// This function errors if we somehow encountered an error.
+/// This function errors with [`Error::UnknownArchiveFormat`] if the bytes aren't recognized as any of the enabled compressions.
+fn detect(tarball: &Path, header: &[u8]) -> Result<Compression, Error> {
+    match Compression::detect(header) {
+        Some(compression) => {
+            debug!("Detected compression of '{}' as {}", tarball.display(), compression);
+            Ok(compression)
+        },
+        None => Err(Error::UnknownArchiveFormat { tarball: tarball.into(), magic: header[..header.len().min(8)].into() }),
+    }
+}
+
+/// Finds out how a tarball is compressed, reading its first bytes if the user didn't tell us.
 ///
-/// # Examples
-/// ```rust
-/// use download::tar::archive;
+/// # Arguments
+/// - `tarball`: The path of the tarball (for errors).
+/// - `handle`: The opened tarball. It is rewound to the start after reading from it.
+/// - `compression`: The [`Compression`] given by the user, if any.
 ///
-/// // Write a test directory
-/// let tmp = std::env::temp_dir();
-/// let dir = tmp.join("example");
-/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
-/// std::fs::create_dir(&dir).unwrap();
-/// std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
-/// std::fs::write(dir.join("file2.txt"), "General Kenobi...\n").unwrap();
-/// std::fs::write(dir.join("file3.txt"), "...you are a bold one\n").unwrap();
+/// # Returns
+/// The [`Compression`] of the tarball.
 ///
-/// // We can archive them!
-/// let tar = tmp.join("example.tar.gz");
-/// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
-/// archive(&dir, &tar, false).unwrap();
+/// # Errors
+/// This function errors if we failed to read the tarball, or if its compression is unknown.
+fn sniff(tarball: &Path, handle: &mut fs::File, compression: Option<Compression>) -> Result<Compression, Error> {
+    if let Some(compression) = compression {
+        return Ok(compression);
+    }
+    let mut header: Vec<u8> = Vec::with_capacity(Compression::DETECT_LEN);
+    if let Err(err) = handle.take(Compression::DETECT_LEN as u64).read_to_end(&mut header).and_then(|_| handle.rewind()) {
+        return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
+    }
+    detect(tarball, &header)
+}
+
+/// Finds out how a tarball is compressed, reading its first bytes if the user didn't tell us.
 ///
-/// assert!(tar.is_file());
-/// ```
-pub fn archive(source: impl AsRef<Path>, tarball: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
-    let source: &Path = source.as_ref();
-    let tarball: &Path = tarball.as_ref();
-    debug!("Archiving '{}' to '{}'...", source.display(), tarball.display());
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The path of the tarball (for errors).
+/// - `handle`: The opened tarball. It is rewound to the start after reading from it.
+/// - `compression`: The [`Compression`] given by the user, if any.
+///
+/// # Returns
+/// The [`Compression`] of the tarball.
+///
+/// # Errors
+/// This function errors if we failed to read the tarball, or if its compression is unknown.
+#[cfg(feature = "async-tokio")]
+async fn sniff_async(tarball: &Path, handle: &mut tfs::File, compression: Option<Compression>) -> Result<Compression, Error> {
+    if let Some(compression) = compression {
+        return Ok(compression);
+    }
+    let mut header: Vec<u8> = Vec::with_capacity(Compression::DETECT_LEN);
+    if let Err(err) = (&mut *handle).take(Compression::DETECT_LEN as u64).read_to_end(&mut header).await {
+        return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
+    }
+    if let Err(err) = handle.rewind().await {
+        return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
+    }
+    detect(tarball, &header)
+}
 
-    // Open the target file
-    let handle: fs::File = match fs::File::create(tarball) {
-        Ok(handle) => handle,
-        Err(err) => {
-            return Err(Error::TargetTarCreate { tarball: tarball.into(), err });
-        },
-    };
+/// Resolves the compression level to archive with.
+///
+/// # Arguments
+/// - `options`: The [`ArchiveOptions`] that may or may not specify a level.
+///
+/// # Returns
+/// The level in the options, or the [`Compression::default_level()`] if they don't specify any.
+///
+/// # Errors
+/// This function errors if the level is not in the [`Compression::levels()`] of the chosen compression.
+fn level(options: &ArchiveOptions) -> Result<u32, Error> {
+    let level: u32 = options.level.unwrap_or(options.compression.default_level());
+    if !options.compression.levels().contains(&level) {
+        return Err(Error::InvalidCompressionLevel { compression: options.compression, level });
+    }
+    Ok(level)
+}
 
-    // Create the encoder & tarfile around this file
-    let enc: GzEncoder<_> = GzEncoder::new(handle, Compression::best());
-    let mut tar: Builder<GzEncoder<_>> = Builder::new(enc);
+/// Wraps a writer in the encoder for the given compression.
+///
+/// # Arguments
+/// - `writer`: The writer to write the compressed tarball to.
+/// - `compression`: The [`Compression`] to use.
+/// - `level`: The level to compress with. Assumed to be in the [`Compression::levels()`].
+/// - `parallel`: Whether to compress on all cores. Only affects gzip.
+///
+/// # Returns
+/// An [`Encoder`] that compresses everything written to it.
+///
+/// # Errors
+/// This function errors if we failed to initialize the encoder.
+fn encoder<W: Write>(writer: W, compression: Compression, level: u32, parallel: bool) -> io::Result<Encoder<W>> {
+    match compression {
+        Compression::None => Ok(Encoder::None(writer)),
+        Compression::Gzip if parallel => Ok(Encoder::ParallelGzip(ParallelGzEncoder::new(writer, level))),
+        Compression::Gzip => Ok(Encoder::Gzip(GzEncoder::new(writer, GzLevel::new(level)))),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Encoder::Xz(XzEncoder::new(writer, level))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Encoder::Zstd(ZstdEncoder::new(writer, level as i32)?)),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Ok(Encoder::Bzip2(BzEncoder::new(writer, BzLevel::new(level)))),
+    }
+}
 
-    // Now add the source recursively
-    let mut is_root_dir: bool = true;
-    let mut todo: Vec<(PathBuf, OsString)> = vec![(source.into(), source.file_name().map(|f| f.into()).unwrap_or_else(|| OsString::from(".")))];
-    while let Some((path, name)) = todo.pop() {
-        // Switch on the file type
-        if path.is_file() {
-            debug!("Adding file '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+/// Writes a path as it appears in a tarball, i.e., with only its normal and `..` components, separated by `/`.
+///
+/// # Arguments
+/// - `path`: The path to write.
+///
+/// # Returns
+/// The path as a string (e.g., `a/b/c` for `./a//b/c/`).
+fn slash_path(path: &Path) -> String {
+    let mut res: String = String::new();
+    for comp in path.components() {
+        let comp: Cow<str> = match comp {
+            Component::Normal(comp) => comp.to_string_lossy(),
+            Component::ParentDir => Cow::Borrowed(".."),
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => continue,
+        };
+        if !res.is_empty() {
+            res.push('/');
+        }
+        res.push_str(&comp);
+    }
+    res
+}
 
-            // Compress as a file
-            if let Err(err) = tar.append_path_with_name(&path, name) {
-                return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
-            }
-        } else if path.is_dir() {
-            // Recurse to add the files
-            let entries: fs::ReadDir = match fs::read_dir(&path) {
-                Ok(entries) => entries,
-                Err(err) => {
-                    return Err(Error::SourceDirRead { path, err });
-                },
-            };
-            for (i, entry) in entries.enumerate() {
-                // Fetch the next entry
-                let entry: fs::DirEntry = match entry {
-                    Ok(entry) => entry,
-                    Err(err) => {
-                        return Err(Error::SourceDirEntryRead { path, entry: i, err });
+/// Resolves a path like the OS would when opening it, but without requiring it to exist.
+///
+/// Symlinks are followed wherever they appear in the path (including at the end), while `..` and anything that doesn't exist are resolved
+/// lexically.
+///
+/// # Arguments
+/// - `path`: The path to resolve. Should be absolute, or relative to the current directory.
+///
+/// # Returns
+/// The resolved path, or [`None`] if we failed to read a symlink or had to follow too many (i.e., the path loops).
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let path: PathBuf = std::env::current_dir().ok()?.join(path);
+    let mut todo: Vec<PathBuf> = path.components().rev().map(|c| PathBuf::from(c.as_os_str())).collect();
+    let mut resolved: PathBuf = PathBuf::new();
+    let mut hops: usize = 0;
+    while let Some(next) = todo.pop() {
+        match next.components().next() {
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            },
+            Some(Component::Normal(_)) => {
+                let candidate: PathBuf = resolved.join(&next);
+                match fs::symlink_metadata(&candidate) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        hops += 1;
+                        if hops > MAX_LINK_HOPS {
+                            return None;
+                        }
+                        let link: PathBuf = fs::read_link(&candidate).ok()?;
+                        todo.extend(link.components().rev().map(|c| PathBuf::from(c.as_os_str())));
                     },
-                };
+                    _ => resolved = candidate,
+                }
+            },
+            Some(Component::CurDir) | None => {},
+            // Roots (and prefixes) reset the path
+            Some(_) => resolved.push(next),
+        }
+    }
+    Some(resolved)
+}
+
+/// Finds the size of an entry once it's unpacked, like [`Entry::size()`] does for synchronous entries.
+///
+/// This variation is built for [`tokio_tar`]'s headers, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `header`: The header of the entry.
+///
+/// # Returns
+/// The size of the entry in bytes, which for sparse files is their full size instead of the size of their data in the tarball.
+#[cfg(feature = "async-tokio")]
+fn entry_size_async(header: &AsyncHeader) -> u64 {
+    let size: io::Result<u64> = match header.as_gnu() {
+        Some(gnu) if header.entry_type().is_gnu_sparse() => gnu.real_size(),
+        _ => header.entry_size(),
+    };
+    // Invalid sizes are already refused by `tokio_tar` itself
+    size.unwrap_or_default()
+}
 
-                // Compute the tar-side path of this file
-                let name: &OsStr = if skip_root_dir && is_root_dir { OsStr::new("") } else { &name };
+/// Converts the header of an entry to a synchronous [`Header`], such that it can be given to the [`UnarchiveCore`] (and [`EntryPredicate`]s).
+///
+/// This variation is built for [`tokio_tar`]'s headers, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `header`: The header of the entry.
+///
+/// # Returns
+/// A [`Header`] with the same bytes.
+#[cfg(feature = "async-tokio")]
+fn header_async(header: &AsyncHeader) -> Header { Header::from_byte_slice(header.as_bytes()).clone() }
 
-                // Add its path
-                todo.push((entry.path(), PathBuf::from(name).join(entry.file_name()).into()));
-            }
-            is_root_dir = false;
-        } else if !path.exists() {
-            return Err(Error::SourceNotFound { path });
-        } else {
-            return Err(Error::SourceNotAFileOrDir { path });
+/// Resolves a path like the OS would when opening it, but without requiring it to exist.
+///
+/// This variation uses [`tokio`]'s filesystem operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `path`: The path to resolve. Should be absolute, or relative to the current directory.
+///
+/// # Returns
+/// The resolved path, or [`None`] if we failed to read a symlink or had to follow too many (i.e., the path loops).
+#[cfg(feature = "async-tokio")]
+async fn resolve_async(path: &Path) -> Option<PathBuf> {
+    let path: PathBuf = std::env::current_dir().ok()?.join(path);
+    let mut todo: Vec<PathBuf> = path.components().rev().map(|c| PathBuf::from(c.as_os_str())).collect();
+    let mut resolved: PathBuf = PathBuf::new();
+    let mut hops: usize = 0;
+    while let Some(next) = todo.pop() {
+        match next.components().next() {
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            },
+            Some(Component::Normal(_)) => {
+                let candidate: PathBuf = resolved.join(&next);
+                match tfs::symlink_metadata(&candidate).await {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        hops += 1;
+                        if hops > MAX_LINK_HOPS {
+                            return None;
+                        }
+                        let link: PathBuf = tfs::read_link(&candidate).await.ok()?;
+                        todo.extend(link.components().rev().map(|c| PathBuf::from(c.as_os_str())));
+                    },
+                    _ => resolved = candidate,
+                }
+            },
+            Some(Component::CurDir) | None => {},
+            // Roots (and prefixes) reset the path
+            Some(_) => resolved.push(next),
         }
     }
+    Some(resolved)
+}
 
-    // Finish writing the archive
-    debug!("Finishing tarball...");
-    match tar.finish() {
-        Ok(_) => Ok(()),
-        Err(err) => Err(Error::TargetTarFinish { tarball: tarball.into(), err }),
+/// Unpacks an entry to where the [`UnarchiveCore`] planned it.
+///
+/// Entries that weren't stripped (see [`UnarchiveOptions::strip_components`]) are simply unpacked by [`Entry::unpack_in()`]. Others are unpacked
+/// like it would, but to their planned target: only if their parent directory resolves to inside the target directory, and with hardlinks
+/// pointing to their stripped source.
+///
+/// # Arguments
+/// - `entry`: The entry to unpack.
+/// - `planned`: The [`PlannedEntry`] describing where to unpack it.
+/// - `target`: The directory to unpack to.
+/// - `root`: The path the target directory resolves to.
+/// - `source`: For hardlinks, the path of their source in the target directory (see [`UnarchiveCore::on_hardlink()`]).
+///
+/// # Returns
+/// True if the entry was unpacked, or false if it would have escaped the target directory.
+///
+/// # Errors
+/// This function errors if we failed to unpack the entry.
+fn unpack<R: Read>(entry: &mut Entry<R>, planned: &PlannedEntry, target: &Path, root: &Path, source: Option<&Path>) -> io::Result<bool> {
+    if !planned.stripped {
+        return entry.unpack_in(target);
+    }
+    let parent: &Path = match planned.target.parent() {
+        Some(parent) if resolve(parent).is_some_and(|parent| parent.starts_with(root)) => parent,
+        _ => return Ok(false),
+    };
+    fs::create_dir_all(parent)?;
+    match source {
+        Some(source) if !resolve(source).is_some_and(|source| source.starts_with(root)) => Ok(false),
+        Some(source) => fs::hard_link(source, &planned.target).map(|_| true),
+        None => entry.unpack(&planned.target).map(|_| true),
     }
 }
 
-/// Archives the given file or directory as a `.tar.gz` file.
+/// Unpacks an entry to where the [`UnarchiveCore`] planned it.
 ///
 /// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
 ///
 /// # Arguments
-/// - `source`: The source file or directory to archive.
-/// - `tarball`: The target tarball file to archive to.
-/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting tarfile
-///   (i.e., the files in the root dir will be in the tar's root instead of the directory). Ignore otherwise.
+/// - `entry`: The entry to unpack.
+/// - `planned`: The [`PlannedEntry`] describing where to unpack it.
+/// - `target`: The directory to unpack to.
+/// - `root`: The path the target directory resolves to.
+/// - `source`: For hardlinks, the path of their source in the target directory (see [`UnarchiveCore::on_hardlink()`]).
+///
+/// # Returns
+/// True if the entry was unpacked, or false if it would have escaped the target directory.
 ///
 /// # Errors
-/// This function errors if we somehow encountered an error.
+/// This function errors if we failed to unpack the entry.
+#[cfg(feature = "async-tokio")]
+async fn unpack_async<R: Unpin + AsyncRead>(
+    entry: &mut AsyncEntry<R>,
+    planned: &PlannedEntry,
+    target: &Path,
+    root: &Path,
+    source: Option<&Path>,
+) -> io::Result<bool> {
+    if !planned.stripped {
+        return entry.unpack_in(target).await;
+    }
+    let parent: &Path = match planned.target.parent() {
+        Some(parent) if resolve_async(parent).await.is_some_and(|parent| parent.starts_with(root)) => parent,
+        _ => return Ok(false),
+    };
+    tfs::create_dir_all(parent).await?;
+    match source {
+        Some(source) if !resolve_async(source).await.is_some_and(|source| source.starts_with(root)) => Ok(false),
+        Some(source) => tfs::hard_link(source, &planned.target).await.map(|_| true),
+        None => entry.unpack(&planned.target).await.map(|_| true),
+    }
+}
+
+/// Compresses a block of input for the [`ParallelGzCore`] as a raw deflate stream that can be concatenated to others.
 ///
-/// # Examples
-/// ```rust
-/// # tokio_test::block_on(async {
-/// use download::tar::archive_async;
+/// # Arguments
+/// - `block`: The input to compress.
+/// - `level`: The level to compress with.
 ///
-/// // Write a test directory
-/// let tmp = std::env::temp_dir();
-/// let dir = tmp.join("example");
-/// # if dir.exists() { tokio::fs::remove_dir_all(&dir).await.unwrap(); }
-/// tokio::fs::create_dir(&dir).await.unwrap();
-/// tokio::fs::write(dir.join("file1.txt"), "Hello there!\n").await.unwrap();
-/// tokio::fs::write(dir.join("file2.txt"), "General Kenobi...\n").await.unwrap();
-/// tokio::fs::write(dir.join("file3.txt"), "...you are a bold one\n").await.unwrap();
+/// # Returns
+/// The compressed block, which ends byte-aligned with a sync flush (and without a final block).
 ///
-/// // We can archive them!
-/// let tar = tmp.join("example.tar.gz");
-/// # if tar.exists() { tokio::fs::remove_file(&tar).await.unwrap(); }
-/// archive_async(&dir, &tar, false).await.unwrap();
+/// # Errors
+/// This function errors if the deflate compressor failed.
+fn deflate_block(block: &[u8], level: GzLevel) -> io::Result<Vec<u8>> {
+    let mut compress: Compress = Compress::new(level, false);
+    let mut output: Vec<u8> = Vec::with_capacity(block.len() + block.len() / 1024 + 64);
+    loop {
+        let consumed: usize = compress.total_in() as usize;
+        compress.compress_vec(&block[consumed..], &mut output, FlushCompress::Sync).map_err(io::Error::other)?;
+        // We're done once all input is consumed and the flush didn't run out of space
+        if compress.total_in() as usize == block.len() && output.len() < output.capacity() {
+            return Ok(output);
+        }
+        output.reserve(output.capacity());
+    }
+}
+
+/// Wraps a reader in the decoder for the given compression.
 ///
-/// assert!(tar.is_file());
-/// # });
-/// ```
+/// # Arguments
+/// - `reader`: The reader to read the compressed tarball from.
+/// - `compression`: The [`Compression`] the tarball is compressed with.
+///
+/// # Returns
+/// A reader that yields the decompressed tarball.
+///
+/// # Errors
+/// This function errors if we failed to initialize the decoder.
+fn decoder<'r, R: 'r + BufRead>(reader: R, compression: Compression) -> io::Result<Box<dyn 'r + Read>> {
+    match compression {
+        Compression::None => Ok(Box::new(reader)),
+        Compression::Gzip => Ok(Box::new(GzDecoder::new(reader))),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Ok(Box::new(XzDecoder::new_multi_decoder(reader))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Ok(Box::new(ZstdDecoder::with_buffer(reader)?)),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Ok(Box::new(MultiBzDecoder::new(reader))),
+    }
+}
+
+/// Wraps an async writer in the encoder for the given compression.
+///
+/// # Arguments
+/// - `writer`: The writer to write the compressed tarball to.
+/// - `compression`: The [`Compression`] to use.
+/// - `level`: The level to compress with. Assumed to be in the [`Compression::levels()`].
+/// - `parallel`: Whether to compress on all cores. Only affects gzip.
+///
+/// # Returns
+/// A writer that compresses everything written to it. Don't forget to shut it down to write the final bytes.
 #[cfg(feature = "async-tokio")]
-pub async fn archive_async(source: impl AsRef<Path>, tarball: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
-    let source: &Path = source.as_ref();
-    let tarball: &Path = tarball.as_ref();
-    debug!("Archiving '{}' to '{}'...", source.display(), tarball.display());
+fn encoder_async<W: 'static + Send + Unpin + AsyncWrite>(
+    writer: W,
+    compression: Compression,
+    level: u32,
+    parallel: bool,
+) -> Box<dyn Send + Unpin + AsyncWrite> {
+    let quality: AsyncLevel = AsyncLevel::Precise(level as i32);
+    match compression {
+        Compression::None => Box::new(writer),
+        Compression::Gzip if parallel => Box::new(AsyncParallelGzEncoder::new(writer, level)),
+        Compression::Gzip => Box::new(AsyncGzipEncoder::with_quality(writer, quality)),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Box::new(AsyncXzEncoder::with_quality(writer, quality)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(AsyncZstdEncoder::with_quality(writer, quality)),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(AsyncBzEncoder::with_quality(writer, quality)),
+    }
+}
 
-    // Open the target file
-    let handle: tfs::File = match tfs::File::create(tarball).await {
+/// Wraps an async reader in the decoder for the given compression.
+///
+/// # Arguments
+/// - `reader`: The reader to read the compressed tarball from.
+/// - `compression`: The [`Compression`] the tarball is compressed with.
+///
+/// # Returns
+/// A reader that yields the decompressed tarball.
+#[cfg(feature = "async-tokio")]
+fn decoder_async<R: 'static + Send + Unpin + tio::AsyncBufRead>(reader: R, compression: Compression) -> Box<dyn Send + Unpin + AsyncRead> {
+    match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(AsyncGzipDecoder::new(reader)),
+        #[cfg(feature = "xz")]
+        Compression::Xz => {
+            let mut dec = AsyncXzDecoder::new(reader);
+            dec.multiple_members(true);
+            Box::new(dec)
+        },
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            let mut dec = AsyncZstdDecoder::new(reader);
+            dec.multiple_members(true);
+            Box::new(dec)
+        },
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => {
+            let mut dec = AsyncBzDecoder::new(reader);
+            dec.multiple_members(true);
+            Box::new(dec)
+        },
+    }
+}
+
+/// Opens a (compressed) tarball to read its entries from.
+///
+/// # Arguments
+/// - `tarball`: The path of the tarball to open.
+/// - `options`: The [`UnarchiveOptions`] that determine how it is decompressed (its [`Compression`] and maximum ratio).
+///
+/// # Returns
+/// The [`Archive`] to read the entries from, together with the [`RatioCounter`] counting the bytes on either side of its decoder.
+///
+/// # Errors
+/// This function errors if we failed to open the tarball, if its compression is unknown or if we failed to initialize its decoder.
+fn open_entries(tarball: &Path, options: &UnarchiveOptions) -> Result<(Archive<Box<dyn Read>>, RatioCounter), Error> {
+    let mut handle: fs::File = match fs::File::open(tarball) {
         Ok(handle) => handle,
         Err(err) => {
-            return Err(Error::TargetTarCreate { tarball: tarball.into(), err });
+            return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
         },
     };
+    let compression: Compression = sniff(tarball, &mut handle, options.compression)?;
+    let counter: RatioCounter = RatioCounter::new(options.limits.max_ratio);
 
-    // Create the encoder & tarfile around this file
-    let enc: AsyncGzipEncoder<_> = AsyncGzipEncoder::new(handle);
-    let mut tar: AsyncBuilder<AsyncGzipEncoder<_>> = AsyncBuilder::new(enc);
+    // Create the decoder & tarfile around this file, counting the bytes on either side of it
+    let dec: Box<dyn Read> = match decoder(io::BufReader::new(RatioReader::new(handle, counter.clone(), false)), compression) {
+        Ok(dec) => Box::new(RatioReader::new(dec, counter.clone(), true)),
+        Err(err) => {
+            return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
+        },
+    };
+    Ok((Archive::new(dec), counter))
+}
 
-    // Now add the source recursively
-    let mut is_root_dir: bool = true;
-    let mut todo: Vec<(PathBuf, OsString)> = vec![(source.into(), source.file_name().map(|f| f.into()).unwrap_or_else(|| OsString::from(".")))];
-    while let Some((path, name)) = todo.pop() {
-        // Switch on the file type
-        if path.is_file() {
-            debug!("Adding file '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+/// Opens a (compressed) tarball to read its entries from.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The path of the tarball to open.
+/// - `options`: The [`UnarchiveOptions`] that determine how it is decompressed (its [`Compression`] and maximum ratio).
+///
+/// # Returns
+/// The [`AsyncArchive`] to read the entries from, together with the [`RatioCounter`] counting the bytes on either side of its decoder.
+///
+/// # Errors
+/// This function errors if we failed to open the tarball or if its compression is unknown.
+#[cfg(feature = "async-tokio")]
+async fn open_entries_async(
+    tarball: &Path,
+    options: &UnarchiveOptions,
+) -> Result<(AsyncArchive<Box<dyn Send + Unpin + AsyncRead>>, RatioCounter), Error> {
+    let mut handle: tfs::File = match tfs::File::open(tarball).await {
+        Ok(handle) => handle,
+        Err(err) => {
+            return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
+        },
+    };
+    let compression: Compression = sniff_async(tarball, &mut handle, options.compression).await?;
+    let counter: RatioCounter = RatioCounter::new(options.limits.max_ratio);
+
+    // Create the decoder & tarfile around this file, counting the bytes on either side of it
+    let dec: Box<dyn Send + Unpin + AsyncRead> = decoder_async(tio::BufReader::new(RatioReader::new(handle, counter.clone(), false)), compression);
+    let dec: Box<dyn Send + Unpin + AsyncRead> = Box::new(RatioReader::new(dec, counter.clone(), true));
+    Ok((AsyncArchive::new(dec), counter))
+}
+
+
+
+
+
+/***** AUXILLARY *****/
+/// The magic bytes that compressed streams start with, together with the name of their codec.
+///
+/// Lists all codecs, regardless of the enabled features, so we can tell the user which feature they're missing.
+const MAGICS: [(&[u8], &str); 4] =
+    [(&[0x1F, 0x8B], "gzip"), (&[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00], "xz"), (&[0x28, 0xB5, 0x2F, 0xFD], "zstd"), (b"BZh", "bzip2")];
+
+/// The offset of the `ustar` magic in the header of the first entry of an uncompressed tarball.
+const USTAR_OFFSET: usize = 257;
+
+/// The maximum number of symlinks we follow when resolving a single path (the same as Linux does).
+const MAX_LINK_HOPS: usize = 40;
+
+
+
+/// Defines how a tarball is compressed.
+///
+/// Only gzip (and no compression) is always available; the other codecs are enabled by their cargo features of the same name (`xz`, `zstd` and
+/// `bzip2`). Since which variants exist depends on those features, the enum is non-exhaustive.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// The tarball isn't compressed at all (i.e., a `.tar`-file).
+    None,
+    /// The tarball is compressed with gzip (i.e., a `.tar.gz`-file).
+    #[default]
+    Gzip,
+    /// The tarball is compressed with xz (i.e., a `.tar.xz`-file).
+    #[cfg(feature = "xz")]
+    Xz,
+    /// The tarball is compressed with zstd (i.e., a `.tar.zst`-file).
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// The tarball is compressed with bzip2 (i.e., a `.tar.bz2`-file).
+    #[cfg(feature = "bzip2")]
+    Bzip2,
+}
+impl Compression {
+    /// All compressions that are available with the enabled features.
+    pub const ALL: &'static [Self] = &[
+        Self::None,
+        Self::Gzip,
+        #[cfg(feature = "xz")]
+        Self::Xz,
+        #[cfg(feature = "zstd")]
+        Self::Zstd,
+        #[cfg(feature = "bzip2")]
+        Self::Bzip2,
+    ];
+
+    /// The number of bytes at the start of a tarball needed by [`Compression::detect()`].
+    pub const DETECT_LEN: usize = USTAR_OFFSET + 5;
+
+    /// Detects how a tarball is compressed from its first bytes.
+    ///
+    /// Compressed tarballs are recognized by the magic bytes of their codec (e.g., `1f 8b` for gzip), and uncompressed tarballs by the `ustar`
+    /// magic in the header of their first entry.
+    ///
+    /// # Arguments
+    /// - `header`: The first [`Compression::DETECT_LEN`] bytes of the tarball (or less if it's shorter).
+    ///
+    /// # Returns
+    /// The detected Compression, or [`None`] if the bytes aren't recognized as any of the enabled compressions.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::tar::Compression;
+    ///
+    /// assert_eq!(Compression::detect(&[0x1F, 0x8B, 0x08, 0x00]), Some(Compression::Gzip));
+    /// assert_eq!(Compression::detect(b"<!DOCTYPE html>"), None);
+    /// ```
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if let Some((_, name)) = MAGICS.iter().find(|(magic, _)| header.starts_with(magic)) {
+            // Note: fails for codecs that aren't enabled
+            return name.parse().ok();
+        }
+        if header.get(USTAR_OFFSET..USTAR_OFFSET + 5) == Some(b"ustar") {
+            return Some(Self::None);
+        }
+        None
+    }
+
+    /// Returns the file extension commonly used for tarballs with this compression.
+    ///
+    /// # Returns
+    /// The extension, without a leading dot (e.g., `tar.gz`).
+    #[inline]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::None => "tar",
+            Self::Gzip => "tar.gz",
+            #[cfg(feature = "xz")]
+            Self::Xz => "tar.xz",
+            #[cfg(feature = "zstd")]
+            Self::Zstd => "tar.zst",
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => "tar.bz2",
+        }
+    }
+
+    /// Returns the level with which we compress when archiving if the [`ArchiveOptions`] don't specify any, regardless of the driver.
+    ///
+    /// # Returns
+    /// The level in the codec's own scale (i.e., the best for gzip and bzip2, and the respective CLI tool's default for xz and zstd).
+    #[inline]
+    pub fn default_level(&self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Gzip => 9,
+            #[cfg(feature = "xz")]
+            Self::Xz => 6,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 3,
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => 9,
+        }
+    }
+
+    /// Returns the compression levels supported by this compression.
+    ///
+    /// # Returns
+    /// The range of levels in the codec's own scale, where higher levels compress better but slower (e.g., `0..=9` for gzip). Uncompressed
+    /// tarballs only support `0`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::tar::Compression;
+    ///
+    /// assert!(Compression::Gzip.levels().contains(&Compression::Gzip.default_level()));
+    /// assert_eq!(Compression::None.levels(), 0..=0);
+    /// ```
+    #[inline]
+    pub fn levels(&self) -> RangeInclusive<u32> {
+        match self {
+            Self::None => 0..=0,
+            Self::Gzip => 0..=9,
+            #[cfg(feature = "xz")]
+            Self::Xz => 0..=9,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 1..=22,
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => 1..=9,
+        }
+    }
+}
+impl Display for Compression {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Gzip => write!(f, "gzip"),
+            #[cfg(feature = "xz")]
+            Self::Xz => write!(f, "xz"),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => write!(f, "zstd"),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => write!(f, "bzip2"),
+        }
+    }
+}
+impl FromStr for Compression {
+    type Err = UnknownCompressionError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().find(|c| c.to_string().eq_ignore_ascii_case(s)).copied().ok_or_else(|| UnknownCompressionError { raw: s.into() })
+    }
+}
+
+
+
+/// Defines what [`archive_with()`] (and friends) do with the symlinks they encounter, including the source itself.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Adds symlinks as link entries pointing to the same (relative or absolute) path, regardless of whether it exists (like `tar` does).
+    Preserve,
+    /// Adds whatever symlinks point to as if it were at their place (like `tar --dereference` does). Fails on dangling symlinks
+    /// ([`Error::SourceSymlinkBroken`]) and on symlinks to any of their own parent directories ([`Error::SourceSymlinkCycle`]).
+    #[default]
+    Follow,
+    /// Fails with [`Error::SourceSymlink`] on any symlink.
+    Error,
+}
+impl SymlinkPolicy {
+    /// All symlink policies.
+    pub const ALL: [Self; 3] = [Self::Preserve, Self::Follow, Self::Error];
+}
+impl Display for SymlinkPolicy {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        match self {
+            Self::Preserve => write!(f, "preserve"),
+            Self::Follow => write!(f, "follow"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+impl FromStr for SymlinkPolicy {
+    type Err = UnknownSymlinkPolicyError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|p| p.to_string().eq_ignore_ascii_case(s)).ok_or_else(|| UnknownSymlinkPolicyError { raw: s.into() })
+    }
+}
+
+
+
+/// Defines how [`archive_with()`] (and friends) create a tarball.
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveOptions {
+    /// If the source is a directory, then this determines whether to trim it (true) or not (false) in the resulting tarball (i.e., the files in
+    /// the root dir will be in the tar's root instead of the directory).
+    pub skip_root_dir: bool,
+    /// How to compress the tarball.
+    pub compression:   Compression,
+    /// The level to compress with, in the [`Compression::levels()`] of the chosen compression. If [`None`], uses its
+    /// [`Compression::default_level()`].
+    pub level:         Option<u32>,
+    /// Whether to compress on all cores (pigz-style). Only affects [`Compression::Gzip`], which then compresses independent blocks in parallel
+    /// that together still form a single, standard gzip stream.
+    pub parallel:      bool,
+    /// What to do with symlinks.
+    pub symlinks:      SymlinkPolicy,
+}
+impl ArchiveOptions {
+    /// Constructor for the ArchiveOptions that keeps the root directory and compresses with gzip.
+    ///
+    /// # Returns
+    /// A new ArchiveOptions that creates the same tarballs as [`archive()`].
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Changes whether to trim the source directory from the names in the tarball.
+    ///
+    /// # Arguments
+    /// - `skip_root_dir`: Whether to trim (true) or keep (false) the source directory.
+    ///
+    /// # Returns
+    /// The same ArchiveOptions but with the new value.
+    #[inline]
+    pub fn with_skip_root_dir(mut self, skip_root_dir: bool) -> Self {
+        self.skip_root_dir = skip_root_dir;
+        self
+    }
+
+    /// Changes how the tarball is compressed.
+    ///
+    /// # Arguments
+    /// - `compression`: The [`Compression`] to use.
+    ///
+    /// # Returns
+    /// The same ArchiveOptions but with the new compression.
+    #[inline]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Changes the level to compress with.
+    ///
+    /// # Arguments
+    /// - `level`: The level to use, in the [`Compression::levels()`] of the chosen compression (checked when archiving).
+    ///
+    /// # Returns
+    /// The same ArchiveOptions but with the new level.
+    #[inline]
+    pub fn with_level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Changes whether to compress on all cores.
+    ///
+    /// # Arguments
+    /// - `parallel`: Whether to compress in parallel (true) or on a single core (false). Only affects [`Compression::Gzip`].
+    ///
+    /// # Returns
+    /// The same ArchiveOptions but with the new value.
+    #[inline]
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Changes what to do with symlinks.
+    ///
+    /// # Arguments
+    /// - `symlinks`: The [`SymlinkPolicy`] to use.
+    ///
+    /// # Returns
+    /// The same ArchiveOptions but with the new policy.
+    #[inline]
+    pub fn with_symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+}
+
+
+
+/// Defines which symlinks or hardlinks [`unarchive_with()`] (and friends) extract.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LinkPolicy {
+    /// Extracts links wherever they point to. Other entries are still never unpacked through symlinks that point outside of the target
+    /// directory, and hardlinks to outside of it are still refused.
+    Allow,
+    /// Only extracts links that resolve to inside the target directory, following any symlinks along the way. For symlinks, this is checked again
+    /// once all entries are extracted, so later entries can't redirect them; and because symlinks are only created after all other files, nothing
+    /// is ever written through them.
+    #[default]
+    Inside,
+    /// Refuses any link.
+    Reject,
+}
+impl LinkPolicy {
+    /// All link policies.
+    pub const ALL: [Self; 3] = [Self::Allow, Self::Inside, Self::Reject];
+}
+impl Display for LinkPolicy {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Inside => write!(f, "inside"),
+            Self::Reject => write!(f, "reject"),
+        }
+    }
+}
+impl FromStr for LinkPolicy {
+    type Err = UnknownLinkPolicyError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|p| p.to_string().eq_ignore_ascii_case(s)).ok_or_else(|| UnknownLinkPolicyError { raw: s.into() })
+    }
+}
+
+
+
+/// Defines which of the [`UnarchiveLimits`] a tarball exceeded (see [`Error::LimitExceeded`]).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LimitKind {
+    /// The total size of all entries, in bytes.
+    Bytes,
+    /// The size of a single entry, in bytes.
+    EntryBytes,
+    /// The number of entries.
+    Entries,
+    /// The number of components in the path of an entry.
+    Depth,
+    /// The length of the path of an entry, in bytes.
+    PathLength,
+    /// The number of bytes decompressed per compressed byte.
+    Ratio,
+}
+impl Display for LimitKind {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        match self {
+            Self::Bytes => write!(f, "total size"),
+            Self::EntryBytes => write!(f, "entry size"),
+            Self::Entries => write!(f, "number of entries"),
+            Self::Depth => write!(f, "path depth"),
+            Self::PathLength => write!(f, "path length"),
+            Self::Ratio => write!(f, "compression ratio"),
+        }
+    }
+}
+
+
+
+/// Defines limits on the tarballs that [`unarchive_with()`] (and friends) read, to protect against decompression bombs.
+///
+/// Reading stops with an [`Error::LimitExceeded`] as soon as any of them is crossed, without unpacking the rest of the tarball. The sizes are
+/// taken from the headers of entries, and as such are checked before unpacking them. By default, there are no limits.
+#[derive(Clone, Debug, Default)]
+pub struct UnarchiveLimits {
+    /// The maximum total size of all entries, in bytes.
+    pub max_bytes:       Option<u64>,
+    /// The maximum size of a single entry, in bytes.
+    pub max_entry_bytes: Option<u64>,
+    /// The maximum number of entries.
+    pub max_entries:     Option<u64>,
+    /// The maximum number of components in the path of an entry (e.g., `a/b/c.txt` has three).
+    pub max_depth:       Option<u64>,
+    /// The maximum length of the path of an entry, in bytes.
+    pub max_path_len:    Option<u64>,
+    /// The maximum number of bytes that may be decompressed per compressed byte. Checked while streaming, once more than
+    /// [`UnarchiveLimits::RATIO_GRACE`] bytes were decompressed.
+    pub max_ratio:       Option<u64>,
+}
+impl UnarchiveLimits {
+    /// The number of decompressed bytes before [`UnarchiveLimits::max_ratio`] is checked, such that the padding of small tarballs (which
+    /// compresses extremely well) doesn't trip it.
+    pub const RATIO_GRACE: u64 = 1024 * 1024;
+
+    /// Constructor for the UnarchiveLimits that doesn't limit anything.
+    ///
+    /// # Returns
+    /// A new UnarchiveLimits that accepts any tarball.
+    #[inline]
+    pub fn none() -> Self { Self::default() }
+
+    /// Limits the total size of all entries.
+    ///
+    /// # Arguments
+    /// - `max_bytes`: The maximum number of bytes of all entries together.
+    ///
+    /// # Returns
+    /// The same UnarchiveLimits but with the new limit.
+    #[inline]
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Limits the size of a single entry.
+    ///
+    /// # Arguments
+    /// - `max_entry_bytes`: The maximum number of bytes of any entry.
+    ///
+    /// # Returns
+    /// The same UnarchiveLimits but with the new limit.
+    #[inline]
+    pub fn with_max_entry_bytes(mut self, max_entry_bytes: u64) -> Self {
+        self.max_entry_bytes = Some(max_entry_bytes);
+        self
+    }
+
+    /// Limits the number of entries.
+    ///
+    /// # Arguments
+    /// - `max_entries`: The maximum number of entries (of any type) in the tarball.
+    ///
+    /// # Returns
+    /// The same UnarchiveLimits but with the new limit.
+    #[inline]
+    pub fn with_max_entries(mut self, max_entries: u64) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Limits how deeply nested entries may be.
+    ///
+    /// # Arguments
+    /// - `max_depth`: The maximum number of components in the path of any entry.
+    ///
+    /// # Returns
+    /// The same UnarchiveLimits but with the new limit.
+    #[inline]
+    pub fn with_max_depth(mut self, max_depth: u64) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limits the length of the paths of entries.
+    ///
+    /// # Arguments
+    /// - `max_path_len`: The maximum number of bytes in the path of any entry.
+    ///
+    /// # Returns
+    /// The same UnarchiveLimits but with the new limit.
+    #[inline]
+    pub fn with_max_path_len(mut self, max_path_len: u64) -> Self {
+        self.max_path_len = Some(max_path_len);
+        self
+    }
+
+    /// Limits how well the tarball may be compressed.
+    ///
+    /// # Arguments
+    /// - `max_ratio`: The maximum number of bytes decompressed per compressed byte (see [`UnarchiveLimits::max_ratio`]).
+    ///
+    /// # Returns
+    /// The same UnarchiveLimits but with the new limit.
+    #[inline]
+    pub fn with_max_ratio(mut self, max_ratio: u64) -> Self {
+        self.max_ratio = Some(max_ratio);
+        self
+    }
+}
+
+
+
+/// Defines the parts of a [`Glob`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum GlobToken {
+    /// Matches exactly this character.
+    Literal(char),
+    /// Matches any single character but `/` (`?`).
+    Any,
+    /// Matches any number of characters but `/` (`*`).
+    Star,
+    /// Matches any number of characters, including `/` (`**` at the end of a pattern).
+    Anything,
+    /// Matches any number of whole components, including none (`**/`).
+    Dirs,
+    /// Matches any single character but `/` that is in one of the ranges, or that isn't if it's negated (`[a-z]` or `[!a-z]`).
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+/// Defines a glob pattern that selects entries by their path in a tarball (see [`UnarchiveOptions::include`]).
+///
+/// It supports `*` (any characters but `/`), `?` (any character but `/`), `[abc]`, `[a-z]` and `[!abc]` (any character in or not in the
+/// set), `**` as a whole component (any number of components) and `\` to escape any of these. Anything else, including a `[` without a
+/// closing `]`, matches literally.
+///
+/// A glob matches a path if it matches the path itself or any of its parent directories, so both `bin` and `bin/*` match everything in
+/// `bin/`. Leading `/` and `./` are ignored.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Glob {
+    /// The pattern as given.
+    raw:    String,
+    /// The parsed pattern.
+    tokens: Vec<GlobToken>,
+}
+impl Glob {
+    /// Constructor for the Glob.
+    ///
+    /// # Arguments
+    /// - `pattern`: The glob pattern (e.g., `bin/*` or `**/*.so`).
+    ///
+    /// # Returns
+    /// A new Glob that matches paths according to the pattern.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let raw: String = pattern.into();
+        let mut pattern: &str = &raw;
+        while let Some(rest) = pattern.strip_prefix("./").or_else(|| pattern.strip_prefix('/')) {
+            pattern = rest;
+        }
+        let chars: Vec<char> = pattern.trim_end_matches('/').chars().collect();
+
+        let mut tokens: Vec<GlobToken> = Vec::with_capacity(chars.len());
+        let mut i: usize = 0;
+        while i < chars.len() {
+            let whole: bool =
+                (i == 0 || chars[i - 1] == '/') && chars.get(i + 1) == Some(&'*') && matches!(chars.get(i + 2), None | Some('/'));
+            match chars[i] {
+                '*' if whole && i + 2 < chars.len() => {
+                    tokens.push(GlobToken::Dirs);
+                    i += 3;
+                },
+                '*' if whole => {
+                    tokens.push(GlobToken::Anything);
+                    i += 2;
+                },
+                '*' => {
+                    if tokens.last() != Some(&GlobToken::Star) {
+                        tokens.push(GlobToken::Star);
+                    }
+                    i += 1;
+                },
+                '?' => {
+                    tokens.push(GlobToken::Any);
+                    i += 1;
+                },
+                '[' => match Self::parse_class(&chars[i + 1..]) {
+                    Some((class, len)) => {
+                        tokens.push(class);
+                        i += 1 + len;
+                    },
+                    None => {
+                        tokens.push(GlobToken::Literal('['));
+                        i += 1;
+                    },
+                },
+                '\\' if i + 1 < chars.len() => {
+                    tokens.push(GlobToken::Literal(chars[i + 1]));
+                    i += 2;
+                },
+                c => {
+                    tokens.push(GlobToken::Literal(c));
+                    i += 1;
+                },
+            }
+        }
+        Self { raw, tokens }
+    }
+
+    /// Constructor for a Glob that matches exactly the given path, without interpreting any special characters in it.
+    ///
+    /// # Arguments
+    /// - `path`: The path to match (e.g., `bin/[weird]*name`).
+    ///
+    /// # Returns
+    /// A new Glob that only matches `path` (and anything in it, if it's a directory).
+    pub fn literal(path: impl AsRef<Path>) -> Self {
+        let raw: String = slash_path(path.as_ref());
+        Self { tokens: raw.chars().map(GlobToken::Literal).collect(), raw }
+    }
+
+    /// Parses a character class, i.e., what follows a `[`.
+    ///
+    /// # Arguments
+    /// - `chars`: The characters after the `[`.
+    ///
+    /// # Returns
+    /// The [`GlobToken::Class`], together with the number of characters it spans (including the closing `]`), or [`None`] if there is no
+    /// closing `]`.
+    fn parse_class(chars: &[char]) -> Option<(GlobToken, usize)> {
+        let negated: bool = matches!(chars.first(), Some('!' | '^'));
+        let start: usize = if negated { 1 } else { 0 };
+        let mut ranges: Vec<(char, char)> = Vec::new();
+        let mut i: usize = start;
+        while i < chars.len() {
+            // A `]` right at the start is part of the set
+            if chars[i] == ']' && i > start {
+                return Some((GlobToken::Class { negated, ranges }, i + 1));
+            }
+            if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|c| *c != ']') {
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+        None
+    }
+
+    /// Marks every token as reached that can be reached from a reached one without consuming anything (i.e., skips empty `*`s and `**`s).
+    ///
+    /// # Arguments
+    /// - `reached`: Whether `tokens[..i]` matches the path so far, for every `i`. Updated in-place.
+    fn skip_empty(&self, reached: &mut [bool]) {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if reached[i] && matches!(token, GlobToken::Star | GlobToken::Anything | GlobToken::Dirs) {
+                reached[i + 1] = true;
+            }
+        }
+    }
+
+    /// Checks whether this glob matches the given path or any of its parent directories.
+    ///
+    /// The path is matched in a single pass, which takes time linear in the length of the path (times the length of the pattern), since
+    /// entries in (untrusted) tarballs may have very long paths.
+    ///
+    /// # Arguments
+    /// - `path`: The path to match, e.g., of an entry in a tarball.
+    ///
+    /// # Returns
+    /// True if it matches, false otherwise.
+    pub fn matches(&self, path: impl AsRef<Path>) -> bool {
+        let path: String = slash_path(path.as_ref());
+        let t: usize = self.tokens.len();
+
+        // `reached[i]` is whether `tokens[..i]` matches the path so far, and `inside[i]` whether we're halfway through the `**/` at `tokens[i]`
+        let mut reached: Vec<bool> = vec![false; t + 1];
+        let mut inside: Vec<bool> = vec![false; t];
+        reached[0] = true;
+        self.skip_empty(&mut reached);
+        for (j, c) in path.chars().enumerate() {
+            // The whole pattern matching a parent directory is enough
+            if c == '/' && j > 0 && reached[t] {
+                return true;
+            }
+
+            // Advance every token that we've reached by this character
+            let mut next: Vec<bool> = vec![false; t + 1];
+            let mut next_inside: Vec<bool> = vec![false; t];
+            for (i, token) in self.tokens.iter().enumerate() {
+                match token {
+                    GlobToken::Literal(l) => next[i + 1] |= reached[i] && c == *l,
+                    GlobToken::Any => next[i + 1] |= reached[i] && c != '/',
+                    GlobToken::Star => next[i] |= reached[i] && c != '/',
+                    GlobToken::Anything => next[i] |= reached[i],
+                    GlobToken::Dirs => {
+                        // (note: a `**/` consumes everything up to and including some `/`)
+                        if reached[i] || inside[i] {
+                            next_inside[i] = true;
+                            next[i + 1] |= c == '/';
+                        }
+                    },
+                    GlobToken::Class { negated, ranges } => {
+                        let in_ranges = |c: char| ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c));
+                        next[i + 1] |= reached[i] && c != '/' && in_ranges(c) != *negated;
+                    },
+                }
+            }
+            self.skip_empty(&mut next);
+            reached = next;
+            inside = next_inside;
+            if !reached.contains(&true) && !inside.contains(&true) {
+                return false;
+            }
+        }
+        !path.is_empty() && reached[t]
+    }
+
+    /// Returns the pattern of this glob.
+    ///
+    /// # Returns
+    /// The pattern as it was given.
+    #[inline]
+    pub fn as_str(&self) -> &str { &self.raw }
+}
+impl Display for Glob {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult { write!(f, "{}", self.raw) }
+}
+impl FromStr for Glob {
+    type Err = Infallible;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Ok(Self::new(s)) }
+}
+impl From<&str> for Glob {
+    #[inline]
+    fn from(value: &str) -> Self { Self::new(value) }
+}
+impl From<String> for Glob {
+    #[inline]
+    fn from(value: String) -> Self { Self::new(value) }
+}
+
+
+
+/// Defines a callback that decides whether to extract an entry, given its path in the tarball and its [`Header`] (see
+/// [`UnarchiveOptions::with_filter()`]).
+#[allow(clippy::type_complexity)]
+#[derive(Clone)]
+pub struct EntryPredicate(Arc<dyn Send + Sync + Fn(&Path, &Header) -> bool>);
+impl EntryPredicate {
+    /// Constructor for the EntryPredicate.
+    ///
+    /// # Arguments
+    /// - `predicate`: The callback, which returns true for the entries to extract.
+    ///
+    /// # Returns
+    /// A new EntryPredicate wrapping the callback.
+    #[inline]
+    pub fn new(predicate: impl 'static + Send + Sync + Fn(&Path, &Header) -> bool) -> Self { Self(Arc::new(predicate)) }
+
+    /// Asks the callback whether to extract an entry.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the entry in the tarball.
+    /// - `header`: The [`Header`] of the entry.
+    ///
+    /// # Returns
+    /// True if the entry should be extracted, false otherwise.
+    #[inline]
+    pub fn call(&self, path: &Path, header: &Header) -> bool { (self.0)(path, header) }
+}
+impl Debug for EntryPredicate {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult { write!(f, "EntryPredicate(..)") }
+}
+
+
+
+/// Defines how [`unarchive_with()`] (and friends) read a tarball.
+#[derive(Clone, Debug, Default)]
+pub struct UnarchiveOptions {
+    /// How the tarball is compressed. If [`None`], it is detected from its first bytes (see [`Compression::detect()`]).
+    pub compression:      Option<Compression>,
+    /// Which symlinks to extract.
+    pub symlinks:         LinkPolicy,
+    /// Which hardlinks to extract.
+    pub hardlinks:        LinkPolicy,
+    /// The limits that the tarball must stay within.
+    pub limits:           UnarchiveLimits,
+    /// The number of leading components to strip from the paths of entries, like GNU tar's `--strip-components`. Entries with no more
+    /// components than this are skipped.
+    pub strip_components: usize,
+    /// If not empty, only entries matching any of these [`Glob`]s are extracted.
+    pub include:          Vec<Glob>,
+    /// Entries matching any of these [`Glob`]s are skipped, even if they're included.
+    pub exclude:          Vec<Glob>,
+    /// If given, only entries accepted by this [`EntryPredicate`] (and the globs) are extracted.
+    pub filter:           Option<EntryPredicate>,
+}
+impl UnarchiveOptions {
+    /// Constructor for the UnarchiveOptions that detects the compression of tarballs.
+    ///
+    /// # Returns
+    /// A new UnarchiveOptions that reads the same tarballs as [`unarchive()`].
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Changes how the tarball is expected to be compressed, instead of detecting it.
+    ///
+    /// # Arguments
+    /// - `compression`: The [`Compression`] of the tarball.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the new compression.
+    #[inline]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Changes which symlinks to extract.
+    ///
+    /// # Arguments
+    /// - `symlinks`: The [`LinkPolicy`] for symlinks.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the new policy.
+    #[inline]
+    pub fn with_symlinks(mut self, symlinks: LinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Changes which hardlinks to extract.
+    ///
+    /// # Arguments
+    /// - `hardlinks`: The [`LinkPolicy`] for hardlinks.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the new policy.
+    #[inline]
+    pub fn with_hardlinks(mut self, hardlinks: LinkPolicy) -> Self {
+        self.hardlinks = hardlinks;
+        self
+    }
+
+    /// Changes the limits that the tarball must stay within.
+    ///
+    /// # Arguments
+    /// - `limits`: The [`UnarchiveLimits`] to enforce.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the new limits.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::tar::{unarchive_with, Error, LimitKind, UnarchiveLimits, UnarchiveOptions};
+    ///
+    /// // Create an archive (see 'archive()' example)
+    /// # let tmp = std::env::temp_dir();
+    /// # let dir = tmp.join("example_with_limits");
+    /// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+    /// # std::fs::create_dir(&dir).unwrap();
+    /// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+    /// # let tar = tmp.join("example_with_limits.tar.gz");
+    /// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+    /// # download::tar::archive(&dir, &tar, false).unwrap();
+    ///
+    /// // It's too big for our taste
+    /// let out = tmp.join("example_with_limits2");
+    /// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+    /// let options = UnarchiveOptions::new().with_limits(UnarchiveLimits::none().with_max_bytes(4));
+    /// assert!(matches!(unarchive_with(&tar, &out, options), Err(Error::LimitExceeded { kind: LimitKind::Bytes, .. })));
+    /// ```
+    #[inline]
+    pub fn with_limits(mut self, limits: UnarchiveLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Changes the number of leading components to strip from the paths of entries (e.g., to get rid of the `foo-1.2.3/`-directory that
+    /// wraps everything in a tarball).
+    ///
+    /// Entries with no more components than this are skipped, and are also left out by [`list_with()`]. Whether entries (and the sources of
+    /// hardlinks) escape the target directory is checked after stripping them.
+    ///
+    /// # Arguments
+    /// - `strip_components`: The number of leading components to strip.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the new number of components to strip.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::tar::{unarchive_with, UnarchiveOptions};
+    ///
+    /// // Create an archive (see 'archive()' example)
+    /// # let tmp = std::env::temp_dir();
+    /// # let dir = tmp.join("example_with_strip_components");
+    /// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+    /// # std::fs::create_dir(&dir).unwrap();
+    /// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+    /// # let tar = tmp.join("example_with_strip_components.tar.gz");
+    /// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+    /// # download::tar::archive(&dir, &tar, false).unwrap();
+    ///
+    /// // Unarchive it without the 'example_with_strip_components'-directory
+    /// let out = tmp.join("example_with_strip_components2");
+    /// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+    /// unarchive_with(&tar, &out, UnarchiveOptions::new().with_strip_components(1)).unwrap();
+    /// assert!(out.join("file1.txt").is_file());
+    /// ```
+    #[inline]
+    pub fn with_strip_components(mut self, strip_components: usize) -> Self {
+        self.strip_components = strip_components;
+        self
+    }
+
+    /// Adds a [`Glob`] that selects entries to extract. Once any are given, entries that match none of them are skipped.
+    ///
+    /// Globs are matched against the paths of entries as they are in the tarball, i.e., before stripping any components. Since they also
+    /// match anything in the directories they match, use `dir` to extract a directory with all its contents. Skipped entries are also left
+    /// out by [`list_with()`], and hardlinks to them fail to extract.
+    ///
+    /// # Arguments
+    /// - `glob`: The [`Glob`] (or its pattern) to include.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the added glob.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::tar::{unarchive_with, UnarchiveOptions};
+    ///
+    /// // Create an archive (see 'archive()' example)
+    /// # let tmp = std::env::temp_dir();
+    /// # let dir = tmp.join("example_with_include");
+    /// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+    /// # std::fs::create_dir(&dir).unwrap();
+    /// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+    /// # std::fs::write(dir.join("file2.md"), "General Kenobi!\n").unwrap();
+    /// # let tar = tmp.join("example_with_include.tar.gz");
+    /// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+    /// # download::tar::archive(&dir, &tar, true).unwrap();
+    ///
+    /// // Only unarchive the text files
+    /// let out = tmp.join("example_with_include2");
+    /// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+    /// unarchive_with(&tar, &out, UnarchiveOptions::new().with_include("*.txt")).unwrap();
+    /// assert!(out.join("file1.txt").is_file());
+    /// assert!(!out.join("file2.md").exists());
+    /// ```
+    #[inline]
+    pub fn with_include(mut self, glob: impl Into<Glob>) -> Self {
+        self.include.push(glob.into());
+        self
+    }
+
+    /// Adds a [`Glob`] that selects entries to skip, even if they're included by [`UnarchiveOptions::with_include()`].
+    ///
+    /// # Arguments
+    /// - `glob`: The [`Glob`] (or its pattern) to exclude.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the added glob.
+    #[inline]
+    pub fn with_exclude(mut self, glob: impl Into<Glob>) -> Self {
+        self.exclude.push(glob.into());
+        self
+    }
+
+    /// Changes the callback that decides which entries to extract, on top of the globs.
+    ///
+    /// It is called with the path of every entry as it is in the tarball (before stripping any components) and its [`Header`], and should
+    /// return false for entries to skip.
+    ///
+    /// # Arguments
+    /// - `filter`: The callback to decide with.
+    ///
+    /// # Returns
+    /// The same UnarchiveOptions but with the new callback.
+    ///
+    /// # Example
+    /// ```rust
+    /// use download::tar::{unarchive_with, UnarchiveOptions};
+    ///
+    /// // Create an archive (see 'archive()' example)
+    /// # let tmp = std::env::temp_dir();
+    /// # let dir = tmp.join("example_with_filter");
+    /// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+    /// # std::fs::create_dir(&dir).unwrap();
+    /// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+    /// # std::fs::write(dir.join("file2.txt"), "").unwrap();
+    /// # let tar = tmp.join("example_with_filter.tar.gz");
+    /// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+    /// # download::tar::archive(&dir, &tar, true).unwrap();
+    ///
+    /// // Skip empty files
+    /// let out = tmp.join("example_with_filter2");
+    /// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+    /// unarchive_with(&tar, &out, UnarchiveOptions::new().with_filter(|_, header| header.size().unwrap_or(0) > 0)).unwrap();
+    /// assert!(out.join("file1.txt").is_file());
+    /// assert!(!out.join("file2.txt").exists());
+    /// ```
+    #[inline]
+    pub fn with_filter(mut self, filter: impl 'static + Send + Sync + Fn(&Path, &Header) -> bool) -> Self {
+        self.filter = Some(EntryPredicate::new(filter));
+        self
+    }
+}
+
+
+
+
+
+/***** CORE *****/
+/// Writes a tarball compressed with any of the supported [`Compression`]s.
+///
+/// This is an enum rather than a trait object because every encoder needs to be finished by value to write its final bytes.
+enum Encoder<W: Write> {
+    /// Writes the tarball as-is.
+    None(W),
+    /// Compresses the tarball with gzip.
+    Gzip(GzEncoder<W>),
+    /// Compresses the tarball with gzip, on all cores.
+    ParallelGzip(ParallelGzEncoder<W>),
+    /// Compresses the tarball with xz.
+    #[cfg(feature = "xz")]
+    Xz(XzEncoder<W>),
+    /// Compresses the tarball with zstd.
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdEncoder<'static, W>),
+    /// Compresses the tarball with bzip2.
+    #[cfg(feature = "bzip2")]
+    Bzip2(BzEncoder<W>),
+}
+impl<W: Write> Encoder<W> {
+    /// Writes the final bytes of the compressed stream.
+    ///
+    /// # Returns
+    /// The wrapped writer.
+    ///
+    /// # Errors
+    /// This function errors if we failed to write the final bytes.
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Self::None(writer) => Ok(writer),
+            Self::Gzip(enc) => enc.finish(),
+            Self::ParallelGzip(enc) => enc.finish(),
+            #[cfg(feature = "xz")]
+            Self::Xz(enc) => enc.finish(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(enc) => enc.finish(),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(enc) => enc.finish(),
+        }
+    }
+}
+impl<W: Write> Write for Encoder<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None(writer) => writer.write(buf),
+            Self::Gzip(enc) => enc.write(buf),
+            Self::ParallelGzip(enc) => enc.write(buf),
+            #[cfg(feature = "xz")]
+            Self::Xz(enc) => enc.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(enc) => enc.write(buf),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(enc) => enc.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::None(writer) => writer.flush(),
+            Self::Gzip(enc) => enc.flush(),
+            Self::ParallelGzip(enc) => enc.flush(),
+            #[cfg(feature = "xz")]
+            Self::Xz(enc) => enc.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(enc) => enc.flush(),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2(enc) => enc.flush(),
+        }
+    }
+}
+
+
+
+/// The number of bytes of input that the [`ParallelGzCore`] compresses per block (the same as `pigz`).
+const PARALLEL_BLOCK_SIZE: usize = 128 * 1024;
+
+/// The header of the gzip member written by the [`ParallelGzCore`] (deflated, no flags, no timestamp, unknown OS).
+const PARALLEL_GZ_HEADER: [u8; 10] = [0x1F, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF];
+
+/// An empty final deflate block (i.e., a fixed Huffman block with only the end-of-block code), which ends the concatenated blocks.
+const PARALLEL_GZ_LAST_BLOCK: [u8; 2] = [0x03, 0x00];
+
+/// A block compressed by the [`ParallelGzPool`]: its index in the batch and the compressed bytes.
+type ParallelGzBlock = (usize, io::Result<Vec<u8>>);
+
+/// A block for the [`ParallelGzPool`] to compress: all input of a batch, the range of the block in it, the index of the block in the batch
+/// and where to send the compressed block to.
+type ParallelGzJob = (Arc<[u8]>, std::ops::Range<usize>, usize, mpsc::Sender<ParallelGzBlock>);
+
+/// The worker threads of a [`ParallelGzCore`], which are spawned once and then compress the blocks of every batch until the core is dropped.
+struct ParallelGzPool {
+    /// Sends blocks to the workers. Only [`None`] while dropping, to tell them to stop.
+    jobs:    Option<mpsc::Sender<ParallelGzJob>>,
+    /// The workers themselves.
+    workers: Vec<std::thread::JoinHandle<()>>,
+}
+impl ParallelGzPool {
+    /// Constructor for the ParallelGzPool that spawns its workers.
+    ///
+    /// # Arguments
+    /// - `threads`: The number of workers to spawn.
+    /// - `level`: The gzip level to compress with.
+    ///
+    /// # Returns
+    /// A new ParallelGzPool with workers waiting for blocks.
+    ///
+    /// # Errors
+    /// This function errors if we failed to spawn a worker.
+    fn new(threads: usize, level: GzLevel) -> io::Result<Self> {
+        let (jobs, queue): (mpsc::Sender<ParallelGzJob>, mpsc::Receiver<ParallelGzJob>) = mpsc::channel();
+        let queue: Arc<Mutex<mpsc::Receiver<ParallelGzJob>>> = Arc::new(Mutex::new(queue));
+        let mut pool: Self = Self { jobs: Some(jobs), workers: Vec::with_capacity(threads) };
+        for i in 0..threads {
+            let queue: Arc<Mutex<mpsc::Receiver<ParallelGzJob>>> = queue.clone();
+            pool.workers.push(std::thread::Builder::new().name(format!("parallel-gz-{i}")).spawn(move || loop {
+                // Only hold the lock while waiting, so the next worker can take the next block while we compress ours
+                let job: Result<ParallelGzJob, mpsc::RecvError> = match queue.lock() {
+                    Ok(queue) => queue.recv(),
+                    Err(_) => return,
+                };
+                let Ok((input, range, index, results)) = job else { return };
+                // If the core stopped listening (e.g., because another block failed), there's nobody to tell
+                let _ = results.send((index, deflate_block(&input[range], level)));
+            })?);
+        }
+        Ok(pool)
+    }
+
+    /// Compresses a batch of input on the workers.
+    ///
+    /// # Arguments
+    /// - `input`: The input to compress, which is cut into blocks of [`PARALLEL_BLOCK_SIZE`] bytes.
+    /// - `output`: The buffer to append the compressed blocks to, in order.
+    ///
+    /// # Errors
+    /// This function errors if we failed to compress any of the blocks, or if a worker died.
+    fn compress(&self, input: Arc<[u8]>, output: &mut Vec<u8>) -> io::Result<()> {
+        let stopped = || io::Error::other("parallel gzip worker stopped unexpectedly");
+        let jobs: &mpsc::Sender<ParallelGzJob> = self.jobs.as_ref().ok_or_else(stopped)?;
+
+        // Hand out all blocks, then collect them in whatever order they finish
+        let (results, finished): (mpsc::Sender<ParallelGzBlock>, mpsc::Receiver<ParallelGzBlock>) = mpsc::channel();
+        let n_blocks: usize = input.len().div_ceil(PARALLEL_BLOCK_SIZE);
+        for index in 0..n_blocks {
+            let range: std::ops::Range<usize> = index * PARALLEL_BLOCK_SIZE..input.len().min((index + 1) * PARALLEL_BLOCK_SIZE);
+            jobs.send((input.clone(), range, index, results.clone())).map_err(|_| stopped())?;
+        }
+        drop(results);
+        let mut blocks: Vec<Option<Vec<u8>>> = vec![None; n_blocks];
+        for (index, block) in finished {
+            blocks[index] = Some(block?);
+        }
+
+        // A missing block means its worker panicked (and dropped its sender without sending)
+        for block in blocks {
+            output.extend_from_slice(&block.ok_or_else(stopped)?);
+        }
+        Ok(())
+    }
+}
+impl Drop for ParallelGzPool {
+    fn drop(&mut self) {
+        // Closing the channel makes every worker stop once it's done
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Implements pigz-style parallel gzip compression, without doing any IO itself.
+///
+/// The input is cut into blocks that are compressed independently, on all cores, into raw deflate streams that end byte-aligned. These are
+/// simply concatenated; and preceded by a gzip header and followed by an empty final block and a trailer with the checksum and size of all
+/// input, they form a single standard gzip member that any decoder (e.g., `gunzip`) reads. The blocks are compressed by a
+/// [`ParallelGzPool`], which is spawned on the first batch and lives as long as the core.
+///
+/// A driver (i.e., [`ParallelGzEncoder`] or [`AsyncParallelGzEncoder`]) gives it input through [`ParallelGzCore::write()`], compresses
+/// whenever that asks for it and writes whatever output it produces, until it writes the final bytes produced by
+/// [`ParallelGzCore::finish()`].
+struct ParallelGzCore {
+    /// The level to compress the blocks with.
+    level:   GzLevel,
+    /// The number of blocks to compress at the same time.
+    threads: usize,
+    /// The workers compressing the blocks, once we've compressed anything.
+    pool:    Option<ParallelGzPool>,
+    /// The input that we've yet to compress.
+    input:   Vec<u8>,
+    /// The checksum (and size) of all input so far.
+    crc:     Crc,
+    /// Whether we've already produced the gzip header.
+    started: bool,
+}
+impl ParallelGzCore {
+    /// Constructor for the ParallelGzCore that compresses on as many threads as there are cores.
+    ///
+    /// # Arguments
+    /// - `level`: The gzip level to compress with.
+    ///
+    /// # Returns
+    /// A new ParallelGzCore that has yet to produce anything.
+    fn new(level: u32) -> Self {
+        let threads: usize = std::thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1);
+        Self {
+            level: GzLevel::new(level),
+            threads,
+            pool: None,
+            input: Vec::with_capacity(threads * PARALLEL_BLOCK_SIZE),
+            crc: Crc::new(),
+            started: false,
+        }
+    }
+
+    /// Takes new input, which should be compressed (with [`ParallelGzCore::compress()`]) as soon as there's a full block for every thread.
+    ///
+    /// # Arguments
+    /// - `buf`: The input to compress.
+    ///
+    /// # Returns
+    /// Whether there's a full batch of blocks to compress.
+    fn write(&mut self, buf: &[u8]) -> bool {
+        self.crc.update(buf);
+        self.input.extend_from_slice(buf);
+        self.input.len() >= self.threads * PARALLEL_BLOCK_SIZE
+    }
+
+    /// Compresses all input given so far and ends the gzip member.
+    ///
+    /// # Arguments
+    /// - `output`: The buffer to append the final bytes to.
+    ///
+    /// # Errors
+    /// This function errors if we failed to compress the input.
+    fn finish(&mut self, output: &mut Vec<u8>) -> io::Result<()> {
+        self.compress(true, output)?;
+        output.extend_from_slice(&PARALLEL_GZ_LAST_BLOCK);
+        output.extend_from_slice(&self.crc.sum().to_le_bytes());
+        output.extend_from_slice(&self.crc.amount().to_le_bytes());
+        Ok(())
+    }
+
+    /// Compresses the buffered input in parallel.
+    ///
+    /// This blocks until the whole batch is compressed.
+    ///
+    /// # Arguments
+    /// - `all`: Whether to compress all input (true) or only full blocks (false).
+    /// - `output`: The buffer to append the compressed blocks to, in order.
+    ///
+    /// # Errors
+    /// This function errors if we failed to compress any of the blocks.
+    fn compress(&mut self, all: bool, output: &mut Vec<u8>) -> io::Result<()> {
+        if !self.started {
+            output.extend_from_slice(&PARALLEL_GZ_HEADER);
+            self.started = true;
+        }
+        let len: usize = if all { self.input.len() } else { self.input.len() - self.input.len() % PARALLEL_BLOCK_SIZE };
+        if len == 0 {
+            return Ok(());
+        }
+
+        let pool: &ParallelGzPool = match &mut self.pool {
+            Some(pool) => pool,
+            pool @ None => pool.insert(ParallelGzPool::new(self.threads, self.level)?),
+        };
+        pool.compress(self.input.drain(..len).collect(), output)
+    }
+}
+
+/// Writes a gzip stream that is compressed on all cores, using a [`ParallelGzCore`].
+struct ParallelGzEncoder<W: Write> {
+    /// The writer to write the compressed stream to.
+    writer: W,
+    /// The core that does the actual compression.
+    core:   ParallelGzCore,
+    /// The compressed output that we've yet to write.
+    output: Vec<u8>,
+}
+impl<W: Write> ParallelGzEncoder<W> {
+    /// Constructor for the ParallelGzEncoder.
+    ///
+    /// # Arguments
+    /// - `writer`: The writer to write the compressed stream to.
+    /// - `level`: The gzip level to compress with.
+    ///
+    /// # Returns
+    /// A new ParallelGzEncoder that hasn't written anything yet.
+    #[inline]
+    fn new(writer: W, level: u32) -> Self { Self { writer, core: ParallelGzCore::new(level), output: Vec::new() } }
+
+    /// Writes the final bytes of the compressed stream.
+    ///
+    /// # Returns
+    /// The wrapped writer.
+    ///
+    /// # Errors
+    /// This function errors if we failed to compress or write the final bytes.
+    fn finish(mut self) -> io::Result<W> {
+        self.core.finish(&mut self.output)?;
+        self.writer.write_all(&self.output)?;
+        Ok(self.writer)
+    }
+
+    /// Writes all the output produced by the core so far.
+    ///
+    /// # Errors
+    /// This function errors if we failed to write the output.
+    fn write_output(&mut self) -> io::Result<()> {
+        if !self.output.is_empty() {
+            self.writer.write_all(&self.output)?;
+            self.output.clear();
+        }
+        Ok(())
+    }
+}
+impl<W: Write> Write for ParallelGzEncoder<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.core.write(buf) {
+            self.core.compress(false, &mut self.output)?;
+        }
+        self.write_output()?;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.core.compress(true, &mut self.output)?;
+        self.write_output()?;
+        self.writer.flush()
+    }
+}
+
+/// Writes a gzip stream that is compressed on all cores to an async writer, using a [`ParallelGzCore`].
+///
+/// Like the [`async_compression`] encoders, it compresses while being polled; but since compressing a batch blocks until it's done, that
+/// happens on tokio's blocking thread pool instead of on the executor.
+#[cfg(feature = "async-tokio")]
+struct AsyncParallelGzEncoder<W> {
+    /// The writer to write the compressed stream to.
+    writer:   W,
+    /// The core that does the actual compression. Only [`None`] while it's compressing a batch in `pending`.
+    core:     Option<ParallelGzCore>,
+    /// The batch being compressed on the blocking thread pool, which gives back the core and its output when done.
+    pending:  Option<tokio::task::JoinHandle<(ParallelGzCore, io::Result<Vec<u8>>)>>,
+    /// The compressed output that we've yet to write.
+    output:   Vec<u8>,
+    /// How much of the `output` we've already written.
+    written:  usize,
+    /// Whether we've already produced the final bytes.
+    finished: bool,
+}
+#[cfg(feature = "async-tokio")]
+impl<W: Unpin + AsyncWrite> AsyncParallelGzEncoder<W> {
+    /// Constructor for the AsyncParallelGzEncoder.
+    ///
+    /// # Arguments
+    /// - `writer`: The writer to write the compressed stream to.
+    /// - `level`: The gzip level to compress with.
+    ///
+    /// # Returns
+    /// A new AsyncParallelGzEncoder that hasn't written anything yet.
+    #[inline]
+    fn new(writer: W, level: u32) -> Self {
+        Self { writer, core: Some(ParallelGzCore::new(level)), pending: None, output: Vec::new(), written: 0, finished: false }
+    }
+
+    /// Moves the core to the blocking thread pool to compress (part of) its input there.
+    ///
+    /// Should only be called once [`AsyncParallelGzEncoder::poll_core()`] is ready, i.e., when all previous output has been written.
+    ///
+    /// # Arguments
+    /// - `compress`: The closure that compresses using the core, appending to the given output.
+    fn spawn(&mut self, compress: impl 'static + Send + FnOnce(&mut ParallelGzCore, &mut Vec<u8>) -> io::Result<()>) {
+        if let Some(mut core) = self.core.take() {
+            let mut output: Vec<u8> = std::mem::take(&mut self.output);
+            self.pending = Some(tokio::task::spawn_blocking(move || {
+                let res: io::Result<()> = compress(&mut core, &mut output);
+                (core, res.map(|_| output))
+            }));
+        }
+    }
+
+    /// Waits until the core is done compressing, and then writes all the output it produced so far.
+    ///
+    /// # Arguments
+    /// - `cx`: The context of the task polling us.
+    ///
+    /// # Returns
+    /// [`Poll::Ready`] with the core once it's idle and all output has been written.
+    ///
+    /// # Errors
+    /// This function errors if we failed to compress or write the output.
+    fn poll_core(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&mut ParallelGzCore>> {
+        if let Some(pending) = &mut self.pending {
+            let res: Result<(ParallelGzCore, io::Result<Vec<u8>>), tokio::task::JoinError> = ready!(Pin::new(pending).poll(cx));
+            self.pending = None;
+            let (core, output): (ParallelGzCore, io::Result<Vec<u8>>) = match res {
+                Ok(res) => res,
+                Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+                Err(err) => return Poll::Ready(Err(err.into())),
+            };
+            self.core = Some(core);
+            self.output = output?;
+        }
+        while self.written < self.output.len() {
+            match ready!(Pin::new(&mut self.writer).poll_write(cx, &self.output[self.written..])) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => self.written += n,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+        self.output.clear();
+        self.written = 0;
+        // Only gone if compressing a batch got cancelled, which leaves us without the core
+        Poll::Ready(self.core.as_mut().ok_or_else(|| io::Error::other("parallel gzip compression was cancelled")))
+    }
+}
+#[cfg(feature = "async-tokio")]
+impl<W: Unpin + AsyncWrite> AsyncWrite for AsyncParallelGzEncoder<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this: &mut Self = self.get_mut();
+        if ready!(this.poll_core(cx))?.write(buf) {
+            this.spawn(|core, output| core.compress(false, output));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this: &mut Self = self.get_mut();
+        // Once the flushed batch is done, there's no input left, so being polled again doesn't start another one
+        if !ready!(this.poll_core(cx))?.input.is_empty() {
+            this.spawn(|core, output| core.compress(true, output));
+            ready!(this.poll_core(cx))?;
+        }
+        Pin::new(&mut this.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this: &mut Self = self.get_mut();
+        ready!(this.poll_core(cx))?;
+        if !this.finished {
+            this.spawn(|core, output| core.finish(output));
+            this.finished = true;
+            ready!(this.poll_core(cx))?;
+        }
+        Pin::new(&mut this.writer).poll_shutdown(cx)
+    }
+}
+
+
+
+/// Implements the logic of walking a file or directory to archive, without doing any IO itself.
+///
+/// A driver (i.e., [`archive()`] or [`archive_async()`]) repeatedly takes the [`ArchiveCore::next()`] source path and the name it should have in the
+/// tarball. If it's a file, it appends it; if it's a directory, it reads the names of its entries and gives them to [`ArchiveCore::on_dir()`],
+/// which tells it under which name to append the directory itself. If it's a symlink, [`ArchiveCore::on_symlink()`] tells it whether to follow it.
+struct ArchiveCore {
+    /// Whether to trim the source directory from the names in the tarball.
+    skip_root_dir: bool,
+    /// Whether we've yet to see the first directory.
+    is_root_dir:   bool,
+    /// What to do with symlinks.
+    symlinks:      SymlinkPolicy,
+    /// The paths still to archive, together with their names in the tarball and the index of their parent in `dirs`. Popped from the back.
+    todo:          Vec<(PathBuf, OsString, Option<usize>)>,
+    /// The directories we've expanded, as their canonical path (if known) and the index of their own parent.
+    dirs:          Vec<(Option<PathBuf>, Option<usize>)>,
+    /// The index of the parent of the path last returned by [`ArchiveCore::next()`].
+    parent:        Option<usize>,
+}
+impl ArchiveCore {
+    /// Constructor for the ArchiveCore.
+    ///
+    /// # Arguments
+    /// - `source`: The source file or directory to archive.
+    /// - `skip_root_dir`: Whether to trim the `source` from the names in the tarball if it's a directory.
+    /// - `symlinks`: What to do with symlinks.
+    ///
+    /// # Returns
+    /// A new ArchiveCore that will first yield the `source` itself.
+    fn new(source: &Path, skip_root_dir: bool, symlinks: SymlinkPolicy) -> Self {
+        Self {
+            skip_root_dir,
+            is_root_dir: true,
+            symlinks,
+            todo: vec![(source.into(), source.file_name().map(|f| f.into()).unwrap_or_else(|| OsString::from(".")), None)],
+            dirs: Vec::new(),
+            parent: None,
+        }
+    }
+
+    /// Returns the next path to archive.
+    ///
+    /// # Returns
+    /// The path of the next file or directory to archive and its name in the tarball, or [`None`] if we're done.
+    #[inline]
+    fn next(&mut self) -> Option<(PathBuf, OsString)> {
+        let (path, name, parent): (PathBuf, OsString, Option<usize>) = self.todo.pop()?;
+        self.parent = parent;
+        Some((path, name))
+    }
+
+    /// Returns whether directories need their canonical path for [`ArchiveCore::on_dir()`].
+    ///
+    /// # Returns
+    /// True if we follow symlinks, and thus need to detect cycles.
+    #[inline]
+    fn needs_canonical(&self) -> bool { self.symlinks == SymlinkPolicy::Follow }
+
+    /// Processes a symlink returned by [`ArchiveCore::next()`].
+    ///
+    /// # Arguments
+    /// - `path`: The path of the symlink.
+    ///
+    /// # Returns
+    /// Whether to follow the symlink (true) or to append it as a link (false).
+    ///
+    /// # Errors
+    /// This function errors if we don't allow symlinks at all.
+    fn on_symlink(&self, path: &Path) -> Result<bool, Error> {
+        match self.symlinks {
+            SymlinkPolicy::Preserve => Ok(false),
+            SymlinkPolicy::Follow => Ok(true),
+            SymlinkPolicy::Error => Err(Error::SourceSymlink { path: path.into() }),
+        }
+    }
+
+    /// Processes the entries of a directory returned by [`ArchiveCore::next()`].
+    ///
+    /// # Arguments
+    /// - `path`: The path of the directory.
+    /// - `name`: The name of the directory in the tarball.
+    /// - `canonical`: The canonical path of the directory if [`ArchiveCore::needs_canonical()`], or [`None`] otherwise.
+    /// - `entries`: The file names of the directory's entries, in any order.
+    ///
+    /// # Returns
+    /// The name under which to append the directory itself (with its metadata) before any of its entries, or [`None`] if it's the root directory
+    /// and we're trimming it.
+    ///
+    /// # Errors
+    /// This function errors if the directory is one of its own parents (i.e., we got here by following a symlink to it).
+    fn on_dir(&mut self, path: &Path, name: &OsStr, canonical: Option<PathBuf>, mut entries: Vec<OsString>) -> Result<Option<OsString>, Error> {
+        // Refuse to loop
+        if let Some(canonical) = &canonical {
+            let mut parent: Option<usize> = self.parent;
+            while let Some(i) = parent {
+                if self.dirs[i].0.as_ref() == Some(canonical) {
+                    return Err(Error::SourceSymlinkCycle { path: path.into(), target: canonical.clone() });
+                }
+                parent = self.dirs[i].1;
+            }
+        }
+        let index: usize = self.dirs.len();
+        self.dirs.push((canonical, self.parent));
+
+        // Compute the tar-side path of the entries
+        let skip: bool = self.skip_root_dir && self.is_root_dir;
+        let name: &OsStr = if skip { OsStr::new("") } else { name };
+        self.is_root_dir = false;
+
+        // Add them in reverse order, such that they are archived in sorted order (independent of the filesystem)
+        entries.sort_unstable();
+        for entry in entries.into_iter().rev() {
+            self.todo.push((path.join(&entry), PathBuf::from(name).join(entry).into(), Some(index)));
+        }
+        Ok(if skip { None } else { Some(name.into()) })
+    }
+}
+
+
+
+/// Keeps track of how many bytes go into and come out of a decoder, to enforce [`UnarchiveLimits::max_ratio`] while streaming.
+///
+/// Clones share the same counts, such that the [`RatioReader`]s around the decoder can count while the [`UnarchiveCore`] checks them.
+#[derive(Clone, Debug)]
+struct RatioCounter {
+    /// The number of compressed and decompressed bytes read so far, respectively.
+    counts:    Arc<(AtomicU64, AtomicU64)>,
+    /// The maximum number of decompressed bytes per compressed byte, if any.
+    max_ratio: Option<u64>,
+}
+impl RatioCounter {
+    /// Constructor for the RatioCounter.
+    ///
+    /// # Arguments
+    /// - `max_ratio`: The maximum number of decompressed bytes per compressed byte, if any.
+    ///
+    /// # Returns
+    /// A new RatioCounter that hasn't counted anything yet.
+    #[inline]
+    fn new(max_ratio: Option<u64>) -> Self { Self { counts: Arc::new((AtomicU64::new(0), AtomicU64::new(0))), max_ratio } }
+
+    /// Computes the current compression ratio.
+    ///
+    /// # Returns
+    /// The number of decompressed bytes per compressed byte so far.
+    #[inline]
+    fn ratio(&self) -> u64 { self.counts.1.load(Ordering::Relaxed) / self.counts.0.load(Ordering::Relaxed).max(1) }
+
+    /// Checks whether the ratio crossed [`UnarchiveLimits::max_ratio`].
+    ///
+    /// # Returns
+    /// True if it did and we're past the [`UnarchiveLimits::RATIO_GRACE`], false otherwise.
+    #[inline]
+    fn exceeded(&self) -> bool {
+        let (compressed, decompressed): (u64, u64) = (self.counts.0.load(Ordering::Relaxed), self.counts.1.load(Ordering::Relaxed));
+        self.max_ratio.is_some_and(|max| decompressed > UnarchiveLimits::RATIO_GRACE && decompressed > compressed.saturating_mul(max))
+    }
+}
+
+/// Wraps a reader on either side of a decoder to count the bytes going through it in a [`RatioCounter`].
+///
+/// On the decompressed side, reading fails as soon as the ratio is exceeded, such that nothing more gets unpacked.
+struct RatioReader<R> {
+    /// The reader to count.
+    reader:       R,
+    /// The counter to count in.
+    counter:      RatioCounter,
+    /// Whether the reader produces decompressed bytes (true) or compressed ones (false).
+    decompressed: bool,
+}
+impl<R> RatioReader<R> {
+    /// Constructor for the RatioReader.
+    ///
+    /// # Arguments
+    /// - `reader`: The reader to count.
+    /// - `counter`: The [`RatioCounter`] to count in.
+    /// - `decompressed`: Whether the reader produces decompressed bytes (true) or compressed ones (false).
+    ///
+    /// # Returns
+    /// A new RatioReader that counts everything read from `reader`.
+    #[inline]
+    fn new(reader: R, counter: RatioCounter, decompressed: bool) -> Self { Self { reader, counter, decompressed } }
+
+    /// Counts the bytes that were just read.
+    ///
+    /// # Arguments
+    /// - `n`: The number of bytes read.
+    ///
+    /// # Errors
+    /// This function errors if this made the ratio exceed its limit.
+    fn on_read(&self, n: usize) -> io::Result<()> {
+        if self.decompressed {
+            self.counter.counts.1.fetch_add(n as u64, Ordering::Relaxed);
+            if self.counter.exceeded() {
+                return Err(io::Error::other("Compression ratio exceeds limit"));
+            }
+        } else {
+            self.counter.counts.0.fetch_add(n as u64, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+impl<R: Read> Read for RatioReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n: usize = self.reader.read(buf)?;
+        self.on_read(n)?;
+        Ok(n)
+    }
+}
+#[cfg(feature = "async-tokio")]
+impl<R: Unpin + AsyncRead> AsyncRead for RatioReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut tio::ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let before: usize = buf.filled().len();
+        ready!(Pin::new(&mut self.reader).poll_read(cx, buf))?;
+        Poll::Ready(self.on_read(buf.filled().len() - before))
+    }
+}
+
+
+
+/// Describes an entry in a tarball that should be extracted (see [`UnarchiveCore::on_entry()`]).
+#[derive(Clone)]
+struct PlannedEntry {
+    /// The path of the entry in the tarball.
+    path:     PathBuf,
+    /// The path the entry will be extracted to.
+    target:   PathBuf,
+    /// Whether leading components were stripped from the path (see [`UnarchiveOptions::strip_components`]), such that it can't be extracted by
+    /// its own path.
+    stripped: bool,
+}
+
+/// Describes a symlink in a tarball that is extracted after all other entries (see [`UnarchiveCore::on_link()`]).
+struct PlannedLink {
+    /// The entry of the symlink itself.
+    entry: PlannedEntry,
+    /// The path the symlink points to, as written in the tarball.
+    link:  PathBuf,
+    /// Whether the symlink must be checked once it exists.
+    check: bool,
+}
+
+/// Implements the logic of going through the entries in a tarball, without doing any IO itself.
+///
+/// A driver (i.e., [`unarchive()`], [`list()`] or their async counterparts) gives every entry to [`UnarchiveCore::on_entry()`] (or its error to
+/// [`UnarchiveCore::on_entry_error()`]), and then reports the result of unpacking it to [`UnarchiveCore::on_unpacked()`]. Links are first given to
+/// [`UnarchiveCore::on_link()`], which may ask the driver to resolve them and report that to [`UnarchiveCore::on_resolved()`].
+///
+/// Symlinks are only unpacked once all files are, such that nothing is ever written through them; and directories are only unpacked once
+/// all other entries are, in the order given by [`UnarchiveCore::order_dirs()`], such that their permissions don't get in the way of unpacking
+/// their contents.
+///
+/// The [`UnarchiveLimits`] are checked for every entry, except for the compression ratio, which the [`RatioReader`]s enforce while streaming.
+/// Any error they cause is reported as an [`Error::LimitExceeded`] instead.
+struct UnarchiveCore<'p> {
+    /// The tarball to extract from.
+    tarball:   &'p Path,
+    /// The directory to extract to.
+    target:    &'p Path,
+    /// Which symlinks to extract.
+    symlinks:  LinkPolicy,
+    /// Which hardlinks to extract.
+    hardlinks: LinkPolicy,
+    /// The number of leading components to strip from the paths of entries.
+    strip:     usize,
+    /// The globs of which entries must match any (if not empty).
+    include:   Vec<Glob>,
+    /// The globs of which entries may match none.
+    exclude:   Vec<Glob>,
+    /// The callback that entries must pass, if any.
+    filter:    Option<EntryPredicate>,
+    /// The limits that the tarball must stay within.
+    limits:    UnarchiveLimits,
+    /// The counter shared with the [`RatioReader`]s.
+    counter:   RatioCounter,
+    /// The index of the next entry.
+    index:     usize,
+    /// The total size of the entries so far.
+    bytes:     u64,
+}
+impl<'p> UnarchiveCore<'p> {
+    /// Constructor for the UnarchiveCore.
+    ///
+    /// # Arguments
+    /// - `tarball`: The tarball to extract from.
+    /// - `target`: The directory to extract to.
+    /// - `options`: The [`UnarchiveOptions`] that determine which entries to extract.
+    /// - `counter`: The [`RatioCounter`] shared with the [`RatioReader`]s around the decoder.
+    ///
+    /// # Returns
+    /// A new UnarchiveCore that expects the first entry.
+    #[inline]
+    fn new(tarball: &'p Path, target: &'p Path, options: &UnarchiveOptions, counter: RatioCounter) -> Self {
+        Self {
+            tarball,
+            target,
+            symlinks: options.symlinks,
+            hardlinks: options.hardlinks,
+            strip: options.strip_components,
+            include: options.include.clone(),
+            exclude: options.exclude.clone(),
+            filter: options.filter.clone(),
+            limits: options.limits.clone(),
+            counter,
+            index: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Checks whether an entry is selected by the [`Glob`]s and the [`EntryPredicate`].
+    ///
+    /// # Arguments
+    /// - `path`: The path of the entry in the tarball.
+    /// - `header`: The [`Header`] of the entry.
+    ///
+    /// # Returns
+    /// True if the entry should be extracted, false if it should be skipped.
+    fn selects(&self, path: &Path, header: &Header) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(path)))
+            && !self.exclude.iter().any(|glob| glob.matches(path))
+            && self.filter.as_ref().is_none_or(|filter| filter.call(path, header))
+    }
+
+    /// Strips the leading components from a path in the tarball.
+    ///
+    /// Like [`Entry::unpack_in()`] does, root and `.` components are ignored, and as such aren't stripped.
+    ///
+    /// # Arguments
+    /// - `path`: The path to strip.
+    ///
+    /// # Returns
+    /// The remaining (relative) path, which may be empty.
+    #[inline]
+    fn strip(&self, path: &Path) -> PathBuf {
+        path.components().filter(|c| matches!(c, Component::Normal(_) | Component::ParentDir)).skip(self.strip).collect()
+    }
+
+    /// Checks a single limit.
+    ///
+    /// # Arguments
+    /// - `entry`: The entry being checked.
+    /// - `kind`: The [`LimitKind`] being checked.
+    /// - `limit`: The limit, if any.
+    /// - `value`: The value that must stay within the limit.
+    ///
+    /// # Errors
+    /// This function errors with an [`Error::LimitExceeded`] if `value` is larger than `limit`.
+    fn check(&self, entry: &Path, kind: LimitKind, limit: Option<u64>, value: u64) -> Result<(), Error> {
+        match limit {
+            Some(limit) if value > limit => {
+                Err(Error::LimitExceeded { tarball: self.tarball.into(), entry: Some(entry.into()), kind, limit, value })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Builds the error for exceeding the compression ratio, if it was.
+    ///
+    /// # Arguments
+    /// - `entry`: The entry we were reading when it happened, if any.
+    ///
+    /// # Returns
+    /// An [`Error::LimitExceeded`] if the [`RatioCounter`] exceeded its limit, or [`None`] otherwise.
+    fn ratio_exceeded(&self, entry: Option<&Path>) -> Option<Error> {
+        if !self.counter.exceeded() {
+            return None;
+        }
+        Some(Error::LimitExceeded {
+            tarball: self.tarball.into(),
+            entry:   entry.map(Into::into),
+            kind:    LimitKind::Ratio,
+            limit:   self.counter.max_ratio.unwrap_or_default(),
+            value:   self.counter.ratio(),
+        })
+    }
+
+    /// Builds the error for failing to read the next entry.
+    ///
+    /// # Arguments
+    /// - `err`: The error that occurred.
+    ///
+    /// # Returns
+    /// An [`Error::SourceTarEntry`], or an [`Error::LimitExceeded`] if the error was caused by exceeding the compression ratio.
+    #[inline]
+    fn on_entry_error(&self, err: io::Error) -> Error {
+        self.ratio_exceeded(None).unwrap_or_else(|| Error::SourceTarEntry { tarball: self.tarball.into(), entry: self.index, err })
+    }
+
+    /// Processes the next entry in the tarball.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the entry, or the error we got while reading it.
+    /// - `header`: The [`Header`] of the entry.
+    /// - `size`: The size of the entry once unpacked, in bytes.
+    ///
+    /// # Returns
+    /// A [`PlannedEntry`] describing where to extract the entry, or [`None`] if it should be skipped because it isn't selected (see
+    /// [`UnarchiveCore::selects()`]) or stripping its leading components left nothing.
+    ///
+    /// # Errors
+    /// This function errors if we failed to read the entry's path, if it escapes the target directory once stripped, or if the entry exceeds
+    /// any of the [`UnarchiveLimits`]. Its size only counts towards them if it isn't skipped.
+    fn on_entry(&mut self, path: io::Result<PathBuf>, header: &Header, size: u64) -> Result<Option<PlannedEntry>, Error> {
+        let index: usize = self.index;
+        self.index += 1;
+        let path: PathBuf = match path {
+            Ok(path) => path,
+            Err(err) => return Err(Error::SourceTarEntryPath { tarball: self.tarball.into(), entry: index, err }),
+        };
+
+        // Check the limits on reading the entry
+        let depth: usize = path.components().filter(|c| matches!(c, Component::Normal(_))).count();
+        self.check(&path, LimitKind::Entries, self.limits.max_entries, self.index as u64)?;
+        self.check(&path, LimitKind::Depth, self.limits.max_depth, depth as u64)?;
+        self.check(&path, LimitKind::PathLength, self.limits.max_path_len, path.as_os_str().len() as u64)?;
+        if !self.selects(&path, header) {
+            debug!("Skipping '{}/{}' (not selected)", self.tarball.display(), path.display());
+            return Ok(None);
+        }
+
+        // Strip the leading components, checking whether the remainder escapes only after that
+        let stripped: PathBuf = self.strip(&path);
+        if self.strip > 0 {
+            if stripped.as_os_str().is_empty() {
+                debug!("Skipping '{}/{}' (nothing left after stripping {} components)", self.tarball.display(), path.display(), self.strip);
+                return Ok(None);
+            } else if stripped.components().any(|c| c == Component::ParentDir) {
+                return Err(Error::SourceTarEntryEscaped { tarball: self.tarball.into(), entry: path });
+            }
+        }
+        let target: PathBuf = self.target.join(stripped);
+
+        // Check the limits on unpacking the entry
+        self.check(&path, LimitKind::EntryBytes, self.limits.max_entry_bytes, size)?;
+        self.bytes = self.bytes.saturating_add(size);
+        self.check(&path, LimitKind::Bytes, self.limits.max_bytes, self.bytes)?;
+        Ok(Some(PlannedEntry { path, target, stripped: self.strip > 0 }))
+    }
+
+    /// Processes the result of unpacking an entry.
+    ///
+    /// # Arguments
+    /// - `entry`: The [`PlannedEntry`] that was unpacked.
+    /// - `res`: The result of unpacking it, which is false if the entry would have escaped the target directory.
+    ///
+    /// # Errors
+    /// This function errors if the unpacking failed (e.g., because the compression ratio was exceeded) or the entry tried to escape.
+    fn on_unpacked(&self, entry: PlannedEntry, res: io::Result<bool>) -> Result<(), Error> {
+        match res {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::SourceTarEntryEscaped { tarball: self.tarball.into(), entry: entry.path }),
+            Err(err) => Err(self.ratio_exceeded(Some(&entry.path)).unwrap_or_else(|| Error::SourceTarEntryUnpack {
+                tarball: self.tarball.into(),
+                entry: entry.path,
+                target: entry.target,
+                err,
+            })),
+        }
+    }
+
+    /// Processes an entry that turned out to be a symlink or hardlink.
+    ///
+    /// # Arguments
+    /// - `entry`: The [`PlannedEntry`] of the link.
+    /// - `hard`: Whether it's a hardlink (true) or a symlink (false).
+    /// - `link`: The path the link points to, as written in the tarball.
+    ///
+    /// # Returns
+    /// Whether the driver must check what the link resolves to (see [`UnarchiveCore::on_resolved()`]). For hardlinks, that's their source in the
+    /// target directory, before unpacking them; for symlinks, that's the unpacked symlink itself, both right after unpacking it and again once
+    /// all symlinks are unpacked.
+    ///
+    /// # Errors
+    /// This function errors if the [`LinkPolicy`] rejects this kind of link.
+    fn on_link(&self, entry: &PlannedEntry, hard: bool, link: &Path) -> Result<bool, Error> {
+        match (if hard { self.hardlinks } else { self.symlinks }, hard) {
+            (LinkPolicy::Allow, _) => Ok(false),
+            (LinkPolicy::Inside, _) => Ok(true),
+            (LinkPolicy::Reject, true) => Err(Error::SourceTarEntryHardlink { tarball: self.tarball.into(), entry: entry.path.clone(), link: link.into() }),
+            (LinkPolicy::Reject, false) => Err(Error::SourceTarEntrySymlink { tarball: self.tarball.into(), entry: entry.path.clone(), link: link.into() }),
+        }
+    }
+
+    /// Finds where the source of a hardlink is extracted to.
+    ///
+    /// # Arguments
+    /// - `entry`: The [`PlannedEntry`] of the hardlink.
+    /// - `link`: The path the hardlink points to, as written in the tarball.
+    ///
+    /// # Returns
+    /// The path of the hardlink's source in the target directory, which has its leading components stripped like the entry itself.
+    ///
+    /// # Errors
+    /// This function errors if nothing is left of the source after stripping it (so it won't be extracted), or if it escapes once stripped.
+    fn on_hardlink(&self, entry: &PlannedEntry, link: &Path) -> Result<PathBuf, Error> {
+        if !entry.stripped {
+            return Ok(self.target.join(link));
+        }
+        let stripped: PathBuf = self.strip(link);
+        if stripped.as_os_str().is_empty() || stripped.components().any(|c| c == Component::ParentDir) {
+            return Err(Error::SourceTarEntryLinkEscaped { tarball: self.tarball.into(), entry: entry.path.clone(), link: link.into() });
+        }
+        Ok(self.target.join(stripped))
+    }
+
+    /// Processes what a link that has to be checked resolves to.
+    ///
+    /// # Arguments
+    /// - `entry`: The [`PlannedEntry`] of the link.
+    /// - `link`: The path the link points to, as written in the tarball.
+    /// - `resolved`: The path the link resolves to, or [`None`] if it couldn't be resolved (e.g., because it loops).
+    /// - `root`: The path the target directory resolves to.
+    ///
+    /// # Errors
+    /// This function errors if the link resolves to outside of the target directory.
+    fn on_resolved(&self, entry: &PlannedEntry, link: &Path, resolved: Option<PathBuf>, root: &Path) -> Result<(), Error> {
+        match resolved {
+            Some(resolved) if resolved.starts_with(root) => Ok(()),
+            _ => Err(Error::SourceTarEntryLinkEscaped { tarball: self.tarball.into(), entry: entry.path.clone(), link: link.into() }),
+        }
+    }
+
+    /// Orders the directory entries that were put aside until all other entries were unpacked.
+    ///
+    /// # Arguments
+    /// - `dirs`: The [`PlannedEntry`]s of the directories, together with whatever the driver needs to unpack them. Sorted such that children come
+    ///   before their parents, so restrictive permissions of a parent don't prevent unpacking its children.
+    #[inline]
+    fn order_dirs<E>(dirs: &mut [(PlannedEntry, E)]) { dirs.sort_by(|(lhs, _), (rhs, _)| rhs.path.cmp(&lhs.path)); }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Archives the given file or directory as a `.tar.gz` file.
+///
+/// If you enabled the `async-tokio` feature, also check the [`archive_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `tarball`: The target tarball file to archive to.
+/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting tarfile
+///   (i.e., the files in the root dir will be in the tar's root instead of the directory). Ignore otherwise.
+///
+/// # Errors
+/// This function errors if we somehow encountered an error.
+///
+/// # Examples
+/// ```rust
+/// use download::tar::archive;
+///
+/// // Write a test directory
+/// let tmp = std::env::temp_dir();
+/// let dir = tmp.join("example");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// std::fs::create_dir(&dir).unwrap();
+/// std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// std::fs::write(dir.join("file2.txt"), "General Kenobi...\n").unwrap();
+/// std::fs::write(dir.join("file3.txt"), "...you are a bold one\n").unwrap();
+///
+/// // We can archive them!
+/// let tar = tmp.join("example.tar.gz");
+/// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+/// archive(&dir, &tar, false).unwrap();
+///
+/// assert!(tar.is_file());
+/// ```
+#[inline]
+pub fn archive(source: impl AsRef<Path>, tarball: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
+    archive_with(source, tarball, ArchiveOptions::new().with_skip_root_dir(skip_root_dir))
+}
+
+/// Archives the given file or directory as a tarball, compressed as given.
+///
+/// Directories are added as entries of their own (with their metadata, such as permissions) before their contents, so empty directories are
+/// preserved too. Symlinks are preserved, followed or refused according to the [`SymlinkPolicy`] in the `options`.
+///
+/// If you enabled the `async-tokio` feature, also check the [`archive_with_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `tarball`: The target tarball file to archive to.
+/// - `options`: The [`ArchiveOptions`] that determine how the tarball is created (e.g., its [`Compression`]).
+///
+/// # Errors
+/// This function errors if we somehow encountered an error.
+///
+/// # Examples
+/// ```rust
+/// use download::tar::{archive_with, ArchiveOptions, Compression};
+///
+/// // Write a test directory
+/// let tmp = std::env::temp_dir();
+/// let dir = tmp.join("example_with");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// std::fs::create_dir(&dir).unwrap();
+/// std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+///
+/// // We can archive them without compression!
+/// let tar = tmp.join("example_with.tar");
+/// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+/// archive_with(&dir, &tar, ArchiveOptions::new().with_compression(Compression::None)).unwrap();
+///
+/// assert!(tar.is_file());
+/// ```
+pub fn archive_with(source: impl AsRef<Path>, tarball: impl AsRef<Path>, options: ArchiveOptions) -> Result<(), Error> {
+    let source: &Path = source.as_ref();
+    let tarball: &Path = tarball.as_ref();
+    debug!("Archiving '{}' to '{}' ({})...", source.display(), tarball.display(), options.compression);
+    let level: u32 = level(&options)?;
+
+    // Open the target file
+    let handle: fs::File = match fs::File::create(tarball) {
+        Ok(handle) => handle,
+        Err(err) => {
+            return Err(Error::TargetTarCreate { tarball: tarball.into(), err });
+        },
+    };
+
+    // Create the encoder & tarfile around this file
+    let enc: Encoder<fs::File> = match encoder(handle, options.compression, level, options.parallel) {
+        Ok(enc) => enc,
+        Err(err) => {
+            return Err(Error::TargetTarCreate { tarball: tarball.into(), err });
+        },
+    };
+    let mut tar: Builder<Encoder<fs::File>> = Builder::new(enc);
+    tar.follow_symlinks(options.symlinks != SymlinkPolicy::Preserve);
+
+    // Now add the source recursively
+    let mut core: ArchiveCore = ArchiveCore::new(source, options.skip_root_dir, options.symlinks);
+    while let Some((path, name)) = core.next() {
+        // Find out what we're dealing with (following symlinks only if told to)
+        let mut metadata: fs::Metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(Error::SourceNotFound { path }),
+            Err(err) => return Err(Error::SourceMetadata { path, err }),
+        };
+        if metadata.file_type().is_symlink() {
+            if !core.on_symlink(&path)? {
+                debug!("Adding symlink '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+                if let Err(err) = tar.append_path_with_name(&path, name) {
+                    return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
+                }
+                continue;
+            }
+            metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(err) => return Err(Error::SourceSymlinkBroken { path, err }),
+            };
+        }
+
+        // Switch on the file type
+        if metadata.is_file() {
+            debug!("Adding file '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+
+            // Compress as a file
+            if let Err(err) = tar.append_path_with_name(&path, name) {
+                return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
+            }
+        } else if metadata.is_dir() {
+            // Recurse to add the files
+            let entries: fs::ReadDir = match fs::read_dir(&path) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    return Err(Error::SourceDirRead { path, err });
+                },
+            };
+            let mut names: Vec<OsString> = Vec::new();
+            for (i, entry) in entries.enumerate() {
+                match entry {
+                    Ok(entry) => names.push(entry.file_name()),
+                    Err(err) => {
+                        return Err(Error::SourceDirEntryRead { path, entry: i, err });
+                    },
+                }
+            }
+            let canonical: Option<PathBuf> = if core.needs_canonical() {
+                match fs::canonicalize(&path) {
+                    Ok(canonical) => Some(canonical),
+                    Err(err) => return Err(Error::SourceMetadata { path, err }),
+                }
+            } else {
+                None
+            };
+            if let Some(name) = core.on_dir(&path, &name, canonical, names)? {
+                debug!("Adding directory '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+                if let Err(err) = tar.append_dir(name, &path) {
+                    return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
+                }
+            }
+        } else {
+            return Err(Error::SourceNotAFileOrDir { path });
+        }
+    }
+
+    // Finish writing the archive
+    debug!("Finishing tarball...");
+    match tar.into_inner() {
+        Ok(enc) => {
+            // Flush the encoder before we quit
+            if let Err(err) = enc.finish() {
+                return Err(Error::TargetTarFlush { tarball: tarball.into(), err });
+            }
+            Ok(())
+        },
+        Err(err) => Err(Error::TargetTarFinish { tarball: tarball.into(), err }),
+    }
+}
+
+/// Archives the given file or directory as a `.tar.gz` file.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `tarball`: The target tarball file to archive to.
+/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting tarfile
+///   (i.e., the files in the root dir will be in the tar's root instead of the directory). Ignore otherwise.
+///
+/// # Errors
+/// This function errors if we somehow encountered an error.
+///
+/// # Examples
+/// ```rust
+/// # tokio_test::block_on(async {
+/// use download::tar::archive_async;
+///
+/// // Write a test directory
+/// let tmp = std::env::temp_dir();
+/// let dir = tmp.join("example");
+/// # if dir.exists() { tokio::fs::remove_dir_all(&dir).await.unwrap(); }
+/// tokio::fs::create_dir(&dir).await.unwrap();
+/// tokio::fs::write(dir.join("file1.txt"), "Hello there!\n").await.unwrap();
+/// tokio::fs::write(dir.join("file2.txt"), "General Kenobi...\n").await.unwrap();
+/// tokio::fs::write(dir.join("file3.txt"), "...you are a bold one\n").await.unwrap();
+///
+/// // We can archive them!
+/// let tar = tmp.join("example.tar.gz");
+/// # if tar.exists() { tokio::fs::remove_file(&tar).await.unwrap(); }
+/// archive_async(&dir, &tar, false).await.unwrap();
+///
+/// assert!(tar.is_file());
+/// # });
+/// ```
+#[cfg(feature = "async-tokio")]
+#[inline]
+pub async fn archive_async(source: impl AsRef<Path>, tarball: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
+    archive_with_async(source, tarball, ArchiveOptions::new().with_skip_root_dir(skip_root_dir)).await
+}
+
+/// Archives the given file or directory as a tarball, compressed as given.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `tarball`: The target tarball file to archive to.
+/// - `options`: The [`ArchiveOptions`] that determine how the tarball is created (e.g., its [`Compression`]).
+///
+/// # Errors
+/// This function errors if we somehow encountered an error.
+#[cfg(feature = "async-tokio")]
+pub async fn archive_with_async(source: impl AsRef<Path>, tarball: impl AsRef<Path>, options: ArchiveOptions) -> Result<(), Error> {
+    let source: &Path = source.as_ref();
+    let tarball: &Path = tarball.as_ref();
+    debug!("Archiving '{}' to '{}' ({})...", source.display(), tarball.display(), options.compression);
+    let level: u32 = level(&options)?;
+
+    // Open the target file
+    let handle: tfs::File = match tfs::File::create(tarball).await {
+        Ok(handle) => handle,
+        Err(err) => {
+            return Err(Error::TargetTarCreate { tarball: tarball.into(), err });
+        },
+    };
+
+    // Create the encoder & tarfile around this file
+    let enc: Box<dyn Send + Unpin + AsyncWrite> = encoder_async(handle, options.compression, level, options.parallel);
+    let mut tar: AsyncBuilder<Box<dyn Send + Unpin + AsyncWrite>> = AsyncBuilder::new(enc);
+    tar.follow_symlinks(options.symlinks != SymlinkPolicy::Preserve);
+
+    // Now add the source recursively
+    let mut core: ArchiveCore = ArchiveCore::new(source, options.skip_root_dir, options.symlinks);
+    while let Some((path, name)) = core.next() {
+        // Find out what we're dealing with (following symlinks only if told to)
+        let mut metadata: std::fs::Metadata = match tfs::symlink_metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(Error::SourceNotFound { path }),
+            Err(err) => return Err(Error::SourceMetadata { path, err }),
+        };
+        if metadata.file_type().is_symlink() {
+            if !core.on_symlink(&path)? {
+                debug!("Adding symlink '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+                if let Err(err) = tar.append_path_with_name(&path, name).await {
+                    return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
+                }
+                continue;
+            }
+            metadata = match tfs::metadata(&path).await {
+                Ok(metadata) => metadata,
+                Err(err) => return Err(Error::SourceSymlinkBroken { path, err }),
+            };
+        }
+
+        // Switch on the file type
+        if metadata.is_file() {
+            debug!("Adding file '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
 
             // Compress as a file
             if let Err(err) = tar.append_path_with_name(&path, name).await {
                 return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
             }
-        } else if path.is_dir() {
+        } else if metadata.is_dir() {
             // Recurse to add the files
             let mut entries: tfs::ReadDir = match tfs::read_dir(&path).await {
                 Ok(entries) => entries,
@@ -321,277 +3084,934 @@ pub async fn archive_async(source: impl AsRef<Path>, tarball: impl AsRef<Path>,
                     return Err(Error::SourceDirRead { path, err });
                 },
             };
-            let mut i: usize = 0;
+            let mut names: Vec<OsString> = Vec::new();
             loop {
-                // Fetch the next entry
-                let entry: tfs::DirEntry = match entries.next_entry().await {
-                    Ok(Some(entry)) => entry,
-                    Ok(None) => {
-                        break;
-                    },
+                match entries.next_entry().await {
+                    Ok(Some(entry)) => names.push(entry.file_name()),
+                    Ok(None) => break,
                     Err(err) => {
-                        return Err(Error::SourceDirEntryRead { path, entry: i, err });
+                        return Err(Error::SourceDirEntryRead { path, entry: names.len(), err });
                     },
-                };
-                i += 1;
+                }
+            }
+            let canonical: Option<PathBuf> = if core.needs_canonical() {
+                match tfs::canonicalize(&path).await {
+                    Ok(canonical) => Some(canonical),
+                    Err(err) => return Err(Error::SourceMetadata { path, err }),
+                }
+            } else {
+                None
+            };
+            if let Some(name) = core.on_dir(&path, &name, canonical, names)? {
+                debug!("Adding directory '{}' as '{}/{}'...", path.display(), tarball.display(), name.to_string_lossy());
+                if let Err(err) = tar.append_dir(name, &path).await {
+                    return Err(Error::TargetTarAppend { source: path, tarball: tarball.into(), err });
+                }
+            }
+        } else {
+            return Err(Error::SourceNotAFileOrDir { path });
+        }
+    }
+
+    // Finish writing the archive
+    debug!("Finishing tarball...");
+    match tar.into_inner().await {
+        Ok(mut enc) => {
+            // Flush the encoder before we quit
+            if let Err(err) = enc.shutdown().await {
+                return Err(Error::TargetTarFlush { tarball: tarball.into(), err });
+            };
+            Ok(())
+        },
+        Err(err) => Err(Error::TargetTarFinish { tarball: tarball.into(), err }),
+    }
+}
+
+/// Archives the given file or directory as a `.tar.gz` file.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. Like [`async_fs`] does for file operations, it
+/// runs [`archive()`] on the [`blocking`] thread pool; so it works on any executor, and produces the exact same tarballs.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `tarball`: The target tarball file to archive to.
+/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting tarfile
+///   (i.e., the files in the root dir will be in the tar's root instead of the directory). Ignore otherwise.
+///
+/// # Errors
+/// This function errors for the same reasons as [`archive()`].
+///
+/// # Examples
+/// ```rust
+/// # futures_lite::future::block_on(async {
+/// use download::tar::archive_smol;
+///
+/// // Write a test directory
+/// let tmp = std::env::temp_dir();
+/// let dir = tmp.join("example_smol");
+/// # if dir.exists() { async_fs::remove_dir_all(&dir).await.unwrap(); }
+/// async_fs::create_dir(&dir).await.unwrap();
+/// async_fs::write(dir.join("file1.txt"), "Hello there!\n").await.unwrap();
+///
+/// // We can archive them!
+/// let tar = tmp.join("example_smol.tar.gz");
+/// # if tar.exists() { async_fs::remove_file(&tar).await.unwrap(); }
+/// archive_smol(&dir, &tar, false).await.unwrap();
+///
+/// assert!(tar.is_file());
+/// # });
+/// ```
+#[cfg(feature = "async-smol")]
+#[inline]
+pub async fn archive_smol(source: impl AsRef<Path>, tarball: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
+    archive_with_smol(source, tarball, ArchiveOptions::new().with_skip_root_dir(skip_root_dir)).await
+}
+
+/// Archives the given file or directory as a tarball, compressed as given.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`archive_with()`] on the [`blocking`]
+/// thread pool.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `tarball`: The target tarball file to archive to.
+/// - `options`: The [`ArchiveOptions`] that determine how the tarball is created (e.g., its [`Compression`]).
+///
+/// # Errors
+/// This function errors for the same reasons as [`archive_with()`].
+#[cfg(feature = "async-smol")]
+pub async fn archive_with_smol(source: impl AsRef<Path>, tarball: impl AsRef<Path>, options: ArchiveOptions) -> Result<(), Error> {
+    let source: PathBuf = source.as_ref().into();
+    let tarball: PathBuf = tarball.as_ref().into();
+    unblock(move || archive_with(source, tarball, options)).await
+}
+
+
+
+/// Unarchives the given tarball to the given location, detecting its [`Compression`] from its first bytes.
+///
+/// If you enabled the `async-tokio` feature, also check the [`unarchive_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+///
+/// # Errors
+/// This function errors if we failed to read or write anything, if some directories do or do not exist, or if the tarball's compression is
+/// unknown (see [`Error::UnknownArchiveFormat`]).
+///
+/// # Examples
+/// ```rust
+/// use download::tar::unarchive;
+///
+/// // Create an archive (see 'archive()' example)
+/// # let tmp = std::env::temp_dir();
+/// # let dir = tmp.join("example");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// # std::fs::create_dir(&dir).unwrap();
+/// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// # std::fs::write(dir.join("file2.txt"), "General Kenobi...\n").unwrap();
+/// # std::fs::write(dir.join("file3.txt"), "...you are a bold one\n").unwrap();
+/// # let tar = tmp.join("example.tar.gz");
+/// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+/// # download::tar::archive(&dir, &tar, false).unwrap();
+///
+/// // Unarchive it to another directory!
+/// let out = tmp.join("example2");
+/// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+/// unarchive(&tar, &out).unwrap();
+///
+/// // Now check the directory contains what we expect :)
+/// let mut entries = std::fs::read_dir(&out).unwrap();
+/// assert_eq!(entries.next().unwrap().unwrap().file_name().to_string_lossy(), "example");
+///
+/// let mut entries: Vec<_> = std::fs::read_dir(out.join("example"))
+///     .unwrap()
+///     .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+///     .collect();
+/// assert!(entries.contains(&"file1.txt".to_string()));
+/// assert!(entries.contains(&"file2.txt".to_string()));
+/// assert!(entries.contains(&"file3.txt".to_string()));
+/// ```
+#[inline]
+pub fn unarchive(tarball: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> { unarchive_with(tarball, target, UnarchiveOptions::new()) }
+
+/// Unarchives the given tarball to the given location, as given by the options.
+///
+/// If you enabled the `async-tokio` feature, also check the [`unarchive_with_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]).
+///
+/// # Errors
+/// This function errors if we failed to read or write anything, if some directories do or do not exist, or if the tarball's compression is
+/// unknown (see [`Error::UnknownArchiveFormat`]). It also stops as soon as the tarball exceeds any of the [`UnarchiveLimits`] (see
+/// [`Error::LimitExceeded`]).
+///
+/// # Examples
+/// ```rust
+/// use download::tar::{unarchive_with, Compression, UnarchiveOptions};
+///
+/// // Create an uncompressed archive (see 'archive_with()' example)
+/// # let tmp = std::env::temp_dir();
+/// # let dir = tmp.join("example_unarchive_with");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// # std::fs::create_dir(&dir).unwrap();
+/// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// # let tar = tmp.join("example_unarchive_with.tar");
+/// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+/// # download::tar::archive_with(&dir, &tar, download::tar::ArchiveOptions::new().with_compression(Compression::None)).unwrap();
+///
+/// // Unarchive it to another directory!
+/// let out = tmp.join("example_unarchive_with2");
+/// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+/// unarchive_with(&tar, &out, UnarchiveOptions::new().with_compression(Compression::None)).unwrap();
+/// assert!(out.join("example_unarchive_with").join("file1.txt").is_file());
+/// ```
+pub fn unarchive_with(tarball: impl AsRef<Path>, target: impl AsRef<Path>, options: UnarchiveOptions) -> Result<(), Error> {
+    let tarball: &Path = tarball.as_ref();
+    let target: &Path = target.as_ref();
+    debug!("Extracting '{}' to '{}'...", tarball.display(), target.display());
+
+    // Whine if the target already exists
+    if target.exists() {
+        return Err(Error::TargetExists { path: target.into() });
+    }
+    if let Err(err) = fs::create_dir(target) {
+        return Err(Error::TargetDirCreate { path: target.into(), err });
+    }
+
+    // Open the source tarfile, counting the bytes on either side of its decoder
+    let (mut tar, counter): (Archive<Box<dyn Read>>, RatioCounter) = open_entries(tarball, &options)?;
+    let entries: Entries<Box<dyn Read>> = match tar.entries() {
+        Ok(entries) => entries,
+        Err(err) => {
+            return Err(Error::SourceTarEntries { tarball: tarball.into(), err });
+        },
+    };
+
+    // Iterate over all of the entries
+    let mut core: UnarchiveCore = UnarchiveCore::new(tarball, target, &options, counter);
+    let root: PathBuf = resolve(target).unwrap_or_else(|| target.into());
+    let mut symlinks: Vec<(PlannedLink, Entry<Box<dyn Read>>)> = Vec::new();
+    let mut dirs: Vec<(PlannedEntry, Entry<Box<dyn Read>>)> = Vec::new();
+    for entry in entries {
+        // Unwrap the entry
+        let mut entry: Entry<Box<dyn Read>> = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                return Err(core.on_entry_error(err));
+            },
+        };
+
+        // Unpack the thing (or put it aside if it's a directory or symlink)
+        let planned: PlannedEntry = match core.on_entry(entry.path().map(Into::into), entry.header(), entry.size())? {
+            Some(planned) => planned,
+            None => continue,
+        };
+        let kind: EntryType = entry.header().entry_type();
+        let mut source: Option<PathBuf> = None;
+        if kind.is_dir() {
+            dirs.push((planned, entry));
+            continue;
+        } else if kind.is_symlink() || kind.is_hard_link() {
+            let link: PathBuf = match entry.link_name() {
+                Ok(link) => link.map(Cow::into_owned).unwrap_or_default(),
+                Err(err) => return Err(Error::SourceTarEntryUnpack { tarball: tarball.into(), entry: planned.path, target: planned.target, err }),
+            };
+            let check: bool = core.on_link(&planned, kind.is_hard_link(), &link)?;
+            if kind.is_symlink() {
+                symlinks.push((PlannedLink { entry: planned, link, check }, entry));
+                continue;
+            }
+            let hardlink: PathBuf = core.on_hardlink(&planned, &link)?;
+            if check {
+                core.on_resolved(&planned, &link, resolve(&hardlink), &root)?;
+            }
+            source = Some(hardlink);
+        }
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.path.display(), planned.target.display());
+        let res: io::Result<bool> = unpack(&mut entry, &planned, target, &root, source.as_deref());
+        core.on_unpacked(planned, res)?;
+    }
+
+    // Then unpack the symlinks, checking them as soon as they exist (so no other symlink is unpacked through them)...
+    let mut checked: Vec<PlannedLink> = Vec::new();
+    for (planned, mut entry) in symlinks {
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.entry.path.display(), planned.entry.target.display());
+        let res: io::Result<bool> = unpack(&mut entry, &planned.entry, target, &root, None);
+        core.on_unpacked(planned.entry.clone(), res)?;
+        if planned.check {
+            if let Err(err) = core.on_resolved(&planned.entry, &planned.link, resolve(&planned.entry.target), &root) {
+                let _ = fs::remove_file(&planned.entry.target);
+                return Err(err);
+            }
+            checked.push(planned);
+        }
+    }
+    // ...and once more now they all exist (so no symlink redirected another)
+    for planned in checked {
+        if let Err(err) = core.on_resolved(&planned.entry, &planned.link, resolve(&planned.entry.target), &root) {
+            let _ = fs::remove_file(&planned.entry.target);
+            return Err(err);
+        }
+    }
+
+    // Then unpack the directories
+    UnarchiveCore::order_dirs(&mut dirs);
+    for (planned, mut entry) in dirs {
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.path.display(), planned.target.display());
+        let res: io::Result<bool> = unpack(&mut entry, &planned, target, &root, None);
+        core.on_unpacked(planned, res)?;
+    }
+
+    // Done
+    Ok(())
+}
+
+/// Unarchives the given tarball to the given location, detecting its [`Compression`] from its first bytes.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+///
+/// # Errors
+/// This function errors if we failed to read or write anything, if some directories do or do not exist, or if the tarball's compression is
+/// unknown (see [`Error::UnknownArchiveFormat`]).
+///
+/// # Examples
+/// ```rust
+/// # tokio_test::block_on(async {
+/// use download::tar::unarchive_async;
+///
+/// // Create an archive (see 'archive()' example)
+/// # let tmp = std::env::temp_dir();
+/// # let dir = tmp.join("example");
+/// # if dir.exists() { tokio::fs::remove_dir_all(&dir).await.unwrap(); }
+/// # tokio::fs::create_dir(&dir).await.unwrap();
+/// # tokio::fs::write(dir.join("file1.txt"), "Hello there!\n").await.unwrap();
+/// # tokio::fs::write(dir.join("file2.txt"), "General Kenobi...\n").await.unwrap();
+/// # tokio::fs::write(dir.join("file3.txt"), "...you are a bold one\n").await.unwrap();
+/// # let tar = tmp.join("example.tar.gz");
+/// # if tar.exists() { tokio::fs::remove_file(&tar).await.unwrap(); }
+/// # download::tar::archive_async(&dir, &tar, false).await.unwrap();
+///
+/// // Unarchive it to another directory!
+/// let out = tmp.join("example2");
+/// # if out.exists() { tokio::fs::remove_dir_all(&out).await.unwrap(); }
+/// unarchive_async(&tar, &out).await.unwrap();
+///
+/// // Now check the directory contains what we expect :)
+/// let mut entries = std::fs::read_dir(&out).unwrap();
+/// assert_eq!(entries.next().unwrap().unwrap().file_name().to_string_lossy(), "example");
+///
+/// let mut entries: Vec<_> = std::fs::read_dir(out.join("example"))
+///     .unwrap()
+///     .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+///     .collect();
+/// assert!(entries.contains(&"file1.txt".to_string()));
+/// assert!(entries.contains(&"file2.txt".to_string()));
+/// assert!(entries.contains(&"file3.txt".to_string()));
+/// # });
+/// ```
+#[cfg(feature = "async-tokio")]
+#[inline]
+pub async fn unarchive_async(tarball: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    unarchive_with_async(tarball, target, UnarchiveOptions::new()).await
+}
+
+/// Unarchives the given tarball to the given location, as given by the options.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]).
+///
+/// # Errors
+/// This function errors if we failed to read or write anything, if some directories do or do not exist, or if the tarball's compression is
+/// unknown (see [`Error::UnknownArchiveFormat`]). It also stops as soon as the tarball exceeds any of the [`UnarchiveLimits`] (see
+/// [`Error::LimitExceeded`]).
+#[cfg(feature = "async-tokio")]
+pub async fn unarchive_with_async(tarball: impl AsRef<Path>, target: impl AsRef<Path>, options: UnarchiveOptions) -> Result<(), Error> {
+    let tarball: &Path = tarball.as_ref();
+    let target: &Path = target.as_ref();
+    debug!("Extracting '{}' to '{}'...", tarball.display(), target.display());
+
+    // Whine if the target already exists
+    if target.exists() {
+        return Err(Error::TargetExists { path: target.into() });
+    }
+    if let Err(err) = tfs::create_dir(target).await {
+        return Err(Error::TargetDirCreate { path: target.into(), err });
+    }
+
+    // Open the source tarfile, counting the bytes on either side of its decoder
+    let (mut tar, counter): (AsyncArchive<Box<dyn Send + Unpin + AsyncRead>>, RatioCounter) = open_entries_async(tarball, &options).await?;
+    let mut entries: AsyncEntries<Box<dyn Send + Unpin + AsyncRead>> = match tar.entries() {
+        Ok(entries) => entries,
+        Err(err) => {
+            return Err(Error::SourceTarEntries { tarball: tarball.into(), err });
+        },
+    };
+
+    // Iterate over all of the entries
+    let mut core: UnarchiveCore = UnarchiveCore::new(tarball, target, &options, counter);
+    let root: PathBuf = resolve_async(target).await.unwrap_or_else(|| target.into());
+    let mut symlinks: Vec<(PlannedLink, AsyncEntry<AsyncArchive<_>>)> = Vec::new();
+    let mut dirs: Vec<(PlannedEntry, AsyncEntry<AsyncArchive<_>>)> = Vec::new();
+    while let Some(entry) = entries.next().await {
+        // Unwrap the entry
+        let mut entry: AsyncEntry<AsyncArchive<_>> = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                return Err(core.on_entry_error(err));
+            },
+        };
 
-                // Compute the tar-side path of this file
-                let name: &OsStr = if skip_root_dir && is_root_dir { OsStr::new("") } else { &name };
+        // Unpack the thing (or put it aside if it's a directory or symlink)
+        let header: Header = header_async(entry.header());
+        let planned: PlannedEntry = match core.on_entry(entry.path().map(Into::into), &header, entry_size_async(entry.header()))? {
+            Some(planned) => planned,
+            None => continue,
+        };
+        let kind: AsyncEntryType = entry.header().entry_type();
+        let mut source: Option<PathBuf> = None;
+        if kind.is_dir() {
+            dirs.push((planned, entry));
+            continue;
+        } else if kind.is_symlink() || kind.is_hard_link() {
+            let link: PathBuf = match entry.link_name() {
+                Ok(link) => link.map(Cow::into_owned).unwrap_or_default(),
+                Err(err) => return Err(Error::SourceTarEntryUnpack { tarball: tarball.into(), entry: planned.path, target: planned.target, err }),
+            };
+            let check: bool = core.on_link(&planned, kind.is_hard_link(), &link)?;
+            if kind.is_symlink() {
+                symlinks.push((PlannedLink { entry: planned, link, check }, entry));
+                continue;
+            }
+            let hardlink: PathBuf = core.on_hardlink(&planned, &link)?;
+            if check {
+                core.on_resolved(&planned, &link, resolve_async(&hardlink).await, &root)?;
+            }
+            source = Some(hardlink);
+        }
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.path.display(), planned.target.display());
+        let res: io::Result<bool> = unpack_async(&mut entry, &planned, target, &root, source.as_deref()).await;
+        core.on_unpacked(planned, res)?;
+    }
 
-                // Add its path
-                todo.push((entry.path(), PathBuf::from(name).join(entry.file_name()).into()));
+    // Then unpack the symlinks, checking them as soon as they exist (so no other symlink is unpacked through them)...
+    let mut checked: Vec<PlannedLink> = Vec::new();
+    for (planned, mut entry) in symlinks {
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.entry.path.display(), planned.entry.target.display());
+        let res: io::Result<bool> = unpack_async(&mut entry, &planned.entry, target, &root, None).await;
+        core.on_unpacked(planned.entry.clone(), res)?;
+        if planned.check {
+            if let Err(err) = core.on_resolved(&planned.entry, &planned.link, resolve_async(&planned.entry.target).await, &root) {
+                let _ = tfs::remove_file(&planned.entry.target).await;
+                return Err(err);
             }
-            is_root_dir = false;
-        } else if !path.exists() {
-            return Err(Error::SourceNotFound { path });
-        } else {
-            return Err(Error::SourceNotAFileOrDir { path });
+            checked.push(planned);
+        }
+    }
+    // ...and once more now they all exist (so no symlink redirected another)
+    for planned in checked {
+        if let Err(err) = core.on_resolved(&planned.entry, &planned.link, resolve_async(&planned.entry.target).await, &root) {
+            let _ = tfs::remove_file(&planned.entry.target).await;
+            return Err(err);
         }
     }
 
-    // Finish writing the archive
-    debug!("Finishing tarball...");
-    match tar.into_inner().await {
-        Ok(mut enc) => {
-            // Flush the encoder before we quit
-            if let Err(err) = enc.shutdown().await {
-                return Err(Error::TargetTarFlush { tarball: tarball.into(), err });
-            };
-            Ok(())
-        },
-        Err(err) => Err(Error::TargetTarFinish { tarball: tarball.into(), err }),
+    // Then unpack the directories
+    UnarchiveCore::order_dirs(&mut dirs);
+    for (planned, mut entry) in dirs {
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.path.display(), planned.target.display());
+        let res: io::Result<bool> = unpack_async(&mut entry, &planned, target, &root, None).await;
+        core.on_unpacked(planned, res)?;
     }
+
+    // Done
+    Ok(())
+}
+
+/// Unarchives the given tarball to the given location, detecting its [`Compression`] from its first bytes.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. Like [`async_fs`] does for file operations, it
+/// runs [`unarchive()`] on the [`blocking`] thread pool; so it works on any executor.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all files in the tarball in this directory.
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive()`].
+#[cfg(feature = "async-smol")]
+#[inline]
+pub async fn unarchive_smol(tarball: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    unarchive_with_smol(tarball, target, UnarchiveOptions::new()).await
+}
+
+/// Unarchives the given tarball to the given location, as given by the options.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`unarchive_with()`] on the
+/// [`blocking`] thread pool.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all files in the tarball in this directory.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]).
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive_with()`].
+#[cfg(feature = "async-smol")]
+pub async fn unarchive_with_smol(tarball: impl AsRef<Path>, target: impl AsRef<Path>, options: UnarchiveOptions) -> Result<(), Error> {
+    let tarball: PathBuf = tarball.as_ref().into();
+    let target: PathBuf = target.as_ref().into();
+    unblock(move || unarchive_with(tarball, target, options)).await
 }
 
 
 
-/// Unarchives the given `.tar.gz` file to the given location.
+/// Unarchives a single file from the given tarball to the given path, detecting the tarball's [`Compression`] from its first bytes.
 ///
-/// If you enabled the `async-tokio` feature, also check the [`unarchive_async()`]-function for async contexts.
+/// If you enabled the `async-tokio` feature, also check the [`unarchive_file_async()`]-function for async contexts.
 ///
 /// # Arguments
 /// - `tarball`: The source tarball file to extract from.
-/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+/// - `entry`: The path of the file in the tarball (e.g., `foo-1.2.3/bin/foo`).
+/// - `target`: The path to write the file to. Its parent directory must exist, but it may not exist itself.
 ///
 /// # Errors
-/// This function errors if we failed to read or write anything or if some directories do or do not exist.
+/// This function errors if the tarball has no regular file at `entry` (see [`Error::SourceTarEntryNotFound`]), or for the same reasons as
+/// [`unarchive()`].
 ///
 /// # Examples
 /// ```rust
-/// use download::tar::unarchive;
+/// use download::tar::unarchive_file;
 ///
 /// // Create an archive (see 'archive()' example)
 /// # let tmp = std::env::temp_dir();
-/// # let dir = tmp.join("example");
+/// # let dir = tmp.join("example_unarchive_file");
 /// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
 /// # std::fs::create_dir(&dir).unwrap();
 /// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
-/// # std::fs::write(dir.join("file2.txt"), "General Kenobi...\n").unwrap();
-/// # std::fs::write(dir.join("file3.txt"), "...you are a bold one\n").unwrap();
-/// # let tar = tmp.join("example.tar.gz");
+/// # let tar = tmp.join("example_unarchive_file.tar.gz");
 /// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
 /// # download::tar::archive(&dir, &tar, false).unwrap();
 ///
-/// // Unarchive it to another directory!
-/// let out = tmp.join("example2");
-/// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
-/// unarchive(&tar, &out).unwrap();
+/// // Extract only the file we need
+/// let out = tmp.join("example_unarchive_file.txt");
+/// # if out.exists() { std::fs::remove_file(&out).unwrap(); }
+/// unarchive_file(&tar, "example_unarchive_file/file1.txt", &out).unwrap();
+/// assert_eq!(std::fs::read_to_string(&out).unwrap(), "Hello there!\n");
+/// ```
+#[inline]
+pub fn unarchive_file(tarball: impl AsRef<Path>, entry: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    unarchive_file_with(tarball, entry, target, UnarchiveOptions::new())
+}
+
+/// Unarchives a single file from the given tarball to the given path, as given by the options.
 ///
-/// // Now check the directory contains what we expect :)
-/// let mut entries = std::fs::read_dir(&out).unwrap();
-/// assert_eq!(entries.next().unwrap().unwrap().file_name().to_string_lossy(), "example");
+/// If you enabled the `async-tokio` feature, also check the [`unarchive_file_with_async()`]-function for async contexts.
 ///
-/// let mut entries: Vec<_> = std::fs::read_dir(out.join("example"))
-///     .unwrap()
-///     .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
-///     .collect();
-/// assert!(entries.contains(&"file1.txt".to_string()));
-/// assert!(entries.contains(&"file2.txt".to_string()));
-/// assert!(entries.contains(&"file3.txt".to_string()));
-/// ```
-pub fn unarchive(tarball: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `entry`: The path of the file in the tarball (e.g., `foo-1.2.3/bin/foo`).
+/// - `target`: The path to write the file to. Its parent directory must exist, but it may not exist itself.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]). Its
+///   [`UnarchiveOptions::include`] and [`UnarchiveOptions::strip_components`] are ignored; the file must still pass the rest of its
+///   filters.
+///
+/// # Errors
+/// This function errors if the tarball has no regular file at `entry` (see [`Error::SourceTarEntryNotFound`]), or for the same reasons as
+/// [`unarchive_with()`].
+pub fn unarchive_file_with(
+    tarball: impl AsRef<Path>,
+    entry: impl AsRef<Path>,
+    target: impl AsRef<Path>,
+    options: UnarchiveOptions,
+) -> Result<(), Error> {
     let tarball: &Path = tarball.as_ref();
+    let name: &Path = entry.as_ref();
     let target: &Path = target.as_ref();
-    debug!("Extracting '{}' to '{}'...", tarball.display(), target.display());
+    debug!("Extracting '{}/{}' to '{}'...", tarball.display(), name.display(), target.display());
 
     // Whine if the target already exists
     if target.exists() {
         return Err(Error::TargetExists { path: target.into() });
     }
-    if let Err(err) = fs::create_dir(target) {
-        return Err(Error::TargetDirCreate { path: target.into(), err });
-    }
+    let options: UnarchiveOptions = UnarchiveOptions { include: vec![Glob::literal(name)], strip_components: 0, ..options };
+    let wanted: String = slash_path(name);
 
-    // Open the source tarfile
-    let handle: fs::File = match fs::File::open(tarball) {
-        Ok(handle) => handle,
+    // Open the source tarfile, counting the bytes on either side of its decoder
+    let (mut tar, counter): (Archive<Box<dyn Read>>, RatioCounter) = open_entries(tarball, &options)?;
+    let entries: Entries<Box<dyn Read>> = match tar.entries() {
+        Ok(entries) => entries,
         Err(err) => {
-            return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
+            return Err(Error::SourceTarEntries { tarball: tarball.into(), err });
         },
     };
 
-    // Create the decoder & tarfile around this file
-    let dec: GzDecoder<_> = GzDecoder::new(io::BufReader::new(handle));
-    let mut tar: Archive<GzDecoder<_>> = Archive::new(dec);
-    let entries: Entries<GzDecoder<_>> = match tar.entries() {
+    // Stream the entries until we find the file
+    let mut core: UnarchiveCore = UnarchiveCore::new(tarball, target, &options, counter);
+    for entry in entries {
+        let mut entry: Entry<Box<dyn Read>> = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                return Err(core.on_entry_error(err));
+            },
+        };
+        let planned: PlannedEntry = match core.on_entry(entry.path().map(Into::into), entry.header(), entry.size())? {
+            Some(planned) => planned,
+            None => continue,
+        };
+        let kind: EntryType = entry.header().entry_type();
+        if !(kind.is_file() || kind.is_contiguous() || kind.is_gnu_sparse()) || slash_path(&planned.path) != wanted {
+            continue;
+        }
+
+        // Unpack it to exactly the target
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.path.display(), target.display());
+        let res: io::Result<bool> = entry.unpack(target).map(|_| true);
+        return core.on_unpacked(PlannedEntry { target: target.into(), ..planned }, res);
+    }
+    Err(Error::SourceTarEntryNotFound { tarball: tarball.into(), entry: name.into() })
+}
+
+/// Unarchives a single file from the given tarball to the given path, detecting the tarball's [`Compression`] from its first bytes.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `entry`: The path of the file in the tarball (e.g., `foo-1.2.3/bin/foo`).
+/// - `target`: The path to write the file to. Its parent directory must exist, but it may not exist itself.
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive_file()`].
+#[cfg(feature = "async-tokio")]
+#[inline]
+pub async fn unarchive_file_async(tarball: impl AsRef<Path>, entry: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    unarchive_file_with_async(tarball, entry, target, UnarchiveOptions::new()).await
+}
+
+/// Unarchives a single file from the given tarball to the given path, as given by the options.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `entry`: The path of the file in the tarball (e.g., `foo-1.2.3/bin/foo`).
+/// - `target`: The path to write the file to. Its parent directory must exist, but it may not exist itself.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (see [`unarchive_file_with()`]).
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive_file_with()`].
+#[cfg(feature = "async-tokio")]
+pub async fn unarchive_file_with_async(
+    tarball: impl AsRef<Path>,
+    entry: impl AsRef<Path>,
+    target: impl AsRef<Path>,
+    options: UnarchiveOptions,
+) -> Result<(), Error> {
+    let tarball: &Path = tarball.as_ref();
+    let name: &Path = entry.as_ref();
+    let target: &Path = target.as_ref();
+    debug!("Extracting '{}/{}' to '{}'...", tarball.display(), name.display(), target.display());
+
+    // Whine if the target already exists
+    if target.exists() {
+        return Err(Error::TargetExists { path: target.into() });
+    }
+    let options: UnarchiveOptions = UnarchiveOptions { include: vec![Glob::literal(name)], strip_components: 0, ..options };
+    let wanted: String = slash_path(name);
+
+    // Open the source tarfile, counting the bytes on either side of its decoder
+    let (mut tar, counter): (AsyncArchive<Box<dyn Send + Unpin + AsyncRead>>, RatioCounter) = open_entries_async(tarball, &options).await?;
+    let mut entries: AsyncEntries<Box<dyn Send + Unpin + AsyncRead>> = match tar.entries() {
         Ok(entries) => entries,
         Err(err) => {
             return Err(Error::SourceTarEntries { tarball: tarball.into(), err });
         },
     };
 
-    // Iterate over all of the entries
-    for (i, entry) in entries.enumerate() {
-        // Unwrap the entry
-        let mut entry: Entry<GzDecoder<_>> = match entry {
+    // Stream the entries until we find the file
+    let mut core: UnarchiveCore = UnarchiveCore::new(tarball, target, &options, counter);
+    while let Some(entry) = entries.next().await {
+        let mut entry: AsyncEntry<AsyncArchive<_>> = match entry {
             Ok(entry) => entry,
             Err(err) => {
-                return Err(Error::SourceTarEntry { tarball: tarball.into(), entry: i, err });
+                return Err(core.on_entry_error(err));
             },
         };
-
-        // Attempt to extract the entry
-        let entry_path: PathBuf = match entry.path() {
-            Ok(entry_path) => entry_path.into(),
-            Err(err) => {
-                return Err(Error::SourceTarEntryPath { tarball: tarball.into(), entry: i, err });
-            },
+        let header: Header = header_async(entry.header());
+        let planned: PlannedEntry = match core.on_entry(entry.path().map(Into::into), &header, entry_size_async(entry.header()))? {
+            Some(planned) => planned,
+            None => continue,
         };
+        let kind: EntryType = header.entry_type();
+        if !(kind.is_file() || kind.is_contiguous() || kind.is_gnu_sparse()) || slash_path(&planned.path) != wanted {
+            continue;
+        }
 
-        // Unpack the thing
-        let target_path: PathBuf = target.join(&entry_path);
-        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), entry_path.display(), target_path.display());
-        match entry.unpack_in(&target) {
-            Ok(true) => {},
-            Ok(false) => {
-                return Err(Error::SourceTarEntryEscaped { tarball: tarball.into(), entry: entry_path });
-            },
+        // Unpack it to exactly the target
+        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), planned.path.display(), target.display());
+        let res: io::Result<bool> = entry.unpack(target).await.map(|_| true);
+        return core.on_unpacked(PlannedEntry { target: target.into(), ..planned }, res);
+    }
+    Err(Error::SourceTarEntryNotFound { tarball: tarball.into(), entry: name.into() })
+}
+
+/// Unarchives a single file from the given tarball to the given path, detecting the tarball's [`Compression`] from its first bytes.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`unarchive_file()`] on the
+/// [`blocking`] thread pool.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `entry`: The path of the file in the tarball (e.g., `foo-1.2.3/bin/foo`).
+/// - `target`: The path to write the file to. Its parent directory must exist, but it may not exist itself.
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive_file()`].
+#[cfg(feature = "async-smol")]
+#[inline]
+pub async fn unarchive_file_smol(tarball: impl AsRef<Path>, entry: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    unarchive_file_with_smol(tarball, entry, target, UnarchiveOptions::new()).await
+}
+
+/// Unarchives a single file from the given tarball to the given path, as given by the options.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`unarchive_file_with()`] on the
+/// [`blocking`] thread pool.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to extract from.
+/// - `entry`: The path of the file in the tarball (e.g., `foo-1.2.3/bin/foo`).
+/// - `target`: The path to write the file to. Its parent directory must exist, but it may not exist itself.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (see [`unarchive_file_with()`]).
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive_file_with()`].
+#[cfg(feature = "async-smol")]
+pub async fn unarchive_file_with_smol(
+    tarball: impl AsRef<Path>,
+    entry: impl AsRef<Path>,
+    target: impl AsRef<Path>,
+    options: UnarchiveOptions,
+) -> Result<(), Error> {
+    let tarball: PathBuf = tarball.as_ref().into();
+    let entry: PathBuf = entry.as_ref().into();
+    let target: PathBuf = target.as_ref().into();
+    unblock(move || unarchive_file_with(tarball, entry, target, options)).await
+}
+
+
+
+/// Lists the paths of the entries in the given tarball without extracting anything, detecting its [`Compression`] from its first bytes.
+///
+/// If you enabled the `async-tokio` feature, also check the [`list_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to list.
+///
+/// # Returns
+/// The paths of all entries in the tarball, in the order in which they appear.
+///
+/// # Errors
+/// This function errors if we failed to read the tarball, or if its compression is unknown (see [`Error::UnknownArchiveFormat`]).
+///
+/// # Examples
+/// ```rust
+/// use download::tar::list;
+///
+/// // Create an archive (see 'archive()' example)
+/// # let tmp = std::env::temp_dir();
+/// # let dir = tmp.join("example_list");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// # std::fs::create_dir(&dir).unwrap();
+/// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// # let tar = tmp.join("example_list.tar.gz");
+/// # if tar.exists() { std::fs::remove_file(&tar).unwrap(); }
+/// # download::tar::archive(&dir, &tar, false).unwrap();
+///
+/// // We can see what's in it
+/// let entries = list(&tar).unwrap();
+/// assert!(entries.contains(&"example_list/file1.txt".into()));
+/// ```
+#[inline]
+pub fn list(tarball: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> { list_with(tarball, UnarchiveOptions::new()) }
+
+/// Lists the paths of the entries in the given tarball without extracting anything, as given by the options.
+///
+/// If you enabled the `async-tokio` feature, also check the [`list_with_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to list.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]).
+///
+/// # Returns
+/// The paths of all entries in the tarball, in the order in which they appear.
+///
+/// # Errors
+/// This function errors if we failed to read the tarball, if its compression is unknown (see [`Error::UnknownArchiveFormat`]), or if it
+/// exceeds any of the [`UnarchiveLimits`] (see [`Error::LimitExceeded`]).
+pub fn list_with(tarball: impl AsRef<Path>, options: UnarchiveOptions) -> Result<Vec<PathBuf>, Error> {
+    let tarball: &Path = tarball.as_ref();
+    debug!("Listing '{}'...", tarball.display());
+
+    // Open the source tarfile, counting the bytes on either side of its decoder
+    let (mut tar, counter): (Archive<Box<dyn Read>>, RatioCounter) = open_entries(tarball, &options)?;
+    let entries: Entries<Box<dyn Read>> = match tar.entries() {
+        Ok(entries) => entries,
+        Err(err) => {
+            return Err(Error::SourceTarEntries { tarball: tarball.into(), err });
+        },
+    };
+
+    // Collect the paths of all of the entries
+    let mut core: UnarchiveCore = UnarchiveCore::new(tarball, Path::new(""), &options, counter);
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+        let entry: Entry<Box<dyn Read>> = match entry {
+            Ok(entry) => entry,
             Err(err) => {
-                return Err(Error::SourceTarEntryUnpack { tarball: tarball.into(), entry: entry_path, target: target_path, err });
+                return Err(core.on_entry_error(err));
             },
+        };
+        if let Some(planned) = core.on_entry(entry.path().map(Into::into), entry.header(), entry.size())? {
+            paths.push(planned.path);
         }
-
-        // Done, go to next entry
     }
 
     // Done
-    Ok(())
+    Ok(paths)
 }
 
-/// Unarchives the given `.tar.gz` file to the given location.
+/// Lists the paths of the entries in the given tarball without extracting anything, detecting its [`Compression`] from its first bytes.
 ///
 /// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
 ///
 /// # Arguments
-/// - `tarball`: The source tarball file to extract from.
-/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+/// - `tarball`: The source tarball file to list.
+///
+/// # Returns
+/// The paths of all entries in the tarball, in the order in which they appear.
 ///
 /// # Errors
-/// This function errors if we failed to read or write anything or if some directories do or do not exist.
+/// This function errors if we failed to read the tarball, or if its compression is unknown (see [`Error::UnknownArchiveFormat`]).
 ///
 /// # Examples
 /// ```rust
 /// # tokio_test::block_on(async {
-/// use download::tar::unarchive_async;
+/// use download::tar::list_async;
 ///
 /// // Create an archive (see 'archive()' example)
 /// # let tmp = std::env::temp_dir();
-/// # let dir = tmp.join("example");
+/// # let dir = tmp.join("example_list_async");
 /// # if dir.exists() { tokio::fs::remove_dir_all(&dir).await.unwrap(); }
 /// # tokio::fs::create_dir(&dir).await.unwrap();
 /// # tokio::fs::write(dir.join("file1.txt"), "Hello there!\n").await.unwrap();
-/// # tokio::fs::write(dir.join("file2.txt"), "General Kenobi...\n").await.unwrap();
-/// # tokio::fs::write(dir.join("file3.txt"), "...you are a bold one\n").await.unwrap();
-/// # let tar = tmp.join("example.tar.gz");
+/// # let tar = tmp.join("example_list_async.tar.gz");
 /// # if tar.exists() { tokio::fs::remove_file(&tar).await.unwrap(); }
 /// # download::tar::archive_async(&dir, &tar, false).await.unwrap();
 ///
-/// // Unarchive it to another directory!
-/// let out = tmp.join("example2");
-/// # if out.exists() { tokio::fs::remove_dir_all(&out).await.unwrap(); }
-/// unarchive_async(&tar, &out).await.unwrap();
-///
-/// // Now check the directory contains what we expect :)
-/// let mut entries = std::fs::read_dir(&out).unwrap();
-/// assert_eq!(entries.next().unwrap().unwrap().file_name().to_string_lossy(), "example");
-///
-/// let mut entries: Vec<_> = std::fs::read_dir(out.join("example"))
-///     .unwrap()
-///     .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
-///     .collect();
-/// assert!(entries.contains(&"file1.txt".to_string()));
-/// assert!(entries.contains(&"file2.txt".to_string()));
-/// assert!(entries.contains(&"file3.txt".to_string()));
+/// // We can see what's in it
+/// let entries = list_async(&tar).await.unwrap();
+/// assert!(entries.contains(&"example_list_async/file1.txt".into()));
 /// # });
 /// ```
 #[cfg(feature = "async-tokio")]
-pub async fn unarchive_async(tarball: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
-    let tarball: &Path = tarball.as_ref();
-    let target: &Path = target.as_ref();
-    debug!("Extracting '{}' to '{}'...", tarball.display(), target.display());
-
-    // Whine if the target already exists
-    if target.exists() {
-        return Err(Error::TargetExists { path: target.into() });
-    }
-    if let Err(err) = tfs::create_dir(target).await {
-        return Err(Error::TargetDirCreate { path: target.into(), err });
-    }
+#[inline]
+pub async fn list_async(tarball: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> { list_with_async(tarball, UnarchiveOptions::new()).await }
 
-    // Open the source tarfile
-    let handle: tfs::File = match tfs::File::open(tarball).await {
-        Ok(handle) => handle,
-        Err(err) => {
-            return Err(Error::SourceTarOpen { tarball: tarball.into(), err });
-        },
-    };
+/// Lists the paths of the entries in the given tarball without extracting anything, as given by the options.
+///
+/// This variation is built using [`tokio`] versions of the normal operations, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to list.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]).
+///
+/// # Returns
+/// The paths of all entries in the tarball, in the order in which they appear.
+///
+/// # Errors
+/// This function errors if we failed to read the tarball, if its compression is unknown (see [`Error::UnknownArchiveFormat`]), or if it
+/// exceeds any of the [`UnarchiveLimits`] (see [`Error::LimitExceeded`]).
+#[cfg(feature = "async-tokio")]
+pub async fn list_with_async(tarball: impl AsRef<Path>, options: UnarchiveOptions) -> Result<Vec<PathBuf>, Error> {
+    let tarball: &Path = tarball.as_ref();
+    debug!("Listing '{}'...", tarball.display());
 
-    // Create the decoder & tarfile around this file
-    let dec: AsyncGzipDecoder<_> = AsyncGzipDecoder::new(tio::BufReader::new(handle));
-    let mut tar: AsyncArchive<AsyncGzipDecoder<_>> = AsyncArchive::new(dec);
-    let mut entries: AsyncEntries<AsyncGzipDecoder<_>> = match tar.entries() {
+    // Open the source tarfile, counting the bytes on either side of its decoder
+    let (mut tar, counter): (AsyncArchive<Box<dyn Send + Unpin + AsyncRead>>, RatioCounter) = open_entries_async(tarball, &options).await?;
+    let mut entries: AsyncEntries<Box<dyn Send + Unpin + AsyncRead>> = match tar.entries() {
         Ok(entries) => entries,
         Err(err) => {
             return Err(Error::SourceTarEntries { tarball: tarball.into(), err });
         },
     };
 
-    // Iterate over all of the entries
-    let mut i: usize = 0;
+    // Collect the paths of all of the entries
+    let mut core: UnarchiveCore = UnarchiveCore::new(tarball, Path::new(""), &options, counter);
+    let mut paths: Vec<PathBuf> = Vec::new();
     while let Some(entry) = entries.next().await {
-        // Unwrap the entry
-        let mut entry: AsyncEntry<AsyncArchive<_>> = match entry {
+        let entry: AsyncEntry<AsyncArchive<_>> = match entry {
             Ok(entry) => entry,
             Err(err) => {
-                return Err(Error::SourceTarEntry { tarball: tarball.into(), entry: i, err });
-            },
-        };
-        i += 1;
-
-        // Attempt to extract the entry
-        let entry_path: PathBuf = match entry.path() {
-            Ok(entry_path) => entry_path.into(),
-            Err(err) => {
-                return Err(Error::SourceTarEntryPath { tarball: tarball.into(), entry: i, err });
+                return Err(core.on_entry_error(err));
             },
         };
-
-        // Unpack the thing
-        let target_path: PathBuf = target.join(&entry_path);
-        debug!("Extracting '{}/{}' to '{}'...", tarball.display(), entry_path.display(), target_path.display());
-        match entry.unpack_in(&target).await {
-            Ok(true) => {},
-            Ok(false) => {
-                return Err(Error::SourceTarEntryEscaped { tarball: tarball.into(), entry: entry_path });
-            },
-            Err(err) => {
-                return Err(Error::SourceTarEntryUnpack { tarball: tarball.into(), entry: entry_path, target: target_path, err });
-            },
+        let header: Header = header_async(entry.header());
+        if let Some(planned) = core.on_entry(entry.path().map(Into::into), &header, entry_size_async(entry.header()))? {
+            paths.push(planned.path);
         }
-
-        // Done, go to next entry
     }
 
     // Done
-    Ok(())
+    Ok(paths)
+}
+
+/// Lists the entries in the given tarball without extracting it, detecting its [`Compression`] from its first bytes.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. Like [`async_fs`] does for file operations, it
+/// runs [`list()`] on the [`blocking`] thread pool; so it works on any executor.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to list.
+///
+/// # Returns
+/// The paths of all entries in the tarball, in the order in which they appear.
+///
+/// # Errors
+/// This function errors for the same reasons as [`list()`].
+#[cfg(feature = "async-smol")]
+#[inline]
+pub async fn list_smol(tarball: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> { list_with_smol(tarball, UnarchiveOptions::new()).await }
+
+/// Lists the entries in the given tarball without extracting it, as given by the options.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`list_with()`] on the [`blocking`]
+/// thread pool.
+///
+/// # Arguments
+/// - `tarball`: The source tarball file to list.
+/// - `options`: The [`UnarchiveOptions`] that determine how the tarball is read (e.g., its [`Compression`]).
+///
+/// # Returns
+/// The paths of all entries in the tarball, in the order in which they appear.
+///
+/// # Errors
+/// This function errors for the same reasons as [`list_with()`].
+#[cfg(feature = "async-smol")]
+pub async fn list_with_smol(tarball: impl AsRef<Path>, options: UnarchiveOptions) -> Result<Vec<PathBuf>, Error> {
+    let tarball: PathBuf = tarball.as_ref().into();
+    unblock(move || list_with(tarball, options)).await
 }
@@ -0,0 +1,863 @@
+//  TESTING.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 17:20:37
+//  Last edited:
+//    18 Oct 2026, 17:20:37
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines utilities for testing code that downloads files, without
+//!   needing a network: an in-memory [`MockTransport`] and a local
+//!   HTTP/1.1 [`TestServer`].
+//!
+//!   Only available when the `testing`-feature is enabled.
+//
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write as _};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::str::FromStr as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{fs, thread};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, LOCATION};
+pub use reqwest::StatusCode;
+use url::Url;
+
+use crate::download::Error;
+use crate::transport::{Transport, TransportResponse};
+
+
+/***** MACROS *****/
+/// Mirrors [`log`]'s [`debug!`]-macro, but only when the `log`-feature it given.
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($t:tt)*) => {
+        ::log::debug!($($t)*)
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($t:tt)*) => {};
+}
+
+
+
+
+
+/***** HELPERS *****/
+/// Describes the outcome of parsing a `Range`-header (see [`parse_range()`]).
+enum ByteRange {
+    /// The range can be served, and spans `start..=end`.
+    Satisfiable { start: usize, end: usize },
+    /// The range starts beyond the end of the body.
+    Unsatisfiable,
+}
+
+/// Parses the value of a `Range`-header for a body of the given length.
+///
+/// Only single byte ranges are supported (e.g., `bytes=0-499`, `bytes=500-` or `bytes=-500`).
+///
+/// # Arguments
+/// - `raw`: The value of the header.
+/// - `len`: The length of the body that the range applies to.
+///
+/// # Returns
+/// The parsed [`ByteRange`], or [`None`] if the header is not a range we understand (in which case it should be ignored).
+fn parse_range(raw: &str, len: usize) -> Option<ByteRange> {
+    let spec: &str = raw.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last): (&str, &str) = spec.split_once('-')?;
+
+    // Suffix ranges count from the end
+    if first.is_empty() {
+        let suffix: usize = last.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(ByteRange::Unsatisfiable);
+        }
+        return Some(ByteRange::Satisfiable { start: len.saturating_sub(suffix), end: len - 1 });
+    }
+
+    // Otherwise, it's an (open) range from some start
+    let start: usize = first.parse().ok()?;
+    let end: Option<usize> = if last.is_empty() { None } else { Some(last.parse().ok()?) };
+    if end.is_some_and(|end| end < start) {
+        return None;
+    }
+    if start >= len {
+        return Some(ByteRange::Unsatisfiable);
+    }
+    Some(ByteRange::Satisfiable { start, end: end.unwrap_or(len - 1).min(len - 1) })
+}
+
+/// Handles a single connection to a [`TestServer`].
+///
+/// # Arguments
+/// - `stream`: The [`TcpStream`] of the connection.
+/// - `routes`: The routes to serve.
+/// - `requests`: The list to record the request in.
+/// - `shutdown`: Set when the server shuts down, to abort stalled responses.
+///
+/// # Errors
+/// This function errors if we failed to read from or write to the connection.
+fn handle_connection(
+    mut stream: TcpStream,
+    routes: &Mutex<HashMap<String, Route>>,
+    requests: &Mutex<Vec<ServerRequest>>,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    // Read the request head
+    let mut head: Vec<u8> = Vec::new();
+    let mut buf: [u8; 1024] = [0; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf)? {
+            0 => return Ok(()),
+            n => head.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    // Parse it
+    let head: String = String::from_utf8_lossy(&head).into();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or("").split(' ');
+    let method: String = request_line.next().unwrap_or("").into();
+    let path: String = request_line.next().unwrap_or("").into();
+    let headers: Vec<(String, String)> = lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().into(), value.trim().into()))
+        .collect();
+    let req: ServerRequest = ServerRequest { method, path, headers };
+    requests.lock().unwrap().push(req.clone());
+
+    // Find what to serve
+    let route: Route = match routes.lock().unwrap().get(&req.path) {
+        Some(route) if !route.head && req.method.eq_ignore_ascii_case("HEAD") => Route::status(StatusCode::METHOD_NOT_ALLOWED),
+        Some(route) => route.clone(),
+        None => Route::bytes("Not Found").with_status(StatusCode::NOT_FOUND),
+    };
+    let body: Vec<u8> = match &route.body {
+        RouteBody::Bytes(body) => body.clone(),
+        RouteBody::File(path) => match fs::read(path) {
+            Ok(body) => body,
+            Err(err) => format!("Failed to read '{}': {}", path.display(), err).into_bytes(),
+        },
+    };
+    let mut status: StatusCode = if matches!(route.body, RouteBody::File(ref path) if !path.is_file()) { StatusCode::NOT_FOUND } else { route.status };
+    let mut headers: Vec<(String, String)> = route.headers.clone();
+    let mut range: (usize, usize) = (0, body.len());
+
+    // Apply conditional and range requests
+    if let Some(etag) = &route.etag {
+        headers.push(("ETag".into(), etag.clone()));
+        if status == StatusCode::OK && req.header("If-None-Match").is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*")) {
+            status = StatusCode::NOT_MODIFIED;
+            range = (0, 0);
+        }
+    }
+    if route.ranges && status == StatusCode::OK {
+        headers.push(("Accept-Ranges".into(), "bytes".into()));
+        let if_range: bool = match (req.header("If-Range"), &route.etag) {
+            (Some(tag), Some(etag)) => tag.trim() == etag,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        match req.header("Range").filter(|_| if_range).and_then(|raw| parse_range(raw, body.len())) {
+            Some(ByteRange::Satisfiable { start, end }) => {
+                status = StatusCode::PARTIAL_CONTENT;
+                headers.push(("Content-Range".into(), format!("bytes {}-{}/{}", start, end, body.len())));
+                range = (start, end + 1);
+            },
+            Some(ByteRange::Unsatisfiable) => {
+                status = StatusCode::RANGE_NOT_SATISFIABLE;
+                headers.push(("Content-Range".into(), format!("bytes */{}", body.len())));
+                range = (0, 0);
+            },
+            None => {},
+        }
+    }
+    let body: &[u8] = &body[range.0..range.1];
+
+    // Write the head
+    write!(stream, "HTTP/1.1 {} {}\r\n", status.as_u16(), status.canonical_reason().unwrap_or(""))?;
+    for (name, value) in &headers {
+        write!(stream, "{name}: {value}\r\n")?;
+    }
+    if route.content_length {
+        write!(stream, "Content-Length: {}\r\n", body.len())?;
+    }
+    write!(stream, "Connection: close\r\n\r\n")?;
+    if req.method.eq_ignore_ascii_case("HEAD") {
+        return stream.flush();
+    }
+
+    // Write the body, possibly slowly and possibly only partially
+    let cutoff: usize = route.stall_after.into_iter().chain(route.drop_after).min().unwrap_or(usize::MAX).min(body.len());
+    for chunk in body[..cutoff].chunks(route.chunk_size.max(1)) {
+        if let Some(delay) = route.chunk_delay {
+            thread::sleep(delay);
+        }
+        if shutdown.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        stream.write_all(chunk)?;
+        stream.flush()?;
+    }
+    if cutoff < body.len() {
+        if route.stall_after.is_some_and(|stall_after| stall_after <= cutoff) {
+            // Keep the connection open without sending anything until the client gives up (or we shut down)
+            stream.set_read_timeout(Some(Duration::from_millis(50)))?;
+            while !shutdown.load(Ordering::Relaxed) {
+                match stream.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(_) => continue,
+                    Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+                    Err(_) => break,
+                }
+            }
+        }
+        return stream.shutdown(Shutdown::Both);
+    }
+    Ok(())
+}
+
+
+
+/// Serves the body of a [`MockResponse`] chunk-by-chunk.
+struct MockBody {
+    /// The full body to serve.
+    body: Vec<u8>,
+    /// How far we've served the body.
+    pos: usize,
+    /// The maximum number of bytes to serve per read.
+    chunk_size: usize,
+    /// If not `None`, fail as soon as this many bytes have been served.
+    fail_after: Option<usize>,
+}
+impl Read for MockBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let end: usize = self.fail_after.unwrap_or(usize::MAX).min(self.body.len());
+        if self.pos >= end {
+            return match self.fail_after {
+                Some(fail_after) if self.pos >= fail_after => {
+                    Err(io::Error::new(io::ErrorKind::ConnectionReset, "scripted failure while reading response body"))
+                },
+                _ => Ok(0),
+            };
+        }
+        let len: usize = buf.len().min(self.chunk_size).min(end - self.pos);
+        buf[..len].copy_from_slice(&self.body[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Defines a scripted response of a [`MockTransport`].
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    /// The status code to return.
+    pub status: StatusCode,
+    /// The headers to return.
+    pub headers: HeaderMap,
+    /// The body to return.
+    pub body: Vec<u8>,
+    /// If not `None`, waits this long before returning the response.
+    pub delay: Option<Duration>,
+    /// The maximum number of bytes of the body that are returned per read.
+    pub chunk_size: usize,
+    /// If not `None`, reading the body fails after this many bytes.
+    pub fail_after: Option<usize>,
+    /// The DER-encoded certificate to present, if any.
+    pub peer_certificate: Option<Vec<u8>>,
+    /// If not `None`, the request fails altogether with this message instead of returning a response.
+    pub failure: Option<String>,
+}
+impl MockResponse {
+    /// Constructor for a MockResponse that returns a `200 OK` with the given body.
+    ///
+    /// # Arguments
+    /// - `body`: The body to return. Its length is announced in a `Content-Length`-header.
+    ///
+    /// # Returns
+    /// A new MockResponse that serves the given body.
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        let body: Vec<u8> = body.into();
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        Self { status: StatusCode::OK, headers, body, delay: None, chunk_size: 65535, fail_after: None, peer_certificate: None, failure: None }
+    }
+
+    /// Constructor for a MockResponse that returns the given status code with an empty body.
+    ///
+    /// # Arguments
+    /// - `status`: The [`StatusCode`] to return.
+    ///
+    /// # Returns
+    /// A new MockResponse that serves the given status code.
+    #[inline]
+    pub fn status(status: StatusCode) -> Self { Self { status, ..Self::ok(Vec::new()) } }
+
+    /// Constructor for a MockResponse that redirects to somewhere else using a `302 Found`.
+    ///
+    /// # Arguments
+    /// - `location`: The (possibly relative) URL to redirect to.
+    ///
+    /// # Returns
+    /// A new MockResponse that redirects to the given location.
+    #[inline]
+    pub fn redirect(location: &str) -> Self { Self::status(StatusCode::FOUND).with_header(LOCATION.as_str(), location) }
+
+    /// Constructor for a MockResponse that fails to connect altogether, e.g., as if the server was unreachable.
+    ///
+    /// # Arguments
+    /// - `message`: The message of the [`Error::Transport`] that is returned.
+    ///
+    /// # Returns
+    /// A new MockResponse that fails the request.
+    #[inline]
+    pub fn failure(message: impl Into<String>) -> Self { Self { failure: Some(message.into()), ..Self::ok(Vec::new()) } }
+
+    /// Sets a header of the response, replacing any existing one with the same name.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the header.
+    /// - `value`: The value of the header.
+    ///
+    /// # Returns
+    /// The same MockResponse but with the header set.
+    ///
+    /// # Panics
+    /// This function panics if the given name or value are not valid in HTTP headers.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        let name: HeaderName = HeaderName::from_str(name).unwrap_or_else(|err| panic!("Invalid header name '{name}': {err}"));
+        let value: HeaderValue = HeaderValue::from_str(value).unwrap_or_else(|err| panic!("Invalid header value '{value}': {err}"));
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Removes the `Content-Length`-header, e.g., to emulate a streaming (chunked) response.
+    ///
+    /// # Returns
+    /// The same MockResponse but without announcing its length.
+    #[inline]
+    pub fn without_content_length(mut self) -> Self {
+        self.headers.remove(CONTENT_LENGTH);
+        self
+    }
+
+    /// Waits before returning the response.
+    ///
+    /// # Arguments
+    /// - `delay`: How long to wait.
+    ///
+    /// # Returns
+    /// The same MockResponse but delayed.
+    #[inline]
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Limits how many bytes of the body are returned per read.
+    ///
+    /// # Arguments
+    /// - `chunk_size`: The maximum number of bytes per read. Must be larger than 0.
+    ///
+    /// # Returns
+    /// The same MockResponse but with the new chunk size.
+    #[inline]
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Fails reading the body after some bytes have been returned, e.g., as if the connection was reset halfway through.
+    ///
+    /// # Arguments
+    /// - `fail_after`: The number of bytes to return successfully first.
+    ///
+    /// # Returns
+    /// The same MockResponse but with a failing body.
+    #[inline]
+    pub fn with_body_failure_after(mut self, fail_after: usize) -> Self {
+        self.fail_after = Some(fail_after);
+        self
+    }
+
+    /// Presents a certificate, e.g., to test certificate pinning.
+    ///
+    /// # Arguments
+    /// - `der`: The DER-encoded certificate to present.
+    ///
+    /// # Returns
+    /// The same MockResponse but with the certificate.
+    #[inline]
+    pub fn with_peer_certificate(mut self, der: impl Into<Vec<u8>>) -> Self {
+        self.peer_certificate = Some(der.into());
+        self
+    }
+}
+
+
+
+/// Implements an in-memory [`Transport`] that returns scripted responses, for use in tests.
+///
+/// Responses are scripted per URL. Every request to a URL returns the next of its responses, except that the last one is repeated indefinitely.
+/// Requests to URLs without responses fail with an [`Error::Transport`].
+///
+/// # Example
+/// ```rust
+/// use download::testing::{MockResponse, MockTransport};
+/// use download::{download_file_with_transport, DownloadSecurity, Error};
+///
+/// let transport = MockTransport::new()
+///     .with_response("https://example.com/old", MockResponse::redirect("http://example.com/new"))
+///     .with_response("http://example.com/new", MockResponse::ok("Hello there!\n"));
+///
+/// // Downloading over HTTP is fine...
+/// let file = std::env::temp_dir().join("example_mock_transport.txt");
+/// download_file_with_transport(&transport, "https://example.com/old", &file, DownloadSecurity::none(), None).unwrap();
+/// assert_eq!(std::fs::read_to_string(&file).unwrap(), "Hello there!\n");
+///
+/// // ...unless we demand HTTPS
+/// assert!(matches!(
+///     download_file_with_transport(&transport, "https://example.com/old", &file, DownloadSecurity::https(), None),
+///     Err(Error::SecurityNoHttps { .. })
+/// ));
+/// assert_eq!(transport.requests().len(), 3);
+/// ```
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    /// The responses that are left per URL.
+    responses: Mutex<HashMap<Url, VecDeque<MockResponse>>>,
+    /// The URLs of all requests sent so far.
+    requests:  Mutex<Vec<Url>>,
+}
+impl MockTransport {
+    /// Constructor for a MockTransport without any responses.
+    ///
+    /// # Returns
+    /// A new MockTransport that fails every request until responses are added.
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Adds a response for the given URL.
+    ///
+    /// # Arguments
+    /// - `url`: The URL to respond to.
+    /// - `response`: The [`MockResponse`] to return after the responses already added for this URL.
+    ///
+    /// # Returns
+    /// The same MockTransport but with the extra response.
+    ///
+    /// # Panics
+    /// This function panics if the given URL is not a valid URL.
+    pub fn with_response(self, url: &str, response: MockResponse) -> Self {
+        let url: Url = Url::from_str(url).unwrap_or_else(|err| panic!("Invalid URL '{url}': {err}"));
+        self.responses.lock().unwrap().entry(url).or_default().push_back(response);
+        self
+    }
+
+    /// Returns the URLs of all the requests sent so far, in order.
+    ///
+    /// # Returns
+    /// A list of [`Url`]s.
+    #[inline]
+    pub fn requests(&self) -> Vec<Url> { self.requests.lock().unwrap().clone() }
+}
+impl Transport for MockTransport {
+    fn get(&self, url: &Url) -> Result<TransportResponse, Error> {
+        self.requests.lock().unwrap().push(url.clone());
+
+        // Find the next response
+        let res: MockResponse = {
+            let mut responses = self.responses.lock().unwrap();
+            match responses.get_mut(url) {
+                Some(queue) if queue.len() > 1 => queue.pop_front().unwrap(),
+                Some(queue) if !queue.is_empty() => queue[0].clone(),
+                _ => return Err(Error::Transport { url: url.as_str().into(), err: format!("no response scripted for '{url}'").into() }),
+            }
+        };
+
+        // Return it
+        if let Some(delay) = res.delay {
+            thread::sleep(delay);
+        }
+        if let Some(failure) = res.failure {
+            return Err(Error::Transport { url: url.as_str().into(), err: failure.into() });
+        }
+        Ok(TransportResponse {
+            status: res.status,
+            headers: res.headers,
+            body: Box::new(MockBody { body: res.body, pos: 0, chunk_size: res.chunk_size, fail_after: res.fail_after }),
+            peer_certificate: res.peer_certificate,
+        })
+    }
+}
+
+
+
+
+/// Defines what a [`TestServer`] serves as the body of a [`Route`].
+#[derive(Clone, Debug)]
+pub enum RouteBody {
+    /// Serves the given bytes.
+    Bytes(Vec<u8>),
+    /// Serves the contents of the given file, as read when the request comes in (or a `404 Not Found` if it doesn't exist).
+    File(PathBuf),
+}
+
+/// Defines a response served by a [`TestServer`] for some path.
+#[derive(Clone, Debug)]
+pub struct Route {
+    /// The status code to return.
+    pub status: StatusCode,
+    /// Any extra headers to return.
+    pub headers: Vec<(String, String)>,
+    /// The body to return.
+    pub body: RouteBody,
+    /// If not `None`, returns this (quoted) ETag and answers matching `If-None-Match`-requests with `304 Not Modified`.
+    pub etag: Option<String>,
+    /// Whether to support `Range`-requests (with `206 Partial Content` and `416 Range Not Satisfiable`).
+    pub ranges: bool,
+    /// Whether to announce the length of the body in a `Content-Length`-header. If not, the end of the body is marked by closing the connection.
+    pub content_length: bool,
+    /// Whether to answer HEAD-requests. If not, they are answered with `405 Method Not Allowed`.
+    pub head: bool,
+    /// The number of bytes to send at once.
+    pub chunk_size: usize,
+    /// If not `None`, waits this long before sending every chunk.
+    pub chunk_delay: Option<Duration>,
+    /// If not `None`, stops sending after this many bytes of the body, but keeps the connection open until the client gives up.
+    pub stall_after: Option<usize>,
+    /// If not `None`, closes the connection after this many bytes of the body.
+    pub drop_after: Option<usize>,
+}
+impl Route {
+    /// Constructor for a Route that serves the given bytes with a `200 OK`.
+    ///
+    /// # Arguments
+    /// - `body`: The body to serve.
+    ///
+    /// # Returns
+    /// A new Route serving the given body.
+    #[inline]
+    pub fn bytes(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: Vec::new(),
+            body: RouteBody::Bytes(body.into()),
+            etag: None,
+            ranges: false,
+            content_length: true,
+            head: true,
+            chunk_size: 65535,
+            chunk_delay: None,
+            stall_after: None,
+            drop_after: None,
+        }
+    }
+
+    /// Constructor for a Route that serves the given file with a `200 OK`.
+    ///
+    /// # Arguments
+    /// - `path`: The path of the file to serve. It is read when a request comes in.
+    ///
+    /// # Returns
+    /// A new Route serving the given file.
+    #[inline]
+    pub fn file(path: impl Into<PathBuf>) -> Self { Self { body: RouteBody::File(path.into()), ..Self::bytes(Vec::new()) } }
+
+    /// Constructor for a Route that returns the given status code with an empty body.
+    ///
+    /// # Arguments
+    /// - `status`: The [`StatusCode`] to return.
+    ///
+    /// # Returns
+    /// A new Route serving the given status code.
+    #[inline]
+    pub fn status(status: StatusCode) -> Self { Self::bytes(Vec::new()).with_status(status) }
+
+    /// Constructor for a Route that redirects to somewhere else.
+    ///
+    /// # Arguments
+    /// - `status`: The [`StatusCode`] to redirect with (e.g., [`StatusCode::FOUND`]).
+    /// - `location`: The (possibly relative) URL to redirect to.
+    ///
+    /// # Returns
+    /// A new Route that redirects to the given location.
+    #[inline]
+    pub fn redirect(status: StatusCode, location: impl Into<String>) -> Self { Self::status(status).with_header("Location", location) }
+
+    /// Changes the status code of the response.
+    ///
+    /// # Arguments
+    /// - `status`: The [`StatusCode`] to return.
+    ///
+    /// # Returns
+    /// The same Route but with the new status code.
+    #[inline]
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a header to the response.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the header.
+    /// - `value`: The value of the header.
+    ///
+    /// # Returns
+    /// The same Route but with the extra header.
+    #[inline]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Returns an ETag, and answers requests for it with `If-None-Match` with `304 Not Modified`.
+    ///
+    /// # Arguments
+    /// - `etag`: The ETag to return, including quotes (e.g., `"v1"`).
+    ///
+    /// # Returns
+    /// The same Route but with an ETag.
+    #[inline]
+    pub fn with_etag(mut self, etag: impl Into<String>) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Supports single `Range`-requests (e.g., `Range: bytes=100-`), honouring `If-Range` if an ETag is given.
+    ///
+    /// # Returns
+    /// The same Route but with range support.
+    #[inline]
+    pub fn with_ranges(mut self) -> Self {
+        self.ranges = true;
+        self
+    }
+
+    /// Doesn't announce the length of the body, but marks its end by closing the connection.
+    ///
+    /// # Returns
+    /// The same Route but without a `Content-Length`-header.
+    #[inline]
+    pub fn without_content_length(mut self) -> Self {
+        self.content_length = false;
+        self
+    }
+
+    /// Refuses HEAD-requests with `405 Method Not Allowed`, like some servers (e.g., for pre-signed URLs) do.
+    ///
+    /// # Returns
+    /// The same Route but without HEAD support.
+    #[inline]
+    pub fn without_head(mut self) -> Self {
+        self.head = false;
+        self
+    }
+
+    /// Sends the body slowly, in chunks with a delay before each of them.
+    ///
+    /// # Arguments
+    /// - `chunk_size`: The number of bytes to send at once.
+    /// - `delay`: How long to wait before sending every chunk.
+    ///
+    /// # Returns
+    /// The same Route but with a slow body.
+    #[inline]
+    pub fn with_slow_body(mut self, chunk_size: usize, delay: Duration) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self.chunk_delay = Some(delay);
+        self
+    }
+
+    /// Stops sending the body after some bytes, but keeps the connection open until the client gives up.
+    ///
+    /// # Arguments
+    /// - `stall_after`: The number of bytes of the body to send first.
+    ///
+    /// # Returns
+    /// The same Route but with a stalling body.
+    #[inline]
+    pub fn with_stall_after(mut self, stall_after: usize) -> Self {
+        self.stall_after = Some(stall_after);
+        self
+    }
+
+    /// Closes the connection after some bytes of the body, e.g., as if the server crashed halfway through.
+    ///
+    /// # Arguments
+    /// - `drop_after`: The number of bytes of the body to send first.
+    ///
+    /// # Returns
+    /// The same Route but with a dropped connection.
+    #[inline]
+    pub fn with_drop_after(mut self, drop_after: usize) -> Self {
+        self.drop_after = Some(drop_after);
+        self
+    }
+}
+
+/// Describes a request received by a [`TestServer`].
+#[derive(Clone, Debug)]
+pub struct ServerRequest {
+    /// The method of the request (e.g., `GET`).
+    pub method:  String,
+    /// The path (and query) of the request (e.g., `/file.txt`).
+    pub path:    String,
+    /// The headers of the request, in order.
+    pub headers: Vec<(String, String)>,
+}
+impl ServerRequest {
+    /// Returns the value of the given header, if it was sent.
+    ///
+    /// # Arguments
+    /// - `name`: The (case-insensitive) name of the header.
+    ///
+    /// # Returns
+    /// The value of the first header with that name, or [`None`] if there is no such header.
+    #[inline]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Implements a tiny HTTP/1.1 server on `127.0.0.1`, for use in tests.
+///
+/// Every path serves a [`Route`], or `404 Not Found` if it has none. Every connection is handled in its own thread and closed after a single response.
+/// The server shuts down when it is dropped.
+///
+/// # Example
+/// ```rust
+/// use download::testing::{Route, StatusCode, TestServer};
+/// use download::{download_file, DownloadSecurity};
+///
+/// let server = TestServer::spawn()
+///     .unwrap()
+///     .with_route("/old", Route::redirect(StatusCode::FOUND, "/new"))
+///     .with_route("/new", Route::bytes("Hello there!\n"));
+///
+/// let file = std::env::temp_dir().join("example_test_server.txt");
+/// download_file(server.url("/old"), &file, DownloadSecurity::none(), None).unwrap();
+/// assert_eq!(std::fs::read_to_string(&file).unwrap(), "Hello there!\n");
+/// assert_eq!(server.requests().len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct TestServer {
+    /// The address the server listens on.
+    addr:     SocketAddr,
+    /// The routes served by the server, per path.
+    routes:   Arc<Mutex<HashMap<String, Route>>>,
+    /// The requests received so far.
+    requests: Arc<Mutex<Vec<ServerRequest>>>,
+    /// Set to stop the server.
+    shutdown: Arc<AtomicBool>,
+}
+impl TestServer {
+    /// Starts a new TestServer on a random port without any routes.
+    ///
+    /// # Returns
+    /// A new TestServer that runs in the background until it is dropped.
+    ///
+    /// # Errors
+    /// This function errors if we failed to bind to a port on `127.0.0.1`.
+    pub fn spawn() -> Result<Self, io::Error> {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0")?;
+        let addr: SocketAddr = listener.local_addr()?;
+        let server: Self = Self {
+            addr,
+            routes: Arc::new(Mutex::new(HashMap::new())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        };
+
+        // Serve in a separate thread
+        let (routes, requests, shutdown) = (server.routes.clone(), server.requests.clone(), server.shutdown.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let (routes, requests, shutdown) = (routes.clone(), requests.clone(), shutdown.clone());
+                thread::spawn(move || {
+                    if let Err(_err) = handle_connection(stream, &routes, &requests, &shutdown) {
+                        debug!("Test server failed to handle connection: {}", _err);
+                    }
+                });
+            }
+        });
+        Ok(server)
+    }
+
+    /// Serves the given route at the given path, replacing any route already there.
+    ///
+    /// # Arguments
+    /// - `path`: The path (and query) to serve the route at (e.g., `/file.txt`).
+    /// - `route`: The [`Route`] to serve.
+    ///
+    /// # Returns
+    /// The same TestServer but with the route.
+    #[inline]
+    pub fn with_route(self, path: impl Into<String>, route: Route) -> Self {
+        self.set_route(path, route);
+        self
+    }
+
+    /// Serves the given route at the given path, replacing any route already there.
+    ///
+    /// Unlike [`TestServer::with_route()`], this can be used while the server is shared, e.g., to change a file in between downloads.
+    ///
+    /// # Arguments
+    /// - `path`: The path (and query) to serve the route at (e.g., `/file.txt`).
+    /// - `route`: The [`Route`] to serve.
+    #[inline]
+    pub fn set_route(&self, path: impl Into<String>, route: Route) { self.routes.lock().unwrap().insert(path.into(), route); }
+
+    /// Returns the address the server listens on.
+    ///
+    /// # Returns
+    /// A [`SocketAddr`] on `127.0.0.1`.
+    #[inline]
+    pub fn addr(&self) -> SocketAddr { self.addr }
+
+    /// Returns the URL of the given path on this server.
+    ///
+    /// # Arguments
+    /// - `path`: The path (and query) to get the URL of (e.g., `/file.txt`).
+    ///
+    /// # Returns
+    /// An `http://`-URL.
+    #[inline]
+    pub fn url(&self, path: &str) -> String { format!("http://{}{}", self.addr, path) }
+
+    /// Returns the requests received so far, in order.
+    ///
+    /// # Returns
+    /// A list of [`ServerRequest`]s.
+    #[inline]
+    pub fn requests(&self) -> Vec<ServerRequest> { self.requests.lock().unwrap().clone() }
+}
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // Wake up the listener so it notices
+        let _ = TcpStream::connect(self.addr);
+    }
+}
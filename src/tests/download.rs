@@ -0,0 +1,572 @@
+//  DOWNLOAD.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 18:32:50
+//  Last edited:
+//    18 Oct 2026, 18:32:50
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines some (lengthier) unit tests for the download functions,
+//!   running against a local [`crate::testing::TestServer`].
+//
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use sha2::{Digest as _, Sha256};
+use tempfile::TempDir;
+
+use crate::testing::{Route, TestServer};
+use crate::*;
+
+
+/***** CONSTANTS *****/
+/// The body served by the test servers.
+const BODY: &[u8] = b"Hello there!\nGeneral Kenobi...\n...you are a bold one\n";
+
+
+
+
+
+/***** HELPERS *****/
+/// Spawns a [`TestServer`] that serves [`BODY`] at `/file`.
+///
+/// # Returns
+/// A new [`TestServer`].
+fn spawn_server() -> TestServer {
+    TestServer::spawn().unwrap_or_else(|err| panic!("Failed to spawn test server: {err}")).with_route("/file", Route::bytes(BODY))
+}
+
+/// Runs a download from the given URL with the given security, in either sync or async mode.
+///
+/// # Arguments
+/// - `url`: The URL to download from.
+/// - `security`: The [`DownloadSecurity`] to download with.
+/// - `use_async`: Whether to use [`download_file_async()`] instead of [`download_file()`].
+///
+/// # Returns
+/// The result of the download, and the downloaded file's contents if it succeeded.
+fn download(url: &str, security: DownloadSecurity, use_async: bool) -> Result<Vec<u8>, Error> {
+    let tempdir: TempDir = TempDir::new().unwrap_or_else(|err| panic!("Failed to create a temporary directory: {err}"));
+    let target: PathBuf = tempdir.path().join("file");
+
+    #[cfg(feature = "async-tokio")]
+    if use_async {
+        tokio_test::block_on(download_file_async(url, &target, security, None))?;
+        return Ok(fs::read(&target).unwrap_or_else(|err| panic!("Failed to read downloaded file '{}': {}", target.display(), err)));
+    }
+    #[cfg(not(feature = "async-tokio"))]
+    let _ = use_async;
+    download_file(url, &target, security, None)?;
+    Ok(fs::read(&target).unwrap_or_else(|err| panic!("Failed to read downloaded file '{}': {}", target.display(), err)))
+}
+
+
+
+
+
+/// Lists the names of the files in the given directory.
+///
+/// # Arguments
+/// - `dir`: The directory to list.
+///
+/// # Returns
+/// The names of its entries, sorted.
+fn list_dir(dir: &std::path::Path) -> Vec<String> {
+    let mut entries: Vec<String> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    entries.sort();
+    entries
+}
+
+
+
+
+
+/***** TESTS *****/
+/// Test whether a plain download works, in both sync and async mode.
+#[test]
+fn test_download() {
+    let server: TestServer = spawn_server();
+    let checksum: [u8; 32] = Sha256::digest(BODY).into();
+    assert_eq!(download(&server.url("/file"), DownloadSecurity::checksum(&checksum), false).unwrap(), BODY);
+    #[cfg(feature = "async-tokio")]
+    assert_eq!(download(&server.url("/file"), DownloadSecurity::checksum(&checksum), true).unwrap(), BODY);
+}
+
+/// Test whether files on disk are served, and whether a wrong checksum is caught.
+#[test]
+fn test_download_file_checksum() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    fs::write(tempdir.path().join("served.txt"), BODY).unwrap();
+    let server: TestServer = spawn_server().with_route("/served.txt", Route::file(tempdir.path().join("served.txt")));
+
+    assert_eq!(download(&server.url("/served.txt"), DownloadSecurity::none(), false).unwrap(), BODY);
+    match download(&server.url("/served.txt"), DownloadSecurity::checksum(&[0; 32]), false) {
+        Err(Error::SecurityChecksum { got, .. }) => assert_eq!(got, hex::encode(Sha256::digest(BODY))),
+        got => panic!("Expected wrong checksum to fail with SecurityChecksum, got {got:?}"),
+    }
+}
+
+/// Test whether non-OK responses are reported.
+#[test]
+fn test_download_not_ok() {
+    let server: TestServer = spawn_server().with_route("/teapot", Route::bytes("I'm a teapot").with_status(StatusCode::IM_A_TEAPOT));
+    match download(&server.url("/teapot"), DownloadSecurity::none(), false) {
+        Err(Error::ResponseNotOk { code, response, .. }) => {
+            assert_eq!(code, StatusCode::IM_A_TEAPOT);
+            assert_eq!(response.as_deref(), Some("I'm a teapot"));
+        },
+        got => panic!("Expected 418 to fail with ResponseNotOk, got {got:?}"),
+    }
+    match download(&server.url("/missing"), DownloadSecurity::none(), false) {
+        Err(Error::ResponseNotOk { code, .. }) => assert_eq!(code, StatusCode::NOT_FOUND),
+        got => panic!("Expected unknown path to fail with ResponseNotOk, got {got:?}"),
+    }
+}
+
+/// Test whether redirects are followed as the policy allows.
+#[test]
+fn test_download_redirects() {
+    let server: TestServer = spawn_server()
+        .with_route("/a", Route::redirect(StatusCode::MOVED_PERMANENTLY, "/b"))
+        .with_route("/b", Route::redirect(StatusCode::TEMPORARY_REDIRECT, "b2"))
+        .with_route("/b2", Route::redirect(StatusCode::PERMANENT_REDIRECT, "/file"));
+
+    assert_eq!(download(&server.url("/a"), DownloadSecurity::none(), false).unwrap(), BODY);
+    #[cfg(feature = "async-tokio")]
+    assert_eq!(download(&server.url("/a"), DownloadSecurity::none(), true).unwrap(), BODY);
+    match download(&server.url("/a"), DownloadSecurity::none().with_redirects(RedirectPolicy::limited(2)), false) {
+        Err(Error::RedirectNotAllowed { url, chain, reason: RedirectViolation::TooManyHops { max: 2 } }) => {
+            assert_eq!(url, server.url("/file"));
+            assert_eq!(chain, vec![server.url("/a"), server.url("/b"), server.url("/b2")]);
+        },
+        got => panic!("Expected third redirect to fail with RedirectNotAllowed, got {got:?}"),
+    }
+
+    // The server is HTTP-only, so we shouldn't even try with HTTPS enabled
+    let before: usize = server.requests().len();
+    assert!(matches!(download(&server.url("/a"), DownloadSecurity::https(), false), Err(Error::SecurityNoHttps { .. })));
+    assert_eq!(server.requests().len(), before);
+}
+
+/// Test whether slow bodies are still downloaded completely.
+#[test]
+fn test_download_slow() {
+    let server: TestServer = spawn_server().with_route("/slow", Route::bytes(BODY).with_slow_body(8, Duration::from_millis(10)));
+    assert_eq!(download(&server.url("/slow"), DownloadSecurity::none(), false).unwrap(), BODY);
+    #[cfg(feature = "async-tokio")]
+    assert_eq!(download(&server.url("/slow"), DownloadSecurity::none(), true).unwrap(), BODY);
+}
+
+/// Test whether dropped connections are reported.
+#[test]
+fn test_download_dropped() {
+    let server: TestServer = spawn_server().with_route("/dropped", Route::bytes(BODY).with_drop_after(10));
+    match download(&server.url("/dropped"), DownloadSecurity::none(), false) {
+        Err(Error::ResponseDownload { .. }) => {},
+        got => panic!("Expected dropped connection to fail with ResponseDownload, got {got:?}"),
+    }
+    #[cfg(feature = "async-tokio")]
+    match download(&server.url("/dropped"), DownloadSecurity::none(), true) {
+        Err(Error::ResponseDownloadAsync { .. }) => {},
+        got => panic!("Expected dropped connection to fail with ResponseDownloadAsync, got {got:?}"),
+    }
+}
+
+/// Test whether stalled bodies are reported by a transport with a timeout.
+#[test]
+fn test_download_stalled() {
+    let server: TestServer = spawn_server().with_route("/stalled", Route::bytes(BODY).with_stall_after(10));
+    let client: Client = Client::builder().redirect(Policy::none()).timeout(Duration::from_millis(250)).build().unwrap();
+    let transport: ReqwestTransport = ReqwestTransport::from_client(client);
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    match download_file_with_transport(&transport, server.url("/stalled"), tempdir.path().join("file"), DownloadSecurity::none(), None) {
+        Err(Error::ResponseDownload { .. }) => {},
+        got => panic!("Expected stalled body to fail with ResponseDownload, got {got:?}"),
+    }
+}
+
+/// Test whether the maximum size is enforced for bodies of unknown length.
+#[test]
+fn test_download_too_large() {
+    let server: TestServer = spawn_server().with_route("/streamed", Route::bytes(vec![0; 4096]).without_content_length().with_slow_body(1024, Duration::ZERO));
+    assert_eq!(download(&server.url("/streamed"), DownloadSecurity::none(), false).unwrap().len(), 4096);
+    match download(&server.url("/streamed"), DownloadSecurity::none().with_max_bytes(2048), false) {
+        Err(Error::ResponseTooLarge { content_length: None, max: 2048, .. }) => {},
+        got => panic!("Expected streamed response to fail with ResponseTooLarge, got {got:?}"),
+    }
+    #[cfg(feature = "async-tokio")]
+    match download(&server.url("/streamed"), DownloadSecurity::none().with_max_bytes(2048), true) {
+        Err(Error::ResponseTooLarge { content_length: None, max: 2048, .. }) => {},
+        got => panic!("Expected streamed response to fail with ResponseTooLarge, got {got:?}"),
+    }
+}
+
+/// Test whether `ensure_file()` only downloads when necessary.
+#[test]
+fn test_ensure_file() {
+    let server: TestServer = spawn_server();
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+    let checksum: [u8; 32] = Sha256::digest(BODY).into();
+
+    assert_eq!(ensure_file(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).unwrap(), Ensured::Downloaded);
+    assert_eq!(ensure_file(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).unwrap(), Ensured::AlreadyPresent);
+    assert_eq!(server.requests().len(), 1);
+
+    // A corrupted file is downloaded again
+    fs::write(&target, "General Kenobi!\n").unwrap();
+    assert_eq!(ensure_file(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).unwrap(), Ensured::Downloaded);
+    assert_eq!(fs::read(&target).unwrap(), BODY);
+    assert_eq!(server.requests().len(), 2);
+
+    // So is one without a checksum to compare against
+    assert_eq!(ensure_file(server.url("/file"), &target, DownloadSecurity::none(), None).unwrap(), Ensured::Downloaded);
+    assert_eq!(server.requests().len(), 3);
+}
+
+/// Test whether `ensure_file_async()` only downloads when necessary.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_ensure_file_async() {
+    let server: TestServer = spawn_server();
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+    let checksum: [u8; 32] = Sha256::digest(BODY).into();
+
+    tokio_test::block_on(async {
+        assert_eq!(ensure_file_async(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).await.unwrap(), Ensured::Downloaded);
+        assert_eq!(
+            ensure_file_async(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).await.unwrap(),
+            Ensured::AlreadyPresent
+        );
+    });
+    assert_eq!(server.requests().len(), 1);
+}
+
+/// Test whether the sync and async drivers produce the same results, both when they succeed and when they fail.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_download_drivers_identical() {
+    let server: TestServer = spawn_server()
+        .with_route("/redirect", Route::redirect(StatusCode::FOUND, "/file"))
+        .with_route("/streamed", Route::bytes(vec![42; 4096]).without_content_length().with_slow_body(1024, Duration::ZERO))
+        .with_route("/teapot", Route::bytes("I'm a teapot").with_status(StatusCode::IM_A_TEAPOT));
+    let checksum: [u8; 32] = Sha256::digest(BODY).into();
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+
+    for (path, security) in [
+        ("/file", DownloadSecurity::checksum(&checksum)),
+        ("/redirect", DownloadSecurity::none()),
+        ("/streamed", DownloadSecurity::none()),
+        ("/file", DownloadSecurity::checksum(&[0; 32])),
+        ("/redirect", DownloadSecurity::none().with_redirects(RedirectPolicy::none())),
+        ("/streamed", DownloadSecurity::none().with_max_bytes(2048)),
+        ("/teapot", DownloadSecurity::none()),
+        ("/file", DownloadSecurity::https()),
+    ] {
+        let url: String = server.url(path);
+        let sync: Result<Vec<u8>, Error> = download_file(&url, &target, security.clone(), None).map(|_| fs::read(&target).unwrap());
+        let asyn: Result<Vec<u8>, Error> =
+            tokio_test::block_on(download_file_async(&url, &target, security, None)).map(|_| fs::read(&target).unwrap());
+        match (sync, asyn) {
+            (Ok(sync), Ok(asyn)) => assert_eq!(sync, asyn, "Drivers downloaded different contents from '{url}'"),
+            (Err(sync), Err(asyn)) => assert_eq!(sync.to_string(), asyn.to_string(), "Drivers failed differently for '{url}'"),
+            (sync, asyn) => panic!("Drivers disagree for '{url}': sync gave {sync:?}, async gave {asyn:?}"),
+        }
+    }
+}
+
+/// Test whether the `async-smol` functions behave like the sync ones.
+#[cfg(feature = "async-smol")]
+#[test]
+fn test_download_smol() {
+    let server: TestServer = spawn_server()
+        .with_route("/redirect", Route::redirect(StatusCode::FOUND, "/file"))
+        .with_route("/streamed", Route::bytes(vec![42; 4096]).without_content_length().with_slow_body(1024, Duration::ZERO))
+        .with_route("/teapot", Route::bytes("I'm a teapot").with_status(StatusCode::IM_A_TEAPOT));
+    let checksum: [u8; 32] = Sha256::digest(BODY).into();
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+
+    for (path, security) in [
+        ("/file", DownloadSecurity::checksum(&checksum)),
+        ("/redirect", DownloadSecurity::none()),
+        ("/streamed", DownloadSecurity::none()),
+        ("/file", DownloadSecurity::checksum(&[0; 32])),
+        ("/redirect", DownloadSecurity::none().with_redirects(RedirectPolicy::none())),
+        ("/streamed", DownloadSecurity::none().with_max_bytes(2048)),
+        ("/teapot", DownloadSecurity::none()),
+        ("/file", DownloadSecurity::https()),
+    ] {
+        let url: String = server.url(path);
+        let sync: Result<Vec<u8>, Error> = download_file(&url, &target, security.clone(), None).map(|_| fs::read(&target).unwrap());
+        let smol: Result<Vec<u8>, Error> =
+            futures_lite::future::block_on(download_file_smol(&url, &target, security, None)).map(|_| fs::read(&target).unwrap());
+        match (sync, smol) {
+            (Ok(sync), Ok(smol)) => assert_eq!(sync, smol, "Drivers downloaded different contents from '{url}'"),
+            (Err(sync), Err(smol)) => assert_eq!(sync.to_string(), smol.to_string(), "Drivers failed differently for '{url}'"),
+            (sync, smol) => panic!("Drivers disagree for '{url}': sync gave {sync:?}, smol gave {smol:?}"),
+        }
+    }
+
+    // Ensuring and verifying work too
+    let target: PathBuf = tempdir.path().join("ensured");
+    futures_lite::future::block_on(async {
+        assert_eq!(ensure_file_smol(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).await.unwrap(), Ensured::Downloaded);
+        assert_eq!(ensure_file_smol(server.url("/file"), &target, DownloadSecurity::checksum(&checksum), None).await.unwrap(), Ensured::AlreadyPresent);
+        verify_file_smol(&target, &checksum, None).await.unwrap();
+        assert!(matches!(verify_file_smol(&target, &[0; 32], None).await, Err(Error::SecurityChecksum { .. })));
+    });
+}
+
+/// Test whether the mode and modification time are set, and whether downloads only ever appear at the target when complete.
+#[test]
+fn test_download_metadata() {
+    let modified: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777);
+    let server: TestServer = spawn_server().with_route("/dated", Route::bytes(BODY).with_header("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT"));
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+
+    #[allow(unused_mut)]
+    let mut security: DownloadSecurity = DownloadSecurity::none().with_last_modified();
+    #[cfg(unix)]
+    {
+        security = security.with_mode(0o751);
+    }
+    type Driver<'t> = Box<dyn Fn(&str, DownloadSecurity) -> Result<(), Error> + 't>;
+    #[allow(unused_mut)]
+    let mut drivers: Vec<Driver> = vec![Box::new(|url, security| download_file(url, &target, security, None))];
+    #[cfg(feature = "async-tokio")]
+    drivers.push(Box::new(|url, security| tokio_test::block_on(download_file_async(url, &target, security, None))));
+    #[cfg(feature = "async-smol")]
+    drivers.push(Box::new(|url, security| futures_lite::future::block_on(download_file_smol(url, &target, security, None))));
+    for download in drivers {
+        // The metadata is set...
+        download(&server.url("/dated"), security.clone()).unwrap();
+        let metadata: fs::Metadata = fs::metadata(&target).unwrap();
+        assert_eq!(metadata.modified().unwrap(), modified);
+        #[cfg(unix)]
+        assert_eq!(std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o777, 0o751);
+        assert_eq!(list_dir(tempdir.path()), ["file"]);
+
+        // ...unless there is none to set
+        download(&server.url("/file"), security.clone()).unwrap();
+        assert!(fs::metadata(&target).unwrap().modified().unwrap() > modified);
+
+        // Failed downloads leave the existing target alone
+        fs::write(&target, "General Kenobi!\n").unwrap();
+        assert!(matches!(download(&server.url("/file"), DownloadSecurity::checksum(&[0; 32])), Err(Error::SecurityChecksum { .. })));
+        assert!(matches!(download(&server.url("/missing"), DownloadSecurity::none()), Err(Error::ResponseNotOk { .. })));
+        assert_eq!(fs::read_to_string(&target).unwrap(), "General Kenobi!\n");
+        assert_eq!(list_dir(tempdir.path()), ["file"]);
+        fs::remove_file(&target).unwrap();
+    }
+}
+
+/// Test whether downloads never touch an existing `<target>.part`, be it a file or a symlink.
+#[test]
+fn test_download_partial_exists() {
+    let server: TestServer = spawn_server();
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+    let outside: PathBuf = tempdir.path().join("outside");
+    fs::write(&outside, "Do not touch\n").unwrap();
+
+    type Driver<'t> = Box<dyn Fn(&str) -> Result<(), Error> + 't>;
+    #[allow(unused_mut)]
+    let mut drivers: Vec<Driver> = vec![Box::new(|url| download_file(url, &target, DownloadSecurity::none(), None))];
+    #[cfg(feature = "async-tokio")]
+    drivers.push(Box::new(|url| tokio_test::block_on(download_file_async(url, &target, DownloadSecurity::none(), None))));
+    #[cfg(feature = "async-smol")]
+    drivers.push(Box::new(|url| futures_lite::future::block_on(download_file_smol(url, &target, DownloadSecurity::none(), None))));
+    for download in drivers {
+        // An unrelated file with the same name is left alone...
+        fs::write(tempdir.path().join("file.part"), "Mine\n").unwrap();
+        download(&server.url("/file")).unwrap();
+        assert_eq!(fs::read(&target).unwrap(), BODY);
+        assert_eq!(fs::read_to_string(tempdir.path().join("file.part")).unwrap(), "Mine\n");
+        assert_eq!(list_dir(tempdir.path()), ["file", "file.part", "outside"]);
+        fs::remove_file(tempdir.path().join("file.part")).unwrap();
+        fs::remove_file(&target).unwrap();
+
+        // ...and so is a symlink (and what it points to)
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, tempdir.path().join("file.part")).unwrap();
+            download(&server.url("/file")).unwrap();
+            assert_eq!(fs::read(&target).unwrap(), BODY);
+            assert_eq!(fs::read_link(tempdir.path().join("file.part")).unwrap(), outside);
+            assert_eq!(list_dir(tempdir.path()), ["file", "file.part", "outside"]);
+            fs::remove_file(tempdir.path().join("file.part")).unwrap();
+            fs::remove_file(&target).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "Do not touch\n");
+    }
+}
+
+/// Test whether probing reports what the server tells us, falling back to ranged GET-requests when HEAD-requests are refused.
+#[test]
+fn test_probe() {
+    let modified: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777);
+    let server: TestServer = spawn_server()
+        .with_route(
+            "/described",
+            Route::bytes(BODY)
+                .with_ranges()
+                .with_etag("\"v1\"")
+                .with_header("Content-Type", "text/plain")
+                .with_header("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT"),
+        )
+        .with_route("/headless", Route::bytes(BODY).with_ranges().without_head())
+        .with_route("/headless-full", Route::bytes(BODY).without_head())
+        .with_route("/redirect", Route::redirect(StatusCode::FOUND, "/described"));
+
+    // Probes with every driver, asserting they agree
+    let probe = |path: &str, security: DownloadSecurity| -> Result<Probe, Error> {
+        let url: String = server.url(path);
+        let sync: Result<Probe, Error> = crate::probe(&url, security.clone());
+        #[cfg(feature = "async-tokio")]
+        match (&sync, tokio_test::block_on(probe_async(&url, security.clone()))) {
+            (Ok(sync), Ok(other)) => assert_eq!(sync, &other, "Drivers disagree on '{url}'"),
+            (Err(sync), Err(other)) => assert_eq!(sync.to_string(), other.to_string(), "Drivers failed differently for '{url}'"),
+            (sync, other) => panic!("Drivers disagree for '{url}': sync gave {sync:?}, async gave {other:?}"),
+        }
+        #[cfg(feature = "async-smol")]
+        match (&sync, futures_lite::future::block_on(probe_smol(&url, security.clone()))) {
+            (Ok(sync), Ok(other)) => assert_eq!(sync, &other, "Drivers disagree on '{url}'"),
+            (Err(sync), Err(other)) => assert_eq!(sync.to_string(), other.to_string(), "Drivers failed differently for '{url}'"),
+            (sync, other) => panic!("Drivers disagree for '{url}': sync gave {sync:?}, smol gave {other:?}"),
+        }
+        let _ = security;
+        sync
+    };
+
+    // A HEAD-request tells us everything
+    let before: usize = server.requests().len();
+    let got: Probe = probe("/described", DownloadSecurity::none()).unwrap();
+    assert!(got.exists());
+    assert_eq!(got.url.as_str(), server.url("/described"));
+    assert_eq!(got.content_length, Some(BODY.len() as u64));
+    assert_eq!(got.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(got.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(got.last_modified, Some(modified));
+    assert!(got.ranges);
+    assert!(server.requests()[before..].iter().all(|req| req.method == "HEAD"));
+
+    // If that's refused, a ranged GET-request does
+    let got: Probe = probe("/headless", DownloadSecurity::none()).unwrap();
+    assert_eq!(got.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(got.content_length, Some(BODY.len() as u64));
+    assert!(got.ranges);
+    let last = server.requests().pop().unwrap();
+    assert_eq!((last.method.as_str(), last.header("Range")), ("GET", Some("bytes=0-0")));
+    let got: Probe = probe("/headless-full", DownloadSecurity::none()).unwrap();
+    assert_eq!(got.status, StatusCode::OK);
+    assert_eq!(got.content_length, Some(BODY.len() as u64));
+    assert!(!got.ranges);
+
+    // Redirects are followed, but missing files are not errors
+    assert_eq!(probe("/redirect", DownloadSecurity::none()).unwrap().url.as_str(), server.url("/described"));
+    let got: Probe = probe("/missing", DownloadSecurity::none()).unwrap();
+    assert!(!got.exists());
+    assert_eq!((got.status, got.content_length), (StatusCode::NOT_FOUND, None));
+
+    // The security policy still applies
+    assert!(matches!(
+        probe("/redirect", DownloadSecurity::none().with_redirects(RedirectPolicy::none())),
+        Err(Error::RedirectNotAllowed { reason: RedirectViolation::TooManyHops { max: 0 }, .. })
+    ));
+    assert!(matches!(probe("/described", DownloadSecurity::https()), Err(Error::SecurityNoHttps { .. })));
+}
+
+/// Test whether the content check refuses HTML pages and bodies with the wrong magic, showing what we got instead.
+#[test]
+fn test_download_content() {
+    let page: &str = "\n<!DOCTYPE html>\n<html><body>\x1b[31mPlease log in\x07</body></html>\n";
+    let gzip: Vec<u8> = [&[0x1F, 0x8B, 0x08][..], &[42; 1024]].concat();
+    let server: TestServer = spawn_server()
+        .with_route("/page", Route::bytes(page).with_header("Content-Type", "text/html; charset=utf-8"))
+        .with_route("/untyped-page", Route::bytes(page))
+        .with_route("/gzip", Route::bytes(gzip.clone()).with_header("Content-Type", "application/gzip"));
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.tar.gz");
+
+    // HTML is caught by its content type or its first bytes, with a sanitized snippet
+    for path in ["/page", "/untyped-page"] {
+        match download_file(server.url(path), &target, DownloadSecurity::none().with_content(ContentCheck::gzip()), None) {
+            Err(Error::UnexpectedContent { reason: ContentViolation::Html, snippet, .. }) => {
+                assert_eq!(snippet, "\n<!DOCTYPE html>\n<html><body>[31mPlease log in</body></html>\n");
+            },
+            got => panic!("Expected HTML from '{path}' to fail with UnexpectedContent, got {got:?}"),
+        }
+        assert!(!target.exists());
+    }
+
+    // The magic and content types are checked too
+    match download_file(server.url("/file"), &target, DownloadSecurity::none().with_content(ContentCheck::gzip()), None) {
+        Err(Error::UnexpectedContent { reason: ContentViolation::Magic { expected, got }, .. }) => {
+            assert_eq!((expected, got), (vec![0x1F, 0x8B], BODY[..2].to_vec()));
+        },
+        got => panic!("Expected non-gzip body to fail with UnexpectedContent, got {got:?}"),
+    }
+    let xz: ContentCheck = ContentCheck::new().with_content_type("application/x-xz");
+    match download_file(server.url("/gzip"), &target, DownloadSecurity::none().with_content(xz), None) {
+        Err(Error::UnexpectedContent { reason: ContentViolation::ContentType { got, .. }, .. }) => assert_eq!(got, "application/gzip"),
+        got => panic!("Expected wrong content type to fail with UnexpectedContent, got {got:?}"),
+    }
+
+    // While the real thing passes
+    let check: ContentCheck = ContentCheck::gzip().with_content_type("application/x-gzip").with_content_type("Application/GZip");
+    assert_eq!(download(&server.url("/gzip"), DownloadSecurity::none().with_content(check.clone()), false).unwrap(), gzip);
+    #[cfg(feature = "async-tokio")]
+    assert_eq!(download(&server.url("/gzip"), DownloadSecurity::none().with_content(check), true).unwrap(), gzip);
+}
+
+/// Test whether the body of non-OK responses is bounded and sanitized, and only shown when asked for.
+#[test]
+fn test_download_error_body() {
+    let body: String = format!("\x1b[1mOops\x1b[0m\r\n{}", "x".repeat(8192));
+    let server: TestServer = spawn_server().with_route(
+        "/error",
+        Route::bytes(body).with_status(StatusCode::INTERNAL_SERVER_ERROR).with_header("Retry-After", "120").with_slow_body(1000, Duration::ZERO),
+    );
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file");
+
+    type Driver<'t> = Box<dyn Fn(&str, DownloadSecurity) -> Result<(), Error> + 't>;
+    #[allow(unused_mut)]
+    let mut drivers: Vec<(&str, Driver)> = vec![("sync", Box::new(|url, security| download_file(url, &target, security, None)))];
+    #[cfg(feature = "async-tokio")]
+    drivers.push(("async", Box::new(|url, security| tokio_test::block_on(download_file_async(url, &target, security, None)))));
+    #[cfg(feature = "async-smol")]
+    drivers.push(("smol", Box::new(|url, security| futures_lite::future::block_on(download_file_smol(url, &target, security, None)))));
+    for (name, driver) in drivers {
+        let err: Error = driver(&server.url("/error"), DownloadSecurity::none().with_max_error_body(16)).unwrap_err();
+        match &err {
+            Error::ResponseNotOk { code, headers, response, .. } => {
+                assert_eq!(*code, StatusCode::INTERNAL_SERVER_ERROR);
+                assert_eq!(headers.get("Retry-After").map(|value| value.as_bytes()), Some(&b"120"[..]), "Wrong headers for {name}");
+                assert_eq!(response.as_deref(), Some("[1mOops[0m\nxx..."), "Wrong response for {name}");
+            },
+            got => panic!("Expected {name} to fail with ResponseNotOk, got {got:?}"),
+        }
+        assert_eq!(err.to_string(), format!("GET-request to '{}' failed with 500 (Internal Server Error)", server.url("/error")));
+        assert!(format!("{err:#}").contains("\n[1mOops[0m\nxx...\n"));
+
+        // Or not read at all
+        match driver(&server.url("/error"), DownloadSecurity::none().with_max_error_body(0)) {
+            Err(Error::ResponseNotOk { response, .. }) => assert_eq!(response, None, "Response read by {name}"),
+            got => panic!("Expected {name} to fail with ResponseNotOk, got {got:?}"),
+        }
+    }
+}
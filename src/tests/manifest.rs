@@ -0,0 +1,387 @@
+//  MANIFEST.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 14:52:03
+//  Last edited:
+//    18 Oct 2026, 14:52:03
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines some (lengthier) unit tests for the [`crate::manifest`]-module.
+//!
+//!   Those that download something do so from a local [`TestServer`](crate::testing::TestServer), such that no network is needed.
+//
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "testing")]
+use reqwest::StatusCode;
+use sha2::{Digest as _, Sha256};
+use tempfile::TempDir;
+
+#[cfg(feature = "testing")]
+use crate::download::{ContentViolation, Error as DownloadError};
+use crate::manifest::*;
+#[cfg(feature = "testing")]
+use crate::testing::{Route, TestServer};
+use crate::Ensured;
+
+
+/***** HELPERS *****/
+/// Creates a `.tar.gz`-artifact with a single file in it.
+///
+/// # Arguments
+/// - `dir`: The (temporary) directory to create the artifact in.
+/// - `contents`: The contents of the file in the artifact (`hello.txt`).
+///
+/// # Returns
+/// The path to the artifact (`dir/artifact.tar.gz`) and its hex-encoded checksum.
+fn create_artifact(dir: &Path, contents: &str) -> (PathBuf, String) {
+    let source: PathBuf = dir.join("payload");
+    fs::create_dir(&source).unwrap_or_else(|err| panic!("Failed to create directory '{}': {}", source.display(), err));
+    fs::write(source.join("hello.txt"), contents).unwrap_or_else(|err| panic!("Failed to write payload: {err}"));
+    let tarball: PathBuf = dir.join("artifact.tar.gz");
+    crate::tar::archive(&source, &tarball, true).unwrap_or_else(|err| panic!("Failed to archive payload: {err}"));
+    let checksum: String = hex::encode(Sha256::digest(fs::read(&tarball).unwrap()));
+    (tarball, checksum)
+}
+
+/// Lists the names in a directory, sorted.
+///
+/// # Arguments
+/// - `dir`: The directory to list.
+///
+/// # Returns
+/// The names of the files and directories in `dir`.
+#[cfg(feature = "testing")]
+fn list_dir(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Failed to read directory '{}': {}", dir.display(), err))
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Spawns a [`TestServer`] to download artifacts from.
+///
+/// # Returns
+/// A new TestServer without any routes.
+#[cfg(feature = "testing")]
+fn spawn_server() -> TestServer { TestServer::spawn().unwrap_or_else(|err| panic!("Failed to spawn test server: {err}")) }
+
+
+
+/***** TESTS *****/
+/// Test whether TOML and JSON manifests are parsed the same.
+#[test]
+fn test_manifest_parse() {
+    let toml: Manifest = Manifest::from_toml_str(
+        r#"
+        [artifacts.foo]
+        url = "https://example.com/foo.tar.gz"
+        sha256 = "0000000000000000000000000000000000000000000000000000000000000000"
+        target = "foo.tar.gz"
+        extract = "foo"
+        https = true
+
+        [artifacts.bar]
+        url = "http://example.com/bar"
+        target = "/tmp/bar"
+        "#,
+        "/some/dir/artifacts.toml",
+    )
+    .unwrap();
+    let json: Manifest = Manifest::from_json_str(
+        r#"{ "artifacts": {
+            "foo": {
+                "url": "https://example.com/foo.tar.gz",
+                "sha256": "0000000000000000000000000000000000000000000000000000000000000000",
+                "target": "foo.tar.gz",
+                "extract": "foo",
+                "https": true
+            },
+            "bar": { "url": "http://example.com/bar", "target": "/tmp/bar" }
+        } }"#,
+        "/some/dir/artifacts.json",
+    )
+    .unwrap();
+    assert_eq!(toml.artifacts, json.artifacts);
+    assert_eq!(toml.root, PathBuf::from("/some/dir"));
+    assert_eq!(toml.lockfile, PathBuf::from("/some/dir/artifacts.lock"));
+    assert_eq!(toml.resolve(&toml.artifacts["foo"].target), PathBuf::from("/some/dir/foo.tar.gz"));
+    assert_eq!(toml.resolve(&toml.artifacts["bar"].target), PathBuf::from("/tmp/bar"));
+    assert!(!toml.artifacts["bar"].https);
+    assert_eq!(toml.artifacts["bar"].sha256, None);
+}
+
+/// Test whether typos in manifests are caught.
+#[test]
+fn test_manifest_unknown_field() {
+    match Manifest::from_toml_str("[artifacts.foo]\nurl = \"https://example.com\"\ntarget = \"foo\"\nsha265 = \"00\"\n", "artifacts.toml") {
+        Err(Error::ManifestParseToml { .. }) => {},
+        got => panic!("Expected unknown field to fail with ManifestParseToml, got {got:?}"),
+    }
+}
+
+/// Test whether unpinned artifacts are refused by `ensure_all()`.
+#[test]
+fn test_ensure_all_unpinned() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let manifest: Manifest =
+        Manifest::from_toml_str("[artifacts.foo]\nurl = \"https://example.com\"\ntarget = \"foo\"\n", tempdir.path().join("artifacts.toml")).unwrap();
+    match ensure_all(&manifest, None) {
+        Err(Error::ArtifactUnpinned { name }) => assert_eq!(name, "foo"),
+        got => panic!("Expected unpinned artifact to fail with ArtifactUnpinned, got {got:?}"),
+    }
+}
+
+/// Test whether an invalid checksum is refused.
+#[test]
+fn test_ensure_all_invalid_checksum() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let manifest: Manifest = Manifest::from_toml_str(
+        "[artifacts.foo]\nurl = \"https://example.com\"\ntarget = \"foo\"\nsha256 = \"deadbeef\"\n",
+        tempdir.path().join("artifacts.toml"),
+    )
+    .unwrap();
+    match ensure_all(&manifest, None) {
+        Err(Error::ArtifactChecksumParse { name, .. }) => assert_eq!(name, "foo"),
+        got => panic!("Expected invalid checksum to fail with ArtifactChecksumParse, got {got:?}"),
+    }
+}
+
+/// Test whether present artifacts are left alone, extracted and recorded in the lockfile.
+#[test]
+fn test_ensure_all_present() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let (_, checksum): (PathBuf, String) = create_artifact(tempdir.path(), "Hello there!\n");
+    let manifest: Manifest = Manifest::from_toml_str(
+        &format!(
+            "[artifacts.foo]\nurl = \"https://example.invalid/foo.tar.gz\"\ntarget = \"artifact.tar.gz\"\nsha256 = \"{checksum}\"\nextract = \
+             \"out/foo\"\n"
+        ),
+        tempdir.path().join("artifacts.toml"),
+    )
+    .unwrap();
+
+    // Ensure it; this should not touch the network
+    let result = ensure_all(&manifest, None).unwrap();
+    assert_eq!(result.get("foo"), Some(&Ensured::AlreadyPresent));
+    assert_eq!(fs::read_to_string(tempdir.path().join("out/foo/hello.txt")).unwrap(), "Hello there!\n");
+
+    // The lockfile now knows about it
+    let lockfile: Lockfile = Lockfile::from_path(&manifest.lockfile).unwrap();
+    assert_eq!(lockfile.artifacts["foo"].url, "https://example.invalid/foo.tar.gz");
+    assert_eq!(lockfile.artifacts["foo"].resolved, None);
+    assert_eq!(lockfile.artifacts["foo"].sha256, checksum);
+
+    // Doing it again is a no-op, even with the extraction in place
+    let result = ensure_all(&manifest, None).unwrap();
+    assert_eq!(result.get("foo"), Some(&Ensured::AlreadyPresent));
+}
+
+/// Test whether the lockfile pins artifacts that the manifest does not.
+#[test]
+fn test_ensure_all_locked() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let (_, checksum): (PathBuf, String) = create_artifact(tempdir.path(), "Hello there!\n");
+    let manifest: Manifest = Manifest::from_toml_str(
+        "[artifacts.foo]\nurl = \"https://example.invalid/foo.tar.gz\"\ntarget = \"artifact.tar.gz\"\n",
+        tempdir.path().join("artifacts.toml"),
+    )
+    .unwrap();
+
+    // Write a lockfile with a stale entry too
+    let mut lockfile: Lockfile = Lockfile::default();
+    lockfile.artifacts.insert("foo".into(), LockedArtifact {
+        url:      "https://example.invalid/foo.tar.gz".into(),
+        resolved: Some("https://mirror.example.invalid/foo.tar.gz".into()),
+        sha256:   checksum.clone(),
+    });
+    lockfile.artifacts.insert("stale".into(), LockedArtifact {
+        url:      "https://example.invalid/stale".into(),
+        resolved: Some("https://example.invalid/stale".into()),
+        sha256:   checksum.clone(),
+    });
+    lockfile.to_path(&manifest.lockfile).unwrap();
+
+    // Locking should not download anything, but should prune the stale entry
+    assert!(lock(&manifest, None).unwrap().is_empty());
+    let lockfile: Lockfile = Lockfile::from_path(&manifest.lockfile).unwrap();
+    assert_eq!(lockfile.artifacts.len(), 1);
+    assert_eq!(lockfile.artifacts["foo"].resolved.as_deref(), Some("https://mirror.example.invalid/foo.tar.gz"));
+
+    // Ensuring should use the lockfile's checksum, and keep what it resolved to
+    let result = ensure_all(&manifest, None).unwrap();
+    assert_eq!(result.get("foo"), Some(&Ensured::AlreadyPresent));
+    let lockfile: Lockfile = Lockfile::from_path(&manifest.lockfile).unwrap();
+    assert_eq!(lockfile.artifacts["foo"].resolved.as_deref(), Some("https://mirror.example.invalid/foo.tar.gz"));
+}
+
+/// Test whether locking downloads unpinned artifacts and records where they were redirected to.
+#[cfg(feature = "testing")]
+#[test]
+fn test_lock_download() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let (tarball, checksum): (PathBuf, String) = create_artifact(tempdir.path(), "Hello there!\n");
+    let server: TestServer = spawn_server()
+        .with_route("/latest", Route::redirect(StatusCode::FOUND, "/foo-1.0.tar.gz"))
+        .with_route("/foo-1.0.tar.gz", Route::file(&tarball));
+    let manifest: Manifest = Manifest::from_toml_str(
+        &format!("[artifacts.foo]\nurl = \"{}\"\ntarget = \"dl/foo.tar.gz\"\nextract = \"out/foo\"\n", server.url("/latest")),
+        tempdir.path().join("artifacts.toml"),
+    )
+    .unwrap();
+
+    // Locking downloads it and records both URLs and the checksum
+    assert_eq!(lock(&manifest, None).unwrap(), vec!["foo".to_string()]);
+    let lockfile: Lockfile = Lockfile::from_path(&manifest.lockfile).unwrap();
+    assert_eq!(lockfile.artifacts["foo"].url, server.url("/latest"));
+    assert_eq!(lockfile.artifacts["foo"].resolved, Some(server.url("/foo-1.0.tar.gz")));
+    assert_eq!(lockfile.artifacts["foo"].sha256, checksum);
+    assert_eq!(fs::read(tempdir.path().join("dl/foo.tar.gz")).unwrap(), fs::read(&tarball).unwrap());
+
+    // Ensuring it afterwards only extracts it, and leaves the lockfile alone
+    let result = ensure_all(&manifest, None).unwrap();
+    assert_eq!(result.get("foo"), Some(&Ensured::AlreadyPresent));
+    assert_eq!(fs::read_to_string(tempdir.path().join("out/foo/hello.txt")).unwrap(), "Hello there!\n");
+    assert_eq!(Lockfile::from_path(&manifest.lockfile).unwrap(), lockfile);
+    assert_eq!(server.requests().iter().filter(|req| req.method == "GET").count(), 2);
+}
+
+/// Test whether artifacts that are re-downloaded have their stale extraction replaced.
+#[cfg(feature = "testing")]
+#[test]
+fn test_ensure_all_redownload() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    fs::create_dir(tempdir.path().join("old")).unwrap();
+    fs::create_dir(tempdir.path().join("new")).unwrap();
+    let (old, _): (PathBuf, String) = create_artifact(&tempdir.path().join("old"), "Hello there!\n");
+    let (new, checksum): (PathBuf, String) = create_artifact(&tempdir.path().join("new"), "General Kenobi!\n");
+    let server: TestServer = spawn_server().with_route("/foo.tar.gz", Route::file(&new));
+    let manifest: Manifest = Manifest::from_toml_str(
+        &format!(
+            "[artifacts.foo]\nurl = \"{}\"\ntarget = \"foo.tar.gz\"\nsha256 = \"{}\"\nextract = \"out\"\n",
+            server.url("/foo.tar.gz"),
+            checksum
+        ),
+        tempdir.path().join("artifacts.toml"),
+    )
+    .unwrap();
+
+    // Pretend an older version was downloaded and extracted before
+    fs::copy(&old, tempdir.path().join("foo.tar.gz")).unwrap();
+    crate::tar::unarchive(&old, tempdir.path().join("out")).unwrap();
+    fs::write(tempdir.path().join("out/stale.txt"), "Leftover\n").unwrap();
+
+    // Ensuring it replaces both the download and its extraction
+    let result = ensure_all(&manifest, None).unwrap();
+    assert_eq!(result.get("foo"), Some(&Ensured::Downloaded));
+    assert_eq!(fs::read_to_string(tempdir.path().join("out/hello.txt")).unwrap(), "General Kenobi!\n");
+    assert!(!tempdir.path().join("out/stale.txt").exists());
+    let lockfile: Lockfile = Lockfile::from_path(&manifest.lockfile).unwrap();
+    assert_eq!(lockfile.artifacts["foo"].resolved, Some(server.url("/foo.tar.gz")));
+    assert_eq!(lockfile.artifacts["foo"].sha256, checksum);
+    assert_eq!(list_dir(tempdir.path()), ["artifacts.lock", "foo.tar.gz", "new", "old", "out"]);
+}
+
+/// Test whether HTML pages are refused for artifacts that are extracted.
+#[cfg(feature = "testing")]
+#[test]
+fn test_ensure_all_html() {
+    let page: &str = "<!DOCTYPE html>\n<html><body>Please log in</body></html>\n";
+    let checksum: String = hex::encode(Sha256::digest(page));
+    let server: TestServer = spawn_server().with_route("/foo.tar.gz", Route::bytes(page));
+    let tempdir: TempDir = TempDir::new().unwrap();
+
+    // Both when ensuring a pinned artifact and when locking an unpinned one
+    for sha256 in [format!("sha256 = \"{checksum}\"\n"), String::new()] {
+        let manifest: Manifest = Manifest::from_toml_str(
+            &format!("[artifacts.foo]\nurl = \"{}\"\ntarget = \"foo.tar.gz\"\nextract = \"out\"\n{}", server.url("/foo.tar.gz"), sha256),
+            tempdir.path().join("artifacts.toml"),
+        )
+        .unwrap();
+        let res: Result<(), Error> = if sha256.is_empty() { lock(&manifest, None).map(|_| ()) } else { ensure_all(&manifest, None).map(|_| ()) };
+        match res {
+            Err(Error::ArtifactDownload { name, err }) if matches!(*err, DownloadError::UnexpectedContent { reason: ContentViolation::Html, .. }) => {
+                assert_eq!(name, "foo")
+            },
+            got => panic!("Expected HTML artifact to fail with ArtifactDownload(UnexpectedContent), got {got:?}"),
+        }
+        assert_eq!(list_dir(tempdir.path()), Vec::<String>::new());
+    }
+}
+
+/// Test whether a failed extraction leaves nothing behind that later runs would accept.
+#[cfg(feature = "testing")]
+#[test]
+fn test_ensure_all_extract_failure() {
+    let corrupt: Vec<u8> = [&[0x1F, 0x8B, 0x08][..], &[42; 1024]].concat();
+    let checksum: String = hex::encode(Sha256::digest(&corrupt));
+    let server: TestServer = spawn_server().with_route("/foo.tar.gz", Route::bytes(corrupt));
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let manifest: Manifest = Manifest::from_toml_str(
+        &format!(
+            "[artifacts.foo]\nurl = \"{}\"\ntarget = \"foo.tar.gz\"\nsha256 = \"{}\"\nextract = \"out/foo\"\n",
+            server.url("/foo.tar.gz"),
+            checksum
+        ),
+        tempdir.path().join("artifacts.toml"),
+    )
+    .unwrap();
+
+    // The first run downloads it but fails to extract it, and the second run (with the download present) must fail the same way
+    for _ in 0..2 {
+        match ensure_all(&manifest, None) {
+            Err(Error::ArtifactExtract { name, .. }) => assert_eq!(name, "foo"),
+            got => panic!("Expected corrupt artifact to fail with ArtifactExtract, got {got:?}"),
+        }
+        assert_eq!(list_dir(tempdir.path()), ["artifacts.lock", "foo.tar.gz", "out"]);
+        assert_eq!(list_dir(&tempdir.path().join("out")), Vec::<String>::new());
+        assert_eq!(Lockfile::from_path(&manifest.lockfile).unwrap().artifacts["foo"].resolved, Some(server.url("/foo.tar.gz")));
+    }
+}
+
+/// Test whether the lockfile still records the artifacts downloaded before another one failed.
+#[cfg(feature = "testing")]
+#[test]
+fn test_lockfile_partial_failure() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    fs::create_dir(tempdir.path().join("src")).unwrap();
+    let (tarball, checksum): (PathBuf, String) = create_artifact(&tempdir.path().join("src"), "Hello there!\n");
+    let server: TestServer =
+        spawn_server().with_route("/a", Route::redirect(StatusCode::FOUND, "/a.tar.gz")).with_route("/a.tar.gz", Route::file(&tarball));
+
+    // Both when locking and when ensuring (where `b` is pinned but still missing)
+    for pin in [String::new(), format!("sha256 = \"{checksum}\"\n")] {
+        let manifest: Manifest = Manifest::from_toml_str(
+            &format!(
+                "[artifacts.a]\nurl = \"{}\"\ntarget = \"a.tar.gz\"\n{}\n[artifacts.b]\nurl = \"{}\"\ntarget = \"b.tar.gz\"\n{}",
+                server.url("/a"),
+                pin,
+                server.url("/b"),
+                pin
+            ),
+            tempdir.path().join("artifacts.toml"),
+        )
+        .unwrap();
+        let res: Result<(), Error> = if pin.is_empty() { lock(&manifest, None).map(|_| ()) } else { ensure_all(&manifest, None).map(|_| ()) };
+        match res {
+            Err(Error::ArtifactDownload { name, .. }) => assert_eq!(name, "b"),
+            got => panic!("Expected missing artifact to fail with ArtifactDownload, got {got:?}"),
+        }
+
+        // `a` is known, including where it came from, and the lockfile was moved into place
+        let lockfile: Lockfile = Lockfile::from_path(&manifest.lockfile).unwrap();
+        assert_eq!(lockfile.artifacts.len(), 1);
+        assert_eq!(lockfile.artifacts["a"].resolved, Some(server.url("/a.tar.gz")));
+        assert_eq!(lockfile.artifacts["a"].sha256, checksum);
+        assert_eq!(list_dir(tempdir.path()), ["a.tar.gz", "artifacts.lock", "src"]);
+        fs::remove_file(tempdir.path().join("a.tar.gz")).unwrap();
+        fs::remove_file(&manifest.lockfile).unwrap();
+    }
+}
@@ -13,5 +13,17 @@
 //
 
 // Declare unit tests
+#[cfg(feature = "testing")]
+pub mod download;
+#[cfg(feature = "manifest")]
+pub mod manifest;
 #[cfg(feature = "tar")]
 pub mod tar;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "__tls")]
+pub mod tls;
+#[cfg(feature = "testing")]
+pub mod transport;
+#[cfg(feature = "zip")]
+pub mod zip;
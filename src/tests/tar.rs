@@ -468,6 +468,15 @@ fn test_archive_unarchive(extra_dirs: PathBuf, skip_dir: bool, use_async: bool)
         }
     }
 
+    // Also generate an empty directory and a nested one
+    let empty1: PathBuf = PathBuf::from("logs");
+    let empty2: PathBuf = PathBuf::from("cache").join("nested").join("empty");
+    for path in [&empty1, &empty2] {
+        if let Err(err) = fs::create_dir_all(source.join(path)) {
+            panic!("Failed to create directory '{}': {}", source.join(path).display(), err);
+        }
+    }
+
 
 
     /***** ARCHIVING *****/
@@ -492,6 +501,8 @@ fn test_archive_unarchive(extra_dirs: PathBuf, skip_dir: bool, use_async: bool)
             run_sync = false;
         }
     });
+    #[cfg(not(feature = "async-tokio"))]
+    let _ = use_async;
     if run_sync {
         // Archive the thing to the temporary folder
         if let Err(err) = archive(&source, &tar_path, skip_dir) {
@@ -606,6 +617,19 @@ fn test_archive_unarchive(extra_dirs: PathBuf, skip_dir: bool, use_async: bool)
             panic!("Path is '{}', but that can never be?", path.display());
         }
     }
+
+    // The empty directories made it too, and are still empty
+    for path in [&empty1, &empty2] {
+        let path: PathBuf = target.join(path);
+        if !path.is_dir() {
+            panic!("Empty directory '{}' was not unarchived as a directory", path.display());
+        }
+        if fs::read_dir(&path).unwrap().next().is_some() {
+            panic!("Empty directory '{}' is not empty after unarchiving", path.display());
+        }
+    }
+    let nested: Vec<PathBuf> = fs::read_dir(target.join("cache").join("nested")).unwrap().map(|e| e.unwrap().file_name().into()).collect();
+    assert_eq!(nested, vec![PathBuf::from("empty")]);
 }
 
 
@@ -647,3 +671,980 @@ fn test_tarball_with_root_extra_path_async() { test_archive_unarchive("some/extr
 #[cfg(feature = "async-tokio")]
 #[test]
 fn test_tarball_without_root_extra_path_async() { test_archive_unarchive("some/extra/folders/lol".into(), true, true); }
+
+
+
+/// Test whether directories are archived as entries with their permissions, and restored with them even if they're read-only.
+#[cfg(unix)]
+#[test]
+fn test_tarball_dir_permissions() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(source.join("readonly").join("inner")).unwrap();
+    fs::create_dir_all(source.join("private")).unwrap();
+    fs::write(source.join("readonly").join("inner").join("a.txt"), "Hello there!").unwrap();
+    let modes: [(&str, u32); 3] = [("readonly/inner", 0o555), ("readonly", 0o555), ("private", 0o700)];
+    for (path, mode) in modes {
+        fs::set_permissions(source.join(path), fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    // Archive it; the directories are listed before their contents
+    let tarball: PathBuf = tempdir.path().join("src.tar.gz");
+    archive(&source, &tarball, false).unwrap();
+    assert_eq!(list(&tarball).unwrap(), ["src", "src/private", "src/readonly", "src/readonly/inner", "src/readonly/inner/a.txt"].map(PathBuf::from));
+
+    // Unarchive it with both drivers
+    #[allow(unused_mut)]
+    let mut targets: Vec<PathBuf> = vec![tempdir.path().join("target")];
+    unarchive(&tarball, &targets[0]).unwrap();
+    #[cfg(feature = "async-tokio")]
+    {
+        targets.push(tempdir.path().join("target-async"));
+        tokio_test::block_on(unarchive_async(&tarball, &targets[1])).unwrap();
+    }
+    for target in &targets {
+        assert_eq!(fs::read_to_string(target.join("src").join("readonly").join("inner").join("a.txt")).unwrap(), "Hello there!");
+        for (path, mode) in modes {
+            assert_eq!(fs::metadata(target.join("src").join(path)).unwrap().permissions().mode() & 0o777, mode, "Wrong permissions for '{path}'");
+        }
+    }
+
+    // Make everything writable again so the tempdir can be removed
+    for root in std::iter::once(source).chain(targets.iter().map(|target| target.join("src"))) {
+        for (path, _) in modes {
+            fs::set_permissions(root.join(path), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+}
+
+/// Archives with the given options using every available driver.
+///
+/// # Arguments
+/// - `source`: The file or directory to archive.
+/// - `tarball`: The tarball to archive to, which gets a suffix for every driver but the sync one.
+/// - `options`: The [`ArchiveOptions`] to archive with.
+///
+/// # Returns
+/// The result of every driver, together with the tarball it wrote.
+fn archive_all(source: &std::path::Path, tarball: &std::path::Path, options: ArchiveOptions) -> Vec<(PathBuf, Result<(), Error>)> {
+    #[allow(unused_mut)]
+    let mut res: Vec<(PathBuf, Result<(), Error>)> = vec![(tarball.into(), archive_with(source, tarball, options.clone()))];
+    #[cfg(feature = "async-tokio")]
+    {
+        let tarball: PathBuf = tarball.with_extension("async.tar.gz");
+        let async_res: Result<(), Error> = tokio_test::block_on(archive_with_async(source, &tarball, options));
+        res.push((tarball, async_res));
+    }
+    res
+}
+
+/// Test whether symlinks are preserved, followed or refused as told when archiving.
+#[cfg(unix)]
+#[test]
+fn test_tarball_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(source.join("data")).unwrap();
+    fs::write(source.join("data").join("file.txt"), "Hello there!").unwrap();
+    fs::write(tempdir.path().join("outside.txt"), "General Kenobi...").unwrap();
+    symlink(tempdir.path().join("outside.txt"), source.join("abs")).unwrap();
+    symlink("data/file.txt", source.join("rel")).unwrap();
+    symlink("data", source.join("reldir")).unwrap();
+    symlink("data", source.join("reldir2")).unwrap();
+
+    // Preserving adds them as links, as-is
+    for (tarball, res) in archive_all(&source, &tempdir.path().join("preserve.tar.gz"), ArchiveOptions::new().with_symlinks(SymlinkPolicy::Preserve)) {
+        res.unwrap();
+        let mut links: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut archive: ::tar::Archive<flate2::read::GzDecoder<fs::File>> = ::tar::Archive::new(flate2::read::GzDecoder::new(fs::File::open(&tarball).unwrap()));
+        for entry in archive.entries().unwrap() {
+            let entry: ::tar::Entry<_> = entry.unwrap();
+            if entry.header().entry_type() == ::tar::EntryType::Symlink {
+                links.push((entry.path().unwrap().into(), entry.link_name().unwrap().unwrap().into()));
+            }
+        }
+        assert_eq!(links, vec![
+            ("src/abs".into(), tempdir.path().join("outside.txt")),
+            ("src/rel".into(), "data/file.txt".into()),
+            ("src/reldir".into(), "data".into()),
+            ("src/reldir2".into(), "data".into()),
+        ]);
+    }
+
+    // Following adds whatever they point to, even if that's the same directory twice
+    for (tarball, res) in archive_all(&source, &tempdir.path().join("follow.tar.gz"), ArchiveOptions::new().with_symlinks(SymlinkPolicy::Follow)) {
+        res.unwrap();
+        let target: PathBuf = tarball.with_extension("target");
+        unarchive(&tarball, &target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("src").join("abs")).unwrap(), "General Kenobi...");
+        for path in ["rel", "reldir/file.txt", "reldir2/file.txt"] {
+            let path: PathBuf = target.join("src").join(path);
+            assert!(!fs::symlink_metadata(&path).unwrap().file_type().is_symlink(), "'{}' was archived as a symlink", path.display());
+            assert_eq!(fs::read_to_string(path).unwrap(), "Hello there!");
+        }
+    }
+
+    // Refusing fails on the first one
+    for (_, res) in archive_all(&source, &tempdir.path().join("error.tar.gz"), ArchiveOptions::new().with_symlinks(SymlinkPolicy::Error)) {
+        match res {
+            Err(Error::SourceSymlink { path }) => assert_eq!(path, source.join("abs")),
+            res => panic!("Expected a refused symlink, got {res:?}"),
+        }
+    }
+
+    // Dangling symlinks are only a problem when following them
+    let dangling: PathBuf = tempdir.path().join("dangling");
+    fs::create_dir(&dangling).unwrap();
+    symlink("missing.txt", dangling.join("gone")).unwrap();
+    for (_, res) in archive_all(&dangling, &tempdir.path().join("dangling-preserve.tar.gz"), ArchiveOptions::new().with_symlinks(SymlinkPolicy::Preserve)) {
+        res.unwrap();
+    }
+    for (_, res) in archive_all(&dangling, &tempdir.path().join("dangling-follow.tar.gz"), ArchiveOptions::new()) {
+        match res {
+            Err(Error::SourceSymlinkBroken { path, .. }) => assert_eq!(path, dangling.join("gone")),
+            res => panic!("Expected a broken symlink, got {res:?}"),
+        }
+    }
+    for (_, res) in archive_all(&dangling, &tempdir.path().join("dangling-error.tar.gz"), ArchiveOptions::new().with_symlinks(SymlinkPolicy::Error)) {
+        assert!(matches!(res, Err(Error::SourceSymlink { .. })), "Expected a refused symlink, got {res:?}");
+    }
+}
+
+/// Test whether following symlinks detects cycles instead of looping forever.
+#[cfg(unix)]
+#[test]
+fn test_tarball_symlink_cycles() {
+    use std::os::unix::fs::symlink;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+
+    // A link to its own parent...
+    let direct: PathBuf = tempdir.path().join("direct");
+    fs::create_dir_all(direct.join("a")).unwrap();
+    symlink("..", direct.join("a").join("loop")).unwrap();
+    // ...to the root itself...
+    let root: PathBuf = tempdir.path().join("root");
+    fs::create_dir_all(&root).unwrap();
+    symlink(&root, root.join("self")).unwrap();
+    // ...and through each other
+    let mutual: PathBuf = tempdir.path().join("mutual");
+    fs::create_dir_all(mutual.join("a")).unwrap();
+    fs::create_dir_all(mutual.join("b")).unwrap();
+    symlink("../b", mutual.join("a").join("to_b")).unwrap();
+    symlink("../a", mutual.join("b").join("to_a")).unwrap();
+
+    for (source, link, target) in [
+        (&direct, direct.join("a").join("loop"), direct.clone()),
+        (&root, root.join("self"), root.clone()),
+        (&mutual, mutual.join("a").join("to_b").join("to_a"), mutual.join("a")),
+    ] {
+        let tarball: PathBuf = tempdir.path().join(format!("{}.tar.gz", source.file_name().unwrap().to_string_lossy()));
+        for (_, res) in archive_all(source, &tarball, ArchiveOptions::new().with_symlinks(SymlinkPolicy::Follow)) {
+            match res {
+                Err(Error::SourceSymlinkCycle { path, target: got }) => {
+                    assert_eq!(path, link);
+                    assert_eq!(got, target.canonicalize().unwrap());
+                },
+                res => panic!("Expected a symlink cycle for '{}', got {res:?}", source.display()),
+            }
+        }
+
+        // Preserving them is fine
+        let tarball: PathBuf = tarball.with_extension("preserve.tar.gz");
+        for (_, res) in archive_all(source, &tarball, ArchiveOptions::new().with_symlinks(SymlinkPolicy::Preserve)) {
+            res.unwrap();
+        }
+    }
+}
+
+/// Test whether symlink policies are parsed from (and printed as) their names.
+#[test]
+fn test_symlink_policy_parse() {
+    for policy in SymlinkPolicy::ALL {
+        assert_eq!(policy.to_string().parse::<SymlinkPolicy>().unwrap(), policy);
+        assert_eq!(policy.to_string().to_uppercase().parse::<SymlinkPolicy>().unwrap(), policy);
+    }
+    assert_eq!(SymlinkPolicy::default(), SymlinkPolicy::Follow);
+    assert_eq!("ignore".parse::<SymlinkPolicy>().unwrap_err().raw, "ignore");
+}
+
+/// Writes a `.tar.gz` fixture with hand-crafted entries, which may contain paths or links that [`archive()`] would never write.
+///
+/// # Arguments
+/// - `path`: The path of the tarball to write.
+/// - `entries`: The entries to write, as their path (written as-is, so it may contain `..`), type and contents (or what they link to, for
+///   links).
+fn write_links_fixture(path: &std::path::Path, entries: &[(&str, ::tar::EntryType, &str)]) {
+    let enc: flate2::write::GzEncoder<fs::File> = flate2::write::GzEncoder::new(fs::File::create(path).unwrap(), flate2::Compression::default());
+    let mut builder: ::tar::Builder<flate2::write::GzEncoder<fs::File>> = ::tar::Builder::new(enc);
+    for (name, kind, contents) in entries {
+        let mut header: ::tar::Header = ::tar::Header::new_gnu();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(*kind);
+        header.set_mode(if kind.is_dir() { 0o755 } else { 0o644 });
+        if kind.is_symlink() || kind.is_hard_link() {
+            header.set_link_name_literal(contents).unwrap();
+            header.set_size(0);
+            header.set_cksum();
+            builder.append(&header, std::io::empty()).unwrap();
+        } else {
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append(&header, contents.as_bytes()).unwrap();
+        }
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+}
+
+/// Unarchives with the given options using every available driver.
+///
+/// # Arguments
+/// - `tarball`: The tarball to unarchive.
+/// - `target`: The directory to unarchive to, which gets a suffix for every driver but the sync one.
+/// - `options`: The [`UnarchiveOptions`] to unarchive with.
+///
+/// # Returns
+/// The result of every driver, together with the directory it unarchived to.
+fn unarchive_all(tarball: &std::path::Path, target: &std::path::Path, options: UnarchiveOptions) -> Vec<(PathBuf, Result<(), Error>)> {
+    #[allow(unused_mut)]
+    let mut res: Vec<(PathBuf, Result<(), Error>)> = vec![(target.into(), unarchive_with(tarball, target, options.clone()))];
+    #[cfg(feature = "async-tokio")]
+    {
+        let target: PathBuf = target.with_extension("async");
+        let async_res: Result<(), Error> = tokio_test::block_on(unarchive_with_async(tarball, &target, options));
+        res.push((target, async_res));
+    }
+    res
+}
+
+/// Test whether links are only extracted as the [`LinkPolicy`] allows, using adversarial tarballs.
+#[cfg(unix)]
+#[test]
+fn test_tarball_link_policy() {
+    use ::tar::EntryType;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let outside: PathBuf = tempdir.path().join("outside");
+    fs::create_dir(&outside).unwrap();
+    fs::write(outside.join("secret.txt"), "Hello there!").unwrap();
+    let outside_str: String = outside.display().to_string();
+
+    // Links that stay inside are fine by default, and can be read through
+    let tarball: PathBuf = tempdir.path().join("inside.tar.gz");
+    write_links_fixture(&tarball, &[
+        ("data/file.txt", EntryType::Regular, "General Kenobi..."),
+        ("rel", EntryType::Symlink, "data/file.txt"),
+        ("dirlink", EntryType::Symlink, "data"),
+        ("dangling", EntryType::Symlink, "data/missing.txt"),
+        ("hard", EntryType::Link, "data/file.txt"),
+    ]);
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("inside"), UnarchiveOptions::new()) {
+        res.unwrap();
+        for path in ["rel", "dirlink/file.txt", "hard"] {
+            assert_eq!(fs::read_to_string(target.join(path)).unwrap(), "General Kenobi...");
+        }
+        assert_eq!(fs::read_link(target.join("dangling")).unwrap(), PathBuf::from("data/missing.txt"));
+    }
+    for (_, res) in unarchive_all(&tarball, &tempdir.path().join("inside-reject"), UnarchiveOptions::new().with_symlinks(LinkPolicy::Reject)) {
+        match res {
+            Err(Error::SourceTarEntrySymlink { entry, link, .. }) => {
+                assert_eq!(entry, PathBuf::from("rel"));
+                assert_eq!(link, PathBuf::from("data/file.txt"));
+            },
+            res => panic!("Expected a rejected symlink, got {res:?}"),
+        }
+    }
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_hardlinks(LinkPolicy::Reject);
+    for (_, res) in unarchive_all(&tarball, &tempdir.path().join("inside-reject-hard"), options) {
+        assert!(matches!(res, Err(Error::SourceTarEntryHardlink { ref entry, .. }) if entry == &PathBuf::from("hard")), "{res:?}");
+    }
+
+    // Symlinks that escape are refused (and removed), unless we allow them
+    #[allow(clippy::type_complexity)]
+    let cases: [(&str, Vec<(&str, EntryType, &str)>, &str); 5] = [
+        ("absolute", vec![("evil", EntryType::Symlink, &outside_str)], "evil"),
+        ("relative", vec![("a/evil", EntryType::Symlink, "../../outside")], "a/evil"),
+        // Only loops once `l2` exists
+        ("loop", vec![("l1", EntryType::Symlink, "l2"), ("l2", EntryType::Symlink, "l1")], "l2"),
+        // Only escapes once `b` exists, which is created after `a`
+        ("redirected", vec![("a", EntryType::Symlink, "b/x/../.."), ("b", EntryType::Symlink, ".")], "a"),
+        ("hardlink", vec![("hard", EntryType::Link, "../outside/secret.txt")], "hard"),
+    ];
+    for (name, entries, evil) in cases {
+        let tarball: PathBuf = tempdir.path().join(format!("{name}.tar.gz"));
+        write_links_fixture(&tarball, &entries);
+        for (target, res) in unarchive_all(&tarball, &tempdir.path().join(name), UnarchiveOptions::new()) {
+            match res {
+                Err(Error::SourceTarEntryLinkEscaped { entry, .. }) => assert_eq!(entry, PathBuf::from(evil), "Wrong entry refused for {name}"),
+                res => panic!("Expected an escaping link for {name}, got {res:?}"),
+            }
+            assert!(fs::symlink_metadata(target.join(evil)).is_err(), "Escaping link '{evil}' for {name} was left behind");
+        }
+        if name != "hardlink" {
+            let options: UnarchiveOptions = UnarchiveOptions::new().with_symlinks(LinkPolicy::Allow);
+            for (_, res) in unarchive_all(&tarball, &tempdir.path().join(format!("{name}-allow")), options) {
+                res.unwrap();
+            }
+        }
+    }
+
+    // Nothing is ever written through symlinks, regardless of the policy
+    let tarball: PathBuf = tempdir.path().join("through.tar.gz");
+    write_links_fixture(&tarball, &[("evil", EntryType::Symlink, &outside_str), ("evil/pwned.txt", EntryType::Regular, "pwned")]);
+    for policy in LinkPolicy::ALL {
+        for (_, res) in unarchive_all(&tarball, &tempdir.path().join(format!("through-{policy}")), UnarchiveOptions::new().with_symlinks(policy)) {
+            assert!(res.is_err(), "Unarchiving through a symlink succeeded with policy {policy}");
+        }
+    }
+    assert_eq!(fs::read_dir(&outside).unwrap().count(), 1);
+    assert_eq!(fs::read_to_string(outside.join("secret.txt")).unwrap(), "Hello there!");
+}
+
+/// Test whether link policies are parsed from (and printed as) their names.
+#[test]
+fn test_link_policy_parse() {
+    for policy in LinkPolicy::ALL {
+        assert_eq!(policy.to_string().parse::<LinkPolicy>().unwrap(), policy);
+        assert_eq!(policy.to_string().to_uppercase().parse::<LinkPolicy>().unwrap(), policy);
+    }
+    assert_eq!(LinkPolicy::default(), LinkPolicy::Inside);
+    assert_eq!("follow".parse::<LinkPolicy>().unwrap_err().raw, "follow");
+}
+
+/// Test whether unarchiving stops at the first entry that exceeds any of the [`UnarchiveLimits`].
+#[test]
+fn test_tarball_limits() {
+    use ::tar::EntryType;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let tarball: PathBuf = tempdir.path().join("limits.tar.gz");
+    write_links_fixture(&tarball, &[
+        ("a.txt", EntryType::Regular, "Hello there"),
+        ("deep/er/b.txt", EntryType::Regular, "General Kenobi..."),
+        ("c.txt", EntryType::Regular, "You are a bold one, or so I hear"),
+    ]);
+
+    // Everything fits exactly
+    let limits: UnarchiveLimits =
+        UnarchiveLimits::none().with_max_bytes(60).with_max_entry_bytes(32).with_max_entries(3).with_max_depth(3).with_max_path_len(13);
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("fits"), UnarchiveOptions::new().with_limits(limits)) {
+        res.unwrap();
+        assert_eq!(fs::read_to_string(target.join("c.txt")).unwrap(), "You are a bold one, or so I hear");
+    }
+
+    // But one less of anything doesn't, and nothing after the offending entry is unpacked
+    let cases: [(&str, UnarchiveLimits, LimitKind, &str, u64); 5] = [
+        ("bytes", UnarchiveLimits::none().with_max_bytes(59), LimitKind::Bytes, "c.txt", 60),
+        ("entry-bytes", UnarchiveLimits::none().with_max_entry_bytes(31), LimitKind::EntryBytes, "c.txt", 32),
+        ("entries", UnarchiveLimits::none().with_max_entries(2), LimitKind::Entries, "c.txt", 3),
+        ("depth", UnarchiveLimits::none().with_max_depth(2), LimitKind::Depth, "deep/er/b.txt", 3),
+        ("path-len", UnarchiveLimits::none().with_max_path_len(12), LimitKind::PathLength, "deep/er/b.txt", 13),
+    ];
+    for (name, limits, kind, entry, value) in cases {
+        for (target, res) in unarchive_all(&tarball, &tempdir.path().join(name), UnarchiveOptions::new().with_limits(limits.clone())) {
+            match res {
+                Err(Error::LimitExceeded { entry: Some(got), kind: got_kind, value: got_value, .. }) => {
+                    assert_eq!((got.as_path(), got_kind, got_value), (std::path::Path::new(entry), kind, value), "Wrong limit exceeded for {name}");
+                },
+                res => panic!("Expected exceeding a limit for {name}, got {res:?}"),
+            }
+            assert!(target.join("a.txt").exists());
+            assert!(!target.join(entry).exists(), "Entry '{entry}' exceeding a limit for {name} was unpacked anyway");
+            assert!(!target.join("c.txt").exists());
+        }
+        assert!(matches!(list_with(&tarball, UnarchiveOptions::new().with_limits(limits.clone())), Err(Error::LimitExceeded { .. })));
+        #[cfg(feature = "async-tokio")]
+        assert!(matches!(
+            tokio_test::block_on(list_with_async(&tarball, UnarchiveOptions::new().with_limits(limits))),
+            Err(Error::LimitExceeded { .. })
+        ));
+    }
+}
+
+/// Test whether unarchiving stops while streaming an entry once it exceeds the compression ratio.
+#[test]
+fn test_tarball_limits_ratio() {
+    use ::tar::EntryType;
+
+    // Some zeroes compress by roughly a factor thousand with gzip
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let tarball: PathBuf = tempdir.path().join("bomb.tar.gz");
+    let zeroes: String = "\0".repeat(4 * 1024 * 1024);
+    write_links_fixture(&tarball, &[("zeroes.bin", EntryType::Regular, &zeroes), ("after.txt", EntryType::Regular, "Hello there!")]);
+
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_limits(UnarchiveLimits::none().with_max_ratio(100));
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("bomb"), options.clone()) {
+        match res {
+            Err(Error::LimitExceeded { entry, kind: LimitKind::Ratio, limit: 100, value, .. }) => {
+                assert_eq!(entry, Some(PathBuf::from("zeroes.bin")));
+                assert!(value > 100);
+            },
+            res => panic!("Expected exceeding the compression ratio, got {res:?}"),
+        }
+        assert!(fs::metadata(target.join("zeroes.bin")).map(|m| m.len()).unwrap_or(0) < zeroes.len() as u64);
+        assert!(!target.join("after.txt").exists());
+    }
+    assert!(matches!(list_with(&tarball, options), Err(Error::LimitExceeded { kind: LimitKind::Ratio, entry: None, .. })));
+
+    // A generous ratio is fine, as is anything uncompressed
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_limits(UnarchiveLimits::none().with_max_ratio(10_000));
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("generous"), options) {
+        res.unwrap();
+        assert_eq!(fs::metadata(target.join("zeroes.bin")).unwrap().len(), zeroes.len() as u64);
+    }
+    let plain: PathBuf = tempdir.path().join("bomb.tar");
+    let mut dec: flate2::read::GzDecoder<fs::File> = flate2::read::GzDecoder::new(fs::File::open(&tarball).unwrap());
+    std::io::copy(&mut dec, &mut fs::File::create(&plain).unwrap()).unwrap();
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_limits(UnarchiveLimits::none().with_max_ratio(1));
+    for (_, res) in unarchive_all(&plain, &tempdir.path().join("plain"), options) {
+        res.unwrap();
+    }
+}
+
+/// Test whether leading components are stripped from entries (and hardlinks), skipping entries with too few of them.
+#[cfg(unix)]
+#[test]
+fn test_tarball_strip_components() {
+    use ::tar::EntryType;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let tarball: PathBuf = tempdir.path().join("foo-1.2.3.tar.gz");
+    write_links_fixture(&tarball, &[
+        ("foo-1.2.3/", EntryType::Directory, ""),
+        ("foo-1.2.3/bin/tool", EntryType::Regular, "Hello there!"),
+        ("foo-1.2.3/README", EntryType::Regular, "General Kenobi..."),
+        ("./foo-1.2.3/lib/libfoo.so", EntryType::Regular, "You are a bold one"),
+        ("foo-1.2.3/link", EntryType::Symlink, "bin/tool"),
+        ("foo-1.2.3/hard", EntryType::Link, "foo-1.2.3/README"),
+        ("top.txt", EntryType::Regular, "Skipped"),
+    ]);
+
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_strip_components(1);
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("strip1"), options.clone()) {
+        res.unwrap();
+        let mut entries: Vec<String> = fs::read_dir(&target).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        entries.sort();
+        assert_eq!(entries, ["README", "bin", "hard", "lib", "link"]);
+        assert_eq!(fs::read_to_string(target.join("bin/tool")).unwrap(), "Hello there!");
+        assert_eq!(fs::read_to_string(target.join("lib/libfoo.so")).unwrap(), "You are a bold one");
+        assert_eq!(fs::read_to_string(target.join("link")).unwrap(), "Hello there!");
+        assert_eq!(fs::read_to_string(target.join("hard")).unwrap(), "General Kenobi...");
+    }
+    let entries: Vec<PathBuf> = list_with(&tarball, options).unwrap();
+    assert!(!entries.contains(&PathBuf::from("top.txt")) && !entries.contains(&PathBuf::from("foo-1.2.3/")));
+    assert_eq!(entries.len(), 5);
+
+    // Hardlinks to something that isn't extracted are refused
+    let tarball: PathBuf = tempdir.path().join("hard.tar.gz");
+    write_links_fixture(&tarball, &[("a/README", EntryType::Regular, "Hello there!"), ("a/b/hard", EntryType::Link, "a/README")]);
+    for (_, res) in unarchive_all(&tarball, &tempdir.path().join("hard"), UnarchiveOptions::new().with_strip_components(2)) {
+        assert!(matches!(res, Err(Error::SourceTarEntryLinkEscaped { ref entry, .. }) if entry == &PathBuf::from("a/b/hard")), "{res:?}");
+    }
+
+    // Whether entries escape is checked after stripping them
+    let tarball: PathBuf = tempdir.path().join("escape.tar.gz");
+    write_links_fixture(&tarball, &[("../pkg/fine.txt", EntryType::Regular, "Hello there!")]);
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("escape-fine"), UnarchiveOptions::new().with_strip_components(1)) {
+        res.unwrap();
+        assert_eq!(fs::read_to_string(target.join("pkg/fine.txt")).unwrap(), "Hello there!");
+    }
+    for (_, res) in unarchive_all(&tarball, &tempdir.path().join("escape-unstripped"), UnarchiveOptions::new()) {
+        assert!(matches!(res, Err(Error::SourceTarEntryEscaped { .. })), "{res:?}");
+    }
+    let tarball: PathBuf = tempdir.path().join("escape2.tar.gz");
+    write_links_fixture(&tarball, &[("pkg/../../evil.txt", EntryType::Regular, "pwned")]);
+    for (_, res) in unarchive_all(&tarball, &tempdir.path().join("escape-evil"), UnarchiveOptions::new().with_strip_components(1)) {
+        assert!(matches!(res, Err(Error::SourceTarEntryEscaped { ref entry, .. }) if entry == &PathBuf::from("pkg/../../evil.txt")), "{res:?}");
+    }
+    assert!(!tempdir.path().join("evil.txt").exists());
+}
+
+/// Test whether [`Glob`]s match the paths (and parents) they should.
+#[test]
+fn test_glob_matches() {
+    let cases: [(&str, &str, bool); 29] = [
+        ("README", "README", true),
+        ("README", "README.md", false),
+        ("*.txt", "file.txt", true),
+        ("*.txt", "a/file.txt", false),
+        ("*/file.txt", "a/file.txt", true),
+        ("foo-*/bin", "foo-1.2.3/bin/tool", true),
+        ("foo-*/bin/*", "foo-1.2.3/bin", false),
+        ("foo-*/bin/*", "foo-1.2.3/bin/tool", true),
+        ("file?.txt", "file1.txt", true),
+        ("file?.txt", "file12.txt", false),
+        ("file[0-9].txt", "file7.txt", true),
+        ("file[!0-9].txt", "file7.txt", false),
+        ("file[^0-9].txt", "fileA.txt", true),
+        ("file[.txt", "file[.txt", true),
+        ("file\\*.txt", "file*.txt", true),
+        ("file\\*.txt", "file1.txt", false),
+        ("**", "a/b/c", true),
+        ("**/*.so", "libfoo.so", true),
+        ("**/*.so", "lib/x86/libfoo.so", true),
+        ("a/**/c", "a/c", true),
+        ("a/**/c", "a/b/b/c", true),
+        ("a/**", "a/b/c", true),
+        ("a/**/", "a/b", true),
+        ("a/**/c", "a/b/c/d", true),
+        ("a/**/c", "a/b/cd", false),
+        ("**/b", "a/b/c", true),
+        ("*/b", "a/a/b", false),
+        ("./bin/tool", "bin/tool", true),
+        ("/bin", "./bin/tool", true),
+    ];
+    for (pattern, path, expected) in cases {
+        assert_eq!(Glob::new(pattern).matches(path), expected, "Glob '{pattern}' on '{path}'");
+    }
+
+    // Long paths (e.g., in malicious tarballs) are matched in linear time
+    let long: String = format!("{}b", "a/".repeat(100_000));
+    assert!(Glob::new("**/a/**/b").matches(&long));
+    assert!(!Glob::new("**/a/**/c").matches(&long));
+
+    // Literal globs don't interpret anything
+    assert!(Glob::literal("a/[weird]*name").matches("a/[weird]*name"));
+    assert!(!Glob::literal("a/*").matches("a/b"));
+    assert_eq!("bin/*".parse::<Glob>().unwrap(), Glob::from("bin/*"));
+    assert_eq!(Glob::new("bin/*").to_string(), "bin/*");
+}
+
+/// Test whether only the entries selected by the include & exclude globs and the filter are extracted and listed.
+#[test]
+fn test_tarball_filters() {
+    use ::tar::EntryType;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let tarball: PathBuf = tempdir.path().join("filters.tar.gz");
+    write_links_fixture(&tarball, &[
+        ("foo/", EntryType::Directory, ""),
+        ("foo/bin/tool", EntryType::Regular, "Hello there!"),
+        ("foo/lib/libfoo.so", EntryType::Regular, "General Kenobi..."),
+        ("foo/lib/libfoo.a", EntryType::Regular, ""),
+        ("foo/README.md", EntryType::Regular, "You are a bold one"),
+    ]);
+
+    // Include & exclude
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_include("foo/bin").with_include("**/*.so").with_exclude("*/lib/*.a");
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("globs"), options.clone()) {
+        res.unwrap();
+        assert_eq!(fs::read_to_string(target.join("foo/bin/tool")).unwrap(), "Hello there!");
+        assert_eq!(fs::read_to_string(target.join("foo/lib/libfoo.so")).unwrap(), "General Kenobi...");
+        assert!(!target.join("foo/lib/libfoo.a").exists());
+        assert!(!target.join("foo/README.md").exists());
+    }
+    let entries: Vec<PathBuf> = list_with(&tarball, options).unwrap();
+    assert_eq!(entries, [PathBuf::from("foo/bin/tool"), PathBuf::from("foo/lib/libfoo.so")]);
+
+    // Exclude only, combined with stripping (which happens after matching)
+    let options: UnarchiveOptions = UnarchiveOptions::new().with_exclude("foo/lib").with_strip_components(1);
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("exclude"), options) {
+        res.unwrap();
+        let mut entries: Vec<String> = fs::read_dir(&target).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        entries.sort();
+        assert_eq!(entries, ["README.md", "bin"]);
+    }
+
+    // A predicate on the header
+    let options: UnarchiveOptions =
+        UnarchiveOptions::new().with_filter(|path, header| header.entry_type().is_dir() || (header.size().unwrap() > 0 && path.extension().is_some()));
+    for (target, res) in unarchive_all(&tarball, &tempdir.path().join("filter"), options) {
+        res.unwrap();
+        assert!(target.join("foo/lib/libfoo.so").is_file());
+        assert!(target.join("foo/README.md").is_file());
+        assert!(!target.join("foo/lib/libfoo.a").exists());
+        assert!(!target.join("foo/bin/tool").exists());
+    }
+}
+
+/// Test whether single files are extracted to exactly the given path.
+#[test]
+fn test_tarball_unarchive_file() {
+    use ::tar::EntryType;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let tarball: PathBuf = tempdir.path().join("file.tar.gz");
+    write_links_fixture(&tarball, &[
+        ("foo/", EntryType::Directory, ""),
+        ("foo/bin/", EntryType::Directory, ""),
+        ("./foo/bin/tool", EntryType::Regular, "Hello there!"),
+        ("foo/bin/tool2", EntryType::Regular, "General Kenobi..."),
+    ]);
+
+    let target: PathBuf = tempdir.path().join("tool");
+    unarchive_file(&tarball, "foo/bin/tool", &target).unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!");
+    assert!(matches!(unarchive_file(&tarball, "foo/bin/tool", &target), Err(Error::TargetExists { .. })));
+    #[cfg(feature = "async-tokio")]
+    {
+        let target: PathBuf = tempdir.path().join("tool2");
+        tokio_test::block_on(unarchive_file_async(&tarball, "./foo/bin/tool2", &target)).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "General Kenobi...");
+    }
+
+    // Directories, prefixes and missing files aren't found
+    for entry in ["foo/bin", "foo/bin/too", "foo/bin/tool3"] {
+        let res: Result<(), Error> = unarchive_file(&tarball, entry, tempdir.path().join("missing"));
+        assert!(matches!(res, Err(Error::SourceTarEntryNotFound { entry: ref e, .. }) if e == &PathBuf::from(entry)), "{res:?}");
+        #[cfg(feature = "async-tokio")]
+        {
+            let res: Result<(), Error> = tokio_test::block_on(unarchive_file_async(&tarball, entry, tempdir.path().join("missing")));
+            assert!(matches!(res, Err(Error::SourceTarEntryNotFound { .. })), "{res:?}");
+        }
+    }
+    assert!(!tempdir.path().join("missing").exists());
+}
+
+/// Test whether the sync and async drivers produce the same tarballs, and extract them in the same way.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_tarball_drivers_identical() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    for (path, contents) in [("b.txt", "Hello there!"), ("a/c.txt", "General Kenobi..."), ("a/b/d.txt", "...you are a bold one"), ("e.txt", "")] {
+        fs::create_dir_all(source.join(path).parent().unwrap()).unwrap();
+        fs::write(source.join(path), contents).unwrap();
+    }
+
+    for skip_dir in [false, true] {
+        // Archive with both drivers
+        let sync_tar: PathBuf = tempdir.path().join(format!("sync-{skip_dir}.tar.gz"));
+        let async_tar: PathBuf = tempdir.path().join(format!("async-{skip_dir}.tar.gz"));
+        archive(&source, &sync_tar, skip_dir).unwrap();
+        tokio_test::block_on(archive_async(&source, &async_tar, skip_dir)).unwrap();
+
+        // Both list the same entries, in the same (sorted) order, regardless of the driver used to list them
+        let entries: Vec<PathBuf> = list(&sync_tar).unwrap();
+        assert_eq!(list(&async_tar).unwrap(), entries);
+        assert_eq!(tokio_test::block_on(list_async(&sync_tar)).unwrap(), entries);
+        assert_eq!(tokio_test::block_on(list_async(&async_tar)).unwrap(), entries);
+        let mut sorted: Vec<PathBuf> = entries.clone();
+        sorted.sort();
+        assert_eq!(entries, sorted);
+
+        // Extracting them with either driver gives the same files
+        let sync_target: PathBuf = tempdir.path().join(format!("sync-{skip_dir}"));
+        let async_target: PathBuf = tempdir.path().join(format!("async-{skip_dir}"));
+        unarchive(&async_tar, &sync_target).unwrap();
+        tokio_test::block_on(unarchive_async(&sync_tar, &async_target)).unwrap();
+        for entry in entries.iter().filter(|e| source.join(e.strip_prefix("src").unwrap_or(e)).is_file()) {
+            let original: Vec<u8> = fs::read(source.join(entry.strip_prefix("src").unwrap_or(entry))).unwrap();
+            assert_eq!(fs::read(sync_target.join(entry)).unwrap(), original);
+            assert_eq!(fs::read(async_target.join(entry)).unwrap(), original);
+        }
+    }
+}
+
+/// Test whether the `async-smol` functions produce the same tarballs as the sync ones, and extract them in the same way.
+#[cfg(feature = "async-smol")]
+#[test]
+fn test_tarball_smol() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(source.join("a")).unwrap();
+    fs::write(source.join("b.txt"), "Hello there!").unwrap();
+    fs::write(source.join("a").join("c.txt"), "General Kenobi...").unwrap();
+
+    // Archiving gives the exact same tarball
+    let sync_tar: PathBuf = tempdir.path().join("sync.tar.gz");
+    let smol_tar: PathBuf = tempdir.path().join("smol.tar.gz");
+    archive(&source, &sync_tar, false).unwrap();
+    futures_lite::future::block_on(archive_smol(&source, &smol_tar, false)).unwrap();
+    assert_eq!(fs::read(&sync_tar).unwrap(), fs::read(&smol_tar).unwrap());
+
+    // Listing and extracting work too
+    assert_eq!(futures_lite::future::block_on(list_smol(&smol_tar)).unwrap(), list(&sync_tar).unwrap());
+    let target: PathBuf = tempdir.path().join("target");
+    futures_lite::future::block_on(unarchive_smol(&smol_tar, &target)).unwrap();
+    assert_eq!(fs::read_to_string(target.join("src").join("a").join("c.txt")).unwrap(), "General Kenobi...");
+    assert!(matches!(futures_lite::future::block_on(unarchive_smol(&smol_tar, &target)), Err(Error::TargetExists { .. })));
+}
+
+
+
+/// Test whether every available [`Compression`] round-trips, produces its own magic bytes, and is refused when read as another compression.
+#[test]
+fn test_tarball_compressions() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(source.join("a")).unwrap();
+    fs::write(source.join("b.txt"), "Hello there!").unwrap();
+    fs::write(source.join("a").join("c.txt"), "General Kenobi...".repeat(1024)).unwrap();
+
+    for compression in Compression::ALL.iter().copied() {
+        // Archive it
+        let tarball: PathBuf = tempdir.path().join(format!("src.{}", compression.extension()));
+        archive_with(&source, &tarball, ArchiveOptions::new().with_compression(compression)).unwrap();
+        let raw: Vec<u8> = fs::read(&tarball).unwrap();
+        let (offset, magic): (usize, &[u8]) = match compression.to_string().as_str() {
+            "none" => (257, b"ustar"),
+            "gzip" => (0, &[0x1F, 0x8B]),
+            "xz" => (0, &[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]),
+            "zstd" => (0, &[0x28, 0xB5, 0x2F, 0xFD]),
+            "bzip2" => (0, b"BZh"),
+            other => panic!("Unknown compression '{other}'"),
+        };
+        assert_eq!(&raw[offset..offset + magic.len()], magic, "{compression}-tarball has the wrong magic");
+        assert_eq!(Compression::detect(&raw[..raw.len().min(Compression::DETECT_LEN)]), Some(compression));
+        assert_eq!(list(&tarball).unwrap(), list_with(&tarball, UnarchiveOptions::new().with_compression(compression)).unwrap());
+
+        // It lists & extracts to the original files
+        let options: UnarchiveOptions = UnarchiveOptions::new().with_compression(compression);
+        assert!(list_with(&tarball, options.clone()).unwrap().contains(&PathBuf::from("src/a/c.txt")));
+        let target: PathBuf = tempdir.path().join(format!("target-{compression}"));
+        unarchive_with(&tarball, &target, options).unwrap();
+        assert_eq!(fs::read_to_string(target.join("src").join("b.txt")).unwrap(), "Hello there!");
+        assert_eq!(fs::read_to_string(target.join("src").join("a").join("c.txt")).unwrap(), "General Kenobi...".repeat(1024));
+
+        // Reading it as another compression fails
+        for other in Compression::ALL.iter().copied().filter(|c| *c != compression && *c != Compression::None) {
+            assert!(list_with(&tarball, UnarchiveOptions::new().with_compression(other)).is_err(), "{compression}-tarball could be read as {other}");
+        }
+    }
+}
+
+/// Test whether compressions are parsed from (and printed as) their names.
+#[test]
+fn test_compression_parse() {
+    for compression in Compression::ALL {
+        assert_eq!(compression.to_string().parse::<Compression>().unwrap(), *compression);
+        assert_eq!(compression.to_string().to_uppercase().parse::<Compression>().unwrap(), *compression);
+    }
+    assert_eq!(Compression::default(), Compression::Gzip);
+    assert_eq!("lzip".parse::<Compression>().unwrap_err().raw, "lzip");
+}
+
+/// Test whether the async drivers read the tarballs of the sync driver (and vice versa) for every available [`Compression`].
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_tarball_compressions_async() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(source.join("a")).unwrap();
+    fs::write(source.join("b.txt"), "Hello there!").unwrap();
+    fs::write(source.join("a").join("c.txt"), "General Kenobi...".repeat(1024)).unwrap();
+
+    for compression in Compression::ALL.iter().copied() {
+        let sync_tar: PathBuf = tempdir.path().join(format!("sync.{}", compression.extension()));
+        let async_tar: PathBuf = tempdir.path().join(format!("async.{}", compression.extension()));
+        archive_with(&source, &sync_tar, ArchiveOptions::new().with_compression(compression)).unwrap();
+        tokio_test::block_on(archive_with_async(&source, &async_tar, ArchiveOptions::new().with_compression(compression))).unwrap();
+
+        // Both list the same entries with either driver
+        let options: UnarchiveOptions = UnarchiveOptions::new().with_compression(compression);
+        let entries: Vec<PathBuf> = list_with(&sync_tar, options.clone()).unwrap();
+        assert_eq!(list_with(&async_tar, options.clone()).unwrap(), entries);
+        assert_eq!(tokio_test::block_on(list_with_async(&sync_tar, options.clone())).unwrap(), entries);
+        assert_eq!(tokio_test::block_on(list_async(&sync_tar)).unwrap(), entries);
+
+        // Both extract to the same files with the other driver
+        let sync_target: PathBuf = tempdir.path().join(format!("sync-{compression}"));
+        let async_target: PathBuf = tempdir.path().join(format!("async-{compression}"));
+        unarchive_with(&async_tar, &sync_target, options.clone()).unwrap();
+        tokio_test::block_on(unarchive_with_async(&sync_tar, &async_target, options)).unwrap();
+        for target in [sync_target, async_target] {
+            assert_eq!(fs::read_to_string(target.join("src").join("a").join("c.txt")).unwrap(), "General Kenobi...".repeat(1024));
+        }
+    }
+}
+
+/// Test whether the `async-smol` functions produce the same tarballs as the sync ones for every available [`Compression`].
+#[cfg(feature = "async-smol")]
+#[test]
+fn test_tarball_compressions_smol() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("b.txt"), "Hello there!").unwrap();
+
+    for compression in Compression::ALL.iter().copied() {
+        let sync_tar: PathBuf = tempdir.path().join(format!("sync.{}", compression.extension()));
+        let smol_tar: PathBuf = tempdir.path().join(format!("smol.{}", compression.extension()));
+        archive_with(&source, &sync_tar, ArchiveOptions::new().with_compression(compression)).unwrap();
+        futures_lite::future::block_on(archive_with_smol(&source, &smol_tar, ArchiveOptions::new().with_compression(compression))).unwrap();
+        assert_eq!(fs::read(&sync_tar).unwrap(), fs::read(&smol_tar).unwrap());
+
+        let options: UnarchiveOptions = UnarchiveOptions::new().with_compression(compression);
+        assert_eq!(futures_lite::future::block_on(list_with_smol(&smol_tar, options.clone())).unwrap(), list_with(&sync_tar, options.clone()).unwrap());
+        let target: PathBuf = tempdir.path().join(format!("target-{compression}"));
+        futures_lite::future::block_on(unarchive_with_smol(&smol_tar, &target, options)).unwrap();
+        assert_eq!(fs::read_to_string(target.join("src").join("b.txt")).unwrap(), "Hello there!");
+    }
+}
+
+
+/// Test whether every available [`Compression`] archives with any of its levels, and refuses levels it doesn't support.
+#[test]
+fn test_tarball_levels() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("b.txt"), "General Kenobi...".repeat(1024)).unwrap();
+
+    for compression in Compression::ALL.iter().copied() {
+        let levels: std::ops::RangeInclusive<u32> = compression.levels();
+        assert!(levels.contains(&compression.default_level()));
+        for level in [*levels.start(), *levels.end()] {
+            let tarball: PathBuf = tempdir.path().join(format!("src-{level}.{}", compression.extension()));
+            archive_with(&source, &tarball, ArchiveOptions::new().with_compression(compression).with_level(level)).unwrap();
+            assert!(list(&tarball).unwrap().contains(&PathBuf::from("src/b.txt")));
+        }
+
+        // Levels out of range are refused before anything is created
+        let tarball: PathBuf = tempdir.path().join(format!("invalid.{}", compression.extension()));
+        match archive_with(&source, &tarball, ArchiveOptions::new().with_compression(compression).with_level(levels.end() + 1)) {
+            Err(Error::InvalidCompressionLevel { compression: got, level }) => {
+                assert_eq!(got, compression);
+                assert_eq!(level, levels.end() + 1);
+            },
+            res => panic!("Expected an invalid compression level for {compression}, got {res:?}"),
+        }
+        assert!(!tarball.exists());
+    }
+
+    // The level matters
+    let fast: PathBuf = tempdir.path().join("fast.tar.gz");
+    let best: PathBuf = tempdir.path().join("best.tar.gz");
+    archive_with(&source, &fast, ArchiveOptions::new().with_level(0)).unwrap();
+    archive_with(&source, &best, ArchiveOptions::new()).unwrap();
+    assert!(fs::metadata(&fast).unwrap().len() > fs::metadata(&best).unwrap().len());
+    assert_eq!(
+        Error::InvalidCompressionLevel { compression: Compression::Gzip, level: 10 }.to_string(),
+        "Compression level 10 is not supported by gzip (expected 0 to 9)"
+    );
+}
+
+/// Test whether parallel gzip compression produces a single, standard gzip member with the same contents as the normal one.
+#[test]
+fn test_tarball_parallel() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(source.join("a")).unwrap();
+    let mut random: Vec<u8> = vec![0; 1024 * 1024];
+    rand::rng().fill_bytes(&mut random);
+    fs::write(source.join("random.bin"), &random).unwrap();
+    fs::write(source.join("a").join("c.txt"), "General Kenobi...".repeat(256 * 1024)).unwrap();
+    fs::write(source.join("empty.txt"), "").unwrap();
+
+    // Compress it both ways, at a few levels
+    let serial: PathBuf = tempdir.path().join("serial.tar.gz");
+    archive_with(&source, &serial, ArchiveOptions::new()).unwrap();
+    let mut expected: Vec<u8> = Vec::new();
+    flate2::read::GzDecoder::new(fs::File::open(&serial).unwrap()).read_to_end(&mut expected).unwrap();
+    for level in [0, 1, 9] {
+        let parallel: PathBuf = tempdir.path().join(format!("parallel-{level}.tar.gz"));
+        archive_with(&source, &parallel, ArchiveOptions::new().with_level(level).with_parallel(true)).unwrap();
+
+        // A decoder that only reads the first member sees the whole tarball, and nothing trails it
+        let raw: Vec<u8> = fs::read(&parallel).unwrap();
+        let mut dec: flate2::bufread::GzDecoder<&[u8]> = flate2::bufread::GzDecoder::new(&raw[..]);
+        let mut got: Vec<u8> = Vec::new();
+        dec.read_to_end(&mut got).unwrap();
+        assert!(got == expected, "Parallel tarball at level {level} decompresses to something else");
+        assert!(dec.into_inner().is_empty(), "Parallel tarball at level {level} has trailing bytes");
+
+        // We can read it ourselves too
+        assert_eq!(Compression::detect(&raw[..Compression::DETECT_LEN]), Some(Compression::Gzip));
+        let target: PathBuf = tempdir.path().join(format!("target-{level}"));
+        unarchive(&parallel, &target).unwrap();
+        assert_eq!(fs::read(target.join("src").join("random.bin")).unwrap(), random);
+        assert_eq!(fs::read_to_string(target.join("src").join("a").join("c.txt")).unwrap(), "General Kenobi...".repeat(256 * 1024));
+        assert_eq!(fs::read(target.join("src").join("empty.txt")).unwrap(), b"");
+    }
+
+    // Parallelism is ignored by other compressions
+    let tarball: PathBuf = tempdir.path().join("parallel.tar");
+    archive_with(&source, &tarball, ArchiveOptions::new().with_compression(Compression::None).with_parallel(true)).unwrap();
+    assert_eq!(fs::read(&tarball).unwrap(), expected);
+
+    // An empty tarball is fine too
+    let empty: PathBuf = tempdir.path().join("empty");
+    fs::create_dir(&empty).unwrap();
+    let tarball: PathBuf = tempdir.path().join("empty.tar.gz");
+    archive_with(&empty, &tarball, ArchiveOptions::new().with_skip_root_dir(true).with_parallel(true)).unwrap();
+    assert_eq!(list(&tarball).unwrap(), Vec::<PathBuf>::new());
+}
+
+/// Test whether the async driver compresses in parallel to a single gzip member with the same contents as its normal compression.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_tarball_parallel_async() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("b.txt"), "General Kenobi...".repeat(256 * 1024)).unwrap();
+
+    let serial: PathBuf = tempdir.path().join("serial.tar.gz");
+    let async_tar: PathBuf = tempdir.path().join("async.tar.gz");
+    tokio_test::block_on(archive_with_async(&source, &serial, ArchiveOptions::new())).unwrap();
+    tokio_test::block_on(archive_with_async(&source, &async_tar, ArchiveOptions::new().with_level(6).with_parallel(true))).unwrap();
+    let mut expected: Vec<u8> = Vec::new();
+    flate2::read::GzDecoder::new(fs::File::open(&serial).unwrap()).read_to_end(&mut expected).unwrap();
+    let raw: Vec<u8> = fs::read(&async_tar).unwrap();
+    let mut dec: flate2::bufread::GzDecoder<&[u8]> = flate2::bufread::GzDecoder::new(&raw[..]);
+    let mut got: Vec<u8> = Vec::new();
+    dec.read_to_end(&mut got).unwrap();
+    assert!(got == expected, "Parallel async tarball decompresses to something else");
+    assert!(dec.into_inner().is_empty(), "Parallel async tarball has trailing bytes");
+
+    let target: PathBuf = tempdir.path().join("target");
+    tokio_test::block_on(unarchive_async(&async_tar, &target)).unwrap();
+    assert_eq!(fs::read_to_string(target.join("src").join("b.txt")).unwrap(), "General Kenobi...".repeat(256 * 1024));
+    assert!(matches!(
+        tokio_test::block_on(archive_with_async(&source, tempdir.path().join("invalid.tar.gz"), ArchiveOptions::new().with_level(10))),
+        Err(Error::InvalidCompressionLevel { .. })
+    ));
+}
+
+
+/// Test whether files that aren't (supported) tarballs are refused with [`Error::UnknownArchiveFormat`] when detecting their compression.
+#[test]
+fn test_tarball_unknown_format() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let mut random: Vec<u8> = vec![0; 1024];
+    rand::rng().fill_bytes(&mut random);
+    random[0] = 0x00;
+    for (name, contents) in [("empty", &b""[..]), ("html", b"<!DOCTYPE html><html><body>Not found</body></html>"), ("random", &random)] {
+        let tarball: PathBuf = tempdir.path().join(name);
+        fs::write(&tarball, contents).unwrap();
+
+        let magic: Vec<u8> = contents[..contents.len().min(8)].to_vec();
+        match list(&tarball) {
+            Err(Error::UnknownArchiveFormat { tarball: path, magic: got }) => {
+                assert_eq!(path, tarball);
+                assert_eq!(got, magic);
+            },
+            res => panic!("Expected an unknown archive format for {name}, got {res:?}"),
+        }
+        assert!(matches!(unarchive(&tarball, tempdir.path().join(format!("{name}-target"))), Err(Error::UnknownArchiveFormat { .. })));
+        #[cfg(feature = "async-tokio")]
+        {
+            assert!(matches!(tokio_test::block_on(list_async(&tarball)), Err(Error::UnknownArchiveFormat { .. })));
+            let target: PathBuf = tempdir.path().join(format!("{name}-async-target"));
+            assert!(matches!(tokio_test::block_on(unarchive_async(&tarball, target)), Err(Error::UnknownArchiveFormat { .. })));
+        }
+
+        // Unless we force a compression, in which case it fails to decode instead
+        assert!(!matches!(list_with(&tarball, UnarchiveOptions::new().with_compression(Compression::Gzip)), Err(Error::UnknownArchiveFormat { .. })));
+    }
+    assert_eq!(
+        Error::UnknownArchiveFormat { tarball: "a.tar".into(), magic: b"<!DOCTYP".to_vec() }.to_string(),
+        "Unknown archive format of tarball 'a.tar' (starts with 3c 21 44 4f 43 54 59 50)"
+    );
+}
+
+/// Test whether tarballs compressed with a codec whose feature is disabled are refused with a hint to enable it.
+#[cfg(not(feature = "xz"))]
+#[test]
+fn test_tarball_disabled_format() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let tarball: PathBuf = tempdir.path().join("src.tar.xz");
+    fs::write(&tarball, [0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00, 0x00, 0x04, 0xE6, 0xD6]).unwrap();
+    let err: Error = list(&tarball).unwrap_err();
+    assert!(matches!(err, Error::UnknownArchiveFormat { .. }));
+    assert!(err.to_string().ends_with("it looks xz-compressed, but the 'xz'-feature is not enabled"), "{err}");
+}
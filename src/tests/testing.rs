@@ -0,0 +1,91 @@
+//  TESTING.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 18:51:26
+//  Last edited:
+//    18 Oct 2026, 18:51:26
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines unit tests for the [`crate::testing::TestServer`] itself,
+//!   covering the HTTP-features that the download functions don't use.
+//
+
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+
+use crate::testing::{Route, TestServer};
+
+
+/***** CONSTANTS *****/
+/// The body served by the test servers.
+const BODY: &[u8] = b"Hello there!\nGeneral Kenobi...\n...you are a bold one\n";
+
+
+
+
+
+/***** TESTS *****/
+/// Test whether range requests are supported.
+#[test]
+fn test_server_ranges() {
+    let server: TestServer = TestServer::spawn().unwrap().with_route("/file", Route::bytes(BODY).with_ranges()).with_route("/plain", Route::bytes(BODY));
+    let client: Client = Client::new();
+
+    let res: Response = client.get(server.url("/file")).header("Range", "bytes=6-11").send().unwrap();
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers()["Content-Range"], format!("bytes 6-11/{}", BODY.len()));
+    assert_eq!(res.bytes().unwrap(), &BODY[6..12]);
+
+    let res: Response = client.get(server.url("/file")).header("Range", "bytes=-4").send().unwrap();
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.bytes().unwrap(), &BODY[BODY.len() - 4..]);
+
+    let res: Response = client.get(server.url("/file")).header("Range", format!("bytes={}-", BODY.len())).send().unwrap();
+    assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+
+    // Routes without range support ignore the header
+    let res: Response = client.get(server.url("/plain")).header("Range", "bytes=6-11").send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.bytes().unwrap(), BODY);
+}
+
+/// Test whether ETags are supported, both for conditional requests and for ranges.
+#[test]
+fn test_server_etag() {
+    let server: TestServer = TestServer::spawn().unwrap().with_route("/file", Route::bytes(BODY).with_etag("\"v1\"").with_ranges());
+    let client: Client = Client::new();
+
+    let res: Response = client.get(server.url("/file")).send().unwrap();
+    assert_eq!(res.headers()["ETag"], "\"v1\"");
+    let res: Response = client.get(server.url("/file")).header("If-None-Match", "\"v1\"").send().unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    let res: Response = client.get(server.url("/file")).header("If-None-Match", "\"v0\"").send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // Ranges are only served if the ETag still matches
+    let res: Response = client.get(server.url("/file")).header("Range", "bytes=6-").header("If-Range", "\"v1\"").send().unwrap();
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    let res: Response = client.get(server.url("/file")).header("Range", "bytes=6-").header("If-Range", "\"v0\"").send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.bytes().unwrap(), BODY);
+}
+
+/// Test whether requests are recorded and `HEAD`-requests are answered without a body.
+#[test]
+fn test_server_requests() {
+    let server: TestServer = TestServer::spawn().unwrap().with_route("/file", Route::bytes(BODY));
+    let client: Client = Client::new();
+
+    let res: Response = client.head(server.url("/file")).header("X-Test", "yes").send().unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["Content-Length"], BODY.len().to_string());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "HEAD");
+    assert_eq!(requests[0].path, "/file");
+    assert_eq!(requests[0].header("x-test"), Some("yes"));
+}
@@ -0,0 +1,222 @@
+//  TLS.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 13:02:11
+//  Last edited:
+//    18 Oct 2026, 13:02:11
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines unit tests for the TLS-related parts of downloading (custom roots and certificate pinning).
+//!   
+//!   These run against a local HTTPS server with a freshly generated CA.
+//
+
+use std::io::{Read as _, Write as _};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::{fs, thread};
+
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use sha2::{Digest as _, Sha256};
+use tempfile::TempDir;
+
+use crate::*;
+
+
+/***** CONSTANTS *****/
+/// The body served by the test servers.
+const BODY: &[u8] = b"Hello there!\nGeneral Kenobi...\n...you are a bold one\n";
+
+
+
+
+
+/***** HELPERS *****/
+/// Describes a running HTTPS test server (see [`spawn_tls_server()`]).
+struct TlsServer {
+    /// The URL where the server serves [`BODY`].
+    url:    String,
+    /// The PEM-encoded certificate of the CA that signed the server's certificate.
+    ca_pem: String,
+    /// The SPKI pin of the server's certificate.
+    pin:    [u8; 32],
+}
+
+/// Spawns a minimal HTTPS server on localhost that serves [`BODY`] for every request.
+///
+/// Its certificate is signed by a freshly generated CA, such that it is only trusted when that CA is given as a root.
+///
+/// # Returns
+/// A [`TlsServer`] describing how to reach and trust the server. The server lives until the test process exits.
+///
+/// # Panics
+/// This function panics if we failed to generate the certificates or bind the server.
+fn spawn_tls_server() -> TlsServer {
+    // Generate a CA and a certificate for localhost signed by it
+    let ca_key: KeyPair = KeyPair::generate().unwrap_or_else(|err| panic!("Failed to generate CA key: {err}"));
+    let mut ca_params: CertificateParams = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params.distinguished_name.push(DnType::CommonName, "download-rs test CA");
+    let ca = ca_params.self_signed(&ca_key).unwrap_or_else(|err| panic!("Failed to self-sign CA certificate: {err}"));
+    let key: KeyPair = KeyPair::generate().unwrap_or_else(|err| panic!("Failed to generate server key: {err}"));
+    let cert = CertificateParams::new(vec!["localhost".into()])
+        .unwrap()
+        .signed_by(&key, &ca, &ca_key)
+        .unwrap_or_else(|err| panic!("Failed to sign server certificate: {err}"));
+
+    // Build the server config with it
+    let config: Arc<ServerConfig> = Arc::new(
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.der().clone()], PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())))
+            .unwrap_or_else(|err| panic!("Failed to build server TLS config: {err}")),
+    );
+
+    // Serve in a separate thread
+    let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap_or_else(|err| panic!("Failed to bind test server: {err}"));
+    let port: u16 = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let mut stream = StreamOwned::new(ServerConnection::new(config.clone()).unwrap(), stream);
+
+            // Read the request head (failing handshakes end up here too)
+            let mut head: Vec<u8> = Vec::new();
+            let mut buf: [u8; 1024] = [0; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => head.extend_from_slice(&buf[..n]),
+                }
+            }
+            if head.is_empty() {
+                continue;
+            }
+
+            // Write the response
+            let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", BODY.len());
+            let _ = stream.write_all(BODY);
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+        }
+    });
+
+    // Done
+    TlsServer { url: format!("https://localhost:{port}/file"), ca_pem: ca.pem(), pin: Sha256::digest(key.public_key_der()).into() }
+}
+
+/// Runs a download against the given server with the given security, in either sync or async mode.
+///
+/// # Arguments
+/// - `server`: The [`TlsServer`] to download from.
+/// - `security`: The [`DownloadSecurity`] to download with.
+/// - `use_async`: Whether to use [`download_file_async()`] instead of [`download_file()`].
+///
+/// # Returns
+/// The result of the download, and the downloaded file's contents if it succeeded.
+fn download(server: &TlsServer, security: DownloadSecurity, use_async: bool) -> Result<Vec<u8>, Error> {
+    let tempdir: TempDir = TempDir::new().unwrap_or_else(|err| panic!("Failed to create a temporary directory: {err}"));
+    let target: PathBuf = tempdir.path().join("file");
+
+    #[cfg(feature = "async-tokio")]
+    if use_async {
+        tokio_test::block_on(download_file_async(&server.url, &target, security, None))?;
+        return Ok(fs::read(&target).unwrap_or_else(|err| panic!("Failed to read downloaded file '{}': {}", target.display(), err)));
+    }
+    #[cfg(not(feature = "async-tokio"))]
+    let _ = use_async;
+    download_file(&server.url, &target, security, None)?;
+    Ok(fs::read(&target).unwrap_or_else(|err| panic!("Failed to read downloaded file '{}': {}", target.display(), err)))
+}
+
+
+
+
+
+/***** TESTS *****/
+/// Test whether a server signed by an unknown CA is rejected.
+#[test]
+fn test_tls_untrusted_root() {
+    let server: TlsServer = spawn_tls_server();
+    match download(&server, DownloadSecurity::https(), false) {
+        Err(Error::RequestExecute { .. }) => {},
+        got => panic!("Expected untrusted certificate to fail with RequestExecute, got {got:?}"),
+    }
+}
+
+/// Test whether a server signed by an extra root is accepted.
+#[test]
+fn test_tls_extra_root() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig = TlsConfig::default().with_root(RootCertificate::Pem(server.ca_pem.clone().into_bytes()));
+    assert_eq!(download(&server, DownloadSecurity::https().with_tls(tls), false).unwrap(), BODY);
+}
+
+/// Test whether a server signed by an extra root is still accepted without the built-in roots.
+#[test]
+fn test_tls_extra_root_only() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig = TlsConfig::default().with_builtin_roots(false).with_root(RootCertificate::Pem(server.ca_pem.clone().into_bytes()));
+    assert_eq!(download(&server, DownloadSecurity::https().with_tls(tls), false).unwrap(), BODY);
+}
+
+/// Test whether an invalid extra root is reported as such.
+#[test]
+fn test_tls_extra_root_invalid() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig = TlsConfig::default().with_root(RootCertificate::Der(b"Definitely not a certificate".to_vec()));
+    match download(&server, DownloadSecurity::https().with_tls(tls), false) {
+        // Note: rustls only parses DER-certificates when the client is built
+        Err(Error::TlsRootCertificate { index: 0, .. } | Error::ClientCreate { .. }) => {},
+        got => panic!("Expected invalid root to fail with TlsRootCertificate or ClientCreate, got {got:?}"),
+    }
+}
+
+/// Test whether a matching pin is accepted.
+#[test]
+fn test_tls_pin() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig =
+        TlsConfig::default().with_root(RootCertificate::Pem(server.ca_pem.clone().into_bytes())).with_spki_pin([0; 32]).with_spki_pin(server.pin);
+    assert_eq!(download(&server, DownloadSecurity::https().with_tls(tls), false).unwrap(), BODY);
+}
+
+/// Test whether a mismatching pin is rejected.
+#[test]
+fn test_tls_pin_mismatch() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig = TlsConfig::default().with_root(RootCertificate::Pem(server.ca_pem.clone().into_bytes())).with_spki_pin([0; 32]);
+    match download(&server, DownloadSecurity::https().with_tls(tls), false) {
+        Err(Error::SecurityCertificatePin { got, .. }) => assert_eq!(got, Some(hex::encode(server.pin))),
+        got => panic!("Expected pin mismatch to fail with SecurityCertificatePin, got {got:?}"),
+    }
+}
+
+/// Test whether a matching pin is accepted in async mode.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_tls_pin_async() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig = TlsConfig::default().with_root(RootCertificate::Pem(server.ca_pem.clone().into_bytes())).with_spki_pin(server.pin);
+    assert_eq!(download(&server, DownloadSecurity::https().with_tls(tls), true).unwrap(), BODY);
+}
+
+/// Test whether a mismatching pin is rejected in async mode.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_tls_pin_mismatch_async() {
+    let server: TlsServer = spawn_tls_server();
+    let tls: TlsConfig = TlsConfig::default().with_root(RootCertificate::Pem(server.ca_pem.clone().into_bytes())).with_spki_pin([0; 32]);
+    match download(&server, DownloadSecurity::https().with_tls(tls), true) {
+        Err(Error::SecurityCertificatePin { .. }) => {},
+        got => panic!("Expected pin mismatch to fail with SecurityCertificatePin, got {got:?}"),
+    }
+}
@@ -0,0 +1,316 @@
+//  TRANSPORT.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 17:41:09
+//  Last edited:
+//    18 Oct 2026, 17:41:09
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines some (lengthier) unit tests for the download functions,
+//!   using the [`crate::testing::MockTransport`] instead of a network.
+//
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use sha2::{Digest as _, Sha256};
+use tempfile::TempDir;
+
+use crate::testing::{MockResponse, MockTransport};
+use crate::{
+    download_file_with_transport, ensure_file_with_transport, probe_with_transport, ContentCheck, ContentViolation, DownloadSecurity, Ensured, Error, Probe,
+    RedirectPolicy, RedirectViolation,
+};
+
+
+/***** TESTS *****/
+/// Test whether a plain download works and is checked against its checksum.
+#[test]
+fn test_mock_download() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport =
+        MockTransport::new().with_response("https://example.com/file.txt", MockResponse::ok("Hello there!\n").with_chunk_size(3));
+
+    let checksum: [u8; 32] = Sha256::digest("Hello there!\n").into();
+    download_file_with_transport(&transport, "https://example.com/file.txt", &target, DownloadSecurity::all(&checksum), None).unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!\n");
+    assert_eq!(transport.requests().len(), 1);
+
+    // A wrong checksum is caught
+    match download_file_with_transport(&transport, "https://example.com/file.txt", &target, DownloadSecurity::checksum(&[0; 32]), None) {
+        Err(Error::SecurityChecksum { got, .. }) => assert_eq!(got, hex::encode(checksum)),
+        got => panic!("Expected wrong checksum to fail with SecurityChecksum, got {got:?}"),
+    }
+}
+
+/// Test whether HTTPS is enforced for every hop of a redirect.
+#[test]
+fn test_mock_https_redirect() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/a", MockResponse::redirect("/b"))
+        .with_response("https://example.com/b", MockResponse::redirect("http://example.com/c"))
+        .with_response("http://example.com/c", MockResponse::ok("Hello there!\n"));
+
+    match download_file_with_transport(&transport, "https://example.com/a", &target, DownloadSecurity::https(), None) {
+        Err(Error::SecurityNoHttps { url, chain }) => {
+            assert_eq!(url, "http://example.com/c");
+            assert_eq!(chain, vec!["https://example.com/a".to_string(), "https://example.com/b".to_string()]);
+        },
+        got => panic!("Expected downgrade to fail with SecurityNoHttps, got {got:?}"),
+    }
+    // We never even asked the HTTP-server
+    assert_eq!(transport.requests().len(), 2);
+}
+
+/// Test whether the redirect policy is enforced.
+#[test]
+fn test_mock_redirect_policy() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/a", MockResponse::redirect("https://example.com/b"))
+        .with_response("https://example.com/b", MockResponse::redirect("https://mirror.example.com/c"))
+        .with_response("https://mirror.example.com/c", MockResponse::ok("Hello there!\n"));
+
+    // By default, this is fine
+    download_file_with_transport(&transport, "https://example.com/a", &target, DownloadSecurity::none(), None).unwrap();
+
+    // But not if we stay on the same origin...
+    let security: DownloadSecurity = DownloadSecurity::none().with_redirects(RedirectPolicy::same_origin(10));
+    match download_file_with_transport(&transport, "https://example.com/a", &target, security, None) {
+        Err(Error::RedirectNotAllowed { reason: RedirectViolation::CrossOrigin { .. }, .. }) => {},
+        got => panic!("Expected cross-origin redirect to fail with RedirectNotAllowed, got {got:?}"),
+    }
+
+    // ...or only allow a single hop
+    let security: DownloadSecurity = DownloadSecurity::none().with_redirects(RedirectPolicy::limited(1));
+    match download_file_with_transport(&transport, "https://example.com/a", &target, security, None) {
+        Err(Error::RedirectNotAllowed { reason: RedirectViolation::TooManyHops { max: 1 }, .. }) => {},
+        got => panic!("Expected second redirect to fail with RedirectNotAllowed, got {got:?}"),
+    }
+}
+
+/// Test whether non-OK responses are reported with their body.
+#[test]
+fn test_mock_not_ok() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let mut res: MockResponse = MockResponse::ok("Nothing to see here");
+    res.status = StatusCode::NOT_FOUND;
+    let transport: MockTransport = MockTransport::new().with_response("https://example.com/file.txt", res);
+
+    match download_file_with_transport(&transport, "https://example.com/file.txt", &target, DownloadSecurity::none(), None) {
+        Err(Error::ResponseNotOk { code, response, .. }) => {
+            assert_eq!(code, StatusCode::NOT_FOUND);
+            assert_eq!(response.as_deref(), Some("Nothing to see here"));
+        },
+        got => panic!("Expected 404 to fail with ResponseNotOk, got {got:?}"),
+    }
+}
+
+/// Test whether the maximum size is enforced, both up front and while streaming.
+#[test]
+fn test_mock_too_large() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/announced", MockResponse::ok(vec![0; 1024]))
+        .with_response("https://example.com/streamed", MockResponse::ok(vec![0; 1024]).without_content_length().with_chunk_size(100));
+
+    match download_file_with_transport(&transport, "https://example.com/announced", &target, DownloadSecurity::none().with_max_bytes(512), None) {
+        Err(Error::ResponseTooLarge { content_length: Some(1024), .. }) => {},
+        got => panic!("Expected announced response to fail with ResponseTooLarge, got {got:?}"),
+    }
+    assert!(!target.exists());
+    match download_file_with_transport(&transport, "https://example.com/streamed", &target, DownloadSecurity::none().with_max_bytes(512), None) {
+        Err(Error::ResponseTooLarge { content_length: None, .. }) => {},
+        got => panic!("Expected streamed response to fail with ResponseTooLarge, got {got:?}"),
+    }
+    assert!(!target.exists());
+}
+
+/// Test whether transport failures are propagated.
+#[test]
+fn test_mock_failures() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/unreachable", MockResponse::failure("connection refused"))
+        .with_response("https://example.com/reset", MockResponse::ok(vec![0; 1024]).with_chunk_size(100).with_body_failure_after(300));
+
+    match download_file_with_transport(&transport, "https://example.com/unreachable", &target, DownloadSecurity::none(), None) {
+        Err(Error::Transport { err, .. }) => assert_eq!(err.to_string(), "connection refused"),
+        got => panic!("Expected unreachable server to fail with Transport, got {got:?}"),
+    }
+    match download_file_with_transport(&transport, "https://example.com/reset", &target, DownloadSecurity::none(), None) {
+        Err(Error::ResponseDownload { .. }) => {},
+        got => panic!("Expected reset connection to fail with ResponseDownload, got {got:?}"),
+    }
+    match download_file_with_transport(&transport, "https://example.com/unscripted", &target, DownloadSecurity::none(), None) {
+        Err(Error::Transport { .. }) => {},
+        got => panic!("Expected unscripted URL to fail with Transport, got {got:?}"),
+    }
+}
+
+/// Test whether responses are returned in order, and whether delays are applied.
+#[test]
+fn test_mock_script() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/file.txt", MockResponse::status(StatusCode::SERVICE_UNAVAILABLE))
+        .with_response("https://example.com/file.txt", MockResponse::ok("Hello there!\n").with_delay(Duration::from_millis(100)));
+
+    assert!(matches!(
+        download_file_with_transport(&transport, "https://example.com/file.txt", &target, DownloadSecurity::none(), None),
+        Err(Error::ResponseNotOk { .. })
+    ));
+    let start: Instant = Instant::now();
+    download_file_with_transport(&transport, "https://example.com/file.txt", &target, DownloadSecurity::none(), None).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!\n");
+}
+
+/// Test whether `ensure_file_with_transport()` only downloads when necessary.
+#[test]
+fn test_mock_ensure() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new().with_response("https://example.com/file.txt", MockResponse::ok("Hello there!\n"));
+    let checksum: [u8; 32] = Sha256::digest("Hello there!\n").into();
+
+    let security: DownloadSecurity = DownloadSecurity::checksum(&checksum);
+    assert_eq!(ensure_file_with_transport(&transport, "https://example.com/file.txt", &target, security.clone(), None).unwrap(), Ensured::Downloaded);
+    assert_eq!(ensure_file_with_transport(&transport, "https://example.com/file.txt", &target, security.clone(), None).unwrap(), Ensured::AlreadyPresent);
+    assert_eq!(transport.requests().len(), 1);
+
+    // Corrupting the file makes us download it again
+    fs::write(&target, "General Kenobi!\n").unwrap();
+    assert_eq!(ensure_file_with_transport(&transport, "https://example.com/file.txt", &target, security, None).unwrap(), Ensured::Downloaded);
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!\n");
+    assert_eq!(transport.requests().len(), 2);
+}
+
+/// Test whether the `async-smol` functions can use a custom transport.
+#[cfg(feature = "async-smol")]
+#[test]
+fn test_mock_smol() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: std::sync::Arc<MockTransport> = std::sync::Arc::new(
+        MockTransport::new()
+            .with_response("https://example.com/a", MockResponse::redirect("/file.txt"))
+            .with_response("https://example.com/file.txt", MockResponse::ok("Hello there!\n").with_chunk_size(3)),
+    );
+
+    let checksum: [u8; 32] = Sha256::digest("Hello there!\n").into();
+    futures_lite::future::block_on(crate::download_file_with_transport_smol(
+        transport.clone(),
+        "https://example.com/a",
+        &target,
+        DownloadSecurity::all(&checksum),
+        None,
+    ))
+    .unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!\n");
+    assert_eq!(transport.requests().len(), 2);
+}
+
+/// Test whether downloads that don't fit on the target's filesystem are refused up front.
+#[cfg(unix)]
+#[test]
+fn test_mock_no_space() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let huge: String = (u64::MAX / 2).to_string();
+    let transport: MockTransport =
+        MockTransport::new().with_response("https://example.com/huge", MockResponse::ok("Hello there!\n").with_header("Content-Length", &huge));
+
+    match download_file_with_transport(&transport, "https://example.com/huge", &target, DownloadSecurity::none(), None) {
+        Err(Error::TargetNoSpace { needed, available: Some(_), .. }) => assert_eq!(needed, u64::MAX / 2),
+        got => panic!("Expected huge response to fail with TargetNoSpace, got {got:?}"),
+    }
+    assert!(!target.exists());
+
+    // Without the check, we trust the server's lies
+    download_file_with_transport(&transport, "https://example.com/huge", &target, DownloadSecurity::none().with_free_space_margin(None), None).unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!\n");
+
+    // The margin counts too
+    let transport: MockTransport = MockTransport::new().with_response("https://example.com/file.txt", MockResponse::ok("Hello there!\n"));
+    let security: DownloadSecurity = DownloadSecurity::none().with_free_space_margin(Some(u64::MAX / 2));
+    assert!(matches!(
+        download_file_with_transport(&transport, "https://example.com/file.txt", &target, security, None),
+        Err(Error::TargetNoSpace { .. })
+    ));
+}
+
+/// Test whether preallocating doesn't change the downloaded file, even if the server announces the wrong length.
+#[test]
+fn test_mock_preallocate() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.txt");
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/file.txt", MockResponse::ok(vec![42; 100_000]).with_chunk_size(4096))
+        .with_response("https://example.com/short", MockResponse::ok("Hello there!\n").with_header("Content-Length", "65536"));
+
+    download_file_with_transport(&transport, "https://example.com/file.txt", &target, DownloadSecurity::none().with_preallocate(), None).unwrap();
+    assert_eq!(fs::read(&target).unwrap(), vec![42; 100_000]);
+    download_file_with_transport(&transport, "https://example.com/short", &target, DownloadSecurity::none().with_preallocate(), None).unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello there!\n");
+}
+
+/// Test whether probing works with transports that only implement GET-requests.
+#[test]
+fn test_mock_probe() {
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/a", MockResponse::redirect("/file.txt"))
+        .with_response(
+            "https://example.com/file.txt",
+            MockResponse::ok("Hello there!\n").with_header("Content-Length", "13").with_header("Content-Type", "text/plain"),
+        );
+
+    let probe: Probe = probe_with_transport(&transport, "https://example.com/a", DownloadSecurity::https()).unwrap();
+    assert!(probe.exists());
+    assert_eq!(probe.url.as_str(), "https://example.com/file.txt");
+    assert_eq!(probe.content_length, Some(13));
+    assert_eq!(probe.content_type.as_deref(), Some("text/plain"));
+    assert!(!probe.ranges);
+    assert_eq!(transport.requests().len(), 2);
+}
+
+/// Test whether the content check looks at enough of the body, regardless of how it is chunked.
+#[test]
+fn test_mock_content() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let target: PathBuf = tempdir.path().join("file.tar.gz");
+    let padded_page: String = format!("{}<html>Not Found</html>", " ".repeat(600));
+    let transport: MockTransport = MockTransport::new()
+        .with_response("https://example.com/short.gz", MockResponse::ok(vec![0x1F, 0x8B]).with_chunk_size(1))
+        .with_response("https://example.com/long.gz", MockResponse::ok([&[0x1F, 0x8B][..], &[0; 2048]].concat()).with_chunk_size(3))
+        .with_response("https://example.com/empty.gz", MockResponse::ok(Vec::new()))
+        .with_response("https://example.com/page.gz", MockResponse::ok(padded_page).with_chunk_size(7));
+
+    let security: DownloadSecurity = DownloadSecurity::none().with_content(ContentCheck::gzip());
+    download_file_with_transport(&transport, "https://example.com/short.gz", &target, security.clone(), None).unwrap();
+    download_file_with_transport(&transport, "https://example.com/long.gz", &target, security.clone(), None).unwrap();
+    assert_eq!(fs::read(&target).unwrap().len(), 2050);
+    match download_file_with_transport(&transport, "https://example.com/empty.gz", &target, security.clone(), None) {
+        Err(Error::UnexpectedContent { reason: ContentViolation::Magic { got, .. }, snippet, .. }) => assert_eq!((got, snippet), (vec![], String::new())),
+        got => panic!("Expected empty body to fail with UnexpectedContent, got {got:?}"),
+    }
+
+    // Only the first bytes are sniffed, so HTML after a lot of whitespace is caught by the magic instead
+    match download_file_with_transport(&transport, "https://example.com/page.gz", &target, security, None) {
+        Err(Error::UnexpectedContent { reason: ContentViolation::Magic { .. }, snippet, .. }) => assert_eq!(snippet, format!("{}...", " ".repeat(256))),
+        got => panic!("Expected padded page to fail with UnexpectedContent, got {got:?}"),
+    }
+}
@@ -0,0 +1,256 @@
+//  ZIP.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 19:40:27
+//  Last edited:
+//    18 Oct 2026, 19:40:27
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines some (lengthier) unit tests for the [`crate::zip`]-module.
+//
+
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use ::zip::write::SimpleFileOptions;
+use ::zip::ZipWriter;
+use tempfile::TempDir;
+
+use crate::zip::*;
+
+
+/***** HELPERS *****/
+/// Writes a test directory with nested, empty and (somewhat) large files and directories.
+///
+/// # Arguments
+/// - `source`: The directory to write.
+fn write_source(source: &Path) {
+    for (path, contents) in [("b.txt", "Hello there!".to_string()), ("a/c.txt", "General Kenobi...".repeat(1024)), ("a/b/d.txt", String::new())] {
+        fs::create_dir_all(source.join(path).parent().unwrap()).unwrap();
+        fs::write(source.join(path), contents).unwrap();
+    }
+    fs::create_dir_all(source.join("empty").join("nested")).unwrap();
+}
+
+/// Writes a zip file with entries of the given names, bypassing our own checks.
+///
+/// # Arguments
+/// - `path`: The path of the zip file to write.
+/// - `names`: The names of the entries to add, all with the same contents.
+fn write_fixture(path: &Path, names: &[&str]) {
+    let mut zip: ZipWriter<fs::File> = ZipWriter::new(fs::File::create(path).unwrap());
+    for name in names {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"Gotcha").unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// Asserts that the given target contains the (extracted) source.
+///
+/// # Arguments
+/// - `source`: The original directory.
+/// - `target`: The directory that should contain the same files and directories.
+fn assert_same(source: &Path, target: &Path) {
+    for (path, contents) in [("b.txt", "Hello there!".to_string()), ("a/c.txt", "General Kenobi...".repeat(1024)), ("a/b/d.txt", String::new())] {
+        assert_eq!(fs::read_to_string(target.join(path)).unwrap(), contents, "File '{path}' differs");
+        assert_eq!(fs::metadata(source.join(path)).unwrap().modified().unwrap().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() / 2, {
+            fs::metadata(target.join(path)).unwrap().modified().unwrap().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() / 2
+        });
+    }
+    assert!(target.join("empty").join("nested").is_dir());
+}
+
+
+
+
+
+/***** TESTS *****/
+/// Test whether archiving and unarchiving round-trips, both with and without the root directory.
+#[test]
+fn test_zip_round_trip() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("some").join("extra").join("src");
+    write_source(&source);
+
+    for skip_dir in [false, true] {
+        let zipfile: PathBuf = tempdir.path().join(format!("src-{skip_dir}.zip"));
+        archive(&source, &zipfile, skip_dir).unwrap();
+
+        // The entries are in sorted order, and include (empty) directories
+        let prefix: &str = if skip_dir { "" } else { "src/" };
+        let expected: Vec<PathBuf> = ["", "a/", "a/b/", "a/b/d.txt", "a/c.txt", "b.txt", "empty/", "empty/nested/"]
+            .into_iter()
+            .filter(|p| !skip_dir || !p.is_empty())
+            .map(|p| PathBuf::from(format!("{prefix}{p}")))
+            .collect();
+        assert_eq!(list(&zipfile).unwrap(), expected);
+
+        // Extracting it gives the same files
+        let target: PathBuf = tempdir.path().join(format!("target-{skip_dir}"));
+        unarchive(&zipfile, &target).unwrap();
+        assert_same(&source, &if skip_dir { target.clone() } else { target.join("src") });
+
+        // But not twice
+        assert!(matches!(unarchive(&zipfile, &target), Err(Error::TargetExists { .. })));
+    }
+
+    // Single files work too
+    let zipfile: PathBuf = tempdir.path().join("file.zip");
+    archive(source.join("b.txt"), &zipfile, false).unwrap();
+    assert_eq!(list(&zipfile).unwrap(), vec![PathBuf::from("b.txt")]);
+}
+
+/// Test whether the permissions of files are kept (on Unix).
+#[cfg(unix)]
+#[test]
+fn test_zip_permissions() {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir(&source).unwrap();
+    fs::write(source.join("run.sh"), "#!/bin/sh\necho Hello there!\n").unwrap();
+    fs::set_permissions(source.join("run.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+    fs::write(source.join("data.txt"), "General Kenobi...").unwrap();
+    fs::set_permissions(source.join("data.txt"), fs::Permissions::from_mode(0o640)).unwrap();
+
+    let zipfile: PathBuf = tempdir.path().join("src.zip");
+    archive(&source, &zipfile, true).unwrap();
+    let target: PathBuf = tempdir.path().join("target");
+    unarchive(&zipfile, &target).unwrap();
+    assert_eq!(fs::metadata(target.join("run.sh")).unwrap().permissions().mode() & 0o777, 0o750);
+    assert_eq!(fs::metadata(target.join("data.txt")).unwrap().permissions().mode() & 0o777, 0o640);
+}
+
+/// Test whether entries that would escape the target directory are refused.
+#[test]
+fn test_zip_escape() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    for (i, name) in ["../evil.txt", "a/../../evil.txt", "a/../../../evil.txt"].into_iter().enumerate() {
+        let zipfile: PathBuf = tempdir.path().join(format!("evil-{i}.zip"));
+        write_fixture(&zipfile, &["fine.txt", name]);
+
+        // Listing is fine, unpacking isn't
+        assert_eq!(list(&zipfile).unwrap(), vec![PathBuf::from("fine.txt"), PathBuf::from(name)]);
+        let target: PathBuf = tempdir.path().join(format!("target-{i}"));
+        match unarchive(&zipfile, &target) {
+            Err(Error::SourceZipEntryEscaped { zipfile: path, entry }) => {
+                assert_eq!(path, zipfile);
+                assert_eq!(entry, PathBuf::from(name));
+            },
+            res => panic!("Expected an escaped entry for '{name}', got {res:?}"),
+        }
+        assert!(!tempdir.path().join("evil.txt").exists());
+    }
+
+    // Names that stay inside are fine, and absolute names are made relative to the target
+    let zipfile: PathBuf = tempdir.path().join("fine.zip");
+    write_fixture(&zipfile, &["a/../fine.txt", "./b.txt", "/tmp/abs.txt"]);
+    let target: PathBuf = tempdir.path().join("target-fine");
+    unarchive(&zipfile, &target).unwrap();
+    assert_eq!(fs::read_to_string(target.join("fine.txt")).unwrap(), "Gotcha");
+    assert_eq!(fs::read_to_string(target.join("b.txt")).unwrap(), "Gotcha");
+    assert_eq!(fs::read_to_string(target.join("tmp").join("abs.txt")).unwrap(), "Gotcha");
+}
+
+/// Test whether files that aren't zip files are refused.
+#[test]
+fn test_zip_invalid() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let zipfile: PathBuf = tempdir.path().join("index.html");
+    fs::write(&zipfile, "<!DOCTYPE html><html><body>Not found</body></html>").unwrap();
+    assert!(matches!(list(&zipfile), Err(Error::SourceZipRead { .. })));
+    assert!(matches!(unarchive(&zipfile, tempdir.path().join("target")), Err(Error::SourceZipRead { .. })));
+    assert!(matches!(list(tempdir.path().join("nope.zip")), Err(Error::SourceZipOpen { .. })));
+    assert!(matches!(archive(tempdir.path().join("nope"), tempdir.path().join("nope.zip"), false), Err(Error::SourceNotFound { .. })));
+}
+
+/// Test whether symlinks are refused when archiving, including ones to a parent directory (which would otherwise never end).
+#[cfg(unix)]
+#[test]
+fn test_zip_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let tempdir: TempDir = TempDir::new().unwrap();
+    fs::write(tempdir.path().join("outside.txt"), "General Kenobi...").unwrap();
+    for (name, link) in [("loop", PathBuf::from("..")), ("outside", tempdir.path().join("outside.txt"))] {
+        let source: PathBuf = tempdir.path().join(name);
+        write_source(&source);
+        symlink(&link, source.join("a").join("link")).unwrap();
+        match archive(&source, tempdir.path().join(format!("{name}.zip")), false) {
+            Err(Error::SourceSymlink { path }) => assert_eq!(path, source.join("a").join("link")),
+            got => panic!("Expected symlink to fail with SourceSymlink, got {got:?}"),
+        }
+    }
+
+    // The source itself too
+    symlink(tempdir.path().join("outside.txt"), tempdir.path().join("source")).unwrap();
+    assert!(matches!(archive(tempdir.path().join("source"), tempdir.path().join("source.zip"), false), Err(Error::SourceSymlink { .. })));
+}
+
+/// Test whether modification times survive a round trip through the zip format, which stores them with a precision of two seconds.
+#[test]
+fn test_zip_times() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    fs::create_dir(&source).unwrap();
+    for (name, secs) in [("epoch.txt", 315_532_800), ("leap.txt", 951_782_400), ("recent.txt", 1_792_324_862), ("end.txt", 4_354_819_198)] {
+        let handle: fs::File = fs::File::create(source.join(name)).unwrap();
+        handle.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    }
+
+    let zipfile: PathBuf = tempdir.path().join("src.zip");
+    archive(&source, &zipfile, true).unwrap();
+    let target: PathBuf = tempdir.path().join("target");
+    unarchive(&zipfile, &target).unwrap();
+    for (name, secs) in [("epoch.txt", 315_532_800), ("leap.txt", 951_782_400), ("recent.txt", 1_792_324_862), ("end.txt", 4_354_819_198)] {
+        assert_eq!(fs::metadata(target.join(name)).unwrap().modified().unwrap(), SystemTime::UNIX_EPOCH + Duration::from_secs(secs), "{name}");
+    }
+}
+
+/// Test whether the async drivers produce the same zip files, and extract them in the same way.
+#[cfg(feature = "async-tokio")]
+#[test]
+fn test_zip_async() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    write_source(&source);
+
+    let sync_zip: PathBuf = tempdir.path().join("sync.zip");
+    let async_zip: PathBuf = tempdir.path().join("async.zip");
+    archive(&source, &sync_zip, false).unwrap();
+    tokio_test::block_on(archive_async(&source, &async_zip, false)).unwrap();
+    assert_eq!(fs::read(&sync_zip).unwrap(), fs::read(&async_zip).unwrap());
+
+    assert_eq!(tokio_test::block_on(list_async(&async_zip)).unwrap(), list(&sync_zip).unwrap());
+    let target: PathBuf = tempdir.path().join("target");
+    tokio_test::block_on(unarchive_async(&async_zip, &target)).unwrap();
+    assert_same(&source, &target.join("src"));
+    assert!(matches!(tokio_test::block_on(unarchive_async(&async_zip, &target)), Err(Error::TargetExists { .. })));
+}
+
+/// Test whether the `async-smol` drivers produce the same zip files, and extract them in the same way.
+#[cfg(feature = "async-smol")]
+#[test]
+fn test_zip_smol() {
+    let tempdir: TempDir = TempDir::new().unwrap();
+    let source: PathBuf = tempdir.path().join("src");
+    write_source(&source);
+
+    let sync_zip: PathBuf = tempdir.path().join("sync.zip");
+    let smol_zip: PathBuf = tempdir.path().join("smol.zip");
+    archive(&source, &sync_zip, false).unwrap();
+    futures_lite::future::block_on(archive_smol(&source, &smol_zip, false)).unwrap();
+    assert_eq!(fs::read(&sync_zip).unwrap(), fs::read(&smol_zip).unwrap());
+
+    assert_eq!(futures_lite::future::block_on(list_smol(&smol_zip)).unwrap(), list(&sync_zip).unwrap());
+    let target: PathBuf = tempdir.path().join("target");
+    futures_lite::future::block_on(unarchive_smol(&smol_zip, &target)).unwrap();
+    assert_same(&source, &target.join("src"));
+}
@@ -0,0 +1,269 @@
+//  TRANSPORT.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 16:48:12
+//  Last edited:
+//    18 Oct 2026, 16:48:12
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines the [`Transport`]-trait that abstracts over the HTTP stack
+//!   used to download files, together with its default [`reqwest`]
+//!   implementation.
+//
+
+use std::io::{self, Read};
+
+use reqwest::blocking::{Client, Request, Response};
+use reqwest::header::HeaderMap;
+use reqwest::redirect::Policy as ReqwestRedirectPolicy;
+use reqwest::{Method, StatusCode};
+use url::Url;
+#[cfg(feature = "__tls")]
+use reqwest::tls::{Certificate, TlsInfo};
+
+use crate::download::{DownloadSecurity, Error};
+
+
+/***** MACROS *****/
+/// Mirrors [`log`]'s [`debug!`]-macro, but only when the `log`-feature it given.
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($t:tt)*) => {
+        ::log::debug!($($t)*)
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($t:tt)*) => {};
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Configures a [`reqwest`] client according to the given [`DownloadSecurity`] and builds it.
+///
+/// # Arguments
+/// - `builder`: The (blocking or async) [`ClientBuilder`] to configure.
+/// - `security`: The [`DownloadSecurity`] that determines how the client is configured (e.g., which root certificates it trusts).
+///
+/// # Returns
+/// A new client that does not follow redirects by itself.
+///
+/// # Errors
+/// This function errors if any of the extra root certificates was invalid, or if we failed to build the client.
+#[cfg_attr(not(feature = "__tls"), allow(unused_variables))]
+pub(crate) fn build_client<B: ClientBuilder>(builder: B, security: &DownloadSecurity) -> Result<B::Client, Error> {
+    #[allow(unused_mut)]
+    let mut builder: B = builder.redirect(ReqwestRedirectPolicy::none());
+    #[cfg(feature = "__tls")]
+    {
+        builder = builder.tls_built_in_root_certs(security.tls.builtin_roots).tls_info(!security.tls.spki_pins.is_empty());
+        for (i, root) in security.tls.extra_roots.iter().enumerate() {
+            match root.load() {
+                Ok(cert) => builder = builder.add_root_certificate(cert),
+                Err(err) => return Err(Error::TlsRootCertificate { index: i, err }),
+            }
+        }
+    }
+    match builder.build() {
+        Ok(client) => Ok(client),
+        Err(err) => Err(Error::ClientCreate { err }),
+    }
+}
+
+
+
+
+
+/***** AUXILLARY *****/
+/// Abstracts over [`reqwest`]'s blocking and async client builders, such that both are configured by [`build_client()`].
+pub(crate) trait ClientBuilder: Sized {
+    /// The client that is built.
+    type Client;
+
+    /// See [`reqwest::blocking::ClientBuilder::redirect()`].
+    fn redirect(self, policy: ReqwestRedirectPolicy) -> Self;
+    /// See [`reqwest::blocking::ClientBuilder::tls_built_in_root_certs()`].
+    #[cfg(feature = "__tls")]
+    fn tls_built_in_root_certs(self, enabled: bool) -> Self;
+    /// See [`reqwest::blocking::ClientBuilder::tls_info()`].
+    #[cfg(feature = "__tls")]
+    fn tls_info(self, enabled: bool) -> Self;
+    /// See [`reqwest::blocking::ClientBuilder::add_root_certificate()`].
+    #[cfg(feature = "__tls")]
+    fn add_root_certificate(self, cert: Certificate) -> Self;
+    /// See [`reqwest::blocking::ClientBuilder::build()`].
+    fn build(self) -> Result<Self::Client, reqwest::Error>;
+}
+impl ClientBuilder for reqwest::blocking::ClientBuilder {
+    type Client = Client;
+
+    #[inline]
+    fn redirect(self, policy: ReqwestRedirectPolicy) -> Self { Self::redirect(self, policy) }
+    #[cfg(feature = "__tls")]
+    #[inline]
+    fn tls_built_in_root_certs(self, enabled: bool) -> Self { Self::tls_built_in_root_certs(self, enabled) }
+    #[cfg(feature = "__tls")]
+    #[inline]
+    fn tls_info(self, enabled: bool) -> Self { Self::tls_info(self, enabled) }
+    #[cfg(feature = "__tls")]
+    #[inline]
+    fn add_root_certificate(self, cert: Certificate) -> Self { Self::add_root_certificate(self, cert) }
+    #[inline]
+    fn build(self) -> Result<Self::Client, reqwest::Error> { Self::build(self) }
+}
+#[cfg(feature = "async-tokio")]
+impl ClientBuilder for reqwest::ClientBuilder {
+    type Client = reqwest::Client;
+
+    #[inline]
+    fn redirect(self, policy: ReqwestRedirectPolicy) -> Self { Self::redirect(self, policy) }
+    #[cfg(feature = "__tls")]
+    #[inline]
+    fn tls_built_in_root_certs(self, enabled: bool) -> Self { Self::tls_built_in_root_certs(self, enabled) }
+    #[cfg(feature = "__tls")]
+    #[inline]
+    fn tls_info(self, enabled: bool) -> Self { Self::tls_info(self, enabled) }
+    #[cfg(feature = "__tls")]
+    #[inline]
+    fn add_root_certificate(self, cert: Certificate) -> Self { Self::add_root_certificate(self, cert) }
+    #[inline]
+    fn build(self) -> Result<Self::Client, reqwest::Error> { Self::build(self) }
+}
+
+
+
+/// Defines the response to a single request sent by a [`Transport`].
+pub struct TransportResponse {
+    /// The status code returned by the server.
+    pub status: StatusCode,
+    /// The headers returned by the server.
+    pub headers: HeaderMap,
+    /// The body of the response, which is read chunk-by-chunk as it is downloaded.
+    pub body: Box<dyn Read + Send>,
+    /// The DER-encoded certificate that the server presented, if any.
+    ///
+    /// Only needed for certificate pinning (see `TlsConfig::spki_pins`); transports that don't support TLS can leave this `None`.
+    pub peer_certificate: Option<Vec<u8>>,
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Abstracts over the HTTP stack used to download files.
+///
+/// Implementations only have to send a single GET-request and return the server's response as-is. In particular, they must **not** follow redirects by
+/// themselves, since the download functions follow them manually to enforce the [`DownloadSecurity`] on every hop.
+///
+/// The default implementation is the [`ReqwestTransport`]. If you enabled the `testing`-feature, check `testing::MockTransport` for an in-memory
+/// implementation that can be scripted in tests.
+pub trait Transport {
+    /// Sends a GET-request to the given URL.
+    ///
+    /// # Arguments
+    /// - `url`: The URL to send the request to.
+    ///
+    /// # Returns
+    /// The [`TransportResponse`] that the server gave, regardless of its status code.
+    ///
+    /// # Errors
+    /// This function errors if we failed to send the request or receive the response's head. Implementations other than the [`ReqwestTransport`]
+    /// should use [`Error::Transport`] to do so.
+    fn get(&self, url: &Url) -> Result<TransportResponse, Error>;
+
+    /// Sends a request with the given method and extra headers to the given URL.
+    ///
+    /// This is used to probe files without downloading them (see `probe()`), and is as such optional. By default, a GET-request is sent with
+    /// [`Transport::get()`] (ignoring the headers) and a HEAD-request is emulated by sending a GET-request and dropping its body. Any other method
+    /// fails.
+    ///
+    /// # Arguments
+    /// - `method`: The [`Method`] of the request (typically GET or HEAD).
+    /// - `url`: The URL to send the request to.
+    /// - `headers`: Any extra headers to send with the request (e.g., `Range`).
+    ///
+    /// # Returns
+    /// The [`TransportResponse`] that the server gave, regardless of its status code.
+    ///
+    /// # Errors
+    /// This function errors if we failed to send the request or receive the response's head.
+    fn send(&self, method: Method, url: &Url, headers: HeaderMap) -> Result<TransportResponse, Error> {
+        let _ = headers;
+        match method {
+            Method::GET => self.get(url),
+            Method::HEAD => self.get(url).map(|res| TransportResponse { body: Box::new(io::empty()), ..res }),
+            method => Err(Error::Transport { url: url.as_str().into(), err: format!("{method}-requests are not supported by this transport").into() }),
+        }
+    }
+}
+
+
+
+/// Implements a [`Transport`] using [`reqwest`]'s blocking [`Client`].
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    /// The client used to send requests.
+    client: Client,
+}
+impl ReqwestTransport {
+    /// Constructor for the ReqwestTransport that configures the client according to the given [`DownloadSecurity`].
+    ///
+    /// # Arguments
+    /// - `security`: The [`DownloadSecurity`] that determines how the client is configured (e.g., which root certificates it trusts).
+    ///
+    /// # Returns
+    /// A new ReqwestTransport that does not follow redirects by itself.
+    ///
+    /// # Errors
+    /// This function errors if any of the extra root certificates was invalid, or if we failed to build the client.
+    #[inline]
+    pub fn new(security: &DownloadSecurity) -> Result<Self, Error> { Ok(Self { client: build_client(Client::builder(), security)? }) }
+
+    /// Constructor for the ReqwestTransport that uses an existing [`Client`].
+    ///
+    /// Note that the client should be built with [`reqwest::redirect::Policy::none()`], or else the redirect- and HTTPS-policies of the
+    /// [`DownloadSecurity`] are only enforced for the URLs that the client doesn't redirect by itself. Similarly, the `TlsConfig` is not applied to it.
+    ///
+    /// # Arguments
+    /// - `client`: The [`Client`] to send requests with.
+    ///
+    /// # Returns
+    /// A new ReqwestTransport that wraps the given client.
+    #[inline]
+    pub fn from_client(client: Client) -> Self { Self { client } }
+}
+impl Transport for ReqwestTransport {
+    #[inline]
+    fn get(&self, url: &Url) -> Result<TransportResponse, Error> { self.send(Method::GET, url, HeaderMap::new()) }
+
+    fn send(&self, method: Method, url: &Url, headers: HeaderMap) -> Result<TransportResponse, Error> {
+        // Send the request with a user-agent header (to make GitHub happy)
+        debug!("Sending {}-request to '{}' using reqwest...", method, url);
+        let req: Request = match self.client.request(method, url.clone()).header("User-Agent", "reqwest").headers(headers).build() {
+            Ok(req) => req,
+            Err(err) => {
+                return Err(Error::RequestCreate { url: url.as_str().into(), err });
+            },
+        };
+        let res: Response = match self.client.execute(req) {
+            Ok(res) => res,
+            Err(err) => {
+                return Err(Error::RequestExecute { url: url.as_str().into(), err });
+            },
+        };
+
+        // Unpack it into a transport-agnostic response
+        #[cfg(feature = "__tls")]
+        let peer_certificate: Option<Vec<u8>> = res.extensions().get::<TlsInfo>().and_then(TlsInfo::peer_certificate).map(Vec::from);
+        #[cfg(not(feature = "__tls"))]
+        let peer_certificate: Option<Vec<u8>> = None;
+        Ok(TransportResponse { status: res.status(), headers: res.headers().clone(), peer_certificate, body: Box::new(res) })
+    }
+}
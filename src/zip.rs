@@ -0,0 +1,736 @@
+//  ZIP.rs
+//    by Lut99
+//
+//  Created:
+//    18 Oct 2026, 19:02:11
+//  Last edited:
+//    18 Oct 2026, 19:02:11
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Defines functions for archiving/unarchiving zip files.
+//!
+//!   Zip files keep their index at the end, so they are read and written
+//!   with seeks. As such, the async functions run the sync ones on a
+//!   thread pool instead of reimplementing them.
+//
+
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter, Result as FResult};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{error, fs, io};
+
+use ::zip::read::ZipFile;
+use ::zip::result::ZipError;
+use ::zip::write::SimpleFileOptions;
+use ::zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter, ZIP64_BYTES_THR};
+#[cfg(feature = "async-smol")]
+use blocking::unblock;
+#[cfg(feature = "async-tokio")]
+use tokio::task::spawn_blocking;
+
+
+/***** MACROS *****/
+/// Mirrors [`log`]'s [`debug!`]-macro, but only when the `log`-feature it given.
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($t:tt)*) => {
+        ::log::debug!($($t)*)
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($t:tt)*) => {};
+}
+
+
+
+
+
+/***** ERRORS *****/
+/// Defines the errors tha may occur when dealing with zip files.
+#[derive(Debug)]
+pub enum Error {
+    // Archive errors
+    /// Failed to read an entry in the to-be-archived directory.
+    SourceDirEntryRead { path: PathBuf, entry: usize, err: std::io::Error },
+    /// Failed to read the to-be-archived directory.
+    SourceDirRead { path: PathBuf, err: std::io::Error },
+    /// Failed to open a to-be-archived file.
+    SourceFileOpen { path: PathBuf, err: std::io::Error },
+    /// Failed to read the metadata of a to-be-archived file or directory.
+    SourceMetadata { path: PathBuf, err: std::io::Error },
+    /// The name of a to-be-archived file or directory is not valid UTF-8, which zip files require.
+    SourceNameNotUnicode { path: PathBuf },
+    /// The given source entry is neither a file nor a directory.
+    SourceNotAFileOrDir { path: PathBuf },
+    /// The given source file did not exist.
+    SourceNotFound { path: PathBuf },
+    /// The given source (or something in it) is a symlink, which are not archived.
+    SourceSymlink { path: PathBuf },
+    /// Failed to add a file or directory to the output zip file.
+    TargetZipAppend { source: PathBuf, zipfile: PathBuf, err: ZipError },
+    /// Failed to create the output zip file.
+    TargetZipCreate { zipfile: PathBuf, err: std::io::Error },
+    /// Failed to finish up the zip file (i.e., write its index).
+    TargetZipFinish { zipfile: PathBuf, err: ZipError },
+    /// Failed to write the contents of a file to the output zip file.
+    TargetZipWrite { source: PathBuf, zipfile: PathBuf, err: std::io::Error },
+
+    // Unarchive errors
+    /// Failed to read one of the entries in the given source zip file.
+    SourceZipEntry { zipfile: PathBuf, entry: usize, err: ZipError },
+    /// Did not extract an entry because its path would have escaped the target directory.
+    SourceZipEntryEscaped { zipfile: PathBuf, entry: PathBuf },
+    /// Failed to unpack an entry from the given source zip file to the given location.
+    SourceZipEntryUnpack { zipfile: PathBuf, entry: PathBuf, target: PathBuf, err: std::io::Error },
+    /// Failed to open the source zip file.
+    SourceZipOpen { zipfile: PathBuf, err: std::io::Error },
+    /// Failed to read the index of the source zip file (e.g., because it isn't a zip file).
+    SourceZipRead { zipfile: PathBuf, err: ZipError },
+    /// Failed to create the target directory.
+    TargetDirCreate { path: PathBuf, err: std::io::Error },
+    /// The target path already exists.
+    TargetExists { path: PathBuf },
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            SourceDirEntryRead { path, entry, .. } => write!(f, "Failed to read entry {} in source directory '{}'", entry, path.display()),
+            SourceDirRead { path, .. } => write!(f, "Failed to read source directory '{}'", path.display()),
+            SourceFileOpen { path, .. } => write!(f, "Failed to open source file '{}'", path.display()),
+            SourceMetadata { path, .. } => write!(f, "Failed to read metadata of source '{}'", path.display()),
+            SourceNameNotUnicode { path } => write!(f, "Name of source '{}' is not valid UTF-8", path.display()),
+            SourceNotAFileOrDir { path } => write!(f, "Source '{}' is not a file or a directory", path.display()),
+            SourceNotFound { path } => write!(f, "Source '{}' not found", path.display()),
+            SourceSymlink { path } => write!(f, "Source '{}' is a symlink, which are not allowed in zip files", path.display()),
+            TargetZipAppend { source, zipfile, .. } => write!(f, "Failed to append '{}' to zip file '{}'", source.display(), zipfile.display()),
+            TargetZipCreate { zipfile, .. } => write!(f, "Failed to create zip file '{}'", zipfile.display()),
+            TargetZipFinish { zipfile, .. } => write!(f, "Failed to finish up zip file '{}'", zipfile.display()),
+            TargetZipWrite { source, zipfile, .. } => write!(f, "Failed to write file '{}' to zip file '{}'", source.display(), zipfile.display()),
+
+            SourceZipEntry { zipfile, entry, .. } => write!(f, "Failed to read entry {} in zip file '{}'", entry, zipfile.display()),
+            SourceZipEntryEscaped { zipfile, entry } => {
+                write!(f, "Entry '{}' in zip file '{}' would have escaped target directory", entry.display(), zipfile.display())
+            },
+            SourceZipEntryUnpack { zipfile, entry, target, .. } => {
+                write!(f, "Failed to unpack entry '{}' in zip file '{}' to '{}'", entry.display(), zipfile.display(), target.display())
+            },
+            SourceZipOpen { zipfile, .. } => write!(f, "Failed to open source zip file '{}'", zipfile.display()),
+            SourceZipRead { zipfile, .. } => write!(f, "Failed to read index of zip file '{}'", zipfile.display()),
+            TargetDirCreate { path, .. } => write!(f, "Failed to create target directory '{}'", path.display()),
+            TargetExists { path } => write!(f, "Target path '{}' already exists", path.display()),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            SourceDirEntryRead { err, .. } => Some(err),
+            SourceDirRead { err, .. } => Some(err),
+            SourceFileOpen { err, .. } => Some(err),
+            SourceMetadata { err, .. } => Some(err),
+            SourceNameNotUnicode { .. } => None,
+            SourceNotAFileOrDir { .. } => None,
+            SourceNotFound { .. } => None,
+            SourceSymlink { .. } => None,
+            TargetZipAppend { err, .. } => Some(err),
+            TargetZipCreate { err, .. } => Some(err),
+            TargetZipFinish { err, .. } => Some(err),
+            TargetZipWrite { err, .. } => Some(err),
+
+            SourceZipEntry { err, .. } => Some(err),
+            SourceZipEntryEscaped { .. } => None,
+            SourceZipEntryUnpack { err, .. } => Some(err),
+            SourceZipOpen { err, .. } => Some(err),
+            SourceZipRead { err, .. } => Some(err),
+            TargetDirCreate { err, .. } => Some(err),
+            TargetExists { .. } => None,
+        }
+    }
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Converts a timestamp to the (UTC) date and time stored in zip files.
+///
+/// # Arguments
+/// - `time`: The [`SystemTime`] to convert.
+///
+/// # Returns
+/// The equivalent [`DateTime`], or [`None`] if it can't be represented in a zip file (i.e., it's before 1980 or after 2107).
+fn to_zip_time(time: SystemTime) -> Option<DateTime> {
+    let secs: u64 = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let (days, secs): (u64, u64) = (secs / 86400, secs % 86400);
+
+    // Find the date of the day (see <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>)
+    let z: u64 = days + 719468;
+    let (era, doe): (u64, u64) = (z / 146097, z % 146097);
+    let yoe: u64 = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy: u64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp: u64 = (5 * doy + 2) / 153;
+    let day: u64 = doy - (153 * mp + 2) / 5 + 1;
+    let month: u64 = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year: u64 = yoe + era * 400 + u64::from(month <= 2);
+
+    DateTime::from_date_and_time(u16::try_from(year).ok()?, month as u8, day as u8, (secs / 3600) as u8, (secs % 3600 / 60) as u8, (secs % 60) as u8)
+        .ok()
+}
+
+/// Converts the (UTC) date and time stored in zip files to a timestamp.
+///
+/// # Arguments
+/// - `time`: The [`DateTime`] to convert.
+///
+/// # Returns
+/// The equivalent [`SystemTime`].
+fn from_zip_time(time: DateTime) -> SystemTime {
+    // Find the day of the date (see <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>)
+    let (month, day): (u64, u64) = (u64::from(time.month()), u64::from(time.day()));
+    let year: u64 = u64::from(time.year()) - u64::from(month <= 2);
+    let (era, yoe): (u64, u64) = (year / 400, year % 400);
+    let doy: u64 = (153 * if month > 2 { month - 3 } else { month + 9 } + 2) / 5 + day - 1;
+    let doe: u64 = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days: u64 = era * 146097 + doe - 719468;
+
+    let secs: u64 = days * 86400 + u64::from(time.hour()) * 3600 + u64::from(time.minute()) * 60 + u64::from(time.second());
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Builds the options with which a file or directory is added to a zip file.
+///
+/// # Arguments
+/// - `metadata`: The [`fs::Metadata`] of the file or directory, of which the modification time (and permissions, on Unix) are kept.
+///
+/// # Returns
+/// The [`SimpleFileOptions`] to add it with.
+fn file_options(metadata: &fs::Metadata) -> SimpleFileOptions {
+    let mut options: SimpleFileOptions = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .compression_level(Some(COMPRESSION_LEVEL))
+        .large_file(metadata.len() >= ZIP64_BYTES_THR);
+    if let Some(mtime) = metadata.modified().ok().and_then(to_zip_time) {
+        options = options.last_modified_time(mtime);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        options = options.unix_permissions(metadata.permissions().mode() & 0o777);
+    }
+    options
+}
+
+/// Opens a zip file and reads its index.
+///
+/// # Arguments
+/// - `zipfile`: The zip file to open.
+///
+/// # Returns
+/// A [`ZipArchive`] to read the entries with.
+///
+/// # Errors
+/// This function errors if we failed to open the file, or if it isn't a (valid) zip file.
+fn open(zipfile: &Path) -> Result<ZipArchive<io::BufReader<fs::File>>, Error> {
+    let handle: fs::File = match fs::File::open(zipfile) {
+        Ok(handle) => handle,
+        Err(err) => return Err(Error::SourceZipOpen { zipfile: zipfile.into(), err }),
+    };
+    match ZipArchive::new(io::BufReader::new(handle)) {
+        Ok(zip) => Ok(zip),
+        Err(err) => Err(Error::SourceZipRead { zipfile: zipfile.into(), err }),
+    }
+}
+
+/// Unpacks a single entry of a zip file.
+///
+/// Files get their modification time (and permissions, on Unix) from the entry. Any missing parent directories are created.
+///
+/// # Arguments
+/// - `entry`: The [`ZipFile`] to unpack.
+/// - `target`: The path to unpack it to.
+///
+/// # Errors
+/// This function errors if we failed to create or write the file or directory.
+fn unpack<R: Read>(entry: &mut ZipFile<R>, target: &Path) -> io::Result<()> {
+    if entry.is_dir() {
+        return fs::create_dir_all(target);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut handle: fs::File = fs::File::create(target)?;
+    io::copy(entry, &mut handle)?;
+    if let Some(mtime) = entry.last_modified() {
+        handle.set_modified(from_zip_time(mtime))?;
+    }
+    #[cfg(unix)]
+    if let Some(mode) = entry.unix_mode() {
+        use std::os::unix::fs::PermissionsExt as _;
+        handle.set_permissions(fs::Permissions::from_mode(mode & 0o777))?;
+    }
+    Ok(())
+}
+
+/// Runs a blocking function on [`tokio`]'s blocking thread pool.
+///
+/// # Arguments
+/// - `func`: The function to run.
+///
+/// # Returns
+/// The result of the function. If it panicked, the panic is resumed on the calling task.
+#[cfg(feature = "async-tokio")]
+async fn run_blocking<T: 'static + Send>(func: impl 'static + Send + FnOnce() -> T) -> T {
+    match spawn_blocking(func).await {
+        Ok(res) => res,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+
+
+
+
+/***** CORE *****/
+/// The deflate level used when archiving (i.e., the best), regardless of the driver.
+const COMPRESSION_LEVEL: i64 = 9;
+
+
+
+/// Implements the logic of walking a file or directory to archive, without doing any IO itself.
+///
+/// Works like the `tar`-module's walk, except that names in zip files must be valid UTF-8 and are always separated by slashes.
+struct ArchiveCore {
+    /// Whether to trim the source directory from the names in the zip file.
+    skip_root_dir: bool,
+    /// Whether we've yet to see the first directory.
+    is_root_dir:   bool,
+    /// The paths still to archive, together with their names in the zip file. Popped from the back.
+    todo:          Vec<(PathBuf, String)>,
+}
+impl ArchiveCore {
+    /// Constructor for the ArchiveCore.
+    ///
+    /// # Arguments
+    /// - `source`: The source file or directory to archive.
+    /// - `skip_root_dir`: Whether to trim the `source` from the names in the zip file if it's a directory.
+    ///
+    /// # Returns
+    /// A new ArchiveCore that will first yield the `source` itself.
+    ///
+    /// # Errors
+    /// This function errors if the name of the `source` is not valid UTF-8.
+    fn new(source: &Path, skip_root_dir: bool) -> Result<Self, Error> {
+        let name: String = match source.file_name().map(|f| f.to_str()) {
+            Some(Some(name)) => name.into(),
+            Some(None) => return Err(Error::SourceNameNotUnicode { path: source.into() }),
+            None => ".".into(),
+        };
+        Ok(Self { skip_root_dir, is_root_dir: true, todo: vec![(source.into(), name)] })
+    }
+
+    /// Returns the next path to archive.
+    ///
+    /// # Returns
+    /// The path of the next file or directory to archive and its name in the zip file, or [`None`] if we're done.
+    #[inline]
+    fn next(&mut self) -> Option<(PathBuf, String)> { self.todo.pop() }
+
+    /// Processes the entries of a directory returned by [`ArchiveCore::next()`].
+    ///
+    /// # Arguments
+    /// - `path`: The path of the directory.
+    /// - `name`: The name of the directory in the zip file.
+    /// - `entries`: The file names of the directory's entries, in any order.
+    ///
+    /// # Returns
+    /// The name of the directory entry to add to the zip file, or [`None`] if it's the root directory and it's skipped.
+    ///
+    /// # Errors
+    /// This function errors if the name of any of the entries is not valid UTF-8.
+    fn on_dir(&mut self, path: &Path, name: &str, mut entries: Vec<OsString>) -> Result<Option<String>, Error> {
+        // Compute the zip-side path of the entries
+        let skip: bool = self.skip_root_dir && self.is_root_dir;
+        self.is_root_dir = false;
+
+        // Add them in reverse order, such that they are archived in sorted order (independent of the filesystem)
+        entries.sort_unstable();
+        for entry in entries.into_iter().rev() {
+            let entry: String = match entry.into_string() {
+                Ok(entry) => entry,
+                Err(entry) => return Err(Error::SourceNameNotUnicode { path: path.join(entry) }),
+            };
+            let entry_name: String = if skip { entry.clone() } else { format!("{name}/{entry}") };
+            self.todo.push((path.join(entry), entry_name));
+        }
+        Ok(if skip { None } else { Some(format!("{name}/")) })
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// Archives the given file or directory as a `.zip` file.
+///
+/// Files are compressed with deflate, and keep their modification time (and permissions, on Unix). Directories are added as entries too, so
+/// empty ones survive a round trip. Symlinks are refused (see [`Error::SourceSymlink`]), since they may point outside of the source or to
+/// one of its own parents; use the `tar`-module to archive them.
+///
+/// If you enabled the `async-tokio` feature, also check the [`archive_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `zipfile`: The target zip file to archive to.
+/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting zip
+///   file (i.e., the files in the root dir will be in the zip's root instead of the directory). Ignore otherwise.
+///
+/// # Errors
+/// This function errors if we failed to read the source, if any of its names isn't valid UTF-8, if it contains any symlinks, or if we failed to
+/// write the zip file.
+///
+/// # Examples
+/// ```rust
+/// use download::zip::archive;
+///
+/// // Write a test directory
+/// let tmp = std::env::temp_dir();
+/// let dir = tmp.join("example_zip");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// std::fs::create_dir(&dir).unwrap();
+/// std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// std::fs::write(dir.join("file2.txt"), "General Kenobi...\n").unwrap();
+///
+/// // We can archive them!
+/// let zip = tmp.join("example_zip.zip");
+/// # if zip.exists() { std::fs::remove_file(&zip).unwrap(); }
+/// archive(&dir, &zip, false).unwrap();
+///
+/// assert!(zip.is_file());
+/// ```
+pub fn archive(source: impl AsRef<Path>, zipfile: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
+    let source: &Path = source.as_ref();
+    let zipfile: &Path = zipfile.as_ref();
+    debug!("Archiving '{}' to '{}'...", source.display(), zipfile.display());
+
+    // Open the target file
+    let handle: fs::File = match fs::File::create(zipfile) {
+        Ok(handle) => handle,
+        Err(err) => {
+            return Err(Error::TargetZipCreate { zipfile: zipfile.into(), err });
+        },
+    };
+    let mut zip: ZipWriter<io::BufWriter<fs::File>> = ZipWriter::new(io::BufWriter::new(handle));
+
+    // Now add the source recursively
+    let mut core: ArchiveCore = ArchiveCore::new(source, skip_root_dir)?;
+    while let Some((path, name)) = core.next() {
+        // (note: we don't follow symlinks, since they may point outside of the source or to one of its own parents)
+        let metadata: fs::Metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(Error::SourceNotFound { path }),
+            Err(err) => return Err(Error::SourceMetadata { path, err }),
+        };
+
+        // Switch on the file type
+        if metadata.is_symlink() {
+            return Err(Error::SourceSymlink { path });
+        } else if metadata.is_file() {
+            debug!("Adding file '{}' as '{}/{}'...", path.display(), zipfile.display(), name);
+
+            // Compress as a file
+            if let Err(err) = zip.start_file(name, file_options(&metadata)) {
+                return Err(Error::TargetZipAppend { source: path, zipfile: zipfile.into(), err });
+            }
+            let mut handle: fs::File = match fs::File::open(&path) {
+                Ok(handle) => handle,
+                Err(err) => return Err(Error::SourceFileOpen { path, err }),
+            };
+            if let Err(err) = io::copy(&mut handle, &mut zip) {
+                return Err(Error::TargetZipWrite { source: path, zipfile: zipfile.into(), err });
+            }
+        } else if metadata.is_dir() {
+            // Recurse to add the files
+            let entries: fs::ReadDir = match fs::read_dir(&path) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    return Err(Error::SourceDirRead { path, err });
+                },
+            };
+            let mut names: Vec<OsString> = Vec::new();
+            for (i, entry) in entries.enumerate() {
+                match entry {
+                    Ok(entry) => names.push(entry.file_name()),
+                    Err(err) => {
+                        return Err(Error::SourceDirEntryRead { path, entry: i, err });
+                    },
+                }
+            }
+            if let Some(name) = core.on_dir(&path, &name, names)? {
+                debug!("Adding directory '{}' as '{}/{}'...", path.display(), zipfile.display(), name);
+                if let Err(err) = zip.add_directory(name, file_options(&metadata)) {
+                    return Err(Error::TargetZipAppend { source: path, zipfile: zipfile.into(), err });
+                }
+            }
+        } else {
+            return Err(Error::SourceNotAFileOrDir { path });
+        }
+    }
+
+    // Finish writing the archive
+    debug!("Finishing zip file...");
+    match zip.finish().and_then(|mut handle| handle.flush().map_err(ZipError::Io)) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Error::TargetZipFinish { zipfile: zipfile.into(), err }),
+    }
+}
+
+/// Archives the given file or directory as a `.zip` file.
+///
+/// This variation runs [`archive()`] on [`tokio`]'s blocking thread pool, and is as such only available on the `async-tokio` feature. It produces
+/// the exact same zip files.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `zipfile`: The target zip file to archive to.
+/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting zip
+///   file (i.e., the files in the root dir will be in the zip's root instead of the directory). Ignore otherwise.
+///
+/// # Errors
+/// This function errors for the same reasons as [`archive()`].
+#[cfg(feature = "async-tokio")]
+pub async fn archive_async(source: impl AsRef<Path>, zipfile: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
+    let source: PathBuf = source.as_ref().into();
+    let zipfile: PathBuf = zipfile.as_ref().into();
+    run_blocking(move || archive(source, zipfile, skip_root_dir)).await
+}
+
+/// Archives the given file or directory as a `.zip` file.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`archive()`] on the [`blocking`]
+/// thread pool; so it works on any executor, and produces the exact same zip files.
+///
+/// # Arguments
+/// - `source`: The source file or directory to archive.
+/// - `zipfile`: The target zip file to archive to.
+/// - `skip_root_dir`: If the `source` points to a directory, then this determines whether to trim it (true) or not (false) in the resulting zip
+///   file (i.e., the files in the root dir will be in the zip's root instead of the directory). Ignore otherwise.
+///
+/// # Errors
+/// This function errors for the same reasons as [`archive()`].
+#[cfg(feature = "async-smol")]
+pub async fn archive_smol(source: impl AsRef<Path>, zipfile: impl AsRef<Path>, skip_root_dir: bool) -> Result<(), Error> {
+    let source: PathBuf = source.as_ref().into();
+    let zipfile: PathBuf = zipfile.as_ref().into();
+    unblock(move || archive(source, zipfile, skip_root_dir)).await
+}
+
+
+
+/// Unarchives the given `.zip` file to the given location.
+///
+/// Entries whose path would escape the target directory (e.g., `../evil`) are refused with [`Error::SourceZipEntryEscaped`], and absolute paths
+/// are extracted relative to the target directory (like the `tar`-module does). Symbolic links are not created, but extracted as regular files
+/// containing the link's target.
+///
+/// If you enabled the `async-tokio` feature, also check the [`unarchive_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `zipfile`: The source zip file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+///
+/// # Errors
+/// This function errors if we failed to read or write anything, if some directories do or do not exist, or if an entry tried to escape.
+///
+/// # Examples
+/// ```rust
+/// use download::zip::unarchive;
+///
+/// // Create an archive (see 'archive()' example)
+/// # let tmp = std::env::temp_dir();
+/// # let dir = tmp.join("example_unzip");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// # std::fs::create_dir(&dir).unwrap();
+/// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// # let zip = tmp.join("example_unzip.zip");
+/// # if zip.exists() { std::fs::remove_file(&zip).unwrap(); }
+/// # download::zip::archive(&dir, &zip, false).unwrap();
+///
+/// // Unarchive it to another directory!
+/// let out = tmp.join("example_unzip2");
+/// # if out.exists() { std::fs::remove_dir_all(&out).unwrap(); }
+/// unarchive(&zip, &out).unwrap();
+/// assert_eq!(std::fs::read_to_string(out.join("example_unzip").join("file1.txt")).unwrap(), "Hello there!\n");
+/// ```
+pub fn unarchive(zipfile: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    let zipfile: &Path = zipfile.as_ref();
+    let target: &Path = target.as_ref();
+    debug!("Extracting '{}' to '{}'...", zipfile.display(), target.display());
+
+    // Whine if the target already exists
+    if target.exists() {
+        return Err(Error::TargetExists { path: target.into() });
+    }
+    if let Err(err) = fs::create_dir(target) {
+        return Err(Error::TargetDirCreate { path: target.into(), err });
+    }
+
+    // Open the source zipfile
+    let mut zip: ZipArchive<io::BufReader<fs::File>> = open(zipfile)?;
+
+    // Iterate over all of the entries
+    for i in 0..zip.len() {
+        let mut entry: ZipFile<_> = match zip.by_index(i) {
+            Ok(entry) => entry,
+            Err(err) => return Err(Error::SourceZipEntry { zipfile: zipfile.into(), entry: i, err }),
+        };
+
+        // Refuse to extract it outside of the target directory
+        let path: PathBuf = match entry.enclosed_name() {
+            Some(path) => path,
+            None => return Err(Error::SourceZipEntryEscaped { zipfile: zipfile.into(), entry: entry.name().into() }),
+        };
+
+        // Unpack the thing
+        let entry_target: PathBuf = target.join(&path);
+        debug!("Extracting '{}/{}' to '{}'...", zipfile.display(), path.display(), entry_target.display());
+        if let Err(err) = unpack(&mut entry, &entry_target) {
+            return Err(Error::SourceZipEntryUnpack { zipfile: zipfile.into(), entry: path, target: entry_target, err });
+        }
+    }
+
+    // Done
+    Ok(())
+}
+
+/// Unarchives the given `.zip` file to the given location.
+///
+/// This variation runs [`unarchive()`] on [`tokio`]'s blocking thread pool, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `zipfile`: The source zip file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive()`].
+#[cfg(feature = "async-tokio")]
+pub async fn unarchive_async(zipfile: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    let zipfile: PathBuf = zipfile.as_ref().into();
+    let target: PathBuf = target.as_ref().into();
+    run_blocking(move || unarchive(zipfile, target)).await
+}
+
+/// Unarchives the given `.zip` file to the given location.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`unarchive()`] on the [`blocking`]
+/// thread pool; so it works on any executor.
+///
+/// # Arguments
+/// - `zipfile`: The source zip file to extract from.
+/// - `target`: The target directory to write to. Note that we will throw all sorts of nasty errors if it already exists somehow.
+///
+/// # Errors
+/// This function errors for the same reasons as [`unarchive()`].
+#[cfg(feature = "async-smol")]
+pub async fn unarchive_smol(zipfile: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<(), Error> {
+    let zipfile: PathBuf = zipfile.as_ref().into();
+    let target: PathBuf = target.as_ref().into();
+    unblock(move || unarchive(zipfile, target)).await
+}
+
+
+
+/// Lists the paths of the entries in the given `.zip` file, without extracting anything.
+///
+/// If you enabled the `async-tokio` feature, also check the [`list_async()`]-function for async contexts.
+///
+/// # Arguments
+/// - `zipfile`: The source zip file to list.
+///
+/// # Returns
+/// The paths of all entries in the zip file, in the order in which they appear.
+///
+/// # Errors
+/// This function errors if we failed to read the zip file.
+///
+/// # Examples
+/// ```rust
+/// use download::zip::list;
+///
+/// // Create an archive (see 'archive()' example)
+/// # let tmp = std::env::temp_dir();
+/// # let dir = tmp.join("example_zip_list");
+/// # if dir.exists() { std::fs::remove_dir_all(&dir).unwrap(); }
+/// # std::fs::create_dir(&dir).unwrap();
+/// # std::fs::write(dir.join("file1.txt"), "Hello there!\n").unwrap();
+/// # let zip = tmp.join("example_zip_list.zip");
+/// # if zip.exists() { std::fs::remove_file(&zip).unwrap(); }
+/// # download::zip::archive(&dir, &zip, false).unwrap();
+///
+/// // We can see what's in it
+/// let entries = list(&zip).unwrap();
+/// assert!(entries.contains(&"example_zip_list/file1.txt".into()));
+/// ```
+pub fn list(zipfile: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+    let zipfile: &Path = zipfile.as_ref();
+    debug!("Listing '{}'...", zipfile.display());
+
+    // Collect the paths of all of the entries
+    let mut zip: ZipArchive<io::BufReader<fs::File>> = open(zipfile)?;
+    let mut paths: Vec<PathBuf> = Vec::with_capacity(zip.len());
+    for i in 0..zip.len() {
+        match zip.by_index_raw(i) {
+            Ok(entry) => paths.push(entry.name().into()),
+            Err(err) => return Err(Error::SourceZipEntry { zipfile: zipfile.into(), entry: i, err }),
+        }
+    }
+
+    // Done
+    Ok(paths)
+}
+
+/// Lists the paths of the entries in the given `.zip` file, without extracting anything.
+///
+/// This variation runs [`list()`] on [`tokio`]'s blocking thread pool, and is as such only available on the `async-tokio` feature.
+///
+/// # Arguments
+/// - `zipfile`: The source zip file to list.
+///
+/// # Returns
+/// The paths of all entries in the zip file, in the order in which they appear.
+///
+/// # Errors
+/// This function errors for the same reasons as [`list()`].
+#[cfg(feature = "async-tokio")]
+pub async fn list_async(zipfile: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+    let zipfile: PathBuf = zipfile.as_ref().into();
+    run_blocking(move || list(zipfile)).await
+}
+
+/// Lists the paths of the entries in the given `.zip` file, without extracting anything.
+///
+/// This variation is runtime-agnostic, and is as such only available on the `async-smol` feature. It runs [`list()`] on the [`blocking`] thread
+/// pool; so it works on any executor.
+///
+/// # Arguments
+/// - `zipfile`: The source zip file to list.
+///
+/// # Returns
+/// The paths of all entries in the zip file, in the order in which they appear.
+///
+/// # Errors
+/// This function errors for the same reasons as [`list()`].
+#[cfg(feature = "async-smol")]
+pub async fn list_smol(zipfile: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+    let zipfile: PathBuf = zipfile.as_ref().into();
+    unblock(move || list(zipfile)).await
+}
+